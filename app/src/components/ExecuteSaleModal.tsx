@@ -138,7 +138,8 @@ export default function ExecuteSaleModal({
     programId: PublicKey,
     propertyId: string,
     buyerWallet: string,
-    sellerWallet: string
+    sellerWallet: string,
+    offerNonce: number
   ) => {
     // Find the marketplace authority - this would be specific to your deployment
     const marketplaceAuthority = new PublicKey("A9xYe8XDnCRyPdy7B75B5PT7JP9ktLtxi6xMBVa7C4Xd");
@@ -160,7 +161,12 @@ export default function ExecuteSaleModal({
     // Derive offer PDA
     const buyerPublicKey = new PublicKey(buyerWallet);
     const [offerPDA] = PublicKey.findProgramAddressSync(
-      [Buffer.from("offer"), propertyPDA.toBuffer(), buyerPublicKey.toBuffer()],
+      [
+        Buffer.from("offer"),
+        propertyPDA.toBuffer(),
+        buyerPublicKey.toBuffer(),
+        new BN(offerNonce).toArrayLike(Buffer, 'le', 8)
+      ],
       programId
     );
     console.log("Offer PDA:", offerPDA.toString());
@@ -208,7 +214,13 @@ export default function ExecuteSaleModal({
     escrowNFTAccount: PublicKey,
     buyerNFTAccount: PublicKey,
     marketplaceAuthority: PublicKey,
-    propertyNftMintPublicKey: PublicKey
+    propertyNftMintPublicKey: PublicKey,
+    payoutPublicKey: PublicKey,
+    insurerPublicKey: PublicKey,
+    taxConfigPublicKey: PublicKey,
+    taxCollectorPublicKey: PublicKey,
+    protocolConfigPublicKey: PublicKey,
+    protocolTreasuryPublicKey: PublicKey
   ) => {
     console.log("Creating execute_sale instruction with the following parameters:");
     console.log("- Program ID:", programId.toString());
@@ -223,6 +235,80 @@ export default function ExecuteSaleModal({
     console.log("- Buyer NFT account:", buyerNFTAccount.toString());
     console.log("- Marketplace authority:", marketplaceAuthority.toString());
     console.log("- NFT mint:", propertyNftMintPublicKey.toString());
+    console.log("- Seller payout wallet:", payoutPublicKey.toString());
+    
+    const [historyIndexPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("history_index"), propertyPDA.toBuffer()],
+      programId
+    );
+    console.log("- History index PDA:", historyIndexPDA.toString());
+    
+    const [sellerConcessionPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("seller_concession"), offerPDA.toBuffer()],
+      programId
+    );
+    console.log("- Seller concession PDA:", sellerConcessionPDA.toString());
+    
+    const [releaseSchedulePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("release_schedule"), escrowPDA.toBuffer()],
+      programId
+    );
+    console.log("- Release schedule PDA:", releaseSchedulePDA.toString());
+    
+    const [treasuryPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), marketplacePDA.toBuffer()],
+      programId
+    );
+    console.log("- Treasury PDA:", treasuryPDA.toString());
+    
+    // Staking accounts only need to exist when staking is configured; the program skips missing ones
+    const [stakingConfigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("staking_config"), marketplacePDA.toBuffer()],
+      programId
+    );
+    const [buyerStakePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake"), marketplacePDA.toBuffer(), buyerPublicKey.toBuffer()],
+      programId
+    );
+    const [sellerStakePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake"), marketplacePDA.toBuffer(), sellerPublicKey.toBuffer()],
+      programId
+    );
+    
+    const [payoutAddressPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("payout_address"), propertyPDA.toBuffer()],
+      programId
+    );
+    
+    const [titleInsurancePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("title_insurance"), offerPDA.toBuffer()],
+      programId
+    );
+    console.log("- Title insurance PDA:", titleInsurancePDA.toString());
+    
+    const [transferApprovalPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transfer_approval"), offerPDA.toBuffer()],
+      programId
+    );
+    
+    const [closingSchedulePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("closing_schedule"), offerPDA.toBuffer()],
+      programId
+    );
+    
+    // The seller's listing slot and bond are only released when the listing has them
+    const [listingSlotPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("listing_slot"), propertyPDA.toBuffer()],
+      programId
+    );
+    const [ownerListingsPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("owner_listings"), marketplacePDA.toBuffer(), sellerPublicKey.toBuffer()],
+      programId
+    );
+    const [listingBondPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("listing_bond"), propertyPDA.toBuffer()],
+      programId
+    );
     
     // Instruction discriminator for execute_sale (first 8 bytes of the SHA256 hash of "execute_sale")
     const discriminator = Buffer.from([37, 74, 217, 157, 79, 49, 35, 6]);
@@ -237,14 +323,35 @@ export default function ExecuteSaleModal({
         { pubkey: offerPDA, isSigner: false, isWritable: true },
         { pubkey: escrowPDA, isSigner: false, isWritable: true },
         { pubkey: transactionHistoryPDA, isSigner: false, isWritable: true },
-        { pubkey: buyerPublicKey, isSigner: true, isWritable: true },
+        { pubkey: historyIndexPDA, isSigner: false, isWritable: true },
+        { pubkey: sellerConcessionPDA, isSigner: false, isWritable: true },
+        { pubkey: releaseSchedulePDA, isSigner: false, isWritable: true },
+        { pubkey: buyerPublicKey, isSigner: false, isWritable: true },
+        { pubkey: buyerPublicKey, isSigner: true, isWritable: true }, // payer
         { pubkey: sellerPublicKey, isSigner: false, isWritable: true },
-        { pubkey: marketplaceAuthority, isSigner: false, isWritable: true },
+        { pubkey: treasuryPDA, isSigner: false, isWritable: true },
+        { pubkey: stakingConfigPDA, isSigner: false, isWritable: false },
+        { pubkey: buyerStakePDA, isSigner: false, isWritable: false },
+        { pubkey: sellerStakePDA, isSigner: false, isWritable: false },
+        { pubkey: payoutAddressPDA, isSigner: false, isWritable: false },
+        { pubkey: payoutPublicKey, isSigner: false, isWritable: true },
         { pubkey: escrowNFTAccount, isSigner: false, isWritable: true },
         { pubkey: buyerNFTAccount, isSigner: false, isWritable: true },
         { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: programId, isSigner: false, isWritable: false }, // co_ownership
+        { pubkey: titleInsurancePDA, isSigner: false, isWritable: true },
+        { pubkey: insurerPublicKey, isSigner: false, isWritable: !insurerPublicKey.equals(programId) },
+        { pubkey: taxConfigPublicKey, isSigner: false, isWritable: false },
+        { pubkey: taxCollectorPublicKey, isSigner: false, isWritable: !taxCollectorPublicKey.equals(programId) },
+        { pubkey: protocolConfigPublicKey, isSigner: false, isWritable: false },
+        { pubkey: protocolTreasuryPublicKey, isSigner: false, isWritable: !protocolTreasuryPublicKey.equals(programId) },
+        { pubkey: transferApprovalPDA, isSigner: false, isWritable: true },
+        { pubkey: closingSchedulePDA, isSigner: false, isWritable: true },
+        { pubkey: listingSlotPDA, isSigner: false, isWritable: true },
+        { pubkey: ownerListingsPDA, isSigner: false, isWritable: true },
+        { pubkey: listingBondPDA, isSigner: false, isWritable: true },
       ],
       data: discriminator
     });
@@ -320,7 +427,12 @@ export default function ExecuteSaleModal({
       );
       
       const [offerPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("offer"), propertyPDA.toBuffer(), buyerPubkey.toBuffer()],
+        [
+          Buffer.from("offer"),
+          propertyPDA.toBuffer(),
+          buyerPubkey.toBuffer(),
+          new BN(offer.offer_nonce).toArrayLike(Buffer, 'le', 8)
+        ],
         programId
       );
       
@@ -414,6 +526,74 @@ export default function ExecuteSaleModal({
         }
       }
 
+      // Proceeds go to the seller's payout address when they set one while owning the property
+      // (layout: discriminator, property, owner, beneficiary)
+      let payoutPubkey = sellerPubkey;
+      const [payoutAddressPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("payout_address"), propertyPDA.toBuffer()],
+        programId
+      );
+      const payoutAddressInfo = await connection.getAccountInfo(payoutAddressPDA);
+      if (payoutAddressInfo && payoutAddressInfo.owner.equals(programId)) {
+        const payoutOwner = new PublicKey(payoutAddressInfo.data.subarray(40, 72));
+        if (payoutOwner.equals(sellerPubkey)) {
+          payoutPubkey = new PublicKey(payoutAddressInfo.data.subarray(72, 104));
+        }
+      }
+      console.log("🏠 Seller payout wallet:", payoutPubkey.toString());
+
+      // A title insurance premium the buyer added is paid to the policy's insurer
+      // (layout: discriminator, offer, buyer, insurer)
+      let insurerPubkey = programId;
+      const [titleInsurancePDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("title_insurance"), offerPDA.toBuffer()],
+        programId
+      );
+      const titleInsuranceInfo = await connection.getAccountInfo(titleInsurancePDA);
+      if (titleInsuranceInfo && titleInsuranceInfo.owner.equals(programId)) {
+        insurerPubkey = new PublicKey(titleInsuranceInfo.data.subarray(72, 104));
+        console.log("🏠 Title insurer:", insurerPubkey.toString());
+      }
+
+      // Transfer tax goes to the collector configured for the property's jurisdiction. The code
+      // sits after the property's variable-length strings: property_id, then price, metadata_uri
+      // and location, then 101 bytes of fixed fields up to the cadastral id.
+      let taxCollectorPubkey = programId;
+      const propertyInfo = await connection.getAccountInfo(propertyPDA);
+      let jurisdictionCode = Buffer.alloc(8);
+      if (propertyInfo) {
+        const data = propertyInfo.data;
+        let offset = 72;
+        offset += 4 + data.readUInt32LE(offset) + 8;
+        offset += 4 + data.readUInt32LE(offset);
+        offset += 4 + data.readUInt32LE(offset);
+        offset += 101;
+        jurisdictionCode = Buffer.from(data.subarray(offset, offset + 8));
+      }
+      const [taxConfigPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tax_config"), marketplacePDA.toBuffer(), jurisdictionCode],
+        programId
+      );
+      // (layout: discriminator, marketplace, jurisdiction_code, tax_bps, collector)
+      const taxConfigInfo = await connection.getAccountInfo(taxConfigPDA);
+      if (taxConfigInfo && taxConfigInfo.owner.equals(programId)) {
+        taxCollectorPubkey = new PublicKey(taxConfigInfo.data.subarray(50, 82));
+        console.log("🏠 Transfer tax collector:", taxCollectorPubkey.toString());
+      }
+
+      // Part of the fee goes to the protocol treasury once the protocol fee is configured
+      // (layout: discriminator, protocol_fee_bps, treasury)
+      let protocolTreasuryPubkey = programId;
+      const [protocolConfigPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("protocol_config")],
+        programId
+      );
+      const protocolConfigInfo = await connection.getAccountInfo(protocolConfigPDA);
+      if (protocolConfigInfo && protocolConfigInfo.owner.equals(programId)) {
+        protocolTreasuryPubkey = new PublicKey(protocolConfigInfo.data.subarray(10, 42));
+        console.log("🏠 Protocol treasury:", protocolTreasuryPubkey.toString());
+      }
+
       // Create the execute_sale instruction to call our Solana program
       console.log("🏠 Creating execute_sale instruction");
       
@@ -429,7 +609,13 @@ export default function ExecuteSaleModal({
         escrowNFTAccount,
         buyerNFTAccount,
         marketplaceAuthority,
-        nftMint
+        nftMint,
+        payoutPubkey,
+        insurerPubkey,
+        taxConfigPDA,
+        taxCollectorPubkey,
+        protocolConfigPDA,
+        protocolTreasuryPubkey
       );
       
       // Add the execute_sale instruction to the transaction
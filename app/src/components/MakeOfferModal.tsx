@@ -4,7 +4,7 @@ import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select";
-import { createOffer } from "../services/offerService";
+import { allocateOfferNonce, createOffer } from "../services/offerService";
 import { submitTransactionNoUpdate, getRecentBlockhash } from "../services/transactionService";
 import { useToast } from "@/components/ui/use-toast";
 import { PublicKey, Transaction, SystemProgram, LAMPORTS_PER_SOL, Connection, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@solana/web3.js';
@@ -72,12 +72,15 @@ export default function MakeOfferModal({
   // Create an offer instruction based on smart contract
   const createMakeOfferInstruction = (
     programId: PublicKey,
+    marketplacePda: PublicKey,
     propertyPda: PublicKey,
+    offerLimitsPda: PublicKey,
     offerPda: PublicKey,
     escrowPda: PublicKey,
     buyerWallet: PublicKey,
     amount: number,
-    expirationTime: number
+    expirationTime: number,
+    offerNonce: number
   ): TransactionInstruction => {
     console.log("Creating make_offer instruction with the following parameters:");
     console.log(`- Program ID: ${programId.toString()}`);
@@ -87,13 +90,14 @@ export default function MakeOfferModal({
     console.log(`- Buyer wallet: ${buyerWallet.toString()}`);
     console.log(`- Amount: ${amount}`);
     console.log(`- Expiration time: ${expirationTime}`);
+    console.log(`- Offer nonce: ${offerNonce}`);
     
     // Use the exact discriminator from IDL
     const instructionDiscriminator = Buffer.from([214, 98, 97, 35, 59, 12, 44, 178]);
     
     // Create a buffer for the entire data payload
-    // 8 bytes for discriminator + 8 bytes for amount + 8 bytes for expiration
-    const dataLayout = Buffer.alloc(24);
+    // 8 bytes for discriminator + 8 bytes for amount + 8 bytes for expiration + 8 bytes for nonce
+    const dataLayout = Buffer.alloc(32);
     
     // Copy the discriminator into the buffer
     instructionDiscriminator.copy(dataLayout, 0);
@@ -107,14 +111,21 @@ export default function MakeOfferModal({
     const expirationTimeBn = new BN(expirationTime);
     const expirationBuffer = Buffer.from(expirationTimeBn.toArray('le', 8));
     expirationBuffer.copy(dataLayout, 16);
+
+    // The nonce seeds the offer PDA, letting a buyer hold several offers on one property
+    const nonceBuffer = Buffer.from(new BN(offerNonce).toArray('le', 8));
+    nonceBuffer.copy(dataLayout, 24);
     
     // Create and return the TransactionInstruction
     return new TransactionInstruction({
       keys: [
-        { pubkey: propertyPda, isSigner: false, isWritable: false },  // property - NOT writable according to IDL
+        { pubkey: marketplacePda, isSigner: false, isWritable: false }, // marketplace
+        { pubkey: propertyPda, isSigner: false, isWritable: true },   // property - writable, tracks open offers
+        { pubkey: offerLimitsPda, isSigner: false, isWritable: false }, // offer_limits
         { pubkey: offerPda, isSigner: false, isWritable: true },     // offer
         { pubkey: escrowPda, isSigner: false, isWritable: true },    // escrow
         { pubkey: buyerWallet, isSigner: true, isWritable: true },   // buyer
+        { pubkey: programId, isSigner: false, isWritable: false },   // price_feed - omitted, only used for USD-priced listings
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }, // system_program
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false } // rent
       ],
@@ -215,11 +226,15 @@ export default function MakeOfferModal({
       // First, create the offer in the database - using the token which contains the correct wallet address
       console.log(`Creating offer for property ${propertyId} with amount ${amount} and expiration days ${expirationDays}`);
       console.log(`Connected wallet: ${walletPublicKeyStr}`);
+      const offerNonce = await allocateOfferNonce(propertyId, token);
+      console.log("Reserved offer nonce:", offerNonce);
       const offer = await createOffer(
         propertyId,
         Number(amount),
         Number(expirationDays),
-        token
+        token,
+        walletPublicKeyStr,
+        offerNonce
       );
 
       console.log("Offer created successfully:", offer);
@@ -261,13 +276,20 @@ export default function MakeOfferModal({
         programId
       );
       console.log("Property PDA:", propertyPDA.toString());
+
+      // Per-property offer cap; may not exist if the marketplace has no cap
+      const [offerLimitsPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("offer_limits"), marketplacePDA.toBuffer()],
+        programId
+      );
       
       // Find the offer PDA
       const [offerPDA] = PublicKey.findProgramAddressSync(
         [
           Buffer.from("offer"),
           propertyPDA.toBuffer(),
-          walletPublicKey.toBuffer()
+          walletPublicKey.toBuffer(),
+          new BN(offerNonce).toArrayLike(Buffer, 'le', 8)
         ],
         programId
       );
@@ -286,12 +308,15 @@ export default function MakeOfferModal({
       // Add the make_offer instruction
       const offerInstruction = createMakeOfferInstruction(
         programId,
+        marketplacePDA,
         propertyPDA,
+        offerLimitsPDA,
         offerPDA,
         escrowPDA,
         walletPublicKey,
         amountLamports,
-        expirationTimeSeconds
+        expirationTimeSeconds,
+        offerNonce
       );
       
       // First simulate the transaction before signing
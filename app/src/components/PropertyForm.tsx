@@ -148,6 +148,7 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
     bedrooms: "",
     bathrooms: "",
     metadata_uri: "https://picsum.photos/400/300", // Default image URL
+    geohash: "",
   });
   const [isSubmitting, setIsSubmitting] = useState(false);
   const [errors, setErrors] = useState<Record<string, string>>({});
@@ -241,6 +242,13 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
       }
     }
     
+    // The program stores an 8 to 10 character base32 geohash for map search
+    if (!formData.geohash.trim()) {
+      newErrors.geohash = "Geohash is required";
+    } else if (!/^[0-9bcdefghjkmnpqrstuvwxyz]{8,10}$/.test(formData.geohash)) {
+      newErrors.geohash = "Geohash must be 8 to 10 lowercase base32 characters";
+    }
+    
     setErrors(newErrors);
     return Object.keys(newErrors).length === 0;
   };
@@ -331,7 +339,8 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
     console.log(`Using discriminator:`, discriminator);
     
     // We need to properly serialize our args according to Anchor's format
-    // For list_property, we have string, u64, string, string, u64, u8, u8
+    // For list_property, we have string, u64, string, string, u64, u8, u8, then the optional
+    // expiry, property type, amenities, geohash, coordinates and content hashes
     
     // Serialize the arguments according to their types
     const serializedArgs = serializeAnchorArgs(args, instructionDef.args);
@@ -376,10 +385,11 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
       // Special case: property_nft_mint needs to be a signer regardless of IDL definition
       const isPropertyNftMint = name === 'property_nft_mint' || accountDef.name === 'property_nft_mint';
       
+      // Optional accounts left out are passed as the program ID, which can't be writable
       return {
         pubkey,
         isSigner: isPropertyNftMint || accountDef.signer === true,
-        isWritable: isPropertyNftMint || accountDef.writable === true
+        isWritable: isPropertyNftMint || (accountDef.writable === true && !pubkey.equals(programId))
       };
     });
     
@@ -402,16 +412,28 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
     // Let's serialize the arguments properly based on their types
     const buffers: Buffer[] = [];
     
-    args.forEach((arg, i) => {
-      const argDef = argDefs[i];
-      const type = argDef.type;
+    const serializeValue = (value: any, type: any) => {
+      // Anchor format: 1-byte tag (0 = None, 1 = Some) followed by the value
+      if (typeof type === 'object' && 'option' in type) {
+        buffers.push(Buffer.from([value === null || value === undefined ? 0 : 1]));
+        if (value !== null && value !== undefined) {
+          serializeValue(value, type.option);
+        }
+        return;
+      }
       
-      console.log(`Serializing arg ${i}: ${argDef.name} (${type}) = ${arg}`);
+      // Defined types here are fieldless enums (e.g. PropertyType), passed as the variant index
+      if (typeof type === 'object' && 'defined' in type) {
+        const indexBuf = Buffer.alloc(1);
+        indexBuf.writeUInt8(Number(value), 0);
+        buffers.push(indexBuf);
+        return;
+      }
       
       switch (type) {
         case 'string': {
           // Anchor format: 4-byte length prefix + UTF-8 bytes
-          const strBytes = Buffer.from(arg);
+          const strBytes = Buffer.from(value);
           const lenBuf = Buffer.alloc(4);
           lenBuf.writeUInt32LE(strBytes.length, 0);
           buffers.push(lenBuf);
@@ -421,35 +443,57 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
         case 'u64': {
           // Anchor format: 8-byte little-endian
           const numBuf = Buffer.alloc(8);
-          if (arg instanceof BN) {
-            const bn = arg as BN;
+          if (value instanceof BN) {
+            const bn = value as BN;
             const arr = bn.toArray('le', 8);
             numBuf.set(arr);
           } else {
-            let bn = new BN(arg);
+            let bn = new BN(value);
             const arr = bn.toArray('le', 8);
             numBuf.set(arr);
           }
           buffers.push(numBuf);
           break;
         }
+        case 'i64': {
+          // Anchor format: 8-byte little-endian two's complement
+          const numBuf = Buffer.alloc(8);
+          numBuf.set(new BN(value).toTwos(64).toArray('le', 8));
+          buffers.push(numBuf);
+          break;
+        }
+        case 'i32': {
+          // Anchor format: 4-byte little-endian
+          const numBuf = Buffer.alloc(4);
+          numBuf.writeInt32LE(Number(value), 0);
+          buffers.push(numBuf);
+          break;
+        }
         case 'u8': {
           // Anchor format: 1-byte
           const numBuf = Buffer.alloc(1);
-          numBuf.writeUInt8(Number(arg), 0);
+          numBuf.writeUInt8(Number(value), 0);
           buffers.push(numBuf);
           break;
         }
         case 'bool': {
           // Anchor format: 1-byte (0 or 1)
           const boolBuf = Buffer.alloc(1);
-          boolBuf.writeUInt8(arg ? 1 : 0, 0);
+          boolBuf.writeUInt8(value ? 1 : 0, 0);
           buffers.push(boolBuf);
           break;
         }
         default:
-          throw new Error(`Unsupported argument type: ${type}`);
+          throw new Error(`Unsupported argument type: ${JSON.stringify(type)}`);
       }
+    };
+    
+    args.forEach((arg, i) => {
+      const argDef = argDefs[i];
+      const type = argDef.type;
+      
+      console.log(`Serializing arg ${i}: ${argDef.name} (${JSON.stringify(type)}) = ${arg}`);
+      serializeValue(arg, type);
     });
     
     return Buffer.concat(buffers);
@@ -578,6 +622,20 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
       const squareFeetBN = new BN(sqFeet);
       
       // 5. Create listing instruction
+      const ownerPublicKey = new PublicKey(publicKey.toString());
+      const [listingBondConfigPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("listing_bond_config"), marketplacePDA.toBuffer()],
+        program.programId
+      );
+      const [listingLimitsPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("listing_limits"), marketplacePDA.toBuffer()],
+        program.programId
+      );
+      const [ownerListingsPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("owner_listings"), marketplacePDA.toBuffer(), ownerPublicKey.toBuffer()],
+        program.programId
+      );
+      
       console.log("Building list_property instruction");
       const listPropertyIx = buildInstruction(
         program.programId,
@@ -589,18 +647,33 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
           formData.location,
           squareFeetBN,
           Number(formData.bedrooms),
-          Number(formData.bathrooms)
+          Number(formData.bathrooms),
+          null, // expires_at
+          0, // property_type: Residential
+          new BN(0), // amenities
+          formData.geohash,
+          null, // latitude_e6
+          null, // longitude_e6
+          null // content_hashes
         ], 
+        // Keys are sent in this order, which must match the ListProperty accounts
         {
           marketplace: marketplacePDA,
           property: propertyPDA,
-          owner: new PublicKey(publicKey.toString()),
+          owner: ownerPublicKey,
           property_nft_mint: propertyNftMint.publicKey,
           owner_nft_account: ownerNftAccount,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           rent: SYSVAR_RENT_PUBKEY,
+          listing_bond_config: listingBondConfigPDA,
+          // No listing bond, listing fee or listing slot; passing the program ID leaves them out
+          listing_bond: program.programId,
+          treasury: program.programId,
+          listing_limits: listingLimitsPDA,
+          owner_listings: ownerListingsPDA,
+          listing_slot: program.programId,
         }
       );
       
@@ -959,6 +1032,23 @@ export function PropertyForm({ onClose }: PropertyFormProps) {
           {errors.metadata_uri && <p className="text-red-500 text-xs mt-1">{errors.metadata_uri}</p>}
         </div>
         
+        <div className="mb-4">
+          <label className="block text-gray-700 text-sm font-bold mb-2" htmlFor="geohash">
+            Geohash
+          </label>
+          <input
+            type="text"
+            id="geohash"
+            name="geohash"
+            value={formData.geohash}
+            onChange={handleChange}
+            className={`w-full px-3 py-2 border rounded-lg ${errors.geohash ? 'border-red-500' : 'border-gray-300'}`}
+            placeholder="e.g., 9q8yyk8y"
+            maxLength={10}
+          />
+          {errors.geohash && <p className="text-red-500 text-xs mt-1">{errors.geohash}</p>}
+        </div>
+        
         <button
           type="submit"
           disabled={isSubmitting}
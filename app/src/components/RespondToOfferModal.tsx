@@ -175,6 +175,7 @@ export default function RespondToOfferModal({
   // Create respond_to_offer instruction 
   const createRespondToOfferInstruction = (
     programId: PublicKey,
+    marketplacePda: PublicKey,
     propertyPda: PublicKey,
     offerPda: PublicKey,
     escrowPda: PublicKey,
@@ -182,10 +183,13 @@ export default function RespondToOfferModal({
     buyerWallet: PublicKey,
     sellerNftAccount: PublicKey,
     escrowNftAccount: PublicKey,
-    accept: boolean
+    nftMint: PublicKey,
+    accept: boolean,
+    saleAuthorizationPda: PublicKey | null
   ): TransactionInstruction => {
     console.log("Creating respond_to_offer instruction with the following parameters:");
     console.log(`- Program ID: ${programId.toString()}`);
+    console.log(`- Marketplace PDA: ${marketplacePda.toString()}`);
     console.log(`- Property PDA: ${propertyPda.toString()}`);
     console.log(`- Offer PDA: ${offerPda.toString()}`);
     console.log(`- Escrow PDA: ${escrowPda.toString()}`);
@@ -193,14 +197,20 @@ export default function RespondToOfferModal({
     console.log(`- Buyer wallet: ${buyerWallet.toString()}`);
     console.log(`- Seller NFT account: ${sellerNftAccount.toString()}`);
     console.log(`- Escrow NFT account: ${escrowNftAccount.toString()}`);
+    console.log(`- NFT mint: ${nftMint.toString()}`);
     console.log(`- Accept: ${accept}`);
     
+    const [propertyGovernancePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("property_governance"), propertyPda.toBuffer()],
+      programId
+    );
+    
     // Use the exact discriminator from IDL for respond_to_offer
     const instructionDiscriminator = Buffer.from([143, 248, 12, 134, 212, 199, 41, 123]);
     
     // Create a buffer for the entire data payload
-    // 8 bytes for discriminator + 1 byte for boolean
-    const dataLayout = Buffer.alloc(9);
+    // 8 bytes for discriminator + 1 byte for boolean + 1 byte for the seller_concession Option tag
+    const dataLayout = Buffer.alloc(10);
     
     // Copy the discriminator into the buffer
     instructionDiscriminator.copy(dataLayout, 0);
@@ -208,9 +218,13 @@ export default function RespondToOfferModal({
     // Set the boolean value (1 for true, 0 for false)
     dataLayout[8] = accept ? 1 : 0;
     
+    // No seller concession (Option::None)
+    dataLayout[9] = 0;
+    
     // Create the instruction with accounts in the right order according to RespondToOffer struct
     return new TransactionInstruction({
       keys: [
+        { pubkey: marketplacePda, isSigner: false, isWritable: false }, // marketplace
         { pubkey: propertyPda, isSigner: false, isWritable: true },     // property
         { pubkey: offerPda, isSigner: false, isWritable: true },       // offer
         { pubkey: escrowPda, isSigner: false, isWritable: true },      // escrow
@@ -218,7 +232,14 @@ export default function RespondToOfferModal({
         { pubkey: buyerWallet, isSigner: false, isWritable: true },    // buyer
         { pubkey: sellerNftAccount, isSigner: false, isWritable: true }, // seller_nft_account
         { pubkey: escrowNftAccount, isSigner: false, isWritable: true }, // escrow_nft_account
+        { pubkey: nftMint, isSigner: false, isWritable: false }, // property_nft_mint
         { pubkey: new PublicKey(TOKEN_PROGRAM_ID), isSigner: false, isWritable: false }, // token_program
+        // Unused optional accounts are passed as the program ID
+        { pubkey: programId, isSigner: false, isWritable: false }, // co_ownership
+        { pubkey: programId, isSigner: false, isWritable: false }, // seller_concession
+        { pubkey: programId, isSigner: false, isWritable: false }, // system_program
+        { pubkey: propertyGovernancePda, isSigner: false, isWritable: false }, // property_governance
+        { pubkey: saleAuthorizationPda ?? programId, isSigner: false, isWritable: !!saleAuthorizationPda }, // sale_authorization
       ],
       programId: programId,
       data: dataLayout
@@ -362,7 +383,8 @@ export default function RespondToOfferModal({
         [
           Buffer.from("offer"),
           propertyPDA.toBuffer(),
-          buyerWallet.toBuffer()
+          buyerWallet.toBuffer(),
+          new BN(offer.offer_nonce).toArrayLike(Buffer, 'le', 8)
         ],
         programId
       );
@@ -487,9 +509,17 @@ export default function RespondToOfferModal({
         return;
       }
       
+      // Governed properties need the sale authorization their governance created by proposal
+      const [saleAuthorizationPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sale_authorization"), propertyPDA.toBuffer()],
+        programId
+      );
+      const saleAuthorizationInfo = await connection.getAccountInfo(saleAuthorizationPDA);
+      
       // After potentially adding the escrow account creation instruction, add the respond_to_offer instruction
       const respondToOfferInstruction = createRespondToOfferInstruction(
         programId,
+        marketplacePDA,
         propertyPDA,
         offerPDA,
         escrowPDA,
@@ -497,7 +527,9 @@ export default function RespondToOfferModal({
         buyerWallet,
         sellerNftAccount,
         escrowNftAccount,
-        accept
+        new PublicKey(nftMintAddress),
+        accept,
+        accept && saleAuthorizationInfo ? saleAuthorizationPDA : null
       );
       transaction.add(respondToOfferInstruction);
       
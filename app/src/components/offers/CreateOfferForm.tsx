@@ -1,6 +1,6 @@
 import { useState, useEffect } from 'react';
 import { useNavigate } from 'react-router-dom';
-import { createOffer } from '../../services/offerService';
+import { allocateOfferNonce, createOffer } from '../../services/offerService';
 import { useAuth } from '../../hooks/useAuth';
 import { useWallet } from '../../hooks/useWallet';
 import { Alert, AlertTitle, Card, CardContent, CardHeader, TextField, Button, Typography, Box, CircularProgress } from '@mui/material';
@@ -69,11 +69,14 @@ export const CreateOfferForm = ({
       console.log(`Creating offer for property ${propertyId} with amount ${amount} SOL`);
       console.log(`Using wallet: ${publicKey}`);
 
+      const offerNonce = await allocateOfferNonce(propertyId, token);
       const offer = await createOffer(
         propertyId,
         amount,
         expirationDays,
-        token
+        token,
+        String(publicKey),
+        offerNonce
       );
 
       setSuccess(true);
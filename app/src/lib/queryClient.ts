@@ -1,9 +1,28 @@
 import { QueryClient, QueryFunction } from "@tanstack/react-query";
 
+/** Error from the backend, carrying its `code` when the body is an ApiError JSON payload */
+export class ApiRequestError extends Error {
+  constructor(
+    public status: number,
+    message: string,
+    public code?: string,
+  ) {
+    super(`${status}: ${message}`);
+  }
+}
+
 async function throwIfResNotOk(res: Response) {
   if (!res.ok) {
     const text = (await res.text()) || res.statusText;
-    throw new Error(`${res.status}: ${text}`);
+    try {
+      const body = JSON.parse(text);
+      if (typeof body?.code === "string" && typeof body?.message === "string") {
+        throw new ApiRequestError(res.status, body.message, body.code);
+      }
+    } catch (e) {
+      if (e instanceof ApiRequestError) throw e;
+    }
+    throw new ApiRequestError(res.status, text);
   }
 }
 
@@ -10,7 +10,8 @@ export const createOffer = async (
   amount: number, 
   expirationDays: number, 
   token: string,
-  buyerWallet: string
+  buyerWallet: string,
+  offerNonce: number
 ): Promise<Offer> => {
   try {
     console.log("Creating offer with token:", token ? "Token exists" : "No token");
@@ -26,6 +27,7 @@ export const createOffer = async (
       amount: amountInLamports,
       expiration_days: expirationDays,
       buyer_wallet: buyerWallet,
+      offer_nonce: offerNonce,
       program_id: PROGRAM_ID.toString()
     };
     console.log('📤 Creating offer with data:', requestData);
@@ -49,6 +51,21 @@ export const createOffer = async (
   }
 };
 
+// Reserves the next offer nonce for the caller on a property; it seeds the offer PDA
+export const allocateOfferNonce = async (propertyId: string, token: string): Promise<number> => {
+  const response = await axios.post(
+    `${API_URL}/api/allocations/offer-nonce`,
+    { property_id: propertyId },
+    {
+      headers: {
+        'Content-Type': 'application/json',
+        'Authorization': `Bearer ${token}`
+      },
+    }
+  );
+  return Number(response.data.reservation.value);
+};
+
 export const updateOffer = async (offerId: string, status: 'accepted' | 'rejected' | 'expired', token: string): Promise<Offer> => {
   try {
     const response = await axios.patch(
@@ -0,0 +1,16 @@
+-- Durable retry queue for DB writes that must follow a confirmed on-chain transaction
+CREATE TABLE pending_db_effects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    signature TEXT UNIQUE NOT NULL,
+    effect_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INT4 NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_pending_db_effects_status_next_attempt
+    ON pending_db_effects (status, next_attempt_at);
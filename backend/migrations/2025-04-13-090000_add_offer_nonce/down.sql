@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE offers
+DROP COLUMN offer_nonce;
@@ -0,0 +1,3 @@
+-- Offer PDAs are seeded by property, buyer and a per-negotiation nonce
+ALTER TABLE offers
+ADD COLUMN offer_nonce BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Outcomes of land-registry checks and the resulting on-chain attestations
+CREATE TABLE property_verifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    property_id TEXT NOT NULL,
+    cadastral_id TEXT NOT NULL,
+    jurisdiction_code TEXT NOT NULL,
+    adapter TEXT NOT NULL,
+    status TEXT NOT NULL,
+    details TEXT,
+    attestation_signature TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_property_verifications_property_id ON property_verifications (property_id);
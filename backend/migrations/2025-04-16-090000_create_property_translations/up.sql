@@ -0,0 +1,12 @@
+-- Per-locale listing text, filled by the translation provider or overridden by owners
+CREATE TABLE property_translations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    property_id TEXT NOT NULL,
+    locale TEXT NOT NULL,
+    location TEXT NOT NULL,
+    description TEXT,
+    source TEXT NOT NULL DEFAULT 'auto',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (property_id, locale)
+);
@@ -0,0 +1,11 @@
+-- Append-only listing history used to derive market status badges
+CREATE TABLE property_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    property_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    price BIGINT,
+    previous_price BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_property_events_property_id_created_at ON property_events (property_id, created_at);
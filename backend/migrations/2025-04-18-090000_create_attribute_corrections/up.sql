@@ -0,0 +1,21 @@
+-- Owner requests to correct verified property attributes, reviewed and co-signed by a verifier
+CREATE TABLE attribute_corrections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    property_id TEXT NOT NULL,
+    owner_wallet TEXT NOT NULL,
+    square_feet BIGINT,
+    bedrooms SMALLINT,
+    bathrooms SMALLINT,
+    reason TEXT,
+    document_uris TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    verifier_wallet TEXT,
+    review_notes TEXT,
+    serialized_transaction TEXT,
+    signature TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_attribute_corrections_property_id ON attribute_corrections (property_id);
+CREATE INDEX idx_attribute_corrections_status ON attribute_corrections (status);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE id_reservations;
+DROP SEQUENCE property_id_seq;
@@ -0,0 +1,17 @@
+-- Property ids and offer nonces handed out before the on-chain transaction is built,
+-- so parallel requests never derive the same PDA
+CREATE SEQUENCE property_id_seq START WITH 1;
+
+CREATE TABLE id_reservations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    value TEXT NOT NULL,
+    reserved_by TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'reserved',
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (kind, scope, value)
+);
+
+CREATE INDEX idx_id_reservations_reserved_by ON id_reservations (reserved_by);
@@ -0,0 +1,15 @@
+-- Audit trail of address screening decisions taken before relaying transactions
+CREATE TABLE risk_screenings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_address TEXT NOT NULL,
+    context TEXT NOT NULL,
+    addresses TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    decision TEXT NOT NULL,
+    matched_address TEXT,
+    reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_risk_screenings_wallet_address ON risk_screenings (wallet_address);
+CREATE INDEX idx_risk_screenings_decision ON risk_screenings (decision);
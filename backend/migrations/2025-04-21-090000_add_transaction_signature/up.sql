@@ -0,0 +1,3 @@
+-- Settlement signature, shown on receipts and linked to the explorer
+ALTER TABLE transactions
+ADD COLUMN signature TEXT;
@@ -0,0 +1,17 @@
+-- Prepared transactions kept server-side so they can be rebuilt when the blockhash expires
+CREATE TABLE transaction_drafts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_address TEXT NOT NULL,
+    fee_payer TEXT NOT NULL,
+    instructions TEXT NOT NULL,
+    nonce_account TEXT,
+    serialized_transaction TEXT NOT NULL,
+    blockhash TEXT NOT NULL,
+    last_valid_block_height BIGINT,
+    refresh_count INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_transaction_drafts_wallet_address ON transaction_drafts (wallet_address);
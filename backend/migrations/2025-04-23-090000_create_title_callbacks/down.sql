@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE callback_deliveries;
+DROP TABLE property_callbacks;
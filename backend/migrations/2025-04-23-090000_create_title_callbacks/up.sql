@@ -0,0 +1,30 @@
+-- Title/escrow company endpoints registered per property
+CREATE TABLE property_callbacks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    property_id TEXT NOT NULL,
+    owner_wallet TEXT NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    -- Each owner registers their own company, so a resale never reuses the seller's endpoint
+    UNIQUE (property_id, owner_wallet)
+);
+
+-- Signed deal payloads sent to those endpoints and their delivery state
+CREATE TABLE callback_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    callback_id UUID NOT NULL REFERENCES property_callbacks (id) ON DELETE CASCADE,
+    property_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMP
+);
+
+CREATE INDEX idx_callback_deliveries_due ON callback_deliveries (status, next_attempt_at);
+CREATE INDEX idx_callback_deliveries_property_id ON callback_deliveries (property_id);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE properties DROP COLUMN organization_id;
+DROP TABLE organization_members;
+DROP TABLE organizations;
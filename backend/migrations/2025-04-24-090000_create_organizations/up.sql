@@ -0,0 +1,26 @@
+-- Institutions whose properties are held on-chain by a multisig and worked on by member wallets
+CREATE TABLE organizations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    owner_wallet TEXT NOT NULL,
+    multisig_address TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE organization_members (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    wallet_address TEXT NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('lister', 'negotiator', 'viewer')),
+    status TEXT NOT NULL DEFAULT 'invited' CHECK (status IN ('invited', 'active')),
+    invited_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (organization_id, wallet_address)
+);
+
+CREATE INDEX idx_organization_members_wallet ON organization_members (wallet_address);
+
+ALTER TABLE properties ADD COLUMN organization_id UUID REFERENCES organizations (id) ON DELETE SET NULL;
+CREATE INDEX idx_properties_organization_id ON properties (organization_id);
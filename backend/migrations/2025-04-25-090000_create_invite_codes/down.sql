@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE referral_conversions;
+DROP TABLE invite_codes;
@@ -0,0 +1,27 @@
+-- Invite codes handed out by wallets or organizations to onboard new users
+CREATE TABLE invite_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code TEXT NOT NULL UNIQUE,
+    owner_wallet TEXT NOT NULL,
+    organization_id UUID REFERENCES organizations (id) ON DELETE CASCADE,
+    max_uses INTEGER,
+    use_count INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_invite_codes_owner_wallet ON invite_codes (owner_wallet);
+
+-- Conversions credited to the code a wallet signed up with, the basis for referral fees
+CREATE TABLE referral_conversions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    invite_code_id UUID NOT NULL REFERENCES invite_codes (id) ON DELETE CASCADE,
+    wallet_address TEXT NOT NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN ('signup', 'first_listing')),
+    property_id TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    -- A wallet signs up once and has one first listing
+    UNIQUE (wallet_address, event_type)
+);
+
+CREATE INDEX idx_referral_conversions_invite_code_id ON referral_conversions (invite_code_id);
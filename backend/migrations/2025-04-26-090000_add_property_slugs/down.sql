@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_properties_slug;
+ALTER TABLE properties DROP COLUMN slug;
@@ -0,0 +1,8 @@
+-- Human-readable listing URLs: <location>-<property id>, e.g. austin-tx-prop-42
+ALTER TABLE properties ADD COLUMN slug TEXT;
+
+UPDATE properties
+SET slug = trim(BOTH '-' FROM lower(regexp_replace(location || '-' || property_id, '[^a-zA-Z0-9]+', '-', 'g')));
+
+ALTER TABLE properties ALTER COLUMN slug SET NOT NULL;
+CREATE UNIQUE INDEX idx_properties_slug ON properties (slug);
@@ -0,0 +1,13 @@
+-- Every confirmed change of a wallet's payout address; the latest row is the current address
+CREATE TABLE payout_address_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_address TEXT NOT NULL,
+    payout_address TEXT NOT NULL,
+    previous_payout_address TEXT,
+    message TEXT NOT NULL,
+    wallet_signature TEXT NOT NULL UNIQUE,
+    payout_signature TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_payout_address_changes_wallet ON payout_address_changes (wallet_address, created_at);
@@ -0,0 +1,18 @@
+-- SPL Governance proposals that authorize accepting an offer on a DAO-held property
+CREATE TABLE governance_proposals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    offer_id UUID NOT NULL REFERENCES offers (id) ON DELETE CASCADE,
+    property_id TEXT NOT NULL,
+    governance TEXT NOT NULL,
+    proposal_address TEXT,
+    min_amount BIGINT NOT NULL,
+    authorization_expires_at TIMESTAMP NOT NULL,
+    status TEXT NOT NULL DEFAULT 'drafted'
+        CHECK (status IN ('drafted', 'voting', 'succeeded', 'defeated', 'cancelled', 'executed')),
+    acceptance_transaction TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_governance_proposals_offer ON governance_proposals (offer_id);
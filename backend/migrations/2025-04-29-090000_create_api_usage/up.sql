@@ -0,0 +1,9 @@
+-- Hourly request counters per authenticated wallet, written by the usage middleware
+CREATE TABLE api_usage (
+    wallet_address TEXT NOT NULL,
+    bucket_start TIMESTAMP NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    client_errors BIGINT NOT NULL DEFAULT 0,
+    server_errors BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (wallet_address, bucket_start)
+);
@@ -0,0 +1,18 @@
+-- Sale records whose on-chain TransactionHistory PDA was closed by archive_history. The archive
+-- transaction's signature is the proof; its HistoryArchived event carries the full record.
+CREATE TABLE archived_histories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    property_id TEXT NOT NULL,
+    history_address TEXT NOT NULL UNIQUE,
+    transaction_index BIGINT NOT NULL,
+    seller_wallet TEXT NOT NULL,
+    buyer_wallet TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    seller_concession BIGINT NOT NULL,
+    sold_at TIMESTAMP NOT NULL,
+    archived_at TIMESTAMP NOT NULL,
+    archive_signature TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_archived_histories_property ON archived_histories (property_id, transaction_index);
@@ -0,0 +1,21 @@
+-- Listings held back until a go-live time. The owner's listing transaction is signed against a
+-- durable nonce so it stays valid until the publisher submits it; the property row is created
+-- inactive right away and revealed once the transaction lands.
+CREATE TABLE scheduled_listings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    property_id TEXT NOT NULL,
+    wallet_address TEXT NOT NULL,
+    serialized_transaction TEXT,
+    metadata TEXT NOT NULL,
+    publish_at TIMESTAMP NOT NULL,
+    status TEXT NOT NULL DEFAULT 'scheduled',
+    signature TEXT,
+    last_error TEXT,
+    published_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_scheduled_listings_due ON scheduled_listings (status, publish_at);
+CREATE INDEX idx_scheduled_listings_wallet ON scheduled_listings (wallet_address);
+CREATE INDEX idx_scheduled_listings_property ON scheduled_listings (property_id);
@@ -0,0 +1,26 @@
+-- Two owners trading properties, optionally with cash to balance the deal. The exchange is one
+-- swap_properties transaction that both owners co-sign; each side's signature is its leg.
+CREATE TABLE swap_deals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    proposer_wallet TEXT NOT NULL,
+    proposer_property_id TEXT NOT NULL,
+    counterparty_wallet TEXT NOT NULL,
+    counterparty_property_id TEXT NOT NULL,
+    -- Lamports the proposer pays on top of their property; negative when the counterparty pays
+    cash_difference BIGINT NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'proposed',
+    proposer_leg_status TEXT NOT NULL DEFAULT 'pending',
+    proposer_leg_signature TEXT,
+    counterparty_leg_status TEXT NOT NULL DEFAULT 'pending',
+    counterparty_leg_signature TEXT,
+    serialized_transaction TEXT,
+    signature TEXT,
+    expires_at TIMESTAMP NOT NULL,
+    accepted_at TIMESTAMP,
+    completed_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_swap_deals_proposer ON swap_deals (proposer_wallet);
+CREATE INDEX idx_swap_deals_counterparty ON swap_deals (counterparty_wallet);
@@ -0,0 +1,218 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Text};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{info, error};
+
+use crate::db;
+use crate::models::IdReservation;
+use crate::schema::id_reservations;
+use crate::transaction::verify_token;
+
+pub const KIND_PROPERTY_ID: &str = "property_id";
+pub const KIND_OFFER_NONCE: &str = "offer_nonce";
+
+/// Property ids are unique marketplace-wide since they seed the property PDA
+const PROPERTY_ID_SCOPE: &str = "marketplace";
+/// Reservations not used within this window are reported as expired; values are never reissued
+const RESERVATION_TTL_MINUTES: i64 = 15;
+
+#[derive(QueryableByName)]
+struct SequenceValue {
+    #[diesel(sql_type = BigInt)]
+    value: i64,
+}
+
+fn lock_scope(conn: &mut PgConnection, key: &str) -> QueryResult<()> {
+    diesel::sql_query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind::<Text, _>(key)
+        .execute(conn)?;
+    Ok(())
+}
+
+fn insert_reservation(
+    conn: &mut PgConnection,
+    kind: &str,
+    scope: &str,
+    value: String,
+    wallet_address: &str,
+) -> QueryResult<IdReservation> {
+    let now = Utc::now().naive_utc();
+    let reservation = IdReservation {
+        id: Uuid::new_v4(),
+        kind: kind.to_string(),
+        scope: scope.to_string(),
+        value,
+        reserved_by: wallet_address.to_string(),
+        status: "reserved".to_string(),
+        expires_at: now + Duration::minutes(RESERVATION_TTL_MINUTES),
+        created_at: now,
+    };
+
+    diesel::insert_into(id_reservations::table)
+        .values(&reservation)
+        .execute(conn)?;
+    Ok(reservation)
+}
+
+/// Reserves the next free `PROP-<n>` id, skipping ids already taken by earlier listings
+pub fn reserve_property_id(conn: &mut PgConnection, wallet_address: &str) -> QueryResult<IdReservation> {
+    use crate::schema::properties::dsl::{properties, property_id};
+
+    conn.transaction(|conn| {
+        loop {
+            let next = diesel::sql_query("SELECT nextval('property_id_seq') AS value")
+                .get_result::<SequenceValue>(conn)?;
+            let candidate = format!("PROP-{}", next.value);
+
+            let taken = diesel::select(diesel::dsl::exists(
+                properties.filter(property_id.eq(&candidate)),
+            ))
+            .get_result::<bool>(conn)?;
+            if !taken {
+                return insert_reservation(conn, KIND_PROPERTY_ID, PROPERTY_ID_SCOPE, candidate, wallet_address);
+            }
+        }
+    })
+}
+
+/// Reserves the next offer nonce for a buyer on a property. The advisory lock serialises
+/// concurrent requests for the same (property, buyer) pair until the reservation commits.
+pub fn reserve_offer_nonce(
+    conn: &mut PgConnection,
+    prop_id: &str,
+    wallet_address: &str,
+) -> QueryResult<IdReservation> {
+    let scope = format!("{}:{}", prop_id, wallet_address);
+
+    conn.transaction(|conn| {
+        lock_scope(conn, &format!("{}:{}", KIND_OFFER_NONCE, scope))?;
+
+        let max_offer_nonce = {
+            use crate::schema::offers::dsl::*;
+            offers
+                .filter(property_id.eq(prop_id))
+                .filter(buyer_wallet.eq(wallet_address))
+                .select(diesel::dsl::max(offer_nonce))
+                .first::<Option<i64>>(conn)?
+        };
+
+        let reserved_values = {
+            use crate::schema::id_reservations::dsl;
+            dsl::id_reservations
+                .filter(dsl::kind.eq(KIND_OFFER_NONCE))
+                .filter(dsl::scope.eq(&scope))
+                .select(dsl::value)
+                .load::<String>(conn)?
+        };
+        let max_reserved = reserved_values.iter().filter_map(|v| v.parse::<i64>().ok()).max();
+
+        let next = max_offer_nonce.max(max_reserved).map_or(0, |n| n + 1);
+        insert_reservation(conn, KIND_OFFER_NONCE, &scope, next.to_string(), wallet_address)
+    })
+}
+
+/// Marks a reservation as used once the transaction relying on it has been recorded
+pub fn mark_consumed(conn: &mut PgConnection, reservation_kind: &str, reservation_scope: &str, reserved_value: &str) {
+    use crate::schema::id_reservations::dsl::*;
+
+    if let Err(e) = diesel::update(
+        id_reservations
+            .filter(kind.eq(reservation_kind))
+            .filter(scope.eq(reservation_scope))
+            .filter(value.eq(reserved_value)),
+    )
+    .set(status.eq("consumed"))
+    .execute(conn)
+    {
+        error!("Failed to consume {} reservation {}: {}", reservation_kind, reserved_value, e);
+    }
+}
+
+pub fn consume_property_id(conn: &mut PgConnection, prop_id: &str) {
+    mark_consumed(conn, KIND_PROPERTY_ID, PROPERTY_ID_SCOPE, prop_id);
+}
+
+pub fn consume_offer_nonce(conn: &mut PgConnection, prop_id: &str, wallet_address: &str, nonce: i64) {
+    mark_consumed(conn, KIND_OFFER_NONCE, &format!("{}:{}", prop_id, wallet_address), &nonce.to_string());
+}
+
+#[derive(Deserialize)]
+pub struct OfferNonceRequest {
+    pub property_id: String,
+}
+
+#[derive(Serialize)]
+pub struct ReservationResponse {
+    pub success: bool,
+    pub message: String,
+    pub reservation: Option<IdReservation>,
+}
+
+/// Reserves a property id for a listing transaction about to be built
+pub async fn allocate_property_id(req: HttpRequest) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    match reserve_property_id(&mut conn, &wallet_address) {
+        Ok(reservation) => {
+            info!("Reserved property id {} for {}", reservation.value, wallet_address);
+            HttpResponse::Ok().json(ReservationResponse {
+                success: true,
+                message: format!("Reserved property id {}", reservation.value),
+                reservation: Some(reservation),
+            })
+        }
+        Err(e) => {
+            error!("Failed to reserve property id: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to reserve property id: {}", e))
+        }
+    }
+}
+
+/// Reserves an offer nonce for the caller on a property
+pub async fn allocate_offer_nonce(req: HttpRequest, data: web::Json<OfferNonceRequest>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    match reserve_offer_nonce(&mut conn, &data.property_id, &wallet_address) {
+        Ok(reservation) => {
+            info!(
+                "Reserved offer nonce {} on {} for {}",
+                reservation.value, data.property_id, wallet_address
+            );
+            HttpResponse::Ok().json(ReservationResponse {
+                success: true,
+                message: format!("Reserved offer nonce {}", reservation.value),
+                reservation: Some(reservation),
+            })
+        }
+        Err(e) => {
+            error!("Failed to reserve offer nonce: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to reserve offer nonce: {}", e))
+        }
+    }
+}
@@ -86,4 +86,28 @@ pub fn store_user_jwt(wallet_address: &str, jwt: &str) -> Result<(), diesel::res
         .set(users::jwt_token.eq(jwt))
         .execute(&mut conn)?;
     Ok(())
-}
\ No newline at end of file
+}
+/// Wallet of a valid bearer JWT in the request headers, for middleware that must not reject
+/// unauthenticated requests
+pub fn bearer_wallet(headers: &actix_web::http::header::HeaderMap) -> Option<String> {
+    let token = headers
+        .get("Authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+    let secret = env::var("JWT_SECRET").ok()?;
+    jsonwebtoken::decode::<Claims>(
+        token,
+        &jsonwebtoken::DecodingKey::from_secret(secret.as_bytes()),
+        &jsonwebtoken::Validation::default(),
+    )
+    .ok()
+    .map(|data| data.claims.sub)
+}
+
+/// Returns true if the wallet is listed in the comma separated `ADMIN_WALLETS` variable
+pub fn is_admin_wallet(wallet_address: &str) -> bool {
+    env::var("ADMIN_WALLETS")
+        .map(|wallets| wallets.split(',').any(|w| w.trim() == wallet_address))
+        .unwrap_or(false)
+}
@@ -0,0 +1,293 @@
+use actix_web::{web, HttpResponse, Responder};
+use base64::{engine::general_purpose, Engine};
+use diesel::prelude::*;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+use crate::db;
+use crate::history_archive;
+use crate::models::{ArchivedHistory, Offer};
+use crate::schema::offers;
+use crate::transaction::{get_marketplace_info, get_property_pubkey, PROGRAM_ID};
+
+/// RFC 7234 warning attached to responses served from an expired cache entry
+const STALE_WARNING: &str = "110 - \"Response is Stale\"";
+
+#[derive(Clone)]
+struct CachedAccount {
+    account: AccountSnapshot,
+    fetched_at: Instant,
+}
+
+#[derive(Clone, Serialize)]
+pub struct AccountSnapshot {
+    pub lamports: u64,
+    pub owner: String,
+    /// Raw account data, base64 encoded, for clients to decode with the program IDL
+    pub data: String,
+}
+
+#[derive(Serialize)]
+pub struct ChainAccountResponse {
+    pub address: String,
+    pub account: AccountSnapshot,
+    pub stale: bool,
+    pub age_secs: u64,
+}
+
+/// How long reads are served from cache, how long a cached read may still be served while the RPC is down,
+/// and how many accounts are kept
+struct CacheConfig {
+    ttl: Duration,
+    max_stale: Duration,
+    max_entries: usize,
+}
+
+impl CacheConfig {
+    fn from_env() -> Self {
+        let read = |key: &str, default: u64| {
+            env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        CacheConfig {
+            ttl: Duration::from_secs(read("CHAIN_CACHE_TTL_SECS", 10)),
+            max_stale: Duration::from_secs(read("CHAIN_CACHE_MAX_STALE_SECS", 3600)),
+            max_entries: read("CHAIN_CACHE_MAX_ENTRIES", 10_000) as usize,
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<Pubkey, CachedAccount>> {
+    static CACHE: OnceLock<Mutex<HashMap<Pubkey, CachedAccount>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached(address: &Pubkey) -> Option<CachedAccount> {
+    cache().lock().ok()?.get(address).cloned()
+}
+
+/// Caches a read, evicting the oldest entry once the cache is full. A missing account is not
+/// cached, so lookups of arbitrary addresses can't grow the cache; it also drops any older copy.
+fn store(address: Pubkey, account: Option<AccountSnapshot>, max_entries: usize) {
+    let mut entries = match cache().lock() {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let account = match account {
+        Some(account) => account,
+        None => {
+            entries.remove(&address);
+            return;
+        }
+    };
+    if !entries.contains_key(&address) && entries.len() >= max_entries {
+        let oldest = entries.iter().min_by_key(|(_, entry)| entry.fetched_at).map(|(key, _)| *key);
+        if let Some(oldest) = oldest {
+            entries.remove(&oldest);
+        }
+    }
+    if max_entries > 0 {
+        entries.insert(address, CachedAccount { account, fetched_at: Instant::now() });
+    }
+}
+
+/// A sale record whose PDA has been closed, served from the archive
+#[derive(Serialize)]
+pub struct ArchivedHistoryResponse {
+    pub address: String,
+    pub archived: bool,
+    /// `archive_signature` is the transaction whose HistoryArchived event carried this record
+    pub record: ArchivedHistory,
+}
+
+/// Result of a cached read; `stale` is set when the RPC failed and an older copy was served
+pub struct CachedRead {
+    pub account: Option<AccountSnapshot>,
+    pub stale: bool,
+    pub age: Duration,
+}
+
+/// Reads an account through the cache. Fresh entries skip the RPC; if the RPC fails, the last good
+/// read is served as stale for up to CHAIN_CACHE_MAX_STALE_SECS.
+pub fn read_account(address: &Pubkey) -> Result<CachedRead, anyhow::Error> {
+    let config = CacheConfig::from_env();
+    let previous = cached(address);
+
+    if let Some(entry) = previous.as_ref().filter(|e| e.fetched_at.elapsed() < config.ttl) {
+        return Ok(CachedRead {
+            account: Some(entry.account.clone()),
+            stale: false,
+            age: entry.fetched_at.elapsed(),
+        });
+    }
+
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+    match rpc_client.get_account_with_commitment(address, rpc_client.commitment()) {
+        Ok(response) => {
+            let account = response.value.map(|account| AccountSnapshot {
+                lamports: account.lamports,
+                owner: account.owner.to_string(),
+                data: general_purpose::STANDARD.encode(&account.data),
+            });
+            store(*address, account.clone(), config.max_entries);
+            Ok(CachedRead { account, stale: false, age: Duration::ZERO })
+        }
+        Err(e) => match previous.filter(|entry| entry.fetched_at.elapsed() < config.max_stale) {
+            Some(entry) => {
+                warn!("RPC read of {} failed, serving cached copy: {}", address, e);
+                Ok(CachedRead {
+                    account: Some(entry.account),
+                    stale: true,
+                    age: entry.fetched_at.elapsed(),
+                })
+            }
+            None => Err(e.into()),
+        },
+    }
+}
+
+async fn account_response(address: Pubkey) -> HttpResponse {
+    let read = match web::block(move || read_account(&address)).await {
+        Ok(Ok(read)) => read,
+        Ok(Err(e)) => {
+            error!("Failed to read account {}: {}", address, e);
+            return HttpResponse::ServiceUnavailable().body(format!("Failed to read account: {}", e));
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    };
+
+    let account = match read.account {
+        Some(account) => account,
+        None => return HttpResponse::NotFound().body("Account not found on chain"),
+    };
+
+    let mut response = HttpResponse::Ok();
+    if read.stale {
+        response.insert_header(("Warning", STALE_WARNING));
+    }
+    response.json(ChainAccountResponse {
+        address: address.to_string(),
+        account,
+        stale: read.stale,
+        age_secs: read.age.as_secs(),
+    })
+}
+
+fn program_id() -> Pubkey {
+    Pubkey::from_str(PROGRAM_ID).expect("PROGRAM_ID is a valid pubkey")
+}
+
+/// The marketplace account
+pub async fn get_marketplace_account() -> impl Responder {
+    match get_marketplace_info(&program_id()) {
+        Ok((marketplace_pda, _)) => account_response(marketplace_pda).await,
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to derive marketplace: {}", e)),
+    }
+}
+
+/// A property's on-chain account
+pub async fn get_property_account(path: web::Path<String>) -> impl Responder {
+    match get_property_pubkey(&path.into_inner(), &program_id()) {
+        Ok(property_pda) => account_response(property_pda).await,
+        Err(e) => HttpResponse::BadRequest().body(format!("Failed to derive property: {}", e)),
+    }
+}
+
+/// The escrow holding an offer's funds; its lamports are the escrowed balance plus rent
+pub async fn get_offer_escrow_account(path: web::Path<String>) -> impl Responder {
+    let offer_uuid = match Uuid::parse_str(&path.into_inner()) {
+        Ok(offer_uuid) => offer_uuid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid offer id"),
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let offer = match offers::table.find(offer_uuid).first::<Offer>(&mut conn) {
+        Ok(offer) => offer,
+        Err(diesel::result::Error::NotFound) => return HttpResponse::NotFound().body("Offer not found"),
+        Err(e) => {
+            error!("Failed to fetch offer: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to fetch offer: {}", e));
+        }
+    };
+
+    let program_id = program_id();
+    let (property_pda, buyer) = match (
+        get_property_pubkey(&offer.property_id, &program_id),
+        Pubkey::from_str(&offer.buyer_wallet),
+    ) {
+        (Ok(property_pda), Ok(buyer)) => (property_pda, buyer),
+        _ => return HttpResponse::InternalServerError().body("Failed to derive offer accounts"),
+    };
+    let (offer_pda, _) = Pubkey::find_program_address(
+        &[
+            b"offer",
+            property_pda.as_ref(),
+            buyer.as_ref(),
+            &(offer.offer_nonce as u64).to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let (escrow_pda, _) = Pubkey::find_program_address(&[b"escrow", offer_pda.as_ref()], &program_id);
+
+    info!("Reading escrow {} for offer {}", escrow_pda, offer_uuid);
+    account_response(escrow_pda).await
+}
+
+/// A property's sale record by index. Records closed by archive_history are served from the
+/// archive, with the archive transaction's signature as proof.
+pub async fn get_property_history_account(path: web::Path<(String, u64)>) -> impl Responder {
+    let (property_id, index) = path.into_inner();
+    let program_id = program_id();
+    let property_pda = match get_property_pubkey(&property_id, &program_id) {
+        Ok(property_pda) => property_pda,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Failed to derive property: {}", e)),
+    };
+    let (history_pda, _) = Pubkey::find_program_address(
+        &[b"transaction", property_pda.as_ref(), &index.to_le_bytes()],
+        &program_id,
+    );
+
+    match web::block(move || read_account(&history_pda)).await {
+        Ok(Ok(read)) if read.account.is_some() => return account_response(history_pda).await,
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => {
+            error!("Failed to read account {}: {}", history_pda, e);
+            return HttpResponse::ServiceUnavailable().body(format!("Failed to read account: {}", e));
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    }
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+    match history_archive::find_archived(&mut conn, &history_pda.to_string()) {
+        Ok(Some(record)) => HttpResponse::Ok().json(ArchivedHistoryResponse {
+            address: history_pda.to_string(),
+            archived: true,
+            record,
+        }),
+        Ok(None) => HttpResponse::NotFound().body("Account not found on chain"),
+        Err(e) => {
+            error!("Failed to fetch archived history: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch archived history: {}", e))
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, NaiveDateTime};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, error};
+use uuid::Uuid;
+
+use crate::db;
+use crate::scheduled_listing::UNPUBLISHED_STATUSES;
+use crate::schema::{properties, scheduled_listings};
+
+const PAGE_SIZE: i64 = 500;
+
+#[derive(Deserialize)]
+pub struct ChangesQuery {
+    /// `next_cursor` from the previous call; omitted to start from the first listing
+    pub since: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PropertyChange {
+    pub property_id: String,
+    pub slug: String,
+    pub is_active: bool,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct ChangesResponse {
+    pub changes: Vec<PropertyChange>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// Cursors are `<updated_at micros>_<row id>`, so rows sharing a timestamp are neither skipped nor repeated
+fn encode_cursor(updated_at: NaiveDateTime, id: Uuid) -> String {
+    format!("{}_{}", updated_at.and_utc().timestamp_micros(), id)
+}
+
+fn decode_cursor(cursor: &str) -> Option<(NaiveDateTime, Uuid)> {
+    let (micros, id) = cursor.split_once('_')?;
+    let updated_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc();
+    Some((updated_at, Uuid::parse_str(id).ok()?))
+}
+
+/// Listings created, edited, sold or delisted since a cursor, oldest first, so the frontend can
+/// revalidate just those pages. Delisted entries have `is_active: false`.
+pub async fn get_changes(query: web::Query<ChangesQuery>) -> impl Responder {
+    let since = match query.since.as_deref().map(decode_cursor) {
+        Some(None) => return HttpResponse::BadRequest().body("Invalid cursor"),
+        Some(since) => since,
+        None => None,
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let mut changed = properties::table
+        .select((
+            properties::id,
+            properties::property_id,
+            properties::slug,
+            properties::is_active,
+            properties::updated_at,
+        ))
+        // Listings waiting on their scheduled publication stay out of the feed until they go live
+        .filter(diesel::dsl::not(
+            properties::property_id.eq_any(
+                scheduled_listings::table
+                    .select(scheduled_listings::property_id)
+                    .filter(scheduled_listings::status.eq_any(UNPUBLISHED_STATUSES)),
+            ),
+        ))
+        .order_by((properties::updated_at.asc(), properties::id.asc()))
+        .limit(PAGE_SIZE + 1)
+        .into_boxed();
+    if let Some((updated_at, id)) = since {
+        changed = changed.filter(
+            properties::updated_at
+                .gt(updated_at)
+                .or(properties::updated_at.eq(updated_at).and(properties::id.gt(id))),
+        );
+    }
+
+    match changed.load::<(Uuid, String, String, bool, NaiveDateTime)>(&mut conn) {
+        Ok(mut rows) => {
+            let has_more = rows.len() as i64 > PAGE_SIZE;
+            rows.truncate(PAGE_SIZE as usize);
+
+            // With nothing new the caller keeps polling from where it was
+            let next_cursor = rows
+                .last()
+                .map(|(id, _, _, _, updated_at)| encode_cursor(*updated_at, *id))
+                .or_else(|| query.since.clone());
+            info!("Returning {} property changes", rows.len());
+
+            HttpResponse::Ok().json(ChangesResponse {
+                changes: rows
+                    .into_iter()
+                    .map(|(_, property_id, slug, is_active, updated_at)| PropertyChange {
+                        property_id,
+                        slug,
+                        is_active,
+                        updated_at,
+                    })
+                    .collect(),
+                next_cursor,
+                has_more,
+            })
+        }
+        Err(e) => {
+            error!("Failed to fetch property changes: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch property changes: {}", e))
+        }
+    }
+}
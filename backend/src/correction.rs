@@ -0,0 +1,649 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use base64::{engine::general_purpose, Engine};
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_program,
+    transaction::Transaction as SolanaTransaction,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+use tracing::{info, error};
+
+use crate::db;
+use crate::models::AttributeCorrection;
+use crate::organization::{self, Permission};
+use crate::retry_queue::{self, DbEffect};
+use crate::risk;
+use crate::schema::attribute_corrections;
+use crate::transaction::{get_marketplace_info, get_property_pubkey, instruction_discriminator, verify_token, PROGRAM_ID};
+
+// Correction request lifecycle
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_REJECTED: &str = "rejected";
+pub const STATUS_APPROVED: &str = "approved";
+pub const STATUS_AWAITING_SIGNATURES: &str = "awaiting_signatures";
+pub const STATUS_APPLIED: &str = "applied";
+pub const STATUS_FAILED: &str = "failed";
+
+#[derive(Deserialize)]
+pub struct CreateCorrectionRequest {
+    pub square_feet: Option<i64>,
+    pub bedrooms: Option<i16>,
+    pub bathrooms: Option<i16>,
+    pub reason: Option<String>,
+    pub document_uris: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewCorrectionRequest {
+    pub approve: bool,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SignCorrectionRequest {
+    /// Base58 signature of the prepared transaction message
+    pub signature: String,
+}
+
+#[derive(Deserialize)]
+pub struct CorrectionsQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CorrectionResponse {
+    pub success: bool,
+    pub message: String,
+    pub correction: Option<AttributeCorrection>,
+    pub missing_signers: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct CorrectionsResponse {
+    pub success: bool,
+    pub message: String,
+    pub corrections: Vec<AttributeCorrection>,
+}
+
+fn verifier_pda(verifier: &Pubkey, program_id: &Pubkey) -> Result<Pubkey, anyhow::Error> {
+    let (marketplace_pda, _) = get_marketplace_info(program_id)?;
+    let (pda, _) = Pubkey::find_program_address(
+        &[b"verifier", marketplace_pda.as_ref(), verifier.as_ref()],
+        program_id,
+    );
+    Ok(pda)
+}
+
+/// A wallet counts as a verifier when its on-chain `Verifier` account exists
+fn is_registered_verifier(wallet_address: &str) -> Result<bool, anyhow::Error> {
+    let program_id = Pubkey::from_str(PROGRAM_ID)?;
+    let verifier = Pubkey::from_str(wallet_address)?;
+    let pda = verifier_pda(&verifier, &program_id)?;
+
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+    Ok(rpc_client.get_account(&pda).is_ok())
+}
+
+/// Reads `attribute_change_count` from the on-chain `Property`, which seeds the next audit PDA
+fn fetch_attribute_change_count(rpc_client: &RpcClient, property_pda: &Pubkey) -> Result<u64, anyhow::Error> {
+    let data = rpc_client.get_account_data(property_pda)?;
+    let mut offset = 8 + 32 + 32; // discriminator, marketplace, owner
+
+    let take = |offset: &mut usize, len: usize| -> Result<&[u8], anyhow::Error> {
+        let slice = data
+            .get(*offset..*offset + len)
+            .ok_or_else(|| anyhow::anyhow!("Property account data is truncated"))?;
+        *offset += len;
+        Ok(slice)
+    };
+    let skip_string = |offset: &mut usize| -> Result<(), anyhow::Error> {
+        let len = u32::from_le_bytes(take(offset, 4)?.try_into()?) as usize;
+        take(offset, len)?;
+        Ok(())
+    };
+
+    skip_string(&mut offset)?; // property_id
+    take(&mut offset, 8)?; // price
+    skip_string(&mut offset)?; // metadata_uri
+    skip_string(&mut offset)?; // location
+    // square_feet, bedrooms, bathrooms, is_active, created_at, updated_at, transaction_count,
+    // nft_mint, min_offer_bps, cadastral_id, jurisdiction_code, registry_verified,
+    // registry_verifier, registry_verified_at, auto_accept
+    take(&mut offset, 8 + 1 + 1 + 1 + 8 + 8 + 8 + 32 + 2 + 32 + 8 + 1 + 32 + 8 + 1)?;
+    if take(&mut offset, 1)?[0] == 1 {
+        take(&mut offset, 8)?; // expires_at
+    }
+    take(&mut offset, 1)?; // price_currency
+
+    Ok(u64::from_le_bytes(take(&mut offset, 8)?.try_into()?))
+}
+
+fn encode_option<T: Copy>(data: &mut Vec<u8>, value: Option<T>, to_bytes: fn(T) -> Vec<u8>) {
+    match value {
+        Some(v) => {
+            data.push(1);
+            data.extend(to_bytes(v));
+        }
+        None => data.push(0),
+    }
+}
+
+/// Builds the unsigned `update_property_attributes` transaction for the owner and verifier to co-sign
+fn build_update_transaction(correction: &AttributeCorrection, verifier_wallet: &str) -> Result<SolanaTransaction, anyhow::Error> {
+    let program_id = Pubkey::from_str(PROGRAM_ID)?;
+    let owner = Pubkey::from_str(&correction.owner_wallet)?;
+    let verifier = Pubkey::from_str(verifier_wallet)?;
+    let property_pda = get_property_pubkey(&correction.property_id, &program_id)?;
+
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+    let change_count = fetch_attribute_change_count(&rpc_client, &property_pda)?;
+    let (attribute_change_pda, _) = Pubkey::find_program_address(
+        &[b"attribute_change", property_pda.as_ref(), &change_count.to_le_bytes()],
+        &program_id,
+    );
+
+    let mut data = instruction_discriminator("update_property_attributes").to_vec();
+    encode_option(&mut data, correction.square_feet.map(|v| v as u64), |v| v.to_le_bytes().to_vec());
+    encode_option(&mut data, correction.bedrooms.map(|v| v as u8), |v| vec![v]);
+    encode_option(&mut data, correction.bathrooms.map(|v| v as u8), |v| vec![v]);
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(property_pda, false),
+            AccountMeta::new(attribute_change_pda, false),
+            AccountMeta::new_readonly(verifier_pda(&verifier, &program_id)?, false),
+            AccountMeta::new(owner, true),
+            AccountMeta::new_readonly(verifier, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    };
+
+    let mut tx = SolanaTransaction::new_unsigned(Message::new(&[instruction], Some(&owner)));
+    tx.message.recent_blockhash = rpc_client.get_latest_blockhash()?;
+    Ok(tx)
+}
+
+fn decode_transaction(serialized: &str) -> Result<SolanaTransaction, anyhow::Error> {
+    let bytes = general_purpose::STANDARD.decode(serialized)?;
+    Ok(bincode::deserialize::<SolanaTransaction>(&bytes)?)
+}
+
+fn encode_transaction(tx: &SolanaTransaction) -> Result<String, anyhow::Error> {
+    Ok(general_purpose::STANDARD.encode(bincode::serialize(tx)?))
+}
+
+fn missing_signers(tx: &SolanaTransaction) -> Vec<String> {
+    let required = tx.message.header.num_required_signatures as usize;
+    tx.message.account_keys[..required]
+        .iter()
+        .zip(tx.signatures.iter())
+        .filter(|(_, sig)| **sig == Signature::default())
+        .map(|(key, _)| key.to_string())
+        .collect()
+}
+
+fn load_correction(conn: &mut PgConnection, correction_id: &str) -> Result<AttributeCorrection, HttpResponse> {
+    let correction_uuid = Uuid::parse_str(correction_id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid correction ID format"))?;
+
+    attribute_corrections::table
+        .find(correction_uuid)
+        .first::<AttributeCorrection>(conn)
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => HttpResponse::NotFound().body("Correction request not found"),
+            e => {
+                error!("Failed to fetch correction request: {}", e);
+                HttpResponse::InternalServerError().body(format!("Failed to fetch correction request: {}", e))
+            }
+        })
+}
+
+fn correction_response(message: String, correction: AttributeCorrection) -> HttpResponse {
+    let missing = correction
+        .serialized_transaction
+        .as_deref()
+        .and_then(|serialized| decode_transaction(serialized).ok())
+        .map(|tx| missing_signers(&tx))
+        .unwrap_or_default();
+
+    HttpResponse::Ok().json(CorrectionResponse {
+        success: true,
+        message,
+        correction: Some(correction),
+        missing_signers: missing,
+    })
+}
+
+/// Lets the owner request a correction of square footage, bedrooms or bathrooms
+pub async fn create_correction(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<CreateCorrectionRequest>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let property_id_param = path.into_inner();
+
+    if data.square_feet.is_none() && data.bedrooms.is_none() && data.bathrooms.is_none() {
+        return HttpResponse::BadRequest().body("At least one attribute must be corrected");
+    }
+    if data.square_feet.is_some_and(|v| v <= 0)
+        || data.bedrooms.is_some_and(|v| !(0..=u8::MAX as i16).contains(&v))
+        || data.bathrooms.is_some_and(|v| !(0..=u8::MAX as i16).contains(&v))
+    {
+        return HttpResponse::BadRequest().body("Attribute values are out of range");
+    }
+    if data.document_uris.is_empty() {
+        return HttpResponse::BadRequest().body("At least one supporting document is required");
+    }
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    // Corrections are signed by the on-chain owner, which is the multisig for org properties
+    let owner = match organization::require_property_permission(
+        &mut conn,
+        &property_id_param,
+        &wallet_address,
+        Permission::List,
+        "Only the property owner can request corrections",
+    ) {
+        Ok(owner) => owner,
+        Err(resp) => return resp,
+    };
+
+    let document_uris = match serde_json::to_string(&data.document_uris) {
+        Ok(uris) => uris,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid document list: {}", e)),
+    };
+
+    let now = Utc::now().naive_utc();
+    let correction = AttributeCorrection {
+        id: Uuid::new_v4(),
+        property_id: property_id_param.clone(),
+        owner_wallet: owner,
+        square_feet: data.square_feet,
+        bedrooms: data.bedrooms,
+        bathrooms: data.bathrooms,
+        reason: data.reason.clone(),
+        document_uris,
+        status: STATUS_PENDING.to_string(),
+        verifier_wallet: None,
+        review_notes: None,
+        serialized_transaction: None,
+        signature: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    match diesel::insert_into(attribute_corrections::table)
+        .values(&correction)
+        .execute(&mut conn)
+    {
+        Ok(_) => {
+            info!("Correction {} requested for property {}", correction.id, property_id_param);
+            correction_response("Correction request submitted".to_string(), correction)
+        }
+        Err(e) => {
+            error!("Failed to create correction request: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to create correction request: {}", e))
+        }
+    }
+}
+
+/// Lists correction requests for a property
+pub async fn get_property_corrections(path: web::Path<String>) -> impl Responder {
+    let property_id_param = path.into_inner();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    use crate::schema::attribute_corrections::dsl::*;
+
+    match attribute_corrections
+        .filter(property_id.eq(&property_id_param))
+        .order_by(created_at.desc())
+        .load::<AttributeCorrection>(&mut conn)
+    {
+        Ok(corrections) => HttpResponse::Ok().json(CorrectionsResponse {
+            success: true,
+            message: format!("Successfully retrieved {} correction requests", corrections.len()),
+            corrections,
+        }),
+        Err(e) => {
+            error!("Failed to fetch correction requests: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch correction requests: {}", e))
+        }
+    }
+}
+
+/// Review queue for registered verifiers, pending requests by default
+pub async fn get_corrections(req: HttpRequest, query: web::Query<CorrectionsQuery>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let wallet = wallet_address.clone();
+    match web::block(move || is_registered_verifier(&wallet)).await {
+        Ok(Ok(true)) => {}
+        Ok(Ok(false)) => return HttpResponse::Forbidden().body("Only registered verifiers can review corrections"),
+        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Failed to check verifier: {}", e)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    }
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    use crate::schema::attribute_corrections::dsl::*;
+
+    let filter_status = query.status.clone().unwrap_or_else(|| STATUS_PENDING.to_string());
+    match attribute_corrections
+        .filter(status.eq(filter_status))
+        .order_by(created_at.asc())
+        .load::<AttributeCorrection>(&mut conn)
+    {
+        Ok(corrections) => HttpResponse::Ok().json(CorrectionsResponse {
+            success: true,
+            message: format!("Found {} correction requests", corrections.len()),
+            corrections,
+        }),
+        Err(e) => {
+            error!("Failed to fetch correction requests: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch correction requests: {}", e))
+        }
+    }
+}
+
+/// Approves or rejects a pending request; the approving verifier becomes the co-signer
+pub async fn review_correction(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<ReviewCorrectionRequest>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let wallet = wallet_address.clone();
+    match web::block(move || is_registered_verifier(&wallet)).await {
+        Ok(Ok(true)) => {}
+        Ok(Ok(false)) => return HttpResponse::Forbidden().body("Only registered verifiers can review corrections"),
+        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Failed to check verifier: {}", e)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    }
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let correction = match load_correction(&mut conn, &path.into_inner()) {
+        Ok(correction) => correction,
+        Err(resp) => return resp,
+    };
+
+    if correction.status != STATUS_PENDING {
+        return HttpResponse::BadRequest().body(format!("Correction request is already {}", correction.status));
+    }
+    if correction.owner_wallet == wallet_address {
+        return HttpResponse::Forbidden().body("Owners cannot review their own correction requests");
+    }
+
+    let new_status = if data.approve { STATUS_APPROVED } else { STATUS_REJECTED };
+
+    use crate::schema::attribute_corrections::dsl::*;
+
+    match diesel::update(attribute_corrections.find(correction.id))
+        .set((
+            status.eq(new_status),
+            verifier_wallet.eq(Some(&wallet_address)),
+            review_notes.eq(&data.notes),
+            updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result::<AttributeCorrection>(&mut conn)
+    {
+        Ok(updated) => {
+            info!("Correction {} {} by verifier {}", updated.id, new_status, wallet_address);
+            correction_response(format!("Correction request {}", new_status), updated)
+        }
+        Err(e) => {
+            error!("Failed to review correction request: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to review correction request: {}", e))
+        }
+    }
+}
+
+/// Assembles the co-signed update transaction with a fresh blockhash, discarding earlier signatures
+pub async fn prepare_correction_transaction(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let correction = match load_correction(&mut conn, &path.into_inner()) {
+        Ok(correction) => correction,
+        Err(resp) => return resp,
+    };
+
+    if correction.status != STATUS_APPROVED && correction.status != STATUS_AWAITING_SIGNATURES {
+        return HttpResponse::BadRequest().body(format!("Correction request is {}", correction.status));
+    }
+
+    let verifier = correction.verifier_wallet.clone().unwrap_or_default();
+    if wallet_address != correction.owner_wallet && wallet_address != verifier {
+        if let Err(resp) = organization::require_property_permission(
+            &mut conn,
+            &correction.property_id,
+            &wallet_address,
+            Permission::List,
+            "Only the owner or reviewing verifier can prepare the transaction",
+        ) {
+            return resp;
+        }
+    }
+
+    let correction_id = correction.id;
+    let serialized = match web::block(move || {
+        let tx = build_update_transaction(&correction, &verifier)?;
+        encode_transaction(&tx)
+    })
+    .await
+    {
+        Ok(Ok(serialized)) => serialized,
+        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Failed to build transaction: {}", e)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    };
+
+    use crate::schema::attribute_corrections::dsl::*;
+
+    match diesel::update(attribute_corrections.find(correction_id))
+        .set((
+            status.eq(STATUS_AWAITING_SIGNATURES),
+            serialized_transaction.eq(Some(serialized)),
+            updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result::<AttributeCorrection>(&mut conn)
+    {
+        Ok(updated) => correction_response("Transaction prepared for signing".to_string(), updated),
+        Err(e) => {
+            error!("Failed to store prepared transaction: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to store prepared transaction: {}", e))
+        }
+    }
+}
+
+/// Adds the caller's signature and submits the transaction once both owner and verifier have signed
+pub async fn sign_correction_transaction(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<SignCorrectionRequest>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let signer = match Pubkey::from_str(&wallet_address) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid wallet address"),
+    };
+
+    let signer_signature = match Signature::from_str(&data.signature) {
+        Ok(sig) => sig,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid signature"),
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let correction = match load_correction(&mut conn, &path.into_inner()) {
+        Ok(correction) => correction,
+        Err(resp) => return resp,
+    };
+
+    if correction.status != STATUS_AWAITING_SIGNATURES {
+        return HttpResponse::BadRequest().body(format!("Correction request is {}", correction.status));
+    }
+
+    let mut tx = match correction.serialized_transaction.as_deref().map(decode_transaction) {
+        Some(Ok(tx)) => tx,
+        Some(Err(e)) => return HttpResponse::InternalServerError().body(format!("Stored transaction is invalid: {}", e)),
+        None => return HttpResponse::BadRequest().body("Transaction has not been prepared"),
+    };
+
+    let position = match tx.get_signing_keypair_positions(&[signer]) {
+        Ok(positions) => positions.into_iter().next().flatten(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Stored transaction is invalid: {}", e)),
+    };
+    let position = match position {
+        Some(position) => position,
+        None => return HttpResponse::Forbidden().body("Wallet is not a required signer of this transaction"),
+    };
+
+    if !signer_signature.verify(signer.as_ref(), &tx.message_data()) {
+        return HttpResponse::BadRequest().body("Signature does not match the prepared transaction");
+    }
+    tx.signatures[position] = signer_signature;
+
+    use crate::schema::attribute_corrections::dsl::*;
+
+    if !missing_signers(&tx).is_empty() {
+        let serialized = match encode_transaction(&tx) {
+            Ok(serialized) => serialized,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to store signature: {}", e)),
+        };
+        return match diesel::update(attribute_corrections.find(correction.id))
+            .set((
+                serialized_transaction.eq(Some(serialized)),
+                updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<AttributeCorrection>(&mut conn)
+        {
+            Ok(updated) => correction_response("Signature recorded".to_string(), updated),
+            Err(e) => {
+                error!("Failed to store signature: {}", e);
+                HttpResponse::InternalServerError().body(format!("Failed to store signature: {}", e))
+            }
+        };
+    }
+
+    if let Err(resp) = risk::check_relay(&wallet_address, "correction", risk::message_addresses(&tx.message)).await {
+        return resp;
+    }
+
+    let result = web::block(move || {
+        let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+        rpc_client.send_and_confirm_transaction(&tx).map_err(Box::new)
+    })
+    .await;
+
+    let tx_signature = match result {
+        Ok(Ok(sig)) => sig.to_string(),
+        Ok(Err(e)) => {
+            error!("Correction {} transaction failed: {}", correction.id, e);
+            let _ = diesel::update(attribute_corrections.find(correction.id))
+                .set((status.eq(STATUS_FAILED), updated_at.eq(Utc::now().naive_utc())))
+                .execute(&mut conn);
+            return HttpResponse::InternalServerError().body(format!("Transaction failed: {}", e));
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    };
+
+    if let Err(e) = retry_queue::apply_or_enqueue(
+        &mut conn,
+        &tx_signature,
+        DbEffect::UpdatePropertyAttributes {
+            property_id: correction.property_id.clone(),
+            square_feet: correction.square_feet,
+            bedrooms: correction.bedrooms,
+            bathrooms: correction.bathrooms,
+        },
+    ) {
+        error!("Failed to persist attribute update for {}: {}", correction.property_id, e);
+    }
+
+    match diesel::update(attribute_corrections.find(correction.id))
+        .set((
+            status.eq(STATUS_APPLIED),
+            signature.eq(Some(&tx_signature)),
+            updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result::<AttributeCorrection>(&mut conn)
+    {
+        Ok(updated) => {
+            info!("Correction {} applied in transaction {}", updated.id, tx_signature);
+            correction_response(format!("Correction applied: {}", tx_signature), updated)
+        }
+        Err(e) => {
+            error!("Failed to mark correction as applied: {}", e);
+            HttpResponse::InternalServerError().body(format!("Correction applied but status update failed: {}", e))
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use base64::{engine::general_purpose, Engine};
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_client::{nonce_utils, rpc_client::RpcClient};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    system_instruction,
+    transaction::Transaction as SolanaTransaction,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+use tracing::{info, error};
+
+use crate::db;
+use crate::models::TransactionDraft;
+use crate::schema::transaction_drafts;
+use crate::transaction::{parse_instructions, verify_token, SerializedInstruction};
+
+/// Drafts can be refreshed for this long after they were first prepared
+const DRAFT_TTL_HOURS: i64 = 24;
+
+#[derive(Deserialize)]
+pub struct CreateDraftRequest {
+    pub instructions: Vec<SerializedInstruction>,
+    /// Defaults to the caller's wallet
+    pub fee_payer: Option<String>,
+    /// Durable nonce account whose authority is the fee payer; the draft then never expires on-chain
+    pub nonce_account: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DraftResponse {
+    pub success: bool,
+    pub message: String,
+    pub draft: Option<TransactionDraft>,
+}
+
+struct BuiltTransaction {
+    serialized: String,
+    blockhash: Hash,
+    last_valid_block_height: Option<u64>,
+}
+
+/// Builds an unsigned transaction from the original instructions. With a durable nonce the
+/// advance instruction is prepended and the nonce value stands in for the blockhash.
+fn build_transaction(
+    instructions: &[Instruction],
+    fee_payer: &Pubkey,
+    nonce_account: Option<&Pubkey>,
+) -> Result<BuiltTransaction, anyhow::Error> {
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+
+    let (instructions, blockhash, last_valid_block_height) = match nonce_account {
+        Some(nonce_pubkey) => {
+            let account = rpc_client.get_account(nonce_pubkey)?;
+            let nonce_data = nonce_utils::data_from_account(&account)?;
+            if nonce_data.authority != *fee_payer {
+                return Err(anyhow::anyhow!("Nonce authority must be the fee payer"));
+            }
+
+            let mut with_advance = vec![system_instruction::advance_nonce_account(nonce_pubkey, fee_payer)];
+            with_advance.extend_from_slice(instructions);
+            (with_advance, nonce_data.blockhash(), None)
+        }
+        None => {
+            let (blockhash, last_valid_block_height) =
+                rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment())?;
+            (instructions.to_vec(), blockhash, Some(last_valid_block_height))
+        }
+    };
+
+    let message = Message::new_with_blockhash(&instructions, Some(fee_payer), &blockhash);
+    let tx = SolanaTransaction::new_unsigned(message);
+    Ok(BuiltTransaction {
+        serialized: general_purpose::STANDARD.encode(bincode::serialize(&tx)?),
+        blockhash,
+        last_valid_block_height,
+    })
+}
+
+fn load_draft(conn: &mut PgConnection, draft_id: &str, wallet: &str) -> Result<TransactionDraft, HttpResponse> {
+    let draft_uuid = Uuid::parse_str(draft_id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid draft ID format"))?;
+
+    let draft = transaction_drafts::table
+        .find(draft_uuid)
+        .first::<TransactionDraft>(conn)
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => HttpResponse::NotFound().body("Draft not found"),
+            e => {
+                error!("Failed to fetch draft: {}", e);
+                HttpResponse::InternalServerError().body(format!("Failed to fetch draft: {}", e))
+            }
+        })?;
+
+    if draft.wallet_address != wallet {
+        return Err(HttpResponse::Forbidden().body("Draft belongs to another wallet"));
+    }
+    Ok(draft)
+}
+
+/// Stores the instructions of a transaction about to be signed and returns it built with a fresh blockhash
+pub async fn create_draft(req: HttpRequest, data: web::Json<CreateDraftRequest>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let data = data.into_inner();
+    if data.instructions.is_empty() {
+        return HttpResponse::BadRequest().body("At least one instruction is required");
+    }
+    let instructions = match parse_instructions(&data.instructions) {
+        Ok(instructions) => instructions,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+
+    let fee_payer_address = data.fee_payer.unwrap_or_else(|| wallet_address.clone());
+    let fee_payer = match Pubkey::from_str(&fee_payer_address) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid fee payer"),
+    };
+    let nonce_account = match data.nonce_account.as_deref().map(Pubkey::from_str).transpose() {
+        Ok(nonce) => nonce,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid nonce account"),
+    };
+
+    let built = match web::block(move || build_transaction(&instructions, &fee_payer, nonce_account.as_ref())).await {
+        Ok(Ok(built)) => built,
+        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Failed to build transaction: {}", e)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    };
+
+    let instructions_json = match serde_json::to_string(&data.instructions) {
+        Ok(json) => json,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to store instructions: {}", e)),
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+    let draft = TransactionDraft {
+        id: Uuid::new_v4(),
+        wallet_address,
+        fee_payer: fee_payer_address,
+        instructions: instructions_json,
+        nonce_account: data.nonce_account,
+        serialized_transaction: built.serialized,
+        blockhash: built.blockhash.to_string(),
+        last_valid_block_height: built.last_valid_block_height.map(|h| h as i64),
+        refresh_count: 0,
+        expires_at: now + Duration::hours(DRAFT_TTL_HOURS),
+        created_at: now,
+        updated_at: now,
+    };
+
+    match diesel::insert_into(transaction_drafts::table)
+        .values(&draft)
+        .get_result::<TransactionDraft>(&mut conn)
+    {
+        Ok(draft) => {
+            info!("Stored transaction draft {} for {}", draft.id, draft.wallet_address);
+            HttpResponse::Ok().json(DraftResponse {
+                success: true,
+                message: "Transaction draft prepared".to_string(),
+                draft: Some(draft),
+            })
+        }
+        Err(e) => {
+            error!("Failed to store transaction draft: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to store transaction draft: {}", e))
+        }
+    }
+}
+
+pub async fn get_draft(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    match load_draft(&mut conn, &path.into_inner(), &wallet_address) {
+        Ok(draft) => HttpResponse::Ok().json(DraftResponse {
+            success: true,
+            message: "Transaction draft found".to_string(),
+            draft: Some(draft),
+        }),
+        Err(resp) => resp,
+    }
+}
+
+/// Rebuilds a stored draft from its original instructions with a fresh blockhash or the current nonce value
+pub async fn refresh_draft(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let draft = match load_draft(&mut conn, &path.into_inner(), &wallet_address) {
+        Ok(draft) => draft,
+        Err(resp) => return resp,
+    };
+
+    if draft.expires_at < Utc::now().naive_utc() {
+        return HttpResponse::Gone().body("Draft has expired, prepare the transaction again");
+    }
+
+    let serialized: Vec<SerializedInstruction> = match serde_json::from_str(&draft.instructions) {
+        Ok(serialized) => serialized,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Stored instructions are invalid: {}", e)),
+    };
+    let instructions = match parse_instructions(&serialized) {
+        Ok(instructions) => instructions,
+        Err(message) => return HttpResponse::InternalServerError().body(message),
+    };
+    let (fee_payer, nonce_account) = match (
+        Pubkey::from_str(&draft.fee_payer),
+        draft.nonce_account.as_deref().map(Pubkey::from_str).transpose(),
+    ) {
+        (Ok(fee_payer), Ok(nonce_account)) => (fee_payer, nonce_account),
+        _ => return HttpResponse::InternalServerError().body("Stored draft has invalid addresses"),
+    };
+
+    let built = match web::block(move || build_transaction(&instructions, &fee_payer, nonce_account.as_ref())).await {
+        Ok(Ok(built)) => built,
+        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Failed to rebuild transaction: {}", e)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    };
+
+    match diesel::update(transaction_drafts::table.find(draft.id))
+        .set((
+            transaction_drafts::serialized_transaction.eq(built.serialized),
+            transaction_drafts::blockhash.eq(built.blockhash.to_string()),
+            transaction_drafts::last_valid_block_height.eq(built.last_valid_block_height.map(|h| h as i64)),
+            transaction_drafts::refresh_count.eq(draft.refresh_count + 1),
+            transaction_drafts::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result::<TransactionDraft>(&mut conn)
+    {
+        Ok(updated) => {
+            info!("Refreshed transaction draft {} ({} refreshes)", updated.id, updated.refresh_count);
+            HttpResponse::Ok().json(DraftResponse {
+                success: true,
+                message: "Transaction draft rebuilt with a fresh blockhash".to_string(),
+                draft: Some(updated),
+            })
+        }
+        Err(e) => {
+            error!("Failed to update transaction draft: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to update transaction draft: {}", e))
+        }
+    }
+}
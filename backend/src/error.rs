@@ -0,0 +1,97 @@
+use actix_web::error::BlockingError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+use tracing::{error, warn};
+
+use crate::transaction::TransactionError;
+
+/// Error returned by API handlers. Each variant maps to an HTTP status and a stable `code`
+/// that the frontend branches on; the message is meant for display.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Unavailable(String),
+    #[error("Database connection failed")]
+    DatabaseConnection(#[from] diesel::ConnectionError),
+    #[error("{context}: {source}")]
+    Database {
+        context: &'static str,
+        #[source]
+        source: diesel::result::Error,
+    },
+    #[error("Transaction failed: {0}")]
+    Transaction(#[from] TransactionError),
+    #[error("Thread pool error: {0}")]
+    ThreadPool(#[from] BlockingError),
+    #[error("{0}")]
+    Internal(String),
+}
+
+#[derive(Serialize)]
+pub struct ApiErrorBody {
+    pub success: bool,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    /// Wraps a query error with what was being attempted, e.g. `.map_err(ApiError::db("Failed to fetch offers"))`
+    pub fn db(context: &'static str) -> impl FnOnce(diesel::result::Error) -> ApiError {
+        move |source| ApiError::Database { context, source }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::InvalidInput(_) => "invalid_input",
+            ApiError::Unavailable(_) => "unavailable",
+            ApiError::DatabaseConnection(_) => "database_unavailable",
+            ApiError::Database { .. } => "database_error",
+            ApiError::Transaction(_) => "transaction_failed",
+            ApiError::ThreadPool(_) | ApiError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::DatabaseConnection(_)
+            | ApiError::Database { .. }
+            | ApiError::Transaction(_)
+            | ApiError::ThreadPool(_)
+            | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("{} ({})", self, self.code());
+        } else {
+            warn!("{} ({})", self, self.code());
+        }
+
+        HttpResponse::build(status).json(ApiErrorBody {
+            success: false,
+            code: self.code(),
+            message: self.to_string(),
+        })
+    }
+}
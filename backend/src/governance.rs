@@ -0,0 +1,462 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose, Engine};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    system_program,
+    transaction::Transaction as SolanaTransaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::str::FromStr;
+use uuid::Uuid;
+use tracing::info;
+
+use crate::db;
+use crate::error::ApiError;
+use crate::models::{GovernanceProposal, Offer, Property};
+use crate::organization::{self, Permission};
+use crate::schema::{governance_proposals, offers, properties};
+use crate::transaction::{authenticated_wallet, get_marketplace_info, get_property_pubkey, instruction_discriminator, PROGRAM_ID};
+
+// Proposal lifecycle, mirrored from the on-chain SPL Governance proposal state
+pub const STATUS_DRAFTED: &str = "drafted";
+pub const STATUS_VOTING: &str = "voting";
+pub const STATUS_SUCCEEDED: &str = "succeeded";
+pub const STATUS_DEFEATED: &str = "defeated";
+pub const STATUS_CANCELLED: &str = "cancelled";
+pub const STATUS_EXECUTED: &str = "executed";
+
+/// How long the sale authorization stays usable after the proposal executes, unless overridden
+const DEFAULT_AUTHORIZATION_DAYS: i64 = 14;
+
+/// Offset of the `state` byte in an SPL Governance ProposalV2 account:
+/// account_type (1) + governance (32) + governing_token_mint (32)
+const PROPOSAL_STATE_OFFSET: usize = 65;
+
+#[derive(Deserialize)]
+pub struct CreateProposalRequest {
+    /// Days the authorization may be used once executed
+    pub authorization_days: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct SetProposalAddressRequest {
+    pub proposal_address: String,
+}
+
+#[derive(Serialize)]
+pub struct AccountMetaDraft {
+    pub pubkey: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// The instruction the proposal has to execute, in the shape governance UIs accept
+#[derive(Serialize)]
+pub struct InstructionDraft {
+    pub program_id: String,
+    pub accounts: Vec<AccountMetaDraft>,
+    /// Base64 instruction data
+    pub data: String,
+    /// Base64 Borsh-encoded SPL Governance `InstructionData`, for pasting as a custom instruction
+    pub serialized: String,
+}
+
+#[derive(Serialize)]
+pub struct ProposalDraft {
+    pub name: String,
+    pub description: String,
+    pub instruction: InstructionDraft,
+}
+
+#[derive(Serialize)]
+pub struct GovernanceProposalResponse {
+    pub success: bool,
+    pub message: String,
+    pub proposal: Option<GovernanceProposal>,
+    /// Included when the proposal is first drafted
+    pub draft: Option<ProposalDraft>,
+}
+
+fn program_id() -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(PROGRAM_ID).map_err(|_| ApiError::Internal("Invalid program ID".to_string()))
+}
+
+fn parse_pubkey(value: &str, what: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(value).map_err(|_| ApiError::InvalidInput(format!("Invalid {}", what)))
+}
+
+/// Governance attached to the property on-chain, read from its PropertyGovernance PDA
+/// (discriminator, property, governance)
+fn fetch_property_governance(rpc_client: &RpcClient, program_id: &Pubkey, property_pda: &Pubkey) -> Option<Pubkey> {
+    let (governance_pda, _) = Pubkey::find_program_address(
+        &[b"property_governance", property_pda.as_ref()],
+        program_id,
+    );
+    let account = rpc_client.get_account(&governance_pda).ok()?;
+    let bytes: [u8; 32] = account.data.get(40..72)?.try_into().ok()?;
+    Some(Pubkey::new_from_array(bytes))
+}
+
+/// Maps an SPL Governance ProposalState to our status; None while it is still a draft
+fn proposal_status(rpc_client: &RpcClient, proposal: &Pubkey) -> Result<Option<&'static str>, anyhow::Error> {
+    let account = rpc_client.get_account(proposal)?;
+    let state = *account
+        .data
+        .get(PROPOSAL_STATE_OFFSET)
+        .ok_or_else(|| anyhow::anyhow!("Not a governance proposal account"))?;
+    Ok(match state {
+        // Draft, SigningOff
+        0 | 1 => None,
+        2 => Some(STATUS_VOTING),
+        3 => Some(STATUS_SUCCEEDED),
+        // Executing, Completed, ExecutingWithErrors
+        4 | 5 | 8 => Some(STATUS_EXECUTED),
+        6 => Some(STATUS_CANCELLED),
+        // Defeated, Vetoed
+        7 | 9 => Some(STATUS_DEFEATED),
+        other => return Err(anyhow::anyhow!("Unknown proposal state {}", other)),
+    })
+}
+
+/// Borsh encoding of SPL Governance's `InstructionData { program_id, accounts, data }`
+fn serialize_governance_instruction(instruction: &Instruction) -> Vec<u8> {
+    let mut bytes = instruction.program_id.to_bytes().to_vec();
+    bytes.extend((instruction.accounts.len() as u32).to_le_bytes());
+    for meta in &instruction.accounts {
+        bytes.extend(meta.pubkey.to_bytes());
+        bytes.push(meta.is_signer as u8);
+        bytes.push(meta.is_writable as u8);
+    }
+    bytes.extend((instruction.data.len() as u32).to_le_bytes());
+    bytes.extend(&instruction.data);
+    bytes
+}
+
+/// `authorize_governed_sale` signed and paid for by the governance. The governance should be a
+/// system-owned signer such as the realm governance's native treasury so that it can pay the rent.
+fn authorize_sale_instruction(
+    program_id: &Pubkey,
+    property_pda: &Pubkey,
+    governance: &Pubkey,
+    min_amount: u64,
+    expires_at: i64,
+) -> Instruction {
+    let (property_governance_pda, _) = Pubkey::find_program_address(
+        &[b"property_governance", property_pda.as_ref()],
+        program_id,
+    );
+    let (sale_authorization_pda, _) = Pubkey::find_program_address(
+        &[b"sale_authorization", property_pda.as_ref()],
+        program_id,
+    );
+
+    let mut data = instruction_discriminator("authorize_governed_sale").to_vec();
+    data.extend(min_amount.to_le_bytes());
+    data.extend(expires_at.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*property_pda, false),
+            AccountMeta::new_readonly(property_governance_pda, false),
+            AccountMeta::new(sale_authorization_pda, false),
+            AccountMeta::new_readonly(*governance, true),
+            AccountMeta::new(*governance, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+fn proposal_draft(offer: &Offer, instruction: &Instruction, expires_at: NaiveDateTime) -> ProposalDraft {
+    ProposalDraft {
+        name: format!("Accept offer on property {}", offer.property_id),
+        description: format!(
+            "Authorizes accepting an offer of at least {} lamports from {} on property {}. \
+             The authorization can be used once, until {} UTC.",
+            offer.amount, offer.buyer_wallet, offer.property_id, expires_at
+        ),
+        instruction: InstructionDraft {
+            program_id: instruction.program_id.to_string(),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| AccountMetaDraft {
+                    pubkey: meta.pubkey.to_string(),
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: general_purpose::STANDARD.encode(&instruction.data),
+            serialized: general_purpose::STANDARD.encode(serialize_governance_instruction(instruction)),
+        },
+    }
+}
+
+/// Unsigned `respond_to_offer(accept)` for the property owner, carrying the sale authorization
+fn build_acceptance_transaction(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    offer: &Offer,
+    property: &Property,
+) -> Result<SolanaTransaction, anyhow::Error> {
+    let (marketplace_pda, _) = get_marketplace_info(program_id)?;
+    let property_pda = get_property_pubkey(&offer.property_id, program_id)?;
+    let owner = Pubkey::from_str(&property.owner_wallet)?;
+    let buyer = Pubkey::from_str(&offer.buyer_wallet)?;
+    let nft_mint = Pubkey::from_str(&property.nft_mint_address)?;
+    let (offer_pda, _) = Pubkey::find_program_address(
+        &[b"offer", property_pda.as_ref(), buyer.as_ref(), &(offer.offer_nonce as u64).to_le_bytes()],
+        program_id,
+    );
+    let (escrow_pda, _) = Pubkey::find_program_address(&[b"escrow", offer_pda.as_ref()], program_id);
+    let (property_governance_pda, _) = Pubkey::find_program_address(
+        &[b"property_governance", property_pda.as_ref()],
+        program_id,
+    );
+    let (sale_authorization_pda, _) = Pubkey::find_program_address(
+        &[b"sale_authorization", property_pda.as_ref()],
+        program_id,
+    );
+
+    // accept = true, no seller concession
+    let mut data = instruction_discriminator("respond_to_offer").to_vec();
+    data.extend([1, 0]);
+
+    let instruction = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(marketplace_pda, false),
+            AccountMeta::new(property_pda, false),
+            AccountMeta::new(offer_pda, false),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new(owner, true),
+            AccountMeta::new(buyer, false),
+            AccountMeta::new(get_associated_token_address(&owner, &nft_mint), false),
+            AccountMeta::new(get_associated_token_address(&escrow_pda, &nft_mint), false),
+            AccountMeta::new_readonly(nft_mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            // Unused optional accounts: co_ownership, seller_concession, system_program
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(property_governance_pda, false),
+            AccountMeta::new(sale_authorization_pda, false),
+        ],
+        data,
+    };
+
+    let mut tx = SolanaTransaction::new_unsigned(Message::new(&[instruction], Some(&owner)));
+    tx.message.recent_blockhash = rpc_client.get_latest_blockhash()?;
+    Ok(tx)
+}
+
+fn load_offer_and_property(conn: &mut PgConnection, offer_uuid: Uuid) -> Result<(Offer, Property), ApiError> {
+    let offer = offers::table
+        .find(offer_uuid)
+        .first::<Offer>(conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch offer"))?
+        .ok_or_else(|| ApiError::NotFound("Offer not found".to_string()))?;
+    let property = properties::table
+        .filter(properties::property_id.eq(&offer.property_id))
+        .first::<Property>(conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+    Ok((offer, property))
+}
+
+fn load_proposal(conn: &mut PgConnection, proposal_id: &str, wallet: &str) -> Result<GovernanceProposal, ApiError> {
+    let proposal_uuid = Uuid::parse_str(proposal_id)
+        .map_err(|_| ApiError::InvalidInput("Invalid proposal ID format".to_string()))?;
+    let proposal = governance_proposals::table
+        .find(proposal_uuid)
+        .first::<GovernanceProposal>(conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch proposal"))?
+        .ok_or_else(|| ApiError::NotFound("Proposal not found".to_string()))?;
+
+    organization::check_property_permission(
+        conn,
+        &proposal.property_id,
+        wallet,
+        Permission::Negotiate,
+        "Only the property owner can manage its governance proposals",
+    )?;
+    Ok(proposal)
+}
+
+/// Drafts the proposal a DAO votes on to approve accepting a pending offer
+pub async fn create_governance_proposal(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<CreateProposalRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let offer_uuid = Uuid::parse_str(&path.into_inner())
+        .map_err(|_| ApiError::InvalidInput("Invalid offer ID format".to_string()))?;
+
+    let mut conn = db::establish_connection()?;
+    let (offer, _) = load_offer_and_property(&mut conn, offer_uuid)?;
+    organization::check_property_permission(
+        &mut conn,
+        &offer.property_id,
+        &wallet_address,
+        Permission::Negotiate,
+        "Only the property owner can propose accepting an offer",
+    )?;
+    if offer.status != "pending" {
+        return Err(ApiError::InvalidInput("Can only propose accepting pending offers".to_string()));
+    }
+
+    let authorization_days = data.authorization_days.unwrap_or(DEFAULT_AUTHORIZATION_DAYS);
+    if !(1..=90).contains(&authorization_days) {
+        return Err(ApiError::InvalidInput("Authorization must last between 1 and 90 days".to_string()));
+    }
+    let expires_at = Utc::now().naive_utc() + Duration::days(authorization_days);
+
+    let program_id = program_id()?;
+    let property_pda = get_property_pubkey(&offer.property_id, &program_id)
+        .map_err(|e| ApiError::Internal(format!("Failed to derive property address: {}", e)))?;
+    let governance = web::block(move || {
+        let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+        fetch_property_governance(&rpc_client, &program_id, &property_pda)
+    })
+    .await?
+    .ok_or_else(|| ApiError::InvalidInput("Property is not held by a governance".to_string()))?;
+
+    let instruction = authorize_sale_instruction(
+        &program_id,
+        &property_pda,
+        &governance,
+        offer.amount as u64,
+        expires_at.and_utc().timestamp(),
+    );
+    let draft = proposal_draft(&offer, &instruction, expires_at);
+
+    let now = Utc::now().naive_utc();
+    let proposal = GovernanceProposal {
+        id: Uuid::new_v4(),
+        offer_id: offer.id,
+        property_id: offer.property_id.clone(),
+        governance: governance.to_string(),
+        proposal_address: None,
+        min_amount: offer.amount,
+        authorization_expires_at: expires_at,
+        status: STATUS_DRAFTED.to_string(),
+        acceptance_transaction: None,
+        created_by: wallet_address,
+        created_at: now,
+        updated_at: now,
+    };
+    diesel::insert_into(governance_proposals::table)
+        .values(&proposal)
+        .execute(&mut conn)
+        .map_err(ApiError::db("Failed to store proposal"))?;
+
+    info!("Drafted governance proposal {} for offer {}", proposal.id, offer.id);
+    Ok(HttpResponse::Ok().json(GovernanceProposalResponse {
+        success: true,
+        message: "Proposal drafted".to_string(),
+        proposal: Some(proposal),
+        draft: Some(draft),
+    }))
+}
+
+/// Links the drafted proposal to the on-chain proposal account created in the governance UI
+pub async fn set_proposal_address(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<SetProposalAddressRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    parse_pubkey(&data.proposal_address, "proposal address")?;
+
+    let mut conn = db::establish_connection()?;
+    let proposal = load_proposal(&mut conn, &path.into_inner(), &wallet_address)?;
+    if proposal.status != STATUS_DRAFTED {
+        return Err(ApiError::InvalidInput("Proposal address can only be set while drafted".to_string()));
+    }
+
+    let proposal = diesel::update(governance_proposals::table.find(proposal.id))
+        .set((
+            governance_proposals::proposal_address.eq(&data.proposal_address),
+            governance_proposals::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result::<GovernanceProposal>(&mut conn)
+        .map_err(ApiError::db("Failed to update proposal"))?;
+
+    Ok(HttpResponse::Ok().json(GovernanceProposalResponse {
+        success: true,
+        message: "Proposal address recorded".to_string(),
+        proposal: Some(proposal),
+        draft: None,
+    }))
+}
+
+/// Refreshes the proposal's voting status from chain. Once it has executed, the owner's
+/// acceptance transaction is prepared and returned for signing.
+pub async fn get_governance_proposal(
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+
+    let mut conn = db::establish_connection()?;
+    let proposal = load_proposal(&mut conn, &path.into_inner(), &wallet_address)?;
+    let Some(proposal_address) = proposal.proposal_address.clone() else {
+        return Ok(HttpResponse::Ok().json(GovernanceProposalResponse {
+            success: true,
+            message: "Proposal has not been created on-chain yet".to_string(),
+            proposal: Some(proposal),
+            draft: None,
+        }));
+    };
+    let proposal_pubkey = parse_pubkey(&proposal_address, "proposal address")?;
+    let (offer, property) = load_offer_and_property(&mut conn, proposal.offer_id)?;
+
+    let program_id = program_id()?;
+    let (status, acceptance_transaction) = web::block(move || {
+        let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+        let status = proposal_status(&rpc_client, &proposal_pubkey)?;
+        let acceptance_transaction = if status == Some(STATUS_EXECUTED) {
+            let tx = build_acceptance_transaction(&rpc_client, &program_id, &offer, &property)?;
+            Some(general_purpose::STANDARD.encode(bincode::serialize(&tx)?))
+        } else {
+            None
+        };
+        Ok::<_, anyhow::Error>((status, acceptance_transaction))
+    })
+    .await?
+    .map_err(|e| ApiError::Unavailable(format!("Failed to read proposal: {}", e)))?;
+
+    let status = status.unwrap_or(STATUS_DRAFTED);
+    let proposal = diesel::update(governance_proposals::table.find(proposal.id))
+        .set((
+            governance_proposals::status.eq(status),
+            governance_proposals::acceptance_transaction.eq(&acceptance_transaction),
+            governance_proposals::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result::<GovernanceProposal>(&mut conn)
+        .map_err(ApiError::db("Failed to update proposal"))?;
+
+    let message = if acceptance_transaction.is_some() {
+        "Proposal executed; acceptance transaction is ready to sign"
+    } else {
+        "Proposal status refreshed"
+    };
+    Ok(HttpResponse::Ok().json(GovernanceProposalResponse {
+        success: true,
+        message: message.to_string(),
+        proposal: Some(proposal),
+        draft: None,
+    }))
+}
@@ -0,0 +1,221 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::str::FromStr;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::ApiError;
+use crate::models::{ArchivedHistory, Property};
+use crate::schema::{archived_histories, properties};
+use crate::transaction::{authenticated_wallet, get_property_pubkey, PROGRAM_ID};
+
+/// discriminator, four pubkeys, price, sold_at, index, concession, version, rent, timestamp
+const HISTORY_ARCHIVED_LEN: usize = 8 + 4 * 32 + 4 * 8 + 1 + 2 * 8;
+
+#[derive(Deserialize)]
+pub struct RecordArchiveRequest {
+    /// Signature of the transaction that ran archive_history
+    pub signature: String,
+}
+
+#[derive(Serialize)]
+pub struct ArchivedHistoriesResponse {
+    pub success: bool,
+    pub message: String,
+    pub records: Vec<ArchivedHistory>,
+}
+
+/// Fields of the program's `HistoryArchived` event
+struct HistoryArchived {
+    transaction_history: Pubkey,
+    property: Pubkey,
+    seller: Pubkey,
+    buyer: Pubkey,
+    price: u64,
+    sold_at: i64,
+    transaction_index: u64,
+    seller_concession: u64,
+    timestamp: i64,
+}
+
+// Computes the Anchor discriminator for a program event
+fn event_discriminator(name: &str) -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(format!("event:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+fn parse_history_archived(data: &[u8]) -> Option<HistoryArchived> {
+    if data.len() < HISTORY_ARCHIVED_LEN || data[..8] != event_discriminator("HistoryArchived") {
+        return None;
+    }
+    let pubkey = |at: usize| Pubkey::try_from(&data[at..at + 32]).ok();
+    let word = |at: usize| data[at..at + 8].try_into().ok();
+    Some(HistoryArchived {
+        transaction_history: pubkey(8)?,
+        property: pubkey(40)?,
+        seller: pubkey(72)?,
+        buyer: pubkey(104)?,
+        price: u64::from_le_bytes(word(136)?),
+        sold_at: i64::from_le_bytes(word(144)?),
+        transaction_index: u64::from_le_bytes(word(152)?),
+        seller_concession: u64::from_le_bytes(word(160)?),
+        // version at 168 and rent_recovered at 169 aren't kept
+        timestamp: i64::from_le_bytes(word(177)?),
+    })
+}
+
+/// Log messages of a finalized, successful transaction
+fn transaction_logs(signature: &str) -> Result<Vec<String>, anyhow::Error> {
+    let response: Value = reqwest::blocking::Client::new()
+        .post("https://api.devnet.solana.com")
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getTransaction",
+            "params": [
+                signature,
+                { "encoding": "json", "commitment": "finalized", "maxSupportedTransactionVersion": 0 }
+            ],
+        }))
+        .send()?
+        .error_for_status()?
+        .json()?;
+
+    let meta = response
+        .pointer("/result/meta")
+        .filter(|meta| !meta.is_null())
+        .ok_or_else(|| anyhow::anyhow!("Transaction not found or not finalized"))?;
+    if !meta["err"].is_null() {
+        return Err(anyhow::anyhow!("Transaction failed"));
+    }
+    Ok(meta["logMessages"]
+        .as_array()
+        .map(|logs| logs.iter().filter_map(|l| l.as_str().map(str::to_string)).collect())
+        .unwrap_or_default())
+}
+
+/// Event payloads logged by `program_id` itself, skipping data from programs it invoked or that
+/// invoked it
+fn program_events(logs: &[String], program_id: &str) -> Vec<Vec<u8>> {
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for log in logs {
+        let mut words = log.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("Program"), Some("data:"), Some(data)) if stack.last() == Some(&program_id) => {
+                if let Ok(bytes) = general_purpose::STANDARD.decode(data) {
+                    events.push(bytes);
+                }
+            }
+            (Some("Program"), Some(id), Some("invoke")) => stack.push(id),
+            (Some("Program"), Some(id), Some("success" | "failed:")) if stack.last() == Some(&id) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+fn timestamp(secs: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default().naive_utc()
+}
+
+/// The archived copy of the history record at `address`, if it has been archived
+pub fn find_archived(conn: &mut PgConnection, address: &str) -> QueryResult<Option<ArchivedHistory>> {
+    archived_histories::table
+        .filter(archived_histories::history_address.eq(address))
+        .first::<ArchivedHistory>(conn)
+        .optional()
+}
+
+/// Stores the sale records closed by an archive_history transaction, read from its
+/// `HistoryArchived` events. Anyone can submit one since the records come from the chain.
+pub async fn record_archived_history(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<RecordArchiveRequest>,
+) -> Result<HttpResponse, ApiError> {
+    authenticated_wallet(&req).await?;
+    let property_id_param = path.into_inner();
+    Signature::from_str(&data.signature)
+        .map_err(|_| ApiError::InvalidInput("Invalid transaction signature".to_string()))?;
+
+    let mut conn = db::establish_connection()?;
+    properties::table
+        .filter(properties::property_id.eq(&property_id_param))
+        .first::<Property>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+
+    let program_id = Pubkey::from_str(PROGRAM_ID)
+        .map_err(|e| ApiError::Internal(format!("Invalid program id: {}", e)))?;
+    let property_pda = get_property_pubkey(&property_id_param, &program_id)
+        .map_err(|e| ApiError::Internal(format!("Failed to derive property: {}", e)))?;
+
+    let signature = data.signature.clone();
+    let logs = web::block(move || transaction_logs(&signature))
+        .await?
+        .map_err(|e| ApiError::InvalidInput(format!("Failed to fetch archive transaction: {}", e)))?;
+    let archived: Vec<HistoryArchived> = program_events(&logs, PROGRAM_ID)
+        .iter()
+        .filter_map(|event| parse_history_archived(event))
+        .filter(|event| event.property == property_pda)
+        .collect();
+    if archived.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "Transaction did not archive any of this property's history".to_string(),
+        ));
+    }
+
+    let now = Utc::now().naive_utc();
+    let records: Vec<ArchivedHistory> = archived
+        .iter()
+        .map(|event| ArchivedHistory {
+            id: Uuid::new_v4(),
+            property_id: property_id_param.clone(),
+            history_address: event.transaction_history.to_string(),
+            transaction_index: event.transaction_index as i64,
+            seller_wallet: event.seller.to_string(),
+            buyer_wallet: event.buyer.to_string(),
+            price: event.price as i64,
+            seller_concession: event.seller_concession as i64,
+            sold_at: timestamp(event.sold_at),
+            archived_at: timestamp(event.timestamp),
+            archive_signature: data.signature.clone(),
+            created_at: now,
+        })
+        .collect();
+
+    // Resubmitting the same archive is harmless
+    diesel::insert_into(archived_histories::table)
+        .values(&records)
+        .on_conflict(archived_histories::history_address)
+        .do_nothing()
+        .execute(&mut conn)
+        .map_err(ApiError::db("Failed to store archived history"))?;
+
+    let records = archived_histories::table
+        .filter(archived_histories::property_id.eq(&property_id_param))
+        .order(archived_histories::transaction_index.asc())
+        .load::<ArchivedHistory>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch archived history"))?;
+
+    info!("Recorded {} archived history records for {}", archived.len(), property_id_param);
+    Ok(HttpResponse::Ok().json(ArchivedHistoriesResponse {
+        success: true,
+        message: "Archived history recorded".to_string(),
+        records,
+    }))
+}
@@ -0,0 +1,358 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+use tracing::{info, error};
+
+use crate::db;
+use crate::models::{InviteCode, OrganizationMember, ReferralConversion};
+use crate::organization::ROLE_VIEWER;
+use crate::schema::{invite_codes, organization_members, organizations, properties, referral_conversions};
+use crate::transaction::verify_token;
+
+pub const CONVERSION_SIGNUP: &str = "signup";
+pub const CONVERSION_FIRST_LISTING: &str = "first_listing";
+
+const CODE_LEN: usize = 10;
+
+#[derive(Deserialize)]
+pub struct CreateInviteRequest {
+    /// Invites into an organization the caller owns; redeemers join it as viewers
+    pub organization_id: Option<Uuid>,
+    pub max_uses: Option<i32>,
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct InviteCodeStats {
+    #[serde(flatten)]
+    pub invite: InviteCode,
+    pub signups: i64,
+    pub first_listings: i64,
+}
+
+#[derive(Serialize)]
+pub struct InviteCodesResponse {
+    pub success: bool,
+    pub message: String,
+    pub invite_codes: Vec<InviteCodeStats>,
+}
+
+#[derive(Serialize)]
+pub struct ConversionsResponse {
+    pub success: bool,
+    pub message: String,
+    pub conversions: Vec<ReferralConversion>,
+}
+
+fn new_code() -> String {
+    Uuid::new_v4().simple().to_string()[..CODE_LEN].to_uppercase()
+}
+
+/// Credits the code a wallet signed up with once that wallet lists its first property.
+/// Wallets that joined without a code are ignored. Failures are logged, never fatal to the caller.
+pub fn record_first_listing(conn: &mut PgConnection, wallet: &str, prop_id: &str) {
+    let signup_code = referral_conversions::table
+        .filter(referral_conversions::wallet_address.eq(wallet))
+        .filter(referral_conversions::event_type.eq(CONVERSION_SIGNUP))
+        .select(referral_conversions::invite_code_id)
+        .first::<Uuid>(conn)
+        .optional();
+
+    let invite_code_id = match signup_code {
+        Ok(Some(invite_code_id)) => invite_code_id,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to look up referral for {}: {}", wallet, e);
+            return;
+        }
+    };
+
+    let conversion = ReferralConversion {
+        id: Uuid::new_v4(),
+        invite_code_id,
+        wallet_address: wallet.to_string(),
+        event_type: CONVERSION_FIRST_LISTING.to_string(),
+        property_id: Some(prop_id.to_string()),
+        created_at: Utc::now().naive_utc(),
+    };
+    match diesel::insert_into(referral_conversions::table)
+        .values(&conversion)
+        .on_conflict_do_nothing()
+        .execute(conn)
+    {
+        Ok(1) => info!("First listing {} by {} credited to invite {}", prop_id, wallet, invite_code_id),
+        Ok(_) => {}
+        Err(e) => error!("Failed to record first listing conversion for {}: {}", wallet, e),
+    }
+}
+
+pub async fn create_invite_code(req: HttpRequest, data: web::Json<CreateInviteRequest>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    if data.max_uses.is_some_and(|uses| uses <= 0) {
+        return HttpResponse::BadRequest().body("max_uses must be positive");
+    }
+    if data.expires_in_days.is_some_and(|days| days <= 0) {
+        return HttpResponse::BadRequest().body("expires_in_days must be positive");
+    }
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    if let Some(org_id) = data.organization_id {
+        match organizations::table
+            .find(org_id)
+            .select(organizations::owner_wallet)
+            .first::<String>(&mut conn)
+        {
+            Ok(owner) if owner == wallet_address => {}
+            Ok(_) => return HttpResponse::Forbidden().body("Only the organization owner can create its invite codes"),
+            Err(diesel::result::Error::NotFound) => return HttpResponse::NotFound().body("Organization not found"),
+            Err(e) => {
+                error!("Failed to fetch organization: {}", e);
+                return HttpResponse::InternalServerError().body(format!("Failed to fetch organization: {}", e));
+            }
+        }
+    }
+
+    let now = Utc::now().naive_utc();
+    let invite = InviteCode {
+        id: Uuid::new_v4(),
+        code: new_code(),
+        owner_wallet: wallet_address,
+        organization_id: data.organization_id,
+        max_uses: data.max_uses,
+        use_count: 0,
+        expires_at: data.expires_in_days.map(|days| now + Duration::days(days)),
+        created_at: now,
+    };
+
+    match diesel::insert_into(invite_codes::table)
+        .values(&invite)
+        .get_result::<InviteCode>(&mut conn)
+    {
+        Ok(invite) => {
+            info!("Created invite code {} for {}", invite.code, invite.owner_wallet);
+            HttpResponse::Ok().json(InviteCodesResponse {
+                success: true,
+                message: "Invite code created".to_string(),
+                invite_codes: vec![InviteCodeStats { invite, signups: 0, first_listings: 0 }],
+            })
+        }
+        Err(e) => {
+            error!("Failed to create invite code: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to create invite code: {}", e))
+        }
+    }
+}
+
+/// The caller's invite codes with the sign-ups and first listings credited to each
+pub async fn get_my_invite_codes(req: HttpRequest) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let codes = invite_codes::table
+            .filter(invite_codes::owner_wallet.eq(&wallet_address))
+            .order_by(invite_codes::created_at.desc())
+            .load::<InviteCode>(conn)?;
+        let ids: Vec<Uuid> = codes.iter().map(|c| c.id).collect();
+
+        let counts: HashMap<(Uuid, String), i64> = referral_conversions::table
+            .filter(referral_conversions::invite_code_id.eq_any(&ids))
+            .group_by((referral_conversions::invite_code_id, referral_conversions::event_type))
+            .select((
+                referral_conversions::invite_code_id,
+                referral_conversions::event_type,
+                diesel::dsl::count_star(),
+            ))
+            .load::<(Uuid, String, i64)>(conn)?
+            .into_iter()
+            .map(|(code_id, kind, count)| ((code_id, kind), count))
+            .collect();
+
+        Ok(codes
+            .into_iter()
+            .map(|invite| {
+                let count = |kind: &str| counts.get(&(invite.id, kind.to_string())).copied().unwrap_or(0);
+                let (signups, first_listings) = (count(CONVERSION_SIGNUP), count(CONVERSION_FIRST_LISTING));
+                InviteCodeStats { invite, signups, first_listings }
+            })
+            .collect::<Vec<_>>())
+    });
+
+    match result {
+        Ok(invite_codes) => HttpResponse::Ok().json(InviteCodesResponse {
+            success: true,
+            message: format!("Found {} invite codes", invite_codes.len()),
+            invite_codes,
+        }),
+        Err(e) => {
+            error!("Failed to fetch invite codes: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch invite codes: {}", e))
+        }
+    }
+}
+
+/// Conversions credited to one of the caller's codes, oldest first, for referral-fee attribution
+pub async fn get_invite_conversions(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+    let code = path.into_inner().to_uppercase();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    match referral_conversions::table
+        .inner_join(invite_codes::table)
+        .filter(invite_codes::code.eq(&code))
+        .filter(invite_codes::owner_wallet.eq(&wallet_address))
+        .order_by(referral_conversions::created_at.asc())
+        .select(referral_conversions::all_columns)
+        .load::<ReferralConversion>(&mut conn)
+    {
+        Ok(conversions) => HttpResponse::Ok().json(ConversionsResponse {
+            success: true,
+            message: format!("Found {} conversions", conversions.len()),
+            conversions,
+        }),
+        Err(e) => {
+            error!("Failed to fetch conversions: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch conversions: {}", e))
+        }
+    }
+}
+
+/// Signs the caller up under an invite code. Only wallets new to the marketplace can redeem,
+/// and organization codes also enroll the wallet as a viewer.
+pub async fn redeem_invite_code(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+    let code = path.into_inner().to_uppercase();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let invite = match invite_codes::table
+            .filter(invite_codes::code.eq(&code))
+            .for_update()
+            .first::<InviteCode>(conn)
+            .optional()?
+        {
+            Some(invite) => invite,
+            None => return Ok(Err(HttpResponse::NotFound().body("Invite code not found"))),
+        };
+
+        let now = Utc::now().naive_utc();
+        if invite.expires_at.is_some_and(|at| at <= now) {
+            return Ok(Err(HttpResponse::Gone().body("Invite code has expired")));
+        }
+        if invite.max_uses.is_some_and(|max| invite.use_count >= max) {
+            return Ok(Err(HttpResponse::Gone().body("Invite code has been used up")));
+        }
+        if invite.owner_wallet == wallet_address {
+            return Ok(Err(HttpResponse::BadRequest().body("You cannot redeem your own invite code")));
+        }
+
+        let already_referred = referral_conversions::table
+            .filter(referral_conversions::wallet_address.eq(&wallet_address))
+            .filter(referral_conversions::event_type.eq(CONVERSION_SIGNUP))
+            .count()
+            .get_result::<i64>(conn)?
+            > 0;
+        let has_listed = properties::table
+            .filter(properties::owner_wallet.eq(&wallet_address))
+            .count()
+            .get_result::<i64>(conn)?
+            > 0;
+        if already_referred || has_listed {
+            return Ok(Err(HttpResponse::Conflict().body("Only new wallets can redeem an invite code")));
+        }
+
+        let conversion = diesel::insert_into(referral_conversions::table)
+            .values(&ReferralConversion {
+                id: Uuid::new_v4(),
+                invite_code_id: invite.id,
+                wallet_address: wallet_address.clone(),
+                event_type: CONVERSION_SIGNUP.to_string(),
+                property_id: None,
+                created_at: now,
+            })
+            .get_result::<ReferralConversion>(conn)?;
+
+        diesel::update(invite_codes::table.find(invite.id))
+            .set(invite_codes::use_count.eq(invite_codes::use_count + 1))
+            .execute(conn)?;
+
+        if let Some(org_id) = invite.organization_id {
+            diesel::insert_into(organization_members::table)
+                .values(&OrganizationMember {
+                    id: Uuid::new_v4(),
+                    organization_id: org_id,
+                    wallet_address: wallet_address.clone(),
+                    role: ROLE_VIEWER.to_string(),
+                    status: "active".to_string(),
+                    invited_by: invite.owner_wallet.clone(),
+                    created_at: now,
+                    updated_at: now,
+                })
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        Ok(Ok(conversion))
+    });
+
+    match result {
+        Ok(Ok(conversion)) => {
+            info!("{} signed up with invite code {}", wallet_address, code);
+            HttpResponse::Ok().json(ConversionsResponse {
+                success: true,
+                message: "Invite code redeemed".to_string(),
+                conversions: vec![conversion],
+            })
+        }
+        Ok(Err(resp)) => resp,
+        Err(e) => {
+            error!("Failed to redeem invite code: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to redeem invite code: {}", e))
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::Transaction as SolanaTransaction,
+};
+use std::env;
+use std::str::FromStr;
+use uuid::Uuid;
+use tracing::{info, error};
+
+use crate::db;
+use crate::models::PropertyVerification;
+use crate::organization::{self, Permission};
+use crate::schema::property_verifications;
+use crate::transaction::{get_property_pubkey, instruction_discriminator, verify_token, PROGRAM_ID};
+
+/// Result of looking up a parcel in a land registry
+#[derive(Debug, Serialize)]
+pub struct RegistryVerdict {
+    pub verified: bool,
+    pub details: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("Registry request failed: {0}")]
+    Request(String),
+    #[error("Registry configuration error: {0}")]
+    Config(String),
+}
+
+/// A source of truth for cadastral records
+pub trait RegistryAdapter: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn verify(&self, cadastral_id: &str, jurisdiction_code: &str) -> Result<RegistryVerdict, RegistryError>;
+}
+
+/// Accepts well-formed IDs, optionally restricted to `LAND_REGISTRY_MOCK_IDS`. Meant for devnet.
+pub struct MockRegistryAdapter {
+    known_ids: Option<Vec<String>>,
+}
+
+impl MockRegistryAdapter {
+    pub fn from_env() -> Self {
+        let known_ids = env::var("LAND_REGISTRY_MOCK_IDS")
+            .ok()
+            .map(|ids| ids.split(',').map(|id| id.trim().to_string()).collect());
+        MockRegistryAdapter { known_ids }
+    }
+}
+
+impl RegistryAdapter for MockRegistryAdapter {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn verify(&self, cadastral_id: &str, jurisdiction_code: &str) -> Result<RegistryVerdict, RegistryError> {
+        let well_formed = !cadastral_id.is_empty()
+            && cadastral_id.len() <= 32
+            && !jurisdiction_code.is_empty()
+            && jurisdiction_code.len() <= 8;
+        let known = match &self.known_ids {
+            Some(ids) => ids.iter().any(|id| id == cadastral_id),
+            None => true,
+        };
+
+        Ok(RegistryVerdict {
+            verified: well_formed && known,
+            details: if well_formed && known {
+                "Parcel accepted by mock registry".to_string()
+            } else {
+                "Parcel unknown to mock registry".to_string()
+            },
+        })
+    }
+}
+
+/// Queries a REST registry at `LAND_REGISTRY_API_URL`:
+/// `GET {url}/parcels/{jurisdiction}/{cadastral_id}` returning `{ "exists": bool, "status": "..." }`
+pub struct HttpRegistryAdapter {
+    base_url: String,
+    api_key: Option<String>,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Deserialize)]
+struct HttpRegistryResponse {
+    exists: bool,
+    status: Option<String>,
+}
+
+impl HttpRegistryAdapter {
+    pub fn from_env() -> Result<Self, RegistryError> {
+        let base_url = env::var("LAND_REGISTRY_API_URL")
+            .map_err(|_| RegistryError::Config("LAND_REGISTRY_API_URL must be set".to_string()))?;
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(15))
+            .build()
+            .map_err(|e| RegistryError::Config(e.to_string()))?;
+
+        Ok(HttpRegistryAdapter {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: env::var("LAND_REGISTRY_API_KEY").ok(),
+            client,
+        })
+    }
+}
+
+impl RegistryAdapter for HttpRegistryAdapter {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn verify(&self, cadastral_id: &str, jurisdiction_code: &str) -> Result<RegistryVerdict, RegistryError> {
+        let url = format!("{}/parcels/{}/{}", self.base_url, jurisdiction_code, cadastral_id);
+        let mut request = self.client.get(&url);
+        if let Some(key) = &self.api_key {
+            request = request.header("X-Api-Key", key);
+        }
+
+        let response = request.send().map_err(|e| RegistryError::Request(e.to_string()))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(RegistryVerdict {
+                verified: false,
+                details: "Parcel not found in registry".to_string(),
+            });
+        }
+        if !response.status().is_success() {
+            return Err(RegistryError::Request(format!("Registry responded with status {}", response.status())));
+        }
+
+        let body: HttpRegistryResponse = response.json().map_err(|e| RegistryError::Request(e.to_string()))?;
+        let status = body.status.unwrap_or_else(|| "unknown".to_string());
+        Ok(RegistryVerdict {
+            verified: body.exists && status != "revoked",
+            details: format!("Registry status: {}", status),
+        })
+    }
+}
+
+/// Picks the adapter configured by `LAND_REGISTRY_ADAPTER` (`mock` by default)
+pub fn adapter_from_env() -> Result<Box<dyn RegistryAdapter>, RegistryError> {
+    match env::var("LAND_REGISTRY_ADAPTER").as_deref() {
+        Ok("http") => Ok(Box::new(HttpRegistryAdapter::from_env()?)),
+        Ok("mock") | Err(_) => Ok(Box::new(MockRegistryAdapter::from_env())),
+        Ok(other) => Err(RegistryError::Config(format!("Unknown land registry adapter: {}", other))),
+    }
+}
+
+/// Sends the on-chain `verify_property` attestation signed by the backend's verifier key
+fn submit_verify_property(property_id: &str) -> Result<String, anyhow::Error> {
+    let program_id = Pubkey::from_str(PROGRAM_ID)?;
+    let verifier_base58 = env::var("VERIFIER_KEYPAIR")
+        .map_err(|_| anyhow::anyhow!("VERIFIER_KEYPAIR must be set"))?;
+    let verifier = Keypair::from_bytes(&bs58::decode(&verifier_base58).into_vec()?)?;
+
+    let property_pda = get_property_pubkey(property_id, &program_id)?;
+    let (marketplace_pda, _) = crate::transaction::get_marketplace_info(&program_id)?;
+    let (verifier_pda, _) = Pubkey::find_program_address(
+        &[b"verifier", marketplace_pda.as_ref(), verifier.pubkey().as_ref()],
+        &program_id,
+    );
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(property_pda, false),
+            AccountMeta::new_readonly(verifier_pda, false),
+            AccountMeta::new_readonly(verifier.pubkey(), true),
+        ],
+        data: instruction_discriminator("verify_property").to_vec(),
+    };
+
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx = SolanaTransaction::new_signed_with_payer(
+        &[instruction],
+        Some(&verifier.pubkey()),
+        &[&verifier],
+        blockhash,
+    );
+    let signature = rpc_client.send_and_confirm_transaction(&tx)?;
+    Ok(signature.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct VerifyRegistryRequest {
+    pub cadastral_id: String,
+    pub jurisdiction_code: String,
+}
+
+#[derive(Serialize)]
+pub struct VerificationResponse {
+    pub success: bool,
+    pub message: String,
+    pub verification: Option<PropertyVerification>,
+}
+
+#[derive(Serialize)]
+pub struct VerificationsResponse {
+    pub success: bool,
+    pub message: String,
+    pub verifications: Vec<PropertyVerification>,
+}
+
+/// Checks a property's cadastral ID against the registry and attests it on-chain when it passes
+pub async fn verify_property_registry(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<VerifyRegistryRequest>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let property_id_param = path.into_inner();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    // Only the owner may request verification of their listing
+    if let Err(resp) = organization::require_property_permission(
+        &mut conn,
+        &property_id_param,
+        &wallet_address,
+        Permission::List,
+        "Only the property owner can request verification",
+    ) {
+        return resp;
+    }
+
+    info!("Verifying cadastral ID {} for property {}", data.cadastral_id, property_id_param);
+
+    let cadastral_id = data.cadastral_id.clone();
+    let jurisdiction_code = data.jurisdiction_code.clone();
+    let prop_id = property_id_param.clone();
+    let outcome = web::block(move || {
+        let adapter = adapter_from_env()?;
+        let verdict = adapter.verify(&cadastral_id, &jurisdiction_code)?;
+        let attestation = if verdict.verified {
+            Some(submit_verify_property(&prop_id).map_err(|e| e.to_string()))
+        } else {
+            None
+        };
+        Ok::<_, RegistryError>((adapter.name(), verdict, attestation))
+    })
+    .await;
+
+    let (adapter_name, status, details, attestation_signature) = match outcome {
+        Ok(Ok((name, verdict, None))) => (name, "rejected", verdict.details, None),
+        Ok(Ok((name, verdict, Some(Ok(signature))))) => (name, "verified", verdict.details, Some(signature)),
+        Ok(Ok((name, _, Some(Err(e))))) => (name, "attestation_failed", e, None),
+        Ok(Err(e)) => ("unknown", "error", e.to_string(), None),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    };
+
+    let verification = PropertyVerification {
+        id: Uuid::new_v4(),
+        property_id: property_id_param.clone(),
+        cadastral_id: data.cadastral_id.clone(),
+        jurisdiction_code: data.jurisdiction_code.clone(),
+        adapter: adapter_name.to_string(),
+        status: status.to_string(),
+        details: Some(details),
+        attestation_signature,
+        created_at: Utc::now().naive_utc(),
+    };
+
+    match diesel::insert_into(property_verifications::table)
+        .values(&verification)
+        .execute(&mut conn)
+    {
+        Ok(_) => {
+            info!("Recorded {} verification for property {}", status, property_id_param);
+            HttpResponse::Ok().json(VerificationResponse {
+                success: status == "verified",
+                message: format!("Verification {}", status),
+                verification: Some(verification),
+            })
+        }
+        Err(e) => {
+            error!("Failed to record verification: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to record verification: {}", e))
+        }
+    }
+}
+
+/// Lists registry verification attempts for a property
+pub async fn get_property_verifications(path: web::Path<String>) -> impl Responder {
+    let property_id_param = path.into_inner();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    use crate::schema::property_verifications::dsl::*;
+
+    match property_verifications
+        .filter(property_id.eq(&property_id_param))
+        .order_by(created_at.desc())
+        .load::<PropertyVerification>(&mut conn)
+    {
+        Ok(verifications) => HttpResponse::Ok().json(VerificationsResponse {
+            success: true,
+            message: format!("Successfully retrieved {} verifications", verifications.len()),
+            verifications,
+        }),
+        Err(e) => {
+            error!("Failed to fetch verifications: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch verifications: {}", e))
+        }
+    }
+}
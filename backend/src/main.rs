@@ -19,15 +19,40 @@ use dotenv::dotenv;
 use serde::{self, Deserialize};
 use std::env;
 use tracing::{info, error};
-use tracing_subscriber;
 
 mod auth;
 mod db;
+mod error;
 mod models;
 mod schema;
 mod transaction;
 mod property;
 mod offer;
+mod retry_queue;
+mod outbox;
+mod land_registry;
+mod translation;
+mod market_status;
+mod correction;
+mod allocation;
+mod risk;
+mod receipt;
+mod draft;
+mod title_callback;
+mod portfolio;
+mod organization;
+mod invite;
+mod slug;
+mod changes;
+mod chain_cache;
+mod request_log;
+mod payout;
+mod governance;
+mod usage;
+mod history_archive;
+mod sandbox;
+mod scheduled_listing;
+mod swap;
 
 #[derive(Deserialize)]
 struct AuthRequest {
@@ -83,7 +108,7 @@ async fn main() -> std::io::Result<()> {
         error!("DATABASE_URL not found in environment!");
     }
     
-    if let Ok(_) = env::var("JWT_SECRET") {
+    if env::var("JWT_SECRET").is_ok() {
         info!("JWT_SECRET loaded successfully");
     } else {
         error!("JWT_SECRET not found in environment!");
@@ -107,13 +132,21 @@ async fn main() -> std::io::Result<()> {
         .parse::<u16>()
         .expect("PORT must be a valid u16");
 
+    // Replay DB writes that failed after their transaction was confirmed
+    retry_queue::spawn_worker();
+    // Deliver webhook/notification intents recorded by the handlers
+    outbox::spawn_dispatcher();
+    title_callback::spawn_dispatcher();
+    // Publish listings scheduled for a go-live time
+    scheduled_listing::spawn_publisher();
+
     info!("Starting Real Estate Marketplace server on port {}", port);
 
     HttpServer::new(|| {
         // Configure CORS
         let cors = Cors::default()
             .allow_any_origin()  // In production, you might want to specify specific origins
-            .allowed_methods(vec!["GET", "POST", "PATCH"])
+            .allowed_methods(vec!["GET", "POST", "PATCH", "PUT"])
             .allowed_headers(vec![
                 actix_web::http::header::AUTHORIZATION,
                 actix_web::http::header::ACCEPT,
@@ -122,18 +155,41 @@ async fn main() -> std::io::Result<()> {
             .max_age(3600);
 
         App::new()
+            .wrap(actix_web::middleware::from_fn(usage::track_usage))
+            .wrap(actix_web::middleware::from_fn(request_log::log_request))
             .wrap(cors)  // Add CORS middleware
             .route("/health", web::get().to(health_check))
             .route("/api/auth", web::post().to(authenticate))
             .route("/api/transactions/submit", web::post().to(transaction::submit_transaction))
             .route("/api/blockhash", web::get().to(transaction::get_recent_blockhash))
             .route("/api/instructions/submit", web::post().to(transaction::submit_instructions))
+            .route("/api/transactions/drafts", web::post().to(draft::create_draft))
+            .route("/api/transactions/drafts/{draft_id}", web::get().to(draft::get_draft))
+            .route("/api/transactions/{draft_id}/refresh", web::post().to(draft::refresh_draft))
+            // Id reservations for transactions built in parallel
+            .route("/api/allocations/property-id", web::post().to(allocation::allocate_property_id))
+            .route("/api/allocations/offer-nonce", web::post().to(allocation::allocate_offer_nonce))
             .route("/api/properties", web::get().to(property::get_properties))
+            // Slug lookups are registered ahead of the {property_id} routes
+            .route("/api/properties/slug/{slug}", web::get().to(slug::get_property_by_slug))
+            .route("/api/sitemap.xml", web::get().to(slug::get_sitemap))
+            // Cached on-chain reads, served stale while the RPC is unavailable
+            .route("/api/chain/marketplace", web::get().to(chain_cache::get_marketplace_account))
+            .route("/api/chain/properties/{property_id}", web::get().to(chain_cache::get_property_account))
+            .route("/api/chain/offers/{offer_id}/escrow", web::get().to(chain_cache::get_offer_escrow_account))
+            .route("/api/chain/properties/{property_id}/history/{index}", web::get().to(chain_cache::get_property_history_account))
+            // Changed listings for targeted frontend revalidation
+            .route("/api/changes", web::get().to(changes::get_changes))
             .route("/api/properties/{property_id}", web::get().to(property::get_property))
             // New endpoints
             .route("/api/properties/{property_id}/nft-mint", web::get().to(property::get_property_nft_mint))
             .route("/api/transactions/submit-no-update", web::post().to(transaction::submit_transaction_no_update))
             .route("/api/properties/{property_id}/update", web::patch().to(property::update_property))
+            // Listings held until a go-live time
+            .route("/api/listings/scheduled", web::post().to(scheduled_listing::schedule_listing))
+            .route("/api/listings/scheduled", web::get().to(scheduled_listing::get_my_scheduled_listings))
+            .route("/api/listings/scheduled/{listing_id}", web::patch().to(scheduled_listing::update_scheduled_listing))
+            .route("/api/listings/scheduled/{listing_id}/cancel", web::post().to(scheduled_listing::cancel_scheduled_listing))
             // Offer endpoints
             .route("/api/offers", web::post().to(offer::create_offer))
             .route("/api/offers/my-offers", web::get().to(offer::get_user_offers))
@@ -144,11 +200,69 @@ async fn main() -> std::io::Result<()> {
             .route("/api/transactions/record-sale", web::post().to(transaction::record_property_sale))
             // New endpoint for fetching transaction history
             .route("/api/transactions", web::get().to(transaction::get_transactions))
+            .route("/api/transactions/{transaction_id}/receipt.pdf", web::get().to(receipt::get_transaction_receipt))
+            // Sale records closed on-chain by archive_history, proven by the archive transaction
+            .route("/api/properties/{property_id}/history/archived", web::post().to(history_archive::record_archived_history))
             // New endpoints for our workaround solution
             .route("/api/transactions/complete-transfer", web::post().to(transaction::complete_nft_transfer))
             .route("/api/properties/update-ownership", web::post().to(transaction::update_property_ownership))
             // New endpoint to create escrow token account
             .route("/api/offers/create-escrow-account", web::post().to(transaction::create_escrow_token_account))
+            // Land registry verification
+            .route("/api/properties/{property_id}/verify-registry", web::post().to(land_registry::verify_property_registry))
+            .route("/api/properties/{property_id}/verifications", web::get().to(land_registry::get_property_verifications))
+            // Attribute correction requests
+            .route("/api/properties/{property_id}/corrections", web::post().to(correction::create_correction))
+            .route("/api/properties/{property_id}/corrections", web::get().to(correction::get_property_corrections))
+            .route("/api/corrections", web::get().to(correction::get_corrections))
+            .route("/api/corrections/{correction_id}/review", web::post().to(correction::review_correction))
+            .route("/api/corrections/{correction_id}/prepare", web::post().to(correction::prepare_correction_transaction))
+            .route("/api/corrections/{correction_id}/sign", web::post().to(correction::sign_correction_transaction))
+            // Title/escrow company callbacks
+            .route("/api/properties/{property_id}/title-callback", web::put().to(title_callback::put_title_callback))
+            .route("/api/properties/{property_id}/title-callback", web::delete().to(title_callback::delete_title_callback))
+            .route("/api/properties/{property_id}/title-callback/deliveries", web::get().to(title_callback::get_title_callback_deliveries))
+            // Organizations and their members
+            .route("/api/orgs", web::post().to(organization::create_organization))
+            .route("/api/orgs", web::get().to(organization::get_my_organizations))
+            .route("/api/orgs/{org_id}", web::get().to(organization::get_organization))
+            .route("/api/orgs/{org_id}/members", web::post().to(organization::invite_member))
+            .route("/api/orgs/{org_id}/members/{wallet_address}", web::delete().to(organization::remove_member))
+            .route("/api/orgs/{org_id}/invitation/accept", web::post().to(organization::accept_invitation))
+            // Invite codes and referral tracking
+            .route("/api/invites", web::post().to(invite::create_invite_code))
+            .route("/api/invites", web::get().to(invite::get_my_invite_codes))
+            .route("/api/invites/{code}", web::get().to(invite::get_invite_conversions))
+            .route("/api/invites/{code}/redeem", web::post().to(invite::redeem_invite_code))
+            // Portfolio analytics
+            .route("/api/me/portfolio/performance", web::get().to(portfolio::get_portfolio_performance))
+            .route("/api/me/portfolio/performance/{property_id}", web::get().to(portfolio::get_property_performance))
+            // Request, relay and webhook usage for the authenticated wallet
+            .route("/api/me/usage", web::get().to(usage::get_my_usage))
+            // Seller payout address, confirmed by the wallet and the payout address
+            .route("/api/me/payout-address", web::get().to(payout::get_payout_address))
+            .route("/api/me/payout-address", web::put().to(payout::set_payout_address))
+            .route("/api/properties/{property_id}/payout-address/prepare", web::post().to(payout::prepare_property_payout))
+            // Governance proposals approving offers on DAO-held properties
+            .route("/api/offers/{offer_id}/governance-proposal", web::post().to(governance::create_governance_proposal))
+            .route("/api/governance-proposals/{proposal_id}", web::get().to(governance::get_governance_proposal))
+            .route("/api/governance-proposals/{proposal_id}/address", web::put().to(governance::set_proposal_address))
+            // Property swap deals between two owners, settled by one co-signed transaction
+            .route("/api/swaps", web::post().to(swap::propose_swap))
+            .route("/api/swaps", web::get().to(swap::get_my_swaps))
+            .route("/api/swaps/{swap_id}", web::get().to(swap::get_swap))
+            .route("/api/swaps/{swap_id}/accept", web::post().to(swap::accept_swap))
+            .route("/api/swaps/{swap_id}/cancel", web::post().to(swap::cancel_swap))
+            .route("/api/swaps/{swap_id}/prepare", web::post().to(swap::prepare_swap))
+            .route("/api/swaps/{swap_id}/sign", web::post().to(swap::sign_swap))
+            // Listing translations
+            .route("/api/properties/{property_id}/translations", web::get().to(translation::get_property_translations))
+            .route("/api/properties/{property_id}/translations/{locale}", web::put().to(translation::put_property_translation))
+            // Admin view of the post-confirmation retry queue
+            // Full sale flow on a local validator for integrators
+            .route("/api/sandbox/simulate-sale", web::post().to(sandbox::simulate_sale_endpoint))
+            .route("/api/admin/pending-effects", web::get().to(retry_queue::get_pending_effects))
+            .route("/api/admin/pending-effects/{effect_id}/retry", web::post().to(retry_queue::retry_pending_effect))
     })
     .bind(("127.0.0.1", port))?
     .run()
@@ -0,0 +1,149 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use uuid::Uuid;
+use tracing::error;
+
+use crate::models::{Property, PropertyEvent};
+
+pub const EVENT_LISTED: &str = "listed";
+pub const EVENT_PRICE_CHANGED: &str = "price_changed";
+pub const EVENT_DELISTED: &str = "delisted";
+pub const EVENT_REACTIVATED: &str = "reactivated";
+pub const EVENT_SOLD: &str = "sold";
+
+/// Badge thresholds in days, overridable through the environment
+pub struct BadgeThresholds {
+    pub new_days: i64,
+    pub price_reduced_days: i64,
+    pub back_on_market_days: i64,
+}
+
+impl BadgeThresholds {
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: i64| {
+            env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        BadgeThresholds {
+            new_days: read("BADGE_NEW_DAYS", 7),
+            price_reduced_days: read("BADGE_PRICE_REDUCED_DAYS", 30),
+            back_on_market_days: read("BADGE_BACK_ON_MARKET_DAYS", 14),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct MarketStatus {
+    pub days_on_market: i64,
+    pub badges: Vec<&'static str>,
+}
+
+/// Listing returned to clients together with its derived market status
+#[derive(Serialize)]
+pub struct ListingResponse {
+    #[serde(flatten)]
+    pub property: Property,
+    pub market_status: MarketStatus,
+}
+
+/// Appends an entry to a property's history. Failures are logged, never fatal to the caller.
+pub fn record_event(
+    conn: &mut PgConnection,
+    prop_id: &str,
+    kind: &str,
+    new_price: Option<i64>,
+    old_price: Option<i64>,
+) {
+    use crate::schema::property_events::dsl::*;
+
+    let event = PropertyEvent {
+        id: Uuid::new_v4(),
+        property_id: prop_id.to_string(),
+        event_type: kind.to_string(),
+        price: new_price,
+        previous_price: old_price,
+        created_at: Utc::now().naive_utc(),
+    };
+
+    if let Err(e) = diesel::insert_into(property_events).values(&event).execute(conn) {
+        error!("Failed to record {} event for {}: {}", kind, prop_id, e);
+    }
+}
+
+fn is_market_entry(kind: &str) -> bool {
+    kind == EVENT_LISTED || kind == EVENT_REACTIVATED || kind == EVENT_SOLD
+}
+
+/// Derives days-on-market and badges from a property's chronologically ordered history
+pub fn derive_status(
+    property: &Property,
+    history: &[PropertyEvent],
+    thresholds: &BadgeThresholds,
+    now: NaiveDateTime,
+) -> MarketStatus {
+    if !property.is_active {
+        return MarketStatus::default();
+    }
+
+    // The current market period starts at the latest listing, relisting or resale
+    let entry = history.iter().rev().find(|e| is_market_entry(&e.event_type));
+    let active_since = entry.map(|e| e.created_at).unwrap_or(property.created_at);
+    let days_on_market = (now - active_since).num_days().max(0);
+
+    let mut badges = Vec::new();
+    let within = |ts: NaiveDateTime, days: i64| now - ts < Duration::days(days);
+
+    match entry.map(|e| e.event_type.as_str()) {
+        Some(EVENT_LISTED) | None if days_on_market < thresholds.new_days => badges.push("new"),
+        Some(EVENT_REACTIVATED) | Some(EVENT_SOLD) if within(active_since, thresholds.back_on_market_days) => {
+            badges.push("back_on_market")
+        }
+        _ => {}
+    }
+
+    let reduced = history.iter().any(|e| {
+        e.event_type == EVENT_PRICE_CHANGED
+            && e.created_at >= active_since
+            && within(e.created_at, thresholds.price_reduced_days)
+            && matches!((e.price, e.previous_price), (Some(new), Some(old)) if new < old)
+    });
+    if reduced {
+        badges.push("price_reduced");
+    }
+
+    MarketStatus { days_on_market, badges }
+}
+
+/// Wraps properties with their market status, loading history in a single query
+pub fn with_market_status(conn: &mut PgConnection, props: Vec<Property>) -> Vec<ListingResponse> {
+    use crate::schema::property_events::dsl::*;
+
+    let ids: Vec<String> = props.iter().map(|p| p.property_id.clone()).collect();
+    let events = property_events
+        .filter(property_id.eq_any(&ids))
+        .order_by(created_at.asc())
+        .load::<PropertyEvent>(conn)
+        .unwrap_or_else(|e| {
+            error!("Failed to load property events: {}", e);
+            Vec::new()
+        });
+
+    let mut by_property: HashMap<String, Vec<PropertyEvent>> = HashMap::new();
+    for event in events {
+        by_property.entry(event.property_id.clone()).or_default().push(event);
+    }
+
+    let thresholds = BadgeThresholds::from_env();
+    let now = Utc::now().naive_utc();
+    props
+        .into_iter()
+        .map(|property| {
+            let history = by_property.get(&property.property_id).map(Vec::as_slice).unwrap_or(&[]);
+            let market_status = derive_status(&property, history, &thresholds, now);
+            ListingResponse { property, market_status }
+        })
+        .collect()
+}
@@ -27,6 +27,8 @@ pub struct Property {
     pub updated_at: chrono::NaiveDateTime,
     pub nft_mint_address: String,  // New field
     pub nft_token_account: String, // New field
+    pub organization_id: Option<Uuid>,
+    pub slug: String,
 }
 
 #[derive(Queryable, Insertable, Serialize, Deserialize)]
@@ -40,6 +42,7 @@ pub struct Offer {
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
     pub expiration_time: chrono::NaiveDateTime,
+    pub offer_nonce: i64,
 }
 
 #[derive(Queryable, Insertable, Serialize, Deserialize)]
@@ -51,4 +54,316 @@ pub struct Transaction {
     pub buyer_wallet: String,
     pub price: i64,
     pub timestamp: chrono::NaiveDateTime,
-}
\ No newline at end of file
+    pub signature: Option<String>,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::pending_db_effects)]
+pub struct PendingDbEffect {
+    pub id: Uuid,
+    pub signature: String,
+    pub effect_type: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::outbox_events)]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    pub event_type: String,
+    pub aggregate_id: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+    pub delivered_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::property_verifications)]
+pub struct PropertyVerification {
+    pub id: Uuid,
+    pub property_id: String,
+    pub cadastral_id: String,
+    pub jurisdiction_code: String,
+    pub adapter: String,
+    pub status: String,
+    pub details: Option<String>,
+    pub attestation_signature: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::property_translations)]
+pub struct PropertyTranslation {
+    pub id: Uuid,
+    pub property_id: String,
+    pub locale: String,
+    pub location: String,
+    pub description: Option<String>,
+    pub source: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::property_events)]
+pub struct PropertyEvent {
+    pub id: Uuid,
+    pub property_id: String,
+    pub event_type: String,
+    pub price: Option<i64>,
+    pub previous_price: Option<i64>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::attribute_corrections)]
+pub struct AttributeCorrection {
+    pub id: Uuid,
+    pub property_id: String,
+    pub owner_wallet: String,
+    pub square_feet: Option<i64>,
+    pub bedrooms: Option<i16>,
+    pub bathrooms: Option<i16>,
+    pub reason: Option<String>,
+    pub document_uris: String,
+    pub status: String,
+    pub verifier_wallet: Option<String>,
+    pub review_notes: Option<String>,
+    pub serialized_transaction: Option<String>,
+    pub signature: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::id_reservations)]
+pub struct IdReservation {
+    pub id: Uuid,
+    pub kind: String,
+    pub scope: String,
+    pub value: String,
+    pub reserved_by: String,
+    pub status: String,
+    pub expires_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::risk_screenings)]
+pub struct RiskScreening {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub context: String,
+    pub addresses: String,
+    pub provider: String,
+    pub decision: String,
+    pub matched_address: Option<String>,
+    pub reason: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::transaction_drafts)]
+pub struct TransactionDraft {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub fee_payer: String,
+    pub instructions: String,
+    pub nonce_account: Option<String>,
+    pub serialized_transaction: String,
+    pub blockhash: String,
+    pub last_valid_block_height: Option<i64>,
+    pub refresh_count: i32,
+    pub expires_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::property_callbacks)]
+pub struct PropertyCallback {
+    pub id: Uuid,
+    pub property_id: String,
+    pub owner_wallet: String,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::callback_deliveries)]
+pub struct CallbackDelivery {
+    pub id: Uuid,
+    pub callback_id: Uuid,
+    pub property_id: String,
+    pub event_type: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+    pub delivered_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::organizations)]
+pub struct Organization {
+    pub id: Uuid,
+    pub name: String,
+    pub owner_wallet: String,
+    pub multisig_address: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::organization_members)]
+pub struct OrganizationMember {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub wallet_address: String,
+    pub role: String,
+    pub status: String,
+    pub invited_by: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::invite_codes)]
+pub struct InviteCode {
+    pub id: Uuid,
+    pub code: String,
+    pub owner_wallet: String,
+    pub organization_id: Option<Uuid>,
+    pub max_uses: Option<i32>,
+    pub use_count: i32,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::referral_conversions)]
+pub struct ReferralConversion {
+    pub id: Uuid,
+    pub invite_code_id: Uuid,
+    pub wallet_address: String,
+    pub event_type: String,
+    pub property_id: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::payout_address_changes)]
+pub struct PayoutAddressChange {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub payout_address: String,
+    pub previous_payout_address: Option<String>,
+    pub message: String,
+    pub wallet_signature: String,
+    pub payout_signature: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::governance_proposals)]
+pub struct GovernanceProposal {
+    pub id: Uuid,
+    pub offer_id: Uuid,
+    pub property_id: String,
+    pub governance: String,
+    pub proposal_address: Option<String>,
+    pub min_amount: i64,
+    pub authorization_expires_at: chrono::NaiveDateTime,
+    pub status: String,
+    pub acceptance_transaction: Option<String>,
+    pub created_by: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::api_usage)]
+pub struct ApiUsage {
+    pub wallet_address: String,
+    pub bucket_start: chrono::NaiveDateTime,
+    pub requests: i64,
+    pub client_errors: i64,
+    pub server_errors: i64,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::archived_histories)]
+pub struct ArchivedHistory {
+    pub id: Uuid,
+    pub property_id: String,
+    pub history_address: String,
+    pub transaction_index: i64,
+    pub seller_wallet: String,
+    pub buyer_wallet: String,
+    pub price: i64,
+    pub seller_concession: i64,
+    pub sold_at: chrono::NaiveDateTime,
+    pub archived_at: chrono::NaiveDateTime,
+    pub archive_signature: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::scheduled_listings)]
+pub struct ScheduledListing {
+    pub id: Uuid,
+    pub property_id: String,
+    pub wallet_address: String,
+    /// Signed listing transaction, cleared once it is published or cancelled
+    #[serde(skip_serializing)]
+    pub serialized_transaction: Option<String>,
+    pub metadata: String,
+    pub publish_at: chrono::NaiveDateTime,
+    pub status: String,
+    pub signature: Option<String>,
+    pub last_error: Option<String>,
+    pub published_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::swap_deals)]
+pub struct SwapDeal {
+    pub id: Uuid,
+    pub proposer_wallet: String,
+    pub proposer_property_id: String,
+    pub counterparty_wallet: String,
+    pub counterparty_property_id: String,
+    /// Lamports the proposer pays on top of their property; negative when the counterparty pays
+    pub cash_difference: i64,
+    pub status: String,
+    pub proposer_leg_status: String,
+    pub proposer_leg_signature: Option<String>,
+    pub counterparty_leg_status: String,
+    pub counterparty_leg_signature: Option<String>,
+    /// Prepared swap_properties transaction, carrying whichever signatures are in so far
+    pub serialized_transaction: Option<String>,
+    /// The swap transaction once it has been sent
+    pub signature: Option<String>,
+    pub expires_at: chrono::NaiveDateTime,
+    pub accepted_at: Option<chrono::NaiveDateTime>,
+    pub completed_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
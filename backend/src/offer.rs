@@ -1,22 +1,28 @@
 #![allow(unused_imports)]
 
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse};
 use diesel::prelude::*;
 use chrono::{Utc, Duration};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tracing::{info, error};
 
+use crate::allocation;
 use crate::db;
+use crate::error::ApiError;
 use crate::models::Offer;
+use crate::outbox;
+use crate::title_callback;
+use crate::organization::{self, Permission};
 use crate::schema::offers::dsl::*;
-use crate::transaction::verify_token;
+use crate::transaction::authenticated_wallet;
 
 #[derive(Deserialize)]
 pub struct CreateOfferRequest {
     pub property_id: String,
     pub amount: i64,
     pub expiration_days: i64,
+    pub offer_nonce: Option<i64>, // Nonce used in the on-chain offer PDA seeds
 }
 
 #[derive(Deserialize)]
@@ -48,12 +54,9 @@ pub struct OffersResponse {
 pub async fn create_offer(
     req: HttpRequest,
     data: web::Json<CreateOfferRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     // Add detailed logging for the wallet address
     info!("Creating offer with authenticated wallet: {}", wallet_address);
@@ -73,13 +76,7 @@ pub async fn create_offer(
         error!("No Authorization header found in request");
     }
 
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     info!("Creating new offer for property: {}", data.property_id);
 
@@ -96,25 +93,25 @@ pub async fn create_offer(
         created_at: now,
         updated_at: now,
         expiration_time: expire_time,
+        offer_nonce: data.offer_nonce.unwrap_or(0),
     };
 
-    match diesel::insert_into(offers)
-        .values(&new_offer)
-        .execute(&mut conn)
-    {
-        Ok(_) => {
-            info!("Successfully created offer for property {}", data.property_id);
-            HttpResponse::Ok().json(OfferResponse {
-                success: true,
-                message: "Offer created successfully".to_string(),
-                offer: Some(new_offer),
-            })
-        },
-        Err(e) => {
-            error!("Failed to create offer: {}", e);
-            HttpResponse::InternalServerError().body(format!("Failed to create offer: {}", e))
-        }
-    }
+    // Notification intent is committed atomically with the offer row
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::insert_into(offers)
+            .values(&new_offer)
+            .execute(conn)?;
+        outbox::record(conn, "offer.created", &new_offer.id.to_string(), &new_offer)
+    })
+    .map_err(ApiError::db("Failed to create offer"))?;
+
+    info!("Successfully created offer for property {}", data.property_id);
+    allocation::consume_offer_nonce(&mut conn, &new_offer.property_id, &new_offer.buyer_wallet, new_offer.offer_nonce);
+    Ok(HttpResponse::Ok().json(OfferResponse {
+        success: true,
+        message: "Offer created successfully".to_string(),
+        offer: Some(new_offer),
+    }))
 }
 
 /// Updates an offer's status
@@ -122,118 +119,75 @@ pub async fn update_offer(
     req: HttpRequest,
     path: web::Path<String>,
     data: web::Json<UpdateOfferRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     let offer_id_str = path.into_inner();
-    
+
     // Parse the offer ID string into a UUID
-    let offer_uuid = match Uuid::parse_str(&offer_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return HttpResponse::BadRequest().body("Invalid offer ID format");
-        }
-    };
-    
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let offer_uuid = Uuid::parse_str(&offer_id_str)
+        .map_err(|_| ApiError::InvalidInput("Invalid offer ID format".to_string()))?;
+
+    let mut conn = db::establish_connection()?;
 
     info!("Updating offer with ID: {}", offer_id_str);
 
     // First, find the offer and check ownership
-    let offer_result = offers
+    let offer = offers
         .filter(id.eq(offer_uuid))
-        .first::<Offer>(&mut conn);
-    
-    let offer = match offer_result {
-        Ok(offer) => offer,
-        Err(diesel::result::Error::NotFound) => {
-            info!("Offer not found");
-            return HttpResponse::NotFound().body("Offer not found");
-        },
-        Err(e) => {
-            error!("Failed to fetch offer: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Failed to fetch offer: {}", e));
-        }
-    };
+        .first::<Offer>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch offer"))?
+        .ok_or_else(|| ApiError::NotFound("Offer not found".to_string()))?;
 
     // Verify ownership
     if offer.buyer_wallet != wallet_address {
-        return HttpResponse::Forbidden().body("You don't have permission to update this offer");
+        return Err(ApiError::Forbidden("You don't have permission to update this offer".to_string()));
     }
 
     // Update the offer
     let now = Utc::now().naive_utc();
-    match diesel::update(offers.find(offer_uuid))
+    diesel::update(offers.find(offer_uuid))
         .set((
             status.eq(&data.status),
             updated_at.eq(now),
         ))
         .execute(&mut conn)
-    {
-        Ok(_) => {
-            info!("Successfully updated offer {}", offer_id_str);
-            HttpResponse::Ok().json(OfferResponse {
-                success: true,
-                message: "Offer updated successfully".to_string(),
-                offer: None,
-            })
-        },
-        Err(e) => {
-            error!("Failed to update offer: {}", e);
-            HttpResponse::InternalServerError().body(format!("Failed to update offer: {}", e))
-        }
-    }
+        .map_err(ApiError::db("Failed to update offer"))?;
+
+    info!("Successfully updated offer {}", offer_id_str);
+    Ok(HttpResponse::Ok().json(OfferResponse {
+        success: true,
+        message: "Offer updated successfully".to_string(),
+        offer: None,
+    }))
 }
 
 /// Retrieves all offers made by the current user
-pub async fn get_user_offers(req: HttpRequest) -> impl Responder {
+pub async fn get_user_offers(req: HttpRequest) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     info!("Fetching offers for user: {}", wallet_address);
 
     // Query all offers where buyer_wallet matches the authenticated user
-    let user_offers = match offers
+    let user_offers = offers
         .filter(buyer_wallet.eq(&wallet_address))
         .order_by(created_at.desc())
-        .load::<Offer>(&mut conn) 
-    {
-        Ok(result) => result,
-        Err(e) => {
-            error!("Failed to fetch user offers: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Failed to fetch offers: {}", e));
-        }
-    };
+        .load::<Offer>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch offers"))?;
 
     info!("Found {} offers for user {}", user_offers.len(), wallet_address);
 
     // Return the offers
-    HttpResponse::Ok().json(OffersResponse {
+    Ok(HttpResponse::Ok().json(OffersResponse {
         success: true,
         message: format!("Successfully retrieved {} offers", user_offers.len()),
         offers: user_offers,
-    })
+    }))
 }
 
 /// Endpoint for a property owner to respond to an offer (accept or reject)
@@ -241,178 +195,144 @@ pub async fn respond_to_offer(
     req: HttpRequest,
     path: web::Path<String>,
     data: web::Json<OfferResponseRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     let offer_id_str = path.into_inner();
-    
+
     // Parse the offer ID string into a UUID
-    let offer_uuid = match Uuid::parse_str(&offer_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return HttpResponse::BadRequest().body("Invalid offer ID format");
-        }
-    };
-    
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let offer_uuid = Uuid::parse_str(&offer_id_str)
+        .map_err(|_| ApiError::InvalidInput("Invalid offer ID format".to_string()))?;
+
+    let mut conn = db::establish_connection()?;
 
     info!("Processing offer response for offer ID: {}", offer_id_str);
 
     // First, find the offer
-    let offer_result = offers
+    let offer = offers
         .filter(id.eq(offer_uuid))
-        .first::<Offer>(&mut conn);
-    
-    let offer = match offer_result {
-        Ok(offer) => offer,
-        Err(diesel::result::Error::NotFound) => {
-            info!("Offer not found");
-            return HttpResponse::NotFound().body("Offer not found");
-        },
-        Err(e) => {
-            error!("Failed to fetch offer: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Failed to fetch offer: {}", e));
-        }
-    };
+        .first::<Offer>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch offer"))?
+        .ok_or_else(|| ApiError::NotFound("Offer not found".to_string()))?;
 
     // Find the property to verify ownership
     use crate::schema::properties::dsl::{properties, property_id as prop_id};
     use crate::schema::properties::dsl::owner_wallet;
-    
-    let property_result = properties
-        .filter(prop_id.eq(&offer.property_id))
-        .first::<crate::models::Property>(&mut conn);
-    
-    let property = match property_result {
-        Ok(prop) => prop,
-        Err(diesel::result::Error::NotFound) => {
-            info!("Property not found");
-            return HttpResponse::NotFound().body("Property not found");
-        },
-        Err(e) => {
-            error!("Failed to fetch property: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Failed to fetch property: {}", e));
-        }
-    };
 
-    // Verify that the request is from the property owner
-    if property.owner_wallet != wallet_address {
-        return HttpResponse::Forbidden().body("Only the property owner can respond to offers");
+    let property = properties
+        .filter(prop_id.eq(&offer.property_id))
+        .first::<crate::models::Property>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+
+    // Verify that the request is from the property owner or an org negotiator
+    let permitted = organization::has_property_permission(
+        &mut conn,
+        &property.owner_wallet,
+        property.organization_id,
+        &wallet_address,
+        Permission::Negotiate,
+    )
+    .map_err(ApiError::db("Failed to verify ownership"))?;
+    if !permitted {
+        return Err(ApiError::Forbidden("Only the property owner can respond to offers".to_string()));
     }
 
     // Verify that the offer status is currently pending
     if offer.status != "pending" {
-        return HttpResponse::BadRequest().body("Can only respond to pending offers");
+        return Err(ApiError::InvalidInput("Can only respond to pending offers".to_string()));
     }
 
     // Verify that the requested status is valid
     if data.status != "accepted" && data.status != "rejected" {
-        return HttpResponse::BadRequest().body("Status must be 'accepted' or 'rejected'");
+        return Err(ApiError::InvalidInput("Status must be 'accepted' or 'rejected'".to_string()));
     }
 
     // Update the offer status
     let now = Utc::now().naive_utc();
-    match diesel::update(offers.find(offer_uuid))
-        .set((
-            status.eq(&data.status),
-            updated_at.eq(now),
-        ))
-        .execute(&mut conn)
-    {
-        Ok(_) => {
-            info!("Successfully updated offer {} to status {}", offer_id_str, data.status);
-            
-            // Log transaction signature if present
-            if let Some(signature) = &data.transaction_signature {
-                info!("Blockchain transaction signature: {}", signature);
-            }
-            
-            HttpResponse::Ok().json(OfferResponse {
-                success: true,
-                message: format!("Offer {} successfully", &data.status),
-                offer: None,
-            })
-        },
-        Err(e) => {
-            error!("Failed to update offer: {}", e);
-            HttpResponse::InternalServerError().body(format!("Failed to update offer: {}", e))
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::update(offers.find(offer_uuid))
+            .set((
+                status.eq(&data.status),
+                updated_at.eq(now),
+            ))
+            .execute(conn)?;
+        let payload = serde_json::json!({
+            "offer_id": offer_uuid,
+            "property_id": offer.property_id,
+            "buyer_wallet": offer.buyer_wallet,
+            "seller_wallet": property.owner_wallet,
+            "amount": offer.amount,
+            "expiration_time": offer.expiration_time,
+            "offer_nonce": offer.offer_nonce,
+            "transaction_signature": data.transaction_signature,
+        });
+        if data.status == "accepted" {
+            title_callback::record(
+                conn,
+                &offer.property_id,
+                &property.owner_wallet,
+                title_callback::EVENT_OFFER_ACCEPTED,
+                &payload,
+            )?;
         }
+        outbox::record(conn, &format!("offer.{}", data.status), &offer_id_str, &payload)
+    })
+    .map_err(ApiError::db("Failed to update offer"))?;
+
+    info!("Successfully updated offer {} to status {}", offer_id_str, data.status);
+
+    // Log transaction signature if present
+    if let Some(signature) = &data.transaction_signature {
+        info!("Blockchain transaction signature: {}", signature);
     }
+
+    Ok(HttpResponse::Ok().json(OfferResponse {
+        success: true,
+        message: format!("Offer {} successfully", &data.status),
+        offer: None,
+    }))
 }
 
 /// Retrieves all offers for a specific property
 pub async fn get_property_offers(
     req: HttpRequest,
     path: web::Path<String>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     let property_id_str = path.into_inner();
-    
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
 
-    // Verify property ownership (only owners can see offers for their property)
-    use crate::schema::properties::dsl::{properties, property_id as prop_id};
-    use crate::schema::properties::dsl::owner_wallet;
-    
-    let is_owner = match properties
-        .filter(prop_id.eq(&property_id_str))
-        .filter(owner_wallet.eq(&wallet_address))
-        .first::<crate::models::Property>(&mut conn)
-    {
-        Ok(_) => true,
-        Err(diesel::result::Error::NotFound) => false,
-        Err(e) => {
-            error!("Failed to check property ownership: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Failed to verify ownership: {}", e));
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
-    if !is_owner {
-        return HttpResponse::Forbidden().body("Only the property owner can view property offers");
-    }
+    // Verify property ownership (only owners and their org members can see offers for their property)
+    organization::check_property_permission(
+        &mut conn,
+        &property_id_str,
+        &wallet_address,
+        Permission::View,
+        "Only the property owner can view property offers",
+    )?;
 
     info!("Fetching offers for property: {}", property_id_str);
 
     // Query all offers for the specific property
-    let property_offers = match offers
+    let property_offers = offers
         .filter(property_id.eq(&property_id_str))
         .order_by(created_at.desc())
-        .load::<Offer>(&mut conn) 
-    {
-        Ok(result) => result,
-        Err(e) => {
-            error!("Failed to fetch property offers: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Failed to fetch offers: {}", e));
-        }
-    };
+        .load::<Offer>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch offers"))?;
 
     info!("Found {} offers for property {}", property_offers.len(), property_id_str);
 
     // Return the offers
-    HttpResponse::Ok().json(OffersResponse {
+    Ok(HttpResponse::Ok().json(OffersResponse {
         success: true,
         message: format!("Successfully retrieved {} offers", property_offers.len()),
         offers: property_offers,
-    })
-} 
\ No newline at end of file
+    }))
+}
@@ -0,0 +1,482 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, ResponseError};
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use uuid::Uuid;
+use tracing::{info, error};
+
+use crate::db;
+use crate::error::ApiError;
+use crate::models::{Organization, OrganizationMember};
+use crate::schema::{organization_members, organizations, properties};
+use crate::transaction::verify_token;
+
+pub const ROLE_LISTER: &str = "lister";
+pub const ROLE_NEGOTIATOR: &str = "negotiator";
+pub const ROLE_VIEWER: &str = "viewer";
+
+const STATUS_INVITED: &str = "invited";
+const STATUS_ACTIVE: &str = "active";
+
+/// What a wallet wants to do with a property
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// See offers and other owner-only details
+    View,
+    /// Edit the listing, its translations, registry verification and corrections
+    List,
+    /// Respond to offers
+    Negotiate,
+}
+
+fn role_allows(role: &str, permission: Permission) -> bool {
+    match permission {
+        Permission::View => matches!(role, ROLE_LISTER | ROLE_NEGOTIATOR | ROLE_VIEWER),
+        Permission::List => role == ROLE_LISTER,
+        Permission::Negotiate => role == ROLE_NEGOTIATOR,
+    }
+}
+
+/// Whether `wallet` may act on a property owned by `owner` on-chain. Org properties also admit
+/// the org owner and active members whose role grants the permission.
+pub fn has_property_permission(
+    conn: &mut PgConnection,
+    owner: &str,
+    organization_id: Option<Uuid>,
+    wallet: &str,
+    permission: Permission,
+) -> QueryResult<bool> {
+    if owner == wallet {
+        return Ok(true);
+    }
+    let Some(org_id) = organization_id else {
+        return Ok(false);
+    };
+
+    let org_owner = organizations::table
+        .find(org_id)
+        .select(organizations::owner_wallet)
+        .first::<String>(conn)
+        .optional()?;
+    if org_owner.as_deref() == Some(wallet) {
+        return Ok(true);
+    }
+
+    let role = organization_members::table
+        .filter(organization_members::organization_id.eq(org_id))
+        .filter(organization_members::wallet_address.eq(wallet))
+        .filter(organization_members::status.eq(STATUS_ACTIVE))
+        .select(organization_members::role)
+        .first::<String>(conn)
+        .optional()?;
+    Ok(role.is_some_and(|role| role_allows(&role, permission)))
+}
+
+/// Loads a property's owner and checks `wallet` against it. Callers get the on-chain owner back
+/// so org-held properties can be addressed by their multisig.
+pub fn check_property_permission(
+    conn: &mut PgConnection,
+    prop_id: &str,
+    wallet: &str,
+    permission: Permission,
+    denied: &'static str,
+) -> Result<String, ApiError> {
+    let (owner, organization_id) = properties::table
+        .filter(properties::property_id.eq(prop_id))
+        .select((properties::owner_wallet, properties::organization_id))
+        .first::<(String, Option<Uuid>)>(conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+
+    if has_property_permission(conn, &owner, organization_id, wallet, permission)
+        .map_err(ApiError::db("Failed to check permissions"))?
+    {
+        Ok(owner)
+    } else {
+        Err(ApiError::Forbidden(denied.to_string()))
+    }
+}
+
+/// `check_property_permission` for handlers that still build their own responses
+pub fn require_property_permission(
+    conn: &mut PgConnection,
+    prop_id: &str,
+    wallet: &str,
+    permission: Permission,
+    denied: &'static str,
+) -> Result<String, HttpResponse> {
+    check_property_permission(conn, prop_id, wallet, permission, denied).map_err(|e| e.error_response())
+}
+
+/// Organization whose multisig is `wallet`, so properties it receives on-chain are org-owned here too
+pub fn organization_for_owner(conn: &mut PgConnection, wallet: &str) -> Option<Uuid> {
+    organizations::table
+        .filter(organizations::multisig_address.eq(wallet))
+        .select(organizations::id)
+        .first::<Uuid>(conn)
+        .optional()
+        .unwrap_or_else(|e| {
+            error!("Failed to look up organization for {}: {}", wallet, e);
+            None
+        })
+}
+
+#[derive(Deserialize)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+    pub multisig_address: String,
+}
+
+#[derive(Deserialize)]
+pub struct InviteMemberRequest {
+    pub wallet_address: String,
+    pub role: String,
+}
+
+#[derive(Serialize)]
+pub struct OrganizationResponse {
+    pub success: bool,
+    pub message: String,
+    pub organization: Option<Organization>,
+    pub members: Vec<OrganizationMember>,
+}
+
+#[derive(Serialize)]
+pub struct OrganizationsResponse {
+    pub success: bool,
+    pub message: String,
+    pub organizations: Vec<Organization>,
+}
+
+#[derive(Serialize)]
+pub struct MemberResponse {
+    pub success: bool,
+    pub message: String,
+    pub member: Option<OrganizationMember>,
+}
+
+fn connect() -> Result<PgConnection, HttpResponse> {
+    db::establish_connection().map_err(|e| {
+        error!("Failed to connect to database: {}", e);
+        HttpResponse::InternalServerError().body("Database connection failed")
+    })
+}
+
+fn load_organization(conn: &mut PgConnection, org_id: &str) -> Result<Organization, HttpResponse> {
+    let org_uuid = Uuid::parse_str(org_id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid organization ID format"))?;
+
+    organizations::table
+        .find(org_uuid)
+        .first::<Organization>(conn)
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => HttpResponse::NotFound().body("Organization not found"),
+            e => {
+                error!("Failed to fetch organization: {}", e);
+                HttpResponse::InternalServerError().body(format!("Failed to fetch organization: {}", e))
+            }
+        })
+}
+
+fn load_owned_organization(conn: &mut PgConnection, org_id: &str, wallet: &str) -> Result<Organization, HttpResponse> {
+    let org = load_organization(conn, org_id)?;
+    if org.owner_wallet != wallet {
+        return Err(HttpResponse::Forbidden().body("Only the organization owner can do this"));
+    }
+    Ok(org)
+}
+
+pub async fn create_organization(req: HttpRequest, data: web::Json<CreateOrganizationRequest>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let name = data.name.trim();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().body("Organization name is required");
+    }
+    if Pubkey::from_str(&data.multisig_address).is_err() {
+        return HttpResponse::BadRequest().body("Invalid multisig address");
+    }
+
+    let mut conn = match connect() {
+        Ok(conn) => conn,
+        Err(resp) => return resp,
+    };
+
+    let now = Utc::now().naive_utc();
+    let org = Organization {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        owner_wallet: wallet_address,
+        multisig_address: data.multisig_address.clone(),
+        created_at: now,
+        updated_at: now,
+    };
+
+    let result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let org = diesel::insert_into(organizations::table)
+            .values(&org)
+            .get_result::<Organization>(conn)?;
+        // Properties the multisig already holds become org-owned
+        diesel::update(properties::table.filter(properties::owner_wallet.eq(&org.multisig_address)))
+            .set(properties::organization_id.eq(Some(org.id)))
+            .execute(conn)?;
+        Ok(org)
+    });
+
+    match result {
+        Ok(org) => {
+            info!("Created organization {} owned by {}", org.id, org.owner_wallet);
+            HttpResponse::Ok().json(OrganizationResponse {
+                success: true,
+                message: "Organization created".to_string(),
+                organization: Some(org),
+                members: Vec::new(),
+            })
+        }
+        Err(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)) => {
+            HttpResponse::Conflict().body("Multisig is already registered to an organization")
+        }
+        Err(e) => {
+            error!("Failed to create organization: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to create organization: {}", e))
+        }
+    }
+}
+
+/// Organizations the caller owns or has joined
+pub async fn get_my_organizations(req: HttpRequest) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match connect() {
+        Ok(conn) => conn,
+        Err(resp) => return resp,
+    };
+
+    let joined = organization_members::table
+        .filter(organization_members::wallet_address.eq(&wallet_address))
+        .filter(organization_members::status.eq(STATUS_ACTIVE))
+        .select(organization_members::organization_id);
+
+    match organizations::table
+        .filter(organizations::owner_wallet.eq(&wallet_address).or(organizations::id.eq_any(joined)))
+        .order_by(organizations::created_at.asc())
+        .load::<Organization>(&mut conn)
+    {
+        Ok(orgs) => HttpResponse::Ok().json(OrganizationsResponse {
+            success: true,
+            message: format!("Found {} organizations", orgs.len()),
+            organizations: orgs,
+        }),
+        Err(e) => {
+            error!("Failed to fetch organizations: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch organizations: {}", e))
+        }
+    }
+}
+
+/// Organization details with its members, visible to the owner and active members
+pub async fn get_organization(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match connect() {
+        Ok(conn) => conn,
+        Err(resp) => return resp,
+    };
+    let org = match load_organization(&mut conn, &path.into_inner()) {
+        Ok(org) => org,
+        Err(resp) => return resp,
+    };
+
+    let members = match organization_members::table
+        .filter(organization_members::organization_id.eq(org.id))
+        .order_by(organization_members::created_at.asc())
+        .load::<OrganizationMember>(&mut conn)
+    {
+        Ok(members) => members,
+        Err(e) => {
+            error!("Failed to fetch organization members: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to fetch members: {}", e));
+        }
+    };
+
+    let is_member = members
+        .iter()
+        .any(|m| m.wallet_address == wallet_address && m.status == STATUS_ACTIVE);
+    if org.owner_wallet != wallet_address && !is_member {
+        return HttpResponse::Forbidden().body("Only organization members can view it");
+    }
+
+    HttpResponse::Ok().json(OrganizationResponse {
+        success: true,
+        message: "Organization found".to_string(),
+        organization: Some(org),
+        members,
+    })
+}
+
+/// Invites a wallet with a role, or changes the role of an existing member
+pub async fn invite_member(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<InviteMemberRequest>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    if !matches!(data.role.as_str(), ROLE_LISTER | ROLE_NEGOTIATOR | ROLE_VIEWER) {
+        return HttpResponse::BadRequest().body("Role must be lister, negotiator or viewer");
+    }
+    if Pubkey::from_str(&data.wallet_address).is_err() {
+        return HttpResponse::BadRequest().body("Invalid member wallet address");
+    }
+
+    let mut conn = match connect() {
+        Ok(conn) => conn,
+        Err(resp) => return resp,
+    };
+    let org = match load_owned_organization(&mut conn, &path.into_inner(), &wallet_address) {
+        Ok(org) => org,
+        Err(resp) => return resp,
+    };
+    if data.wallet_address == org.owner_wallet {
+        return HttpResponse::BadRequest().body("The organization owner already has every permission");
+    }
+
+    let now = Utc::now().naive_utc();
+    let member = OrganizationMember {
+        id: Uuid::new_v4(),
+        organization_id: org.id,
+        wallet_address: data.wallet_address.clone(),
+        role: data.role.clone(),
+        status: STATUS_INVITED.to_string(),
+        invited_by: wallet_address,
+        created_at: now,
+        updated_at: now,
+    };
+
+    match diesel::insert_into(organization_members::table)
+        .values(&member)
+        .on_conflict((organization_members::organization_id, organization_members::wallet_address))
+        .do_update()
+        .set((
+            organization_members::role.eq(&data.role),
+            organization_members::updated_at.eq(now),
+        ))
+        .get_result::<OrganizationMember>(&mut conn)
+    {
+        Ok(member) => {
+            info!("{} is {} of organization {} ({})", member.wallet_address, member.role, org.id, member.status);
+            HttpResponse::Ok().json(MemberResponse {
+                success: true,
+                message: format!("Member {}", member.status),
+                member: Some(member),
+            })
+        }
+        Err(e) => {
+            error!("Failed to invite member: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to invite member: {}", e))
+        }
+    }
+}
+
+/// Invited wallet joins the organization
+pub async fn accept_invitation(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let mut conn = match connect() {
+        Ok(conn) => conn,
+        Err(resp) => return resp,
+    };
+    let org = match load_organization(&mut conn, &path.into_inner()) {
+        Ok(org) => org,
+        Err(resp) => return resp,
+    };
+
+    match diesel::update(
+        organization_members::table
+            .filter(organization_members::organization_id.eq(org.id))
+            .filter(organization_members::wallet_address.eq(&wallet_address))
+            .filter(organization_members::status.eq(STATUS_INVITED)),
+    )
+    .set((
+        organization_members::status.eq(STATUS_ACTIVE),
+        organization_members::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .get_result::<OrganizationMember>(&mut conn)
+    {
+        Ok(member) => {
+            info!("{} joined organization {}", wallet_address, org.id);
+            HttpResponse::Ok().json(MemberResponse {
+                success: true,
+                message: "Invitation accepted".to_string(),
+                member: Some(member),
+            })
+        }
+        Err(diesel::result::Error::NotFound) => HttpResponse::NotFound().body("No pending invitation"),
+        Err(e) => {
+            error!("Failed to accept invitation: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to accept invitation: {}", e))
+        }
+    }
+}
+
+/// Owner removes a member, or a member leaves
+pub async fn remove_member(req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+    let (org_id, member_wallet) = path.into_inner();
+
+    let mut conn = match connect() {
+        Ok(conn) => conn,
+        Err(resp) => return resp,
+    };
+    let org = match load_organization(&mut conn, &org_id) {
+        Ok(org) => org,
+        Err(resp) => return resp,
+    };
+    if org.owner_wallet != wallet_address && member_wallet != wallet_address {
+        return HttpResponse::Forbidden().body("Only the organization owner can remove other members");
+    }
+
+    match diesel::delete(
+        organization_members::table
+            .filter(organization_members::organization_id.eq(org.id))
+            .filter(organization_members::wallet_address.eq(&member_wallet)),
+    )
+    .execute(&mut conn)
+    {
+        Ok(0) => HttpResponse::NotFound().body("Member not found"),
+        Ok(_) => {
+            info!("{} removed from organization {}", member_wallet, org.id);
+            HttpResponse::Ok().json(MemberResponse {
+                success: true,
+                message: "Member removed".to_string(),
+                member: None,
+            })
+        }
+        Err(e) => {
+            error!("Failed to remove member: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to remove member: {}", e))
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::env;
+use uuid::Uuid;
+use tracing::{info, error, warn};
+
+use crate::db;
+use crate::models::OutboxEvent;
+use crate::schema::outbox_events;
+
+/// How often the dispatcher polls for undelivered events
+const POLL_INTERVAL_SECS: u64 = 10;
+/// Upper bound for the exponential backoff between delivery attempts
+const MAX_BACKOFF_SECS: i64 = 3600;
+/// Maximum number of events delivered per poll
+const BATCH_SIZE: i64 = 50;
+
+/// Records a notification intent. Call this inside the same `conn.transaction`
+/// as the state change so the intent is never lost or sent for a rolled back write.
+pub fn record<T: Serialize>(
+    conn: &mut PgConnection,
+    event_type: &str,
+    aggregate_id: &str,
+    payload: &T,
+) -> QueryResult<()> {
+    let payload = serde_json::to_string(payload)
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+    let now = Utc::now().naive_utc();
+
+    let event = OutboxEvent {
+        id: Uuid::new_v4(),
+        event_type: event_type.to_string(),
+        aggregate_id: aggregate_id.to_string(),
+        payload,
+        status: "pending".to_string(),
+        attempts: 0,
+        last_error: None,
+        next_attempt_at: now,
+        created_at: now,
+        delivered_at: None,
+    };
+
+    diesel::insert_into(outbox_events::table)
+        .values(&event)
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn next_backoff(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(0, 16) as u32;
+    let secs = (POLL_INTERVAL_SECS as i64).saturating_mul(1i64 << exponent);
+    Duration::seconds(secs.min(MAX_BACKOFF_SECS))
+}
+
+fn deliver(client: &reqwest::blocking::Client, url: &str, event: &OutboxEvent) -> Result<(), String> {
+    let payload: serde_json::Value = serde_json::from_str(&event.payload).map_err(|e| e.to_string())?;
+    let body = serde_json::json!({
+        "id": event.id,
+        "event_type": event.event_type,
+        "aggregate_id": event.aggregate_id,
+        "payload": payload,
+        "created_at": event.created_at,
+    });
+
+    // Receivers deduplicate on the event id since delivery is at-least-once
+    let response = client
+        .post(url)
+        .header("X-Outbox-Event-Id", event.id.to_string())
+        .json(&body)
+        .send()
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Webhook responded with status {}", response.status()))
+    }
+}
+
+/// Delivers every due event once, returning how many were delivered
+pub fn dispatch_due(conn: &mut PgConnection, client: &reqwest::blocking::Client, url: &str) -> QueryResult<usize> {
+    use crate::schema::outbox_events::dsl::*;
+
+    let now = Utc::now().naive_utc();
+    let due = outbox_events
+        .filter(status.eq("pending"))
+        .filter(next_attempt_at.le(now))
+        .order_by(created_at.asc())
+        .limit(BATCH_SIZE)
+        .load::<OutboxEvent>(conn)?;
+
+    let mut delivered = 0;
+    for event in due {
+        let now = Utc::now().naive_utc();
+        match deliver(client, url, &event) {
+            Ok(()) => {
+                diesel::update(outbox_events.find(event.id))
+                    .set((
+                        status.eq("delivered"),
+                        attempts.eq(event.attempts + 1),
+                        last_error.eq(None::<String>),
+                        delivered_at.eq(Some(now)),
+                    ))
+                    .execute(conn)?;
+                delivered += 1;
+            }
+            Err(e) => {
+                let tries = event.attempts + 1;
+                diesel::update(outbox_events.find(event.id))
+                    .set((
+                        attempts.eq(tries),
+                        last_error.eq(Some(e.clone())),
+                        next_attempt_at.eq(now + next_backoff(tries)),
+                    ))
+                    .execute(conn)?;
+                warn!("Delivery {} of outbox event {} failed: {}", tries, event.id, e);
+            }
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Spawns the poller that delivers outbox events to `NOTIFICATION_WEBHOOK_URL`.
+/// Events keep accumulating while the URL is unset and are sent once it is configured.
+pub fn spawn_dispatcher() {
+    let url = match env::var("NOTIFICATION_WEBHOOK_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            warn!("NOTIFICATION_WEBHOOK_URL not set, outbox events will not be delivered");
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(POLL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let url = url.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let client = reqwest::blocking::Client::builder()
+                    .timeout(std::time::Duration::from_secs(10))
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                let mut conn = db::establish_connection().map_err(|e| e.to_string())?;
+                dispatch_due(&mut conn, &client, &url).map_err(|e| e.to_string())
+            })
+            .await;
+
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(count)) => info!("Delivered {} outbox events", count),
+                Ok(Err(e)) => error!("Outbox dispatch failed: {}", e),
+                Err(e) => error!("Outbox dispatcher panicked: {}", e),
+            }
+        }
+    });
+}
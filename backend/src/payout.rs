@@ -0,0 +1,260 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose, Engine};
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    system_program,
+    transaction::Transaction as SolanaTransaction,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+use tracing::info;
+
+use crate::auth;
+use crate::db;
+use crate::error::ApiError;
+use crate::models::{PayoutAddressChange, Property};
+use crate::schema::{payout_address_changes, properties};
+use crate::transaction::{authenticated_wallet, get_property_pubkey, instruction_discriminator, PROGRAM_ID};
+
+/// How old a signed confirmation may be when it is submitted
+const CONFIRMATION_TTL_SECS: i64 = 5 * 60;
+/// Tolerated clock skew for confirmations dated in the future
+const CONFIRMATION_SKEW_SECS: i64 = 60;
+
+#[derive(Deserialize)]
+pub struct SetPayoutAddressRequest {
+    pub payout_address: String,
+    /// Unix timestamp embedded in the signed confirmation message
+    pub timestamp: i64,
+    /// Base58 signature of the confirmation message by the authenticated wallet
+    pub wallet_signature: String,
+    /// Base58 signature of the same message by the payout address
+    pub payout_signature: String,
+}
+
+#[derive(Serialize)]
+pub struct PayoutAddressResponse {
+    pub success: bool,
+    pub message: String,
+    /// None when proceeds go to the wallet itself
+    pub payout_address: Option<String>,
+    pub history: Vec<PayoutAddressChange>,
+}
+
+#[derive(Serialize)]
+pub struct PreparedPayoutTransaction {
+    pub success: bool,
+    pub message: String,
+    pub payout_address: Option<String>,
+    /// Base64 unsigned transaction for the owner to sign
+    pub serialized_transaction: String,
+}
+
+/// Message both the wallet and the new payout address sign to confirm a change
+pub fn confirmation_message(wallet: &str, payout_address: &str, timestamp: i64) -> String {
+    format!(
+        "Confirm payout address {} for wallet {} at {}",
+        payout_address, wallet, timestamp
+    )
+}
+
+/// The wallet's current payout address, or None when proceeds go to the wallet itself
+pub fn current_payout_address(conn: &mut PgConnection, wallet: &str) -> QueryResult<Option<String>> {
+    let latest = payout_address_changes::table
+        .filter(payout_address_changes::wallet_address.eq(wallet))
+        .order(payout_address_changes::created_at.desc())
+        .select(payout_address_changes::payout_address)
+        .first::<String>(conn)
+        .optional()?;
+    Ok(latest.filter(|payout| payout != wallet))
+}
+
+/// Sets where the wallet's sale proceeds are paid. Setting it back to the wallet itself clears it.
+pub async fn set_payout_address(
+    req: HttpRequest,
+    data: web::Json<SetPayoutAddressRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+
+    Pubkey::from_str(&data.payout_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid payout address".to_string()))?;
+
+    let age = Utc::now().timestamp() - data.timestamp;
+    if !(-CONFIRMATION_SKEW_SECS..=CONFIRMATION_TTL_SECS).contains(&age) {
+        return Err(ApiError::InvalidInput("Confirmation has expired".to_string()));
+    }
+
+    let message = confirmation_message(&wallet_address, &data.payout_address, data.timestamp);
+    if !auth::verify_wallet_signature(&wallet_address, &data.wallet_signature, &message) {
+        return Err(ApiError::Unauthorized("Invalid wallet signature".to_string()));
+    }
+    if !auth::verify_wallet_signature(&data.payout_address, &data.payout_signature, &message) {
+        return Err(ApiError::Unauthorized("Invalid payout address signature".to_string()));
+    }
+
+    let mut conn = db::establish_connection()?;
+    let previous = current_payout_address(&mut conn, &wallet_address)
+        .map_err(ApiError::db("Failed to fetch payout address"))?;
+
+    let change = PayoutAddressChange {
+        id: Uuid::new_v4(),
+        wallet_address: wallet_address.clone(),
+        payout_address: data.payout_address.clone(),
+        previous_payout_address: previous,
+        message,
+        wallet_signature: data.wallet_signature.clone(),
+        payout_signature: data.payout_signature.clone(),
+        created_at: Utc::now().naive_utc(),
+    };
+
+    // The unique wallet signature stops a captured confirmation from being replayed
+    diesel::insert_into(payout_address_changes::table)
+        .values(&change)
+        .execute(&mut conn)
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _) => {
+                ApiError::InvalidInput("Confirmation has already been used".to_string())
+            }
+            e => ApiError::db("Failed to store payout address")(e),
+        })?;
+
+    info!("Payout address for {} set to {}", wallet_address, data.payout_address);
+    payout_address_response(&mut conn, &wallet_address, "Payout address updated")
+}
+
+/// Returns the wallet's current payout address and its change history
+pub async fn get_payout_address(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+    payout_address_response(&mut conn, &wallet_address, "Payout address retrieved")
+}
+
+fn payout_address_response(
+    conn: &mut PgConnection,
+    wallet_address: &str,
+    message: &str,
+) -> Result<HttpResponse, ApiError> {
+    let history = payout_address_changes::table
+        .filter(payout_address_changes::wallet_address.eq(wallet_address))
+        .order(payout_address_changes::created_at.desc())
+        .load::<PayoutAddressChange>(conn)
+        .map_err(ApiError::db("Failed to fetch payout address history"))?;
+    let payout_address = history
+        .first()
+        .map(|change| change.payout_address.clone())
+        .filter(|payout| payout != wallet_address);
+
+    Ok(HttpResponse::Ok().json(PayoutAddressResponse {
+        success: true,
+        message: message.to_string(),
+        payout_address,
+        history,
+    }))
+}
+
+/// Builds the unsigned transaction that points a property's on-chain payout address at the
+/// owner's stored payout address, or clears it, so settlement pays the right wallet
+fn build_payout_transaction(
+    property_id: &str,
+    owner: &Pubkey,
+    payout_address: Option<&Pubkey>,
+) -> Result<SolanaTransaction, anyhow::Error> {
+    let program_id = Pubkey::from_str(PROGRAM_ID)?;
+    let property_pda = get_property_pubkey(property_id, &program_id)?;
+    let (payout_address_pda, _) = Pubkey::find_program_address(
+        &[b"payout_address", property_pda.as_ref()],
+        &program_id,
+    );
+
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+    let instruction = match payout_address {
+        Some(beneficiary) => {
+            let mut data = instruction_discriminator("set_payout_address").to_vec();
+            data.extend_from_slice(beneficiary.as_ref());
+            Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(property_pda, false),
+                    AccountMeta::new(payout_address_pda, false),
+                    AccountMeta::new(*owner, true),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+                data,
+            }
+        }
+        None => {
+            if rpc_client.get_account(&payout_address_pda).is_err() {
+                return Err(anyhow::anyhow!("Property has no payout address to clear"));
+            }
+            Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(property_pda, false),
+                    AccountMeta::new(payout_address_pda, false),
+                    AccountMeta::new(*owner, true),
+                ],
+                data: instruction_discriminator("clear_payout_address").to_vec(),
+            }
+        }
+    };
+
+    let mut tx = SolanaTransaction::new_unsigned(Message::new(&[instruction], Some(owner)));
+    tx.message.recent_blockhash = rpc_client.get_latest_blockhash()?;
+    Ok(tx)
+}
+
+/// Prepares the transaction that applies the owner's payout address to one of their properties.
+/// Sales settled after it lands pay the seller's proceeds to that address.
+pub async fn prepare_property_payout(
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let property_id_param = path.into_inner();
+
+    let mut conn = db::establish_connection()?;
+    let property = properties::table
+        .filter(properties::property_id.eq(&property_id_param))
+        .first::<Property>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+
+    // The on-chain instruction must be signed by the owner wallet itself
+    if property.owner_wallet != wallet_address {
+        return Err(ApiError::Forbidden("Only the property owner can set its payout address".to_string()));
+    }
+
+    let payout_address = current_payout_address(&mut conn, &wallet_address)
+        .map_err(ApiError::db("Failed to fetch payout address"))?;
+    let owner = Pubkey::from_str(&wallet_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid wallet address".to_string()))?;
+    let beneficiary = payout_address
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|_| ApiError::Internal("Stored payout address is invalid".to_string()))?;
+
+    let property_id_owned = property_id_param.clone();
+    let tx = web::block(move || build_payout_transaction(&property_id_owned, &owner, beneficiary.as_ref()))
+        .await?
+        .map_err(|e| ApiError::InvalidInput(format!("Failed to prepare payout transaction: {}", e)))?;
+    let serialized_transaction = bincode::serialize(&tx)
+        .map(|bytes| general_purpose::STANDARD.encode(bytes))
+        .map_err(|e| ApiError::Internal(format!("Failed to serialize transaction: {}", e)))?;
+
+    info!("Prepared payout transaction for property {}", property_id_param);
+    Ok(HttpResponse::Ok().json(PreparedPayoutTransaction {
+        success: true,
+        message: "Payout transaction prepared".to_string(),
+        payout_address,
+        serialized_transaction,
+    }))
+}
@@ -0,0 +1,368 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::error;
+
+use crate::db;
+use crate::market_status::EVENT_PRICE_CHANGED;
+use crate::models::{Property, PropertyEvent, Transaction};
+use crate::schema::{properties, property_events, transactions};
+use crate::transaction::verify_token;
+
+const DAYS_PER_YEAR: f64 = 365.25;
+
+#[derive(Serialize, Clone)]
+pub struct ValuationMark {
+    pub at: NaiveDateTime,
+    pub value: i64,
+    /// purchase, price_change, sale or current
+    pub source: &'static str,
+}
+
+/// One holding period of a property: bought (or originally listed) by the wallet, then sold or still held
+#[derive(Serialize)]
+pub struct PositionPerformance {
+    pub property_id: String,
+    pub status: &'static str,
+    pub acquired_at: Option<NaiveDateTime>,
+    /// None when the wallet listed the property itself, so there is no purchase to measure against
+    pub purchase_price: Option<i64>,
+    pub sold_at: Option<NaiveDateTime>,
+    pub sale_price: Option<i64>,
+    /// Current asking price, used as the valuation estimate for held properties
+    pub current_valuation: Option<i64>,
+    pub unrealized_gain: Option<i64>,
+    pub realized_pnl: Option<i64>,
+    pub holding_return: Option<f64>,
+    pub annualized_return: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marks: Option<Vec<ValuationMark>>,
+}
+
+#[derive(Serialize)]
+pub struct PortfolioPerformance {
+    pub wallet_address: String,
+    pub held_count: usize,
+    pub sold_count: usize,
+    /// Purchase prices of held properties with a known purchase
+    pub cost_basis: i64,
+    pub current_valuation: i64,
+    pub unrealized_gain: i64,
+    pub realized_pnl: i64,
+    pub time_weighted_return: Option<f64>,
+    pub annualized_time_weighted_return: Option<f64>,
+    pub positions: Vec<PositionPerformance>,
+}
+
+#[derive(Serialize)]
+pub struct PortfolioResponse {
+    pub success: bool,
+    pub message: String,
+    pub performance: Option<PortfolioPerformance>,
+}
+
+#[derive(Serialize)]
+pub struct PositionsResponse {
+    pub success: bool,
+    pub message: String,
+    pub positions: Vec<PositionPerformance>,
+}
+
+struct Lot {
+    property_id: String,
+    cost: Option<i64>,
+    acquired_at: Option<NaiveDateTime>,
+    proceeds: Option<i64>,
+    sold_at: Option<NaiveDateTime>,
+}
+
+fn annualize(total_return: f64, from: NaiveDateTime, to: NaiveDateTime) -> Option<f64> {
+    let days = (to - from).num_seconds() as f64 / 86_400.0;
+    if days < 1.0 || total_return <= -1.0 {
+        return None;
+    }
+    Some((1.0 + total_return).powf(DAYS_PER_YEAR / days) - 1.0)
+}
+
+/// Pairs the wallet's purchases with its later sales of the same property, in settlement order
+fn build_lots(wallet: &str, history: &[Transaction], held: &[Property]) -> Vec<Lot> {
+    let mut lots: Vec<Lot> = Vec::new();
+
+    for tx in history {
+        if tx.buyer_wallet == wallet {
+            lots.push(Lot {
+                property_id: tx.property_id.clone(),
+                cost: Some(tx.price),
+                acquired_at: Some(tx.timestamp),
+                proceeds: None,
+                sold_at: None,
+            });
+        }
+        if tx.seller_wallet == wallet {
+            let open = lots
+                .iter_mut()
+                .rev()
+                .find(|lot| lot.property_id == tx.property_id && lot.sold_at.is_none());
+            match open {
+                Some(lot) => {
+                    lot.proceeds = Some(tx.price);
+                    lot.sold_at = Some(tx.timestamp);
+                }
+                None => lots.push(Lot {
+                    property_id: tx.property_id.clone(),
+                    cost: None,
+                    acquired_at: None,
+                    proceeds: Some(tx.price),
+                    sold_at: Some(tx.timestamp),
+                }),
+            }
+        }
+    }
+
+    // Properties the wallet listed itself and still owns have no purchase on record
+    for property in held {
+        let has_open_lot = lots
+            .iter()
+            .any(|lot| lot.property_id == property.property_id && lot.sold_at.is_none());
+        if !has_open_lot {
+            lots.push(Lot {
+                property_id: property.property_id.clone(),
+                cost: None,
+                acquired_at: Some(property.created_at),
+                proceeds: None,
+                sold_at: None,
+            });
+        }
+    }
+
+    lots
+}
+
+/// Valuation points of a lot with a known purchase: the purchase, price changes while held,
+/// and the sale or the current asking price
+fn lot_marks(
+    lot: &Lot,
+    current_price: Option<i64>,
+    events: &[PropertyEvent],
+    now: NaiveDateTime,
+) -> Option<Vec<ValuationMark>> {
+    let (cost, acquired_at) = (lot.cost?, lot.acquired_at?);
+    let end = lot.sold_at.unwrap_or(now);
+
+    let mut marks = vec![ValuationMark { at: acquired_at, value: cost, source: "purchase" }];
+    marks.extend(
+        events
+            .iter()
+            .filter(|e| e.event_type == EVENT_PRICE_CHANGED && e.created_at > acquired_at && e.created_at < end)
+            .filter_map(|e| e.price.map(|value| ValuationMark { at: e.created_at, value, source: "price_change" })),
+    );
+    match (lot.proceeds, lot.sold_at) {
+        (Some(value), Some(at)) => marks.push(ValuationMark { at, value, source: "sale" }),
+        _ => marks.push(ValuationMark { at: now, value: current_price?, source: "current" }),
+    }
+    Some(marks)
+}
+
+fn value_at(marks: &[ValuationMark], at: NaiveDateTime) -> Option<i64> {
+    marks.iter().rev().find(|m| m.at <= at).map(|m| m.value)
+}
+
+/// Chains sub-period returns between every valuation point so purchases and sales
+/// (the portfolio's cash flows) don't distort the result
+fn time_weighted_return(series: &[Vec<ValuationMark>]) -> Option<(f64, NaiveDateTime, NaiveDateTime)> {
+    let mut points: Vec<NaiveDateTime> = series.iter().flatten().map(|m| m.at).collect();
+    points.sort();
+    points.dedup();
+    if points.len() < 2 {
+        return None;
+    }
+
+    let mut growth = 1.0;
+    let mut chained = false;
+    for window in points.windows(2) {
+        let (t0, t1) = (window[0], window[1]);
+        let (mut start, mut end) = (0i64, 0i64);
+        for marks in series {
+            let (first, last) = (marks[0].at, marks[marks.len() - 1].at);
+            if first <= t0 && last >= t1 {
+                start += value_at(marks, t0).unwrap_or(0);
+                end += value_at(marks, t1).unwrap_or(0);
+            }
+        }
+        if start > 0 {
+            growth *= end as f64 / start as f64;
+            chained = true;
+        }
+    }
+
+    chained.then(|| (growth - 1.0, points[0], points[points.len() - 1]))
+}
+
+fn compute_performance(
+    conn: &mut PgConnection,
+    wallet: &str,
+    include_marks: bool,
+) -> Result<PortfolioPerformance, diesel::result::Error> {
+    let history = transactions::table
+        .filter(transactions::buyer_wallet.eq(wallet).or(transactions::seller_wallet.eq(wallet)))
+        .order_by(transactions::timestamp.asc())
+        .load::<Transaction>(conn)?;
+    let held = properties::table
+        .filter(properties::owner_wallet.eq(wallet))
+        .load::<Property>(conn)?;
+
+    let lots = build_lots(wallet, &history, &held);
+    let ids: Vec<String> = lots.iter().map(|lot| lot.property_id.clone()).collect();
+
+    let current_prices: HashMap<String, i64> = properties::table
+        .filter(properties::property_id.eq_any(&ids))
+        .select((properties::property_id, properties::price))
+        .load::<(String, i64)>(conn)?
+        .into_iter()
+        .collect();
+
+    let mut events_by_property: HashMap<String, Vec<PropertyEvent>> = HashMap::new();
+    for event in property_events::table
+        .filter(property_events::property_id.eq_any(&ids))
+        .order_by(property_events::created_at.asc())
+        .load::<PropertyEvent>(conn)?
+    {
+        events_by_property.entry(event.property_id.clone()).or_default().push(event);
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut series = Vec::new();
+    let mut positions = Vec::with_capacity(lots.len());
+    let (mut cost_basis, mut current_valuation, mut unrealized_gain, mut realized_pnl) = (0, 0, 0, 0);
+
+    for lot in &lots {
+        let held_now = lot.sold_at.is_none();
+        let current_price = if held_now { current_prices.get(&lot.property_id).copied() } else { None };
+        let events = events_by_property.get(&lot.property_id).map(Vec::as_slice).unwrap_or(&[]);
+        let marks = lot_marks(lot, current_price, events, now);
+
+        let end_value = if held_now { current_price } else { lot.proceeds };
+        let gain = match (lot.cost, end_value) {
+            (Some(cost), Some(value)) => Some(value - cost),
+            _ => None,
+        };
+        let holding_return = match (lot.cost, gain) {
+            (Some(cost), Some(gain)) if cost > 0 => Some(gain as f64 / cost as f64),
+            _ => None,
+        };
+        let annualized_return = match (holding_return, lot.acquired_at) {
+            (Some(r), Some(from)) => annualize(r, from, lot.sold_at.unwrap_or(now)),
+            _ => None,
+        };
+
+        if held_now {
+            current_valuation += current_price.unwrap_or(0);
+            if let (Some(cost), Some(gain)) = (lot.cost, gain) {
+                cost_basis += cost;
+                unrealized_gain += gain;
+            }
+        } else {
+            realized_pnl += gain.unwrap_or(0);
+        }
+
+        positions.push(PositionPerformance {
+            property_id: lot.property_id.clone(),
+            status: if held_now { "held" } else { "sold" },
+            acquired_at: lot.acquired_at,
+            purchase_price: lot.cost,
+            sold_at: lot.sold_at,
+            sale_price: lot.proceeds,
+            current_valuation: current_price,
+            unrealized_gain: if held_now { gain } else { None },
+            realized_pnl: if held_now { None } else { gain },
+            holding_return,
+            annualized_return,
+            marks: if include_marks { marks.clone() } else { None },
+        });
+        if let Some(marks) = marks {
+            series.push(marks);
+        }
+    }
+
+    let twr = time_weighted_return(&series);
+    Ok(PortfolioPerformance {
+        wallet_address: wallet.to_string(),
+        held_count: positions.iter().filter(|p| p.status == "held").count(),
+        sold_count: positions.iter().filter(|p| p.status == "sold").count(),
+        cost_basis,
+        current_valuation,
+        unrealized_gain,
+        realized_pnl,
+        time_weighted_return: twr.map(|(r, _, _)| r),
+        annualized_time_weighted_return: twr.and_then(|(r, from, to)| annualize(r, from, to)),
+        positions,
+    })
+}
+
+/// Unrealized and realized gains plus time-weighted return across everything the wallet has bought or sold
+pub async fn get_portfolio_performance(req: HttpRequest) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let result = web::block(move || {
+        let mut conn = db::establish_connection().map_err(|e| e.to_string())?;
+        compute_performance(&mut conn, &wallet_address, false).map_err(|e| e.to_string())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(performance)) => HttpResponse::Ok().json(PortfolioResponse {
+            success: true,
+            message: format!("Computed performance for {} positions", performance.positions.len()),
+            performance: Some(performance),
+        }),
+        Ok(Err(e)) => {
+            error!("Failed to compute portfolio performance: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to compute portfolio performance: {}", e))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    }
+}
+
+/// Holding periods of one property with the valuation points behind their returns
+pub async fn get_property_performance(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+    let property_id = path.into_inner();
+
+    let result = web::block(move || {
+        let mut conn = db::establish_connection().map_err(|e| e.to_string())?;
+        compute_performance(&mut conn, &wallet_address, true).map_err(|e| e.to_string())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(performance)) => {
+            let positions: Vec<PositionPerformance> = performance
+                .positions
+                .into_iter()
+                .filter(|p| p.property_id == property_id)
+                .collect();
+            if positions.is_empty() {
+                return HttpResponse::NotFound().body("Property is not part of this portfolio");
+            }
+            HttpResponse::Ok().json(PositionsResponse {
+                success: true,
+                message: format!("Found {} holding periods", positions.len()),
+                positions,
+            })
+        }
+        Ok(Err(e)) => {
+            error!("Failed to compute property performance: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to compute property performance: {}", e))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
+    }
+}
@@ -1,76 +1,60 @@
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse};
 use diesel::prelude::*;
-use tracing::{info, error};
+use tracing::info;
 use crate::db;
+use crate::error::ApiError;
 use crate::models::Property;
 use crate::schema::properties::dsl::*;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use crate::transaction::verify_token;
+use crate::transaction::authenticated_wallet;
+use crate::translation;
+use crate::market_status;
+use crate::organization::{self, Permission};
 use diesel::AsChangeset;
 
-/// Fetches all active properties from the database
-pub async fn get_properties() -> impl Responder {
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+/// Fetches all active properties from the database, localized to the request locale
+pub async fn get_properties(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    let mut conn = db::establish_connection()?;
 
     info!("Fetching all active properties");
     
-    let result = properties
+    let mut props = properties
         .filter(is_active.eq(true))
         .order_by(created_at.desc())
-        .load::<Property>(&mut conn);
-    
-    match result {
-        Ok(props) => {
-            info!("Successfully fetched {} properties", props.len());
-            HttpResponse::Ok().json(props)
-        },
-        Err(e) => {
-            error!("Failed to fetch properties: {}", e);
-            HttpResponse::InternalServerError().body(format!("Failed to fetch properties: {}", e))
-        }
+        .load::<Property>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch properties"))?;
+
+    info!("Successfully fetched {} properties", props.len());
+    if let Some(locale) = translation::request_locale(&req) {
+        translation::localize_properties(&mut conn, &mut props, &locale);
     }
+    Ok(HttpResponse::Ok().json(market_status::with_market_status(&mut conn, props)))
 }
 
-/// Fetches a specific property by its ID
-pub async fn get_property(path: web::Path<String>) -> impl Responder {
+/// Fetches a specific property by its ID, localized to the request locale
+pub async fn get_property(req: HttpRequest, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
     let property_id_param = path.into_inner();
     
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     info!("Fetching property with ID: {}", property_id_param);
     
-    let result = properties
+    let prop = properties
         .filter(property_id.eq(property_id_param))
         .filter(is_active.eq(true))
-        .first::<Property>(&mut conn);
-    
-    match result {
-        Ok(prop) => {
-            info!("Successfully fetched property");
-            HttpResponse::Ok().json(prop)
-        },
-        Err(diesel::result::Error::NotFound) => {
-            info!("Property not found");
-            HttpResponse::NotFound().body("Property not found")
-        },
-        Err(e) => {
-            error!("Failed to fetch property: {}", e);
-            HttpResponse::InternalServerError().body(format!("Failed to fetch property: {}", e))
-        }
+        .first::<Property>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+
+    info!("Successfully fetched property");
+    let mut props = [prop];
+    if let Some(locale) = translation::request_locale(&req) {
+        translation::localize_properties(&mut conn, &mut props, &locale);
     }
+    let listing = market_status::with_market_status(&mut conn, props.into());
+    Ok(HttpResponse::Ok().json(&listing[0]))
 }
 
 // Add a new response type just for NFT mint information
@@ -82,42 +66,27 @@ pub struct NftMintResponse {
 }
 
 /// Fetches just the NFT mint address for a property
-pub async fn get_property_nft_mint(path: web::Path<String>) -> impl Responder {
+pub async fn get_property_nft_mint(path: web::Path<String>) -> Result<HttpResponse, ApiError> {
     let property_id_param = path.into_inner();
     
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     info!("Fetching NFT mint for property ID: {}", property_id_param);
     
-    let result = properties
+    let (prop_id, mint, owner) = properties
         .filter(property_id.eq(property_id_param))
         .select((property_id, nft_mint_address, owner_wallet))
-        .first::<(String, String, String)>(&mut conn);
-    
-    match result {
-        Ok((prop_id, mint, owner)) => {
-            info!("Successfully fetched NFT mint address");
-            HttpResponse::Ok().json(NftMintResponse {
-                property_id: prop_id,
-                nft_mint_address: mint,
-                owner_wallet: owner,
-            })
-        },
-        Err(diesel::result::Error::NotFound) => {
-            info!("Property not found");
-            HttpResponse::NotFound().body("Property not found")
-        },
-        Err(e) => {
-            error!("Failed to fetch property NFT mint: {}", e);
-            HttpResponse::InternalServerError().body(format!("Failed to fetch property NFT mint: {}", e))
-        }
-    }
+        .first::<(String, String, String)>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property NFT mint"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+
+    info!("Successfully fetched NFT mint address");
+    Ok(HttpResponse::Ok().json(NftMintResponse {
+        property_id: prop_id,
+        nft_mint_address: mint,
+        owner_wallet: owner,
+    }))
 }
 
 #[derive(Deserialize)]
@@ -138,45 +107,35 @@ pub async fn update_property(
     req: HttpRequest,
     path: web::Path<String>,
     data: web::Json<UpdatePropertyRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     let property_id_param = path.into_inner();
     
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     info!("Updating property with ID: {}", property_id_param);
     
     // First, find the property and check ownership
-    let property_result = properties
+    let property = properties
         .filter(property_id.eq(&property_id_param))
-        .first::<Property>(&mut conn);
-    
-    let property = match property_result {
-        Ok(prop) => prop,
-        Err(diesel::result::Error::NotFound) => {
-            info!("Property not found");
-            return HttpResponse::NotFound().body("Property not found");
-        },
-        Err(e) => {
-            error!("Failed to fetch property: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Failed to fetch property: {}", e));
-        }
-    };
-    
-    // Verify ownership
-    if property.owner_wallet != wallet_address {
-        return HttpResponse::Forbidden().body("You don't have permission to update this property");
+        .first::<Property>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+    
+    // Verify ownership, or a lister role in the owning organization
+    let permitted = organization::has_property_permission(
+        &mut conn,
+        &property.owner_wallet,
+        property.organization_id,
+        &wallet_address,
+        Permission::List,
+    )
+    .map_err(ApiError::db("Failed to check permissions"))?;
+    if !permitted {
+        return Err(ApiError::Forbidden("You don't have permission to update this property".to_string()));
     }
     
     // Check if we have any changes to make
@@ -219,27 +178,49 @@ pub async fn update_property(
     
     if !has_changes {
         // No changes to make
-        return HttpResponse::Ok().json(UpdatePropertyResponse {
+        return Ok(HttpResponse::Ok().json(UpdatePropertyResponse {
             success: true,
             message: "No changes requested".to_string(),
-        });
+        }));
     }
     
     // Execute the update with the changes
-    match diesel::update(properties.filter(property_id.eq(&property_id_param)))
+    diesel::update(properties.filter(property_id.eq(&property_id_param)))
         .set(&changes)
-        .execute(&mut conn) 
-    {
-        Ok(_) => {
-            info!("Successfully updated property {}", property_id_param);
-            HttpResponse::Ok().json(UpdatePropertyResponse {
-                success: true,
-                message: "Property updated successfully".to_string(),
-            })
-        },
-        Err(e) => {
-            error!("Failed to update property: {}", e);
-            HttpResponse::InternalServerError().body(format!("Failed to update property: {}", e))
-        }
+        .execute(&mut conn)
+        .map_err(ApiError::db("Failed to update property"))?;
+    info!("Successfully updated property {}", property_id_param);
+
+    // Keep the listing history in sync for market status badges
+    if let Some(new_price) = changes.price.filter(|p| *p != property.price) {
+        market_status::record_event(
+            &mut conn,
+            &property_id_param,
+            market_status::EVENT_PRICE_CHANGED,
+            Some(new_price),
+            Some(property.price),
+        );
+    }
+    match changes.is_active {
+        Some(true) if !property.is_active => market_status::record_event(
+            &mut conn,
+            &property_id_param,
+            market_status::EVENT_REACTIVATED,
+            Some(changes.price.unwrap_or(property.price)),
+            None,
+        ),
+        Some(false) if property.is_active => market_status::record_event(
+            &mut conn,
+            &property_id_param,
+            market_status::EVENT_DELISTED,
+            None,
+            None,
+        ),
+        _ => {}
     }
+
+    Ok(HttpResponse::Ok().json(UpdatePropertyResponse {
+        success: true,
+        message: "Property updated successfully".to_string(),
+    }))
 } 
\ No newline at end of file
@@ -0,0 +1,247 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, ResponseError};
+use diesel::prelude::*;
+use printpdf::{BuiltinFont, Color, IndirectFontRef, Mm, PdfDocument, PdfLayerReference, Rect, Rgb};
+use qrcode::QrCode;
+use solana_sdk::signature::{Keypair, Signer};
+use std::env;
+use std::path::PathBuf;
+use uuid::Uuid;
+use tracing::{info, error};
+
+use crate::db;
+use crate::error::ApiError;
+use crate::models::{Property, Transaction};
+use crate::schema::{marketplace, properties, transactions};
+use crate::transaction::verify_token;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+fn cache_dir() -> PathBuf {
+    PathBuf::from(env::var("RECEIPT_CACHE_DIR").unwrap_or_else(|_| "receipts".to_string()))
+}
+
+fn explorer_url(signature: &str) -> String {
+    let cluster = env::var("SOLANA_CLUSTER").unwrap_or_else(|_| "devnet".to_string());
+    format!("https://explorer.solana.com/tx/{}?cluster={}", signature, cluster)
+}
+
+fn format_sol(lamports: i64) -> String {
+    format!("{:.9} SOL", lamports as f64 / LAMPORTS_PER_SOL)
+}
+
+/// Everything the receipt states, in the order it is signed
+struct ReceiptContents {
+    transaction: Transaction,
+    property: Option<Property>,
+    fee_bps: i64,
+}
+
+impl ReceiptContents {
+    fn fee(&self) -> i64 {
+        self.transaction.price * self.fee_bps / 10_000
+    }
+
+    /// Canonical text covered by the receipt signature
+    fn canonical(&self) -> String {
+        format!(
+            "receipt:{}|property:{}|seller:{}|buyer:{}|price:{}|fee_bps:{}|fee:{}|timestamp:{}|signature:{}",
+            self.transaction.id,
+            self.transaction.property_id,
+            self.transaction.seller_wallet,
+            self.transaction.buyer_wallet,
+            self.transaction.price,
+            self.fee_bps,
+            self.fee(),
+            self.transaction.timestamp.and_utc().timestamp(),
+            self.transaction.signature.as_deref().unwrap_or(""),
+        )
+    }
+}
+
+fn load_signing_keypair() -> anyhow::Result<Keypair> {
+    let base58 = env::var("RECEIPT_SIGNING_KEYPAIR")
+        .map_err(|_| anyhow::anyhow!("RECEIPT_SIGNING_KEYPAIR must be set"))?;
+    Ok(Keypair::from_bytes(&bs58::decode(&base58).into_vec()?)?)
+}
+
+fn draw_qr(layer: &PdfLayerReference, data: &str, x: f32, y: f32, size: f32) -> anyhow::Result<()> {
+    let code = QrCode::new(data.as_bytes())?;
+    let width = code.width();
+    let module = size / width as f32;
+
+    layer.set_fill_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != qrcode::Color::Dark {
+            continue;
+        }
+        let col = (i % width) as f32;
+        let row = (i / width) as f32;
+        let left = x + col * module;
+        let top = y + size - row * module;
+        layer.add_rect(Rect::new(Mm(left), Mm(top - module), Mm(left + module), Mm(top)));
+    }
+    Ok(())
+}
+
+/// Writes one line of text at the cursor and moves it down by `gap`
+fn line(layer: &PdfLayerReference, y: &mut f32, text: &str, size: f32, font: &IndirectFontRef, gap: f32) {
+    layer.use_text(text, size, Mm(20.0), Mm(*y), font);
+    *y -= gap;
+}
+
+fn render_pdf(contents: &ReceiptContents, signer: &Keypair) -> anyhow::Result<Vec<u8>> {
+    let tx = &contents.transaction;
+    let receipt_signature = signer.sign_message(contents.canonical().as_bytes());
+
+    let (doc, page, layer) = PdfDocument::new("Settlement Receipt", Mm(210.0), Mm(297.0), "Receipt");
+    let layer = doc.get_page(page).get_layer(layer);
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let mono = doc.add_builtin_font(BuiltinFont::Courier)?;
+
+    let mut y = 270.0;
+
+    line(&layer, &mut y, "Settlement Receipt", 20.0, &bold, 12.0);
+    line(&layer, &mut y, &format!("Receipt ID: {}", tx.id), 9.0, &mono, 5.0);
+    line(&layer, &mut y, &format!("Settled: {} UTC", tx.timestamp.format("%Y-%m-%d %H:%M:%S")), 10.0, &regular, 12.0);
+
+    line(&layer, &mut y, "Parties", 13.0, &bold, 7.0);
+    line(&layer, &mut y, &format!("Seller: {}", tx.seller_wallet), 9.0, &mono, 5.0);
+    line(&layer, &mut y, &format!("Buyer:  {}", tx.buyer_wallet), 9.0, &mono, 12.0);
+
+    line(&layer, &mut y, "Property", 13.0, &bold, 7.0);
+    line(&layer, &mut y, &format!("Property ID: {}", tx.property_id), 10.0, &regular, 5.0);
+    if let Some(property) = &contents.property {
+        line(&layer, &mut y, &format!("Location: {}", property.location), 10.0, &regular, 5.0);
+        line(
+            &layer,
+            &mut y,
+            &format!(
+                "{} sq ft, {} bedrooms, {} bathrooms",
+                property.square_feet, property.bedrooms, property.bathrooms
+            ),
+            10.0,
+            &regular,
+            5.0,
+        );
+        line(&layer, &mut y, &format!("NFT mint: {}", property.nft_mint_address), 9.0, &mono, 5.0);
+    }
+    y -= 7.0;
+
+    line(&layer, &mut y, "Amounts", 13.0, &bold, 7.0);
+    line(&layer, &mut y, &format!("Sale price:       {}", format_sol(tx.price)), 10.0, &mono, 5.0);
+    line(
+        &layer,
+        &mut y,
+        &format!("Marketplace fee:  {} ({:.2}%)", format_sol(contents.fee()), contents.fee_bps as f64 / 100.0),
+        10.0,
+        &mono,
+        5.0,
+    );
+    line(&layer, &mut y, &format!("Seller proceeds:  {}", format_sol(tx.price - contents.fee())), 10.0, &mono, 12.0);
+
+    line(&layer, &mut y, "On-chain settlement", 13.0, &bold, 7.0);
+    match &tx.signature {
+        Some(signature) => {
+            line(&layer, &mut y, "Transaction signature:", 10.0, &regular, 5.0);
+            line(&layer, &mut y, &signature[..signature.len().min(44)], 8.0, &mono, 4.0);
+            if signature.len() > 44 {
+                line(&layer, &mut y, &signature[44..], 8.0, &mono, 5.0);
+            }
+            line(&layer, &mut y, &explorer_url(signature), 7.0, &mono, 5.0);
+        }
+        None => line(&layer, &mut y, "Transaction signature was not recorded for this sale", 10.0, &regular, 5.0),
+    }
+    let qr_top = y;
+    y -= 50.0;
+
+    line(&layer, &mut y, "Receipt signature", 13.0, &bold, 7.0);
+    line(&layer, &mut y, &format!("Signer: {}", signer.pubkey()), 9.0, &mono, 5.0);
+    let receipt_signature = receipt_signature.to_string();
+    line(&layer, &mut y, &receipt_signature[..receipt_signature.len().min(44)], 8.0, &mono, 4.0);
+    if receipt_signature.len() > 44 {
+        line(&layer, &mut y, &receipt_signature[44..], 8.0, &mono, 5.0);
+    }
+    line(&layer, &mut y, "Ed25519 over the receipt fields listed above, verifiable against the signer key.", 8.0, &regular, 5.0);
+
+    if let Some(signature) = &tx.signature {
+        draw_qr(&layer, &explorer_url(signature), 20.0, qr_top - 45.0, 45.0)?;
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+/// Renders the receipt for a completed sale, or serves the copy cached on first generation.
+/// Only the buyer and seller of the sale may download it.
+pub async fn get_transaction_receipt(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let transaction_id = match Uuid::parse_str(&path.into_inner()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid transaction id"),
+    };
+
+    let result = web::block(move || -> Result<Vec<u8>, ApiError> {
+        let mut conn = db::establish_connection()?;
+
+        let transaction = transactions::table
+            .filter(transactions::id.eq(transaction_id))
+            .first::<Transaction>(&mut conn)
+            .optional()
+            .map_err(ApiError::db("Failed to load transaction"))?
+            .ok_or_else(|| ApiError::NotFound("Transaction not found".to_string()))?;
+
+        if transaction.buyer_wallet != wallet_address && transaction.seller_wallet != wallet_address {
+            return Err(ApiError::Forbidden("Only the buyer or seller can download this receipt".to_string()));
+        }
+
+        let cached = cache_dir().join(format!("{}.pdf", transaction_id));
+        if let Ok(bytes) = std::fs::read(&cached) {
+            return Ok(bytes);
+        }
+
+        let property = properties::table
+            .filter(properties::property_id.eq(&transaction.property_id))
+            .first::<Property>(&mut conn)
+            .optional()
+            .map_err(ApiError::db("Failed to load property"))?;
+
+        let fee_bps = marketplace::table
+            .select(marketplace::fee_percentage)
+            .first::<i64>(&mut conn)
+            .optional()
+            .map_err(ApiError::db("Failed to load marketplace"))?
+            .unwrap_or(0);
+
+        let signer = load_signing_keypair().map_err(|e| {
+            error!("Receipt signing key unavailable: {}", e);
+            ApiError::Internal("Receipt signing is not configured".to_string())
+        })?;
+
+        let contents = ReceiptContents { transaction, property, fee_bps };
+        let bytes = render_pdf(&contents, &signer)
+            .map_err(|e| ApiError::Internal(format!("Failed to render receipt: {}", e)))?;
+
+        if let Err(e) = std::fs::create_dir_all(cache_dir()).and_then(|_| std::fs::write(&cached, &bytes)) {
+            error!("Failed to cache receipt {}: {}", transaction_id, e);
+        }
+        info!("Generated receipt for transaction {}", transaction_id);
+        Ok(bytes)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(bytes)) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                "Content-Disposition",
+                format!("inline; filename=\"receipt-{}.pdf\"", transaction_id),
+            ))
+            .body(bytes),
+        Ok(Err(e)) => e.error_response(),
+        Err(e) => ApiError::from(e).error_response(),
+    }
+}
@@ -0,0 +1,188 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::env;
+use std::sync::OnceLock;
+use std::time::Instant;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+use crate::auth;
+
+const REDACTED: &str = "[redacted]";
+
+/// Payload fields redacted unless REQUEST_LOG_REDACT_FIELDS overrides the list
+const DEFAULT_REDACT_FIELDS: &str =
+    "signature,transaction_signature,serialized_transaction,private_key,secret,token,jwt_token,keypair";
+
+/// How the authenticated wallet appears in request logs
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WalletMode {
+    Plain,
+    Hashed,
+    Omit,
+}
+
+struct LogConfig {
+    enabled: bool,
+    /// Fraction of successful requests logged; failures are always logged
+    sample_rate: f64,
+    wallet_mode: WalletMode,
+    wallet_salt: String,
+    log_bodies: bool,
+    /// Log raw paths rather than route patterns; patterns keep wallets in paths out of the logs
+    raw_paths: bool,
+    redact_fields: HashSet<String>,
+}
+
+impl LogConfig {
+    fn from_env() -> Self {
+        let flag = |key: &str, default: bool| {
+            env::var(key).map(|v| v == "true").unwrap_or(default)
+        };
+        LogConfig {
+            enabled: flag("REQUEST_LOG_ENABLED", true),
+            sample_rate: env::var("REQUEST_LOG_SAMPLE_RATE")
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .map(|rate| rate.clamp(0.0, 1.0))
+                .unwrap_or(1.0),
+            wallet_mode: match env::var("REQUEST_LOG_WALLET").as_deref() {
+                Ok("plain") => WalletMode::Plain,
+                Ok("omit") => WalletMode::Omit,
+                _ => WalletMode::Hashed,
+            },
+            wallet_salt: env::var("REQUEST_LOG_WALLET_SALT").unwrap_or_default(),
+            log_bodies: flag("REQUEST_LOG_BODIES", false),
+            raw_paths: flag("REQUEST_LOG_RAW_PATHS", false),
+            redact_fields: env::var("REQUEST_LOG_REDACT_FIELDS")
+                .unwrap_or_else(|_| DEFAULT_REDACT_FIELDS.to_string())
+                .split(',')
+                .map(|field| field.trim().to_ascii_lowercase())
+                .filter(|field| !field.is_empty())
+                .collect(),
+        }
+    }
+}
+
+fn config() -> &'static LogConfig {
+    static CONFIG: OnceLock<LogConfig> = OnceLock::new();
+    CONFIG.get_or_init(LogConfig::from_env)
+}
+
+/// Wallet from a valid bearer JWT, rendered per REQUEST_LOG_WALLET
+fn request_wallet(req: &ServiceRequest, config: &LogConfig) -> Option<String> {
+    if config.wallet_mode == WalletMode::Omit {
+        return None;
+    }
+    let wallet = auth::bearer_wallet(req.headers())?;
+
+    Some(match config.wallet_mode {
+        WalletMode::Plain => wallet,
+        _ => {
+            let digest = Sha256::digest(format!("{}{}", config.wallet_salt, wallet).as_bytes());
+            hex::encode(&digest[..8])
+        }
+    })
+}
+
+/// Replaces the values of configured fields, at any depth, with a placeholder
+fn redact(value: &mut Value, fields: &HashSet<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if fields.contains(&key.to_ascii_lowercase()) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact(field, fields);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, fields)),
+        _ => {}
+    }
+}
+
+/// JSON request body with sensitive fields redacted. The payload is put back for the handler.
+async fn redacted_body(req: &mut ServiceRequest, config: &LogConfig) -> Option<String> {
+    if !config.log_bodies || req.content_type() != "application/json" {
+        return None;
+    }
+    let bytes = match req.extract::<web::Bytes>().await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read request body for logging: {}", e);
+            return None;
+        }
+    };
+    req.set_payload(bytes.clone().into());
+
+    let mut body: Value = serde_json::from_slice(&bytes).ok()?;
+    redact(&mut body, &config.redact_fields);
+    Some(body.to_string())
+}
+
+/// Logs each request's method, route, wallet, latency and outcome. Wrapped around the app
+/// with `middleware::from_fn(request_log::log_request)`.
+pub async fn log_request(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let config = config();
+    if !config.enabled {
+        return next.call(req).await;
+    }
+
+    let started = Instant::now();
+    let request_id = Uuid::new_v4();
+    let method = req.method().to_string();
+    let raw_path = req.path().to_string();
+    let wallet = request_wallet(&req, config);
+    let body = redacted_body(&mut req, config).await;
+
+    let result = next.call(req).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (status, route) = match &result {
+        Ok(res) => (
+            res.status().as_u16(),
+            res.request().match_pattern().filter(|_| !config.raw_paths),
+        ),
+        Err(e) => (e.as_response_error().status_code().as_u16(), None),
+    };
+    let route = route.unwrap_or(raw_path);
+    let outcome = match status {
+        500.. => "server_error",
+        400.. => "client_error",
+        _ => "success",
+    };
+
+    // Failures are always kept; successes are sampled
+    let sampled = (request_id.as_u128() % 10_000) as f64 / 10_000.0 < config.sample_rate;
+    if outcome == "success" && !sampled {
+        return result;
+    }
+
+    let wallet = wallet.as_deref().unwrap_or("-");
+    let body = body.as_deref().unwrap_or("-");
+    match outcome {
+        "server_error" => error!(
+            target: "request", %request_id, %method, %route, wallet, status, latency_ms, outcome, body,
+            "request completed"
+        ),
+        "client_error" => warn!(
+            target: "request", %request_id, %method, %route, wallet, status, latency_ms, outcome, body,
+            "request completed"
+        ),
+        _ => info!(
+            target: "request", %request_id, %method, %route, wallet, status, latency_ms, outcome, body,
+            "request completed"
+        ),
+    }
+
+    result
+}
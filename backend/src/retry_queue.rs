@@ -0,0 +1,401 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use uuid::Uuid;
+use tracing::{info, error, warn};
+
+use crate::auth;
+use crate::db;
+use crate::models::{PendingDbEffect, Property};
+use crate::schema::pending_db_effects;
+use crate::transaction::{self, verify_token, ListPropertyRequest};
+
+/// Number of failed replays after which an effect is parked as `stuck`
+const MAX_ATTEMPTS: i32 = 10;
+/// How often the background worker looks for due effects
+const POLL_INTERVAL_SECS: u64 = 30;
+/// Upper bound for the exponential backoff between replays
+const MAX_BACKOFF_SECS: i64 = 3600;
+
+/// A database write that has to happen once an on-chain transaction is confirmed
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum DbEffect {
+    InsertProperty(Property),
+    /// A listing recorded without a database connection; the row and its bookkeeping are built
+    /// when it is replayed
+    RecordListing {
+        wallet_address: String,
+        metadata: ListPropertyRequest,
+    },
+    UpdatePropertyAttributes {
+        property_id: String,
+        square_feet: Option<i64>,
+        bedrooms: Option<i16>,
+        bathrooms: Option<i16>,
+    },
+}
+
+impl DbEffect {
+    fn effect_type(&self) -> &'static str {
+        match self {
+            DbEffect::InsertProperty(_) => "insert_property",
+            DbEffect::RecordListing { .. } => "record_listing",
+            DbEffect::UpdatePropertyAttributes { .. } => "update_property_attributes",
+        }
+    }
+
+    fn apply(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        match self {
+            DbEffect::InsertProperty(new_property) => {
+                use crate::schema::properties;
+
+                // Replays must be idempotent, so an already inserted row is not an error
+                diesel::insert_into(properties::table)
+                    .values(new_property)
+                    .on_conflict(properties::property_id)
+                    .do_nothing()
+                    .execute(conn)?;
+            }
+            DbEffect::RecordListing { wallet_address, metadata } => {
+                use crate::schema::properties;
+
+                let new_property = transaction::listing_row(conn, wallet_address, metadata, true);
+                let inserted = diesel::insert_into(properties::table)
+                    .values(&new_property)
+                    .on_conflict(properties::property_id)
+                    .do_nothing()
+                    .execute(conn)?;
+                if inserted > 0 {
+                    transaction::record_listed(conn, wallet_address, metadata);
+                }
+            }
+            DbEffect::UpdatePropertyAttributes { property_id, square_feet, bedrooms, bathrooms } => {
+                use crate::schema::properties;
+
+                let target = properties::table.filter(properties::property_id.eq(property_id));
+                conn.transaction(|conn| {
+                    if let Some(value) = square_feet {
+                        diesel::update(target).set(properties::square_feet.eq(value)).execute(conn)?;
+                    }
+                    if let Some(value) = bedrooms {
+                        diesel::update(target).set(properties::bedrooms.eq(value)).execute(conn)?;
+                    }
+                    if let Some(value) = bathrooms {
+                        diesel::update(target).set(properties::bathrooms.eq(value)).execute(conn)?;
+                    }
+                    diesel::update(target)
+                        .set(properties::updated_at.eq(Utc::now().naive_utc()))
+                        .execute(conn)
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Persists an effect keyed by the transaction signature so it can be replayed later
+pub fn enqueue(conn: &mut PgConnection, signature: &str, effect: &DbEffect, reason: &str) -> QueryResult<()> {
+    let payload = serde_json::to_string(effect)
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+    let now = Utc::now().naive_utc();
+
+    let pending = PendingDbEffect {
+        id: Uuid::new_v4(),
+        signature: signature.to_string(),
+        effect_type: effect.effect_type().to_string(),
+        payload,
+        status: "pending".to_string(),
+        attempts: 0,
+        last_error: Some(reason.to_string()),
+        next_attempt_at: now,
+        created_at: now,
+        updated_at: now,
+    };
+
+    diesel::insert_into(pending_db_effects::table)
+        .values(&pending)
+        .on_conflict(pending_db_effects::signature)
+        .do_nothing()
+        .execute(conn)?;
+
+    warn!("Queued {} effect for transaction {}", pending.effect_type, signature);
+    Ok(())
+}
+
+/// Whether an effect was written right away or left for the worker to replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectOutcome {
+    Applied,
+    Queued,
+}
+
+/// Applies an effect right away and falls back to the retry queue if the write fails
+pub fn apply_or_enqueue(conn: &mut PgConnection, signature: &str, effect: DbEffect) -> QueryResult<EffectOutcome> {
+    match effect.apply(conn) {
+        Ok(()) => Ok(EffectOutcome::Applied),
+        Err(e) => {
+            error!("Failed to apply {} effect for {}: {}", effect.effect_type(), signature, e);
+            enqueue(conn, signature, &effect, &e.to_string())?;
+            Ok(EffectOutcome::Queued)
+        }
+    }
+}
+
+/// Effect that could not even reach the database, kept in a local spool file
+#[derive(Serialize, Deserialize)]
+struct SpooledEffect {
+    signature: String,
+    reason: String,
+    effect: DbEffect,
+}
+
+fn spool_path() -> PathBuf {
+    env::var("RETRY_SPOOL_PATH")
+        .unwrap_or_else(|_| "pending_db_effects.jsonl".to_string())
+        .into()
+}
+
+/// Appends an effect to the local spool when no database connection is available. The worker
+/// moves spooled effects into the queue once it can connect again.
+pub fn spool(signature: &str, effect: DbEffect, reason: &str) -> std::io::Result<()> {
+    let effect_type = effect.effect_type();
+    let line = serde_json::to_string(&SpooledEffect {
+        signature: signature.to_string(),
+        reason: reason.to_string(),
+        effect,
+    })?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(spool_path())?;
+    writeln!(file, "{}", line)?;
+    file.sync_data()?;
+
+    warn!("Spooled {} effect for transaction {}", effect_type, signature);
+    Ok(())
+}
+
+/// Moves spooled effects into the queue, returning how many were moved. The spool is renamed
+/// before it is read so effects spooled meanwhile land in a fresh file; a partly drained file is
+/// picked up again on the next run, which is safe since enqueueing is keyed by signature.
+pub fn drain_spool(conn: &mut PgConnection) -> Result<usize, String> {
+    let path = spool_path();
+    let draining = path.with_extension("draining");
+    if !draining.exists() {
+        match fs::rename(&path, &draining) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Failed to claim retry spool: {}", e)),
+        }
+    }
+
+    let contents = fs::read_to_string(&draining).map_err(|e| format!("Failed to read retry spool: {}", e))?;
+    let mut moved = 0;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<SpooledEffect>(line) {
+            Ok(item) => {
+                enqueue(conn, &item.signature, &item.effect, &item.reason).map_err(|e| e.to_string())?;
+                moved += 1;
+            }
+            Err(e) => error!("Skipping unreadable spooled effect: {}", e),
+        }
+    }
+
+    fs::remove_file(&draining).map_err(|e| format!("Failed to remove drained retry spool: {}", e))?;
+    Ok(moved)
+}
+
+fn next_backoff(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(0, 16) as u32;
+    let secs = (POLL_INTERVAL_SECS as i64).saturating_mul(1i64 << exponent);
+    Duration::seconds(secs.min(MAX_BACKOFF_SECS))
+}
+
+/// Replays every due effect once, returning how many were applied successfully
+pub fn process_due(conn: &mut PgConnection) -> QueryResult<usize> {
+    use crate::schema::pending_db_effects::dsl::*;
+
+    let now = Utc::now().naive_utc();
+    let due = pending_db_effects
+        .filter(status.eq("pending"))
+        .filter(next_attempt_at.le(now))
+        .order_by(created_at.asc())
+        .limit(100)
+        .load::<PendingDbEffect>(conn)?;
+
+    let mut applied = 0;
+    for item in due {
+        let result = serde_json::from_str::<DbEffect>(&item.payload)
+            .map_err(|e| e.to_string())
+            .and_then(|effect| effect.apply(conn).map_err(|e| e.to_string()));
+
+        let now = Utc::now().naive_utc();
+        match result {
+            Ok(()) => {
+                diesel::update(pending_db_effects.find(item.id))
+                    .set((
+                        status.eq("done"),
+                        attempts.eq(item.attempts + 1),
+                        last_error.eq(None::<String>),
+                        updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+                info!("Replayed {} effect for transaction {}", item.effect_type, item.signature);
+                applied += 1;
+            }
+            Err(e) => {
+                let tries = item.attempts + 1;
+                let new_status = if tries >= MAX_ATTEMPTS { "stuck" } else { "pending" };
+                diesel::update(pending_db_effects.find(item.id))
+                    .set((
+                        status.eq(new_status),
+                        attempts.eq(tries),
+                        last_error.eq(Some(e.clone())),
+                        next_attempt_at.eq(now + next_backoff(tries)),
+                        updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+                error!(
+                    "Replay {} of {} effect for {} failed: {}",
+                    tries, item.effect_type, item.signature, e
+                );
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Spawns the background task that keeps replaying queued effects until they succeed
+pub fn spawn_worker() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(POLL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let result = tokio::task::spawn_blocking(|| {
+                let mut conn = db::establish_connection().map_err(|e| e.to_string())?;
+                let spooled = drain_spool(&mut conn)?;
+                if spooled > 0 {
+                    info!("Moved {} spooled effects into the retry queue", spooled);
+                }
+                process_due(&mut conn).map_err(|e| e.to_string())
+            })
+            .await;
+
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(count)) => info!("Retry queue applied {} pending effects", count),
+                Ok(Err(e)) => error!("Retry queue run failed: {}", e),
+                Err(e) => error!("Retry queue worker panicked: {}", e),
+            }
+        }
+    });
+}
+
+#[derive(Serialize)]
+pub struct PendingEffectsResponse {
+    pub success: bool,
+    pub message: String,
+    pub effects: Vec<PendingDbEffect>,
+}
+
+#[derive(Deserialize)]
+pub struct PendingEffectsQuery {
+    pub status: Option<String>,
+}
+
+/// Lists queued effects that have not been applied yet (admin only)
+pub async fn get_pending_effects(
+    req: HttpRequest,
+    query: web::Query<PendingEffectsQuery>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    if !auth::is_admin_wallet(&wallet_address) {
+        return HttpResponse::Forbidden().body("Admin access required");
+    }
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    use crate::schema::pending_db_effects::dsl::*;
+
+    let mut items = pending_db_effects
+        .filter(status.ne("done"))
+        .order_by(created_at.asc())
+        .into_boxed();
+    if let Some(filter_status) = &query.status {
+        items = items.filter(status.eq(filter_status.clone()));
+    }
+
+    match items.load::<PendingDbEffect>(&mut conn) {
+        Ok(effects) => HttpResponse::Ok().json(PendingEffectsResponse {
+            success: true,
+            message: format!("Found {} unapplied effects", effects.len()),
+            effects,
+        }),
+        Err(e) => {
+            error!("Failed to fetch pending effects: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch pending effects: {}", e))
+        }
+    }
+}
+
+/// Puts a stuck or pending effect back at the front of the queue (admin only)
+pub async fn retry_pending_effect(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    if !auth::is_admin_wallet(&wallet_address) {
+        return HttpResponse::Forbidden().body("Admin access required");
+    }
+
+    let effect_uuid = match Uuid::parse_str(&path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid effect ID format"),
+    };
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    use crate::schema::pending_db_effects::dsl::*;
+
+    let now: NaiveDateTime = Utc::now().naive_utc();
+    match diesel::update(pending_db_effects.find(effect_uuid).filter(status.ne("done")))
+        .set((status.eq("pending"), next_attempt_at.eq(now), updated_at.eq(now)))
+        .execute(&mut conn)
+    {
+        Ok(0) => HttpResponse::NotFound().body("Pending effect not found"),
+        Ok(_) => {
+            info!("Admin {} requeued effect {}", wallet_address, effect_uuid);
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "Effect scheduled for immediate retry"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to requeue effect: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to requeue effect: {}", e))
+        }
+    }
+}
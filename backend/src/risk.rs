@@ -0,0 +1,279 @@
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::Deserialize;
+use solana_sdk::{message::Message, pubkey::Pubkey};
+use std::collections::HashSet;
+use std::env;
+use uuid::Uuid;
+use tracing::{info, error, warn};
+
+use crate::db;
+use crate::error::ApiError;
+use crate::models::RiskScreening;
+use crate::schema::risk_screenings;
+
+/// Outcome of screening a single address
+#[derive(Debug)]
+pub struct RiskVerdict {
+    pub blocked: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RiskError {
+    #[error("Risk provider request failed: {0}")]
+    Request(String),
+    #[error("Risk provider configuration error: {0}")]
+    Config(String),
+}
+
+/// A sanctions or wallet-risk data source
+pub trait RiskProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn screen(&self, address: &str) -> Result<RiskVerdict, RiskError>;
+}
+
+/// Addresses from `RISK_DENYLIST` (comma separated) and `RISK_DENYLIST_FILE` (one per line)
+pub struct DenylistProvider {
+    denied: HashSet<String>,
+}
+
+impl DenylistProvider {
+    pub fn from_env() -> Self {
+        let mut denied: HashSet<String> = env::var("RISK_DENYLIST")
+            .map(|list| list.split(',').map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect())
+            .unwrap_or_default();
+
+        if let Ok(path) = env::var("RISK_DENYLIST_FILE") {
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => denied.extend(
+                    contents
+                        .lines()
+                        .map(|line| line.trim())
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(str::to_string),
+                ),
+                Err(e) => error!("Failed to read risk denylist {}: {}", path, e),
+            }
+        }
+
+        DenylistProvider { denied }
+    }
+}
+
+impl RiskProvider for DenylistProvider {
+    fn name(&self) -> &'static str {
+        "denylist"
+    }
+
+    fn screen(&self, address: &str) -> Result<RiskVerdict, RiskError> {
+        let blocked = self.denied.contains(address);
+        Ok(RiskVerdict {
+            blocked,
+            reason: blocked.then(|| "Address is on the local denylist".to_string()),
+        })
+    }
+}
+
+/// Queries a screening API at `RISK_API_URL`:
+/// `GET {url}/addresses/{address}` returning `{ "blocked": bool, "category": "..." }`
+pub struct HttpRiskProvider {
+    base_url: String,
+    api_key: Option<String>,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Deserialize)]
+struct HttpRiskResponse {
+    blocked: bool,
+    category: Option<String>,
+}
+
+impl HttpRiskProvider {
+    pub fn from_env() -> Result<Self, RiskError> {
+        let base_url = env::var("RISK_API_URL")
+            .map_err(|_| RiskError::Config("RISK_API_URL must be set".to_string()))?;
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .map_err(|e| RiskError::Config(e.to_string()))?;
+
+        Ok(HttpRiskProvider {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: env::var("RISK_API_KEY").ok(),
+            client,
+        })
+    }
+}
+
+impl RiskProvider for HttpRiskProvider {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn screen(&self, address: &str) -> Result<RiskVerdict, RiskError> {
+        let mut request = self.client.get(format!("{}/addresses/{}", self.base_url, address));
+        if let Some(key) = &self.api_key {
+            request = request.header("X-Api-Key", key);
+        }
+
+        let response = request.send().map_err(|e| RiskError::Request(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(RiskError::Request(format!("Provider responded with status {}", response.status())));
+        }
+
+        let body: HttpRiskResponse = response.json().map_err(|e| RiskError::Request(e.to_string()))?;
+        Ok(RiskVerdict {
+            blocked: body.blocked,
+            reason: body.blocked.then(|| format!("Flagged as {}", body.category.unwrap_or_else(|| "high risk".to_string()))),
+        })
+    }
+}
+
+/// Screening is opt-in through `RISK_SCREENING_ENABLED`
+pub fn screening_enabled() -> bool {
+    matches!(env::var("RISK_SCREENING_ENABLED").as_deref(), Ok("true") | Ok("1"))
+}
+
+/// Picks the remote provider configured by `RISK_PROVIDER`; the denylist is always consulted
+pub fn provider_from_env() -> Result<Option<Box<dyn RiskProvider>>, RiskError> {
+    match env::var("RISK_PROVIDER").as_deref() {
+        Ok("http") => Ok(Some(Box::new(HttpRiskProvider::from_env()?))),
+        Ok("none") | Err(_) => Ok(None),
+        Ok(other) => Err(RiskError::Config(format!("Unknown risk provider: {}", other))),
+    }
+}
+
+/// When the remote provider is unavailable, relaying is refused unless `RISK_FAIL_OPEN=true`
+fn fail_open() -> bool {
+    matches!(env::var("RISK_FAIL_OPEN").as_deref(), Ok("true") | Ok("1"))
+}
+
+/// Every account a message touches, excluding the programs it invokes
+pub fn message_addresses(message: &Message) -> Vec<Pubkey> {
+    let program_indices: HashSet<u8> = message
+        .instructions
+        .iter()
+        .map(|ix| ix.program_id_index)
+        .collect();
+
+    message
+        .account_keys
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !program_indices.contains(&(*i as u8)))
+        .map(|(_, key)| *key)
+        .collect()
+}
+
+struct Decision {
+    provider: String,
+    decision: &'static str,
+    matched_address: Option<String>,
+    reason: Option<String>,
+}
+
+fn decide(addresses: &[String]) -> Decision {
+    let denylist = DenylistProvider::from_env();
+    let remote = provider_from_env();
+
+    let provider = match &remote {
+        Ok(Some(p)) => format!("{}+{}", denylist.name(), p.name()),
+        _ => denylist.name().to_string(),
+    };
+
+    for address in addresses {
+        if let Ok(RiskVerdict { blocked: true, reason }) = denylist.screen(address) {
+            return Decision { provider, decision: "blocked", matched_address: Some(address.clone()), reason };
+        }
+    }
+
+    let remote = match remote {
+        Ok(Some(p)) => p,
+        Ok(None) => return Decision { provider, decision: "allowed", matched_address: None, reason: None },
+        Err(e) => return provider_failure(provider, e),
+    };
+
+    for address in addresses {
+        match remote.screen(address) {
+            Ok(RiskVerdict { blocked: true, reason }) => {
+                return Decision { provider, decision: "blocked", matched_address: Some(address.clone()), reason };
+            }
+            Ok(_) => {}
+            Err(e) => return provider_failure(provider, e),
+        }
+    }
+
+    Decision { provider, decision: "allowed", matched_address: None, reason: None }
+}
+
+fn provider_failure(provider: String, e: RiskError) -> Decision {
+    warn!("Risk screening unavailable: {}", e);
+    Decision {
+        provider,
+        decision: if fail_open() { "allowed_unscreened" } else { "blocked_unscreened" },
+        matched_address: None,
+        reason: Some(e.to_string()),
+    }
+}
+
+/// Screens the addresses of a transaction about to be relayed and records the decision.
+/// Returns the error to send back when relaying must not go ahead.
+pub async fn screen_relay(wallet_address: &str, context: &str, addresses: Vec<Pubkey>) -> Result<(), ApiError> {
+    if !screening_enabled() {
+        return Ok(());
+    }
+
+    let mut addresses: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+    if !addresses.iter().any(|a| a == wallet_address) {
+        addresses.push(wallet_address.to_string());
+    }
+
+    let wallet = wallet_address.to_string();
+    let context = context.to_string();
+    let decision = web::block(move || {
+        let decision = decide(&addresses);
+
+        let screening = RiskScreening {
+            id: Uuid::new_v4(),
+            wallet_address: wallet,
+            context,
+            addresses: addresses.join(","),
+            provider: decision.provider.clone(),
+            decision: decision.decision.to_string(),
+            matched_address: decision.matched_address.clone(),
+            reason: decision.reason.clone(),
+            created_at: Utc::now().naive_utc(),
+        };
+        match db::establish_connection() {
+            Ok(mut conn) => {
+                if let Err(e) = diesel::insert_into(risk_screenings::table).values(&screening).execute(&mut conn) {
+                    error!("Failed to record risk screening: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to connect to database: {}", e),
+        }
+
+        info!(
+            "Risk screening for {} ({}): {}",
+            screening.wallet_address, screening.context, screening.decision
+        );
+        decision
+    })
+    .await?;
+
+    match decision.decision {
+        "allowed" | "allowed_unscreened" => Ok(()),
+        "blocked_unscreened" => Err(ApiError::Unavailable("Risk screening is unavailable, try again later".to_string())),
+        _ => Err(ApiError::Forbidden(format!(
+            "Transaction blocked by compliance screening: {}",
+            decision.matched_address.unwrap_or_default()
+        ))),
+    }
+}
+
+/// `screen_relay` for handlers that still build their own responses
+pub async fn check_relay(wallet_address: &str, context: &str, addresses: Vec<Pubkey>) -> Result<(), HttpResponse> {
+    screen_relay(wallet_address, context, addresses).await.map_err(|e| e.error_response())
+}
@@ -0,0 +1,408 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose, Engine};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::Transaction as SolanaTransaction,
+};
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+use std::env;
+use std::str::FromStr;
+use tracing::info;
+
+use crate::chain_cache::AccountSnapshot;
+use crate::error::ApiError;
+use crate::transaction::{authenticated_wallet, instruction_discriminator, PROGRAM_ID};
+
+/// SOL airdropped to each generated wallet before the run
+const AIRDROP_SOL: u64 = 100;
+const DEFAULT_PRICE: u64 = LAMPORTS_PER_SOL;
+/// Offers expire a day after the run
+const OFFER_TTL_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Deserialize)]
+pub struct SimulateSaleRequest {
+    /// Marketplace fee in basis points; defaults to 250
+    pub fee_bps: Option<u64>,
+    /// Listing price in lamports; defaults to 1 SOL
+    pub price: Option<u64>,
+    /// Offer amount in lamports; defaults to the listing price
+    pub offer_amount: Option<u64>,
+    pub property_type: Option<u8>,
+    pub amenities: Option<u64>,
+    pub geohash: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SimulatedAccount {
+    pub name: &'static str,
+    pub address: String,
+    /// None once the account has been closed
+    pub account: Option<AccountSnapshot>,
+}
+
+#[derive(Serialize)]
+pub struct SimulatedStep {
+    pub name: &'static str,
+    pub signature: Option<String>,
+    pub compute_units: Option<u64>,
+    pub fee: u64,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    /// Program accounts after the step
+    pub accounts: Vec<SimulatedAccount>,
+}
+
+#[derive(Serialize)]
+pub struct SimulateSaleResponse {
+    pub success: bool,
+    pub message: String,
+    pub marketplace_authority: String,
+    pub seller: String,
+    pub buyer: String,
+    pub steps: Vec<SimulatedStep>,
+}
+
+/// Generated wallets and the accounts the run creates
+struct Sandbox {
+    program_id: Pubkey,
+    authority: Keypair,
+    seller: Keypair,
+    buyer: Keypair,
+    nft_mint: Keypair,
+    marketplace: Pubkey,
+    property: Pubkey,
+    listing_bond_config: Pubkey,
+    listing_limits: Pubkey,
+    owner_listings: Pubkey,
+    offer_limits: Pubkey,
+    offer: Pubkey,
+    escrow: Pubkey,
+    property_governance: Pubkey,
+    sale_authorization: Pubkey,
+    property_id: String,
+}
+
+impl Sandbox {
+    fn new(program_id: Pubkey) -> Self {
+        let authority = Keypair::new();
+        let seller = Keypair::new();
+        let buyer = Keypair::new();
+        // Unique per run so repeated runs on one validator don't collide
+        let property_id = format!("sandbox-{}", Utc::now().timestamp_millis());
+
+        let find = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &program_id).0;
+        let marketplace = find(&[b"marketplace", authority.pubkey().as_ref()]);
+        let property = find(&[b"property", marketplace.as_ref(), property_id.as_bytes()]);
+        let offer = find(&[b"offer", property.as_ref(), buyer.pubkey().as_ref(), &0u64.to_le_bytes()]);
+        Sandbox {
+            listing_bond_config: find(&[b"listing_bond_config", marketplace.as_ref()]),
+            listing_limits: find(&[b"listing_limits", marketplace.as_ref()]),
+            owner_listings: find(&[b"owner_listings", marketplace.as_ref(), seller.pubkey().as_ref()]),
+            offer_limits: find(&[b"offer_limits", marketplace.as_ref()]),
+            escrow: find(&[b"escrow", offer.as_ref()]),
+            property_governance: find(&[b"property_governance", property.as_ref()]),
+            sale_authorization: find(&[b"sale_authorization", property.as_ref()]),
+            program_id,
+            authority,
+            seller,
+            buyer,
+            nft_mint: Keypair::new(),
+            marketplace,
+            property,
+            offer,
+            property_id,
+        }
+    }
+
+    fn tracked_accounts(&self) -> [(&'static str, Pubkey); 4] {
+        [
+            ("marketplace", self.marketplace),
+            ("property", self.property),
+            ("offer", self.offer),
+            ("escrow", self.escrow),
+        ]
+    }
+}
+
+fn put_string(data: &mut Vec<u8>, value: &str) {
+    data.extend((value.len() as u32).to_le_bytes());
+    data.extend(value.as_bytes());
+}
+
+fn initialize_marketplace_ix(sandbox: &Sandbox, fee_bps: u64) -> Instruction {
+    let mut data = instruction_discriminator("initialize_marketplace").to_vec();
+    data.extend(fee_bps.to_le_bytes());
+    Instruction {
+        program_id: sandbox.program_id,
+        accounts: vec![
+            AccountMeta::new(sandbox.marketplace, false),
+            AccountMeta::new(sandbox.authority.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Mint account with the seller as mint authority, plus the seller's token account
+fn create_nft_mint_ixs(rpc_client: &RpcClient, sandbox: &Sandbox) -> Result<Vec<Instruction>, anyhow::Error> {
+    let seller = sandbox.seller.pubkey();
+    let mint = sandbox.nft_mint.pubkey();
+    let rent = rpc_client.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+    Ok(vec![
+        system_instruction::create_account(&seller, &mint, rent, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_mint2(&spl_token::id(), &mint, &seller, None, 0)?,
+        create_associated_token_account(&seller, &seller, &mint, &spl_token::id()),
+    ])
+}
+
+fn list_property_ix(sandbox: &Sandbox, request: &SimulateSaleRequest) -> Instruction {
+    let seller = sandbox.seller.pubkey();
+    let mint = sandbox.nft_mint.pubkey();
+
+    let mut data = instruction_discriminator("list_property").to_vec();
+    put_string(&mut data, &sandbox.property_id);
+    data.extend(request.price.unwrap_or(DEFAULT_PRICE).to_le_bytes());
+    put_string(&mut data, "https://example.com/sandbox.json");
+    put_string(&mut data, "Sandbox");
+    data.extend(1_000u64.to_le_bytes()); // square_feet
+    data.extend([2, 1]); // bedrooms, bathrooms
+    data.push(0); // expires_at: None
+    data.push(request.property_type.unwrap_or(0));
+    data.extend(request.amenities.unwrap_or(0).to_le_bytes());
+    put_string(&mut data, request.geohash.as_deref().unwrap_or("9q8yyk8y"));
+    data.extend([0, 0]); // latitude_e6, longitude_e6: None
+    data.push(0); // content_hashes: None
+
+    Instruction {
+        program_id: sandbox.program_id,
+        accounts: vec![
+            AccountMeta::new(sandbox.marketplace, false),
+            AccountMeta::new(sandbox.property, false),
+            AccountMeta::new(seller, true),
+            AccountMeta::new(mint, false),
+            AccountMeta::new(get_associated_token_address(&seller, &mint), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sandbox.listing_bond_config, false),
+            // Unused optional listing_bond; the sandbox marketplace charges no bond
+            AccountMeta::new_readonly(sandbox.program_id, false),
+            // Unused optional treasury; the sandbox marketplace charges no listing fee
+            AccountMeta::new_readonly(sandbox.program_id, false),
+            AccountMeta::new_readonly(sandbox.listing_limits, false),
+            AccountMeta::new(sandbox.owner_listings, false),
+            // Unused optional listing_slot; the sandbox marketplace caps no listings
+            AccountMeta::new_readonly(sandbox.program_id, false),
+        ],
+        data,
+    }
+}
+
+fn make_offer_ix(sandbox: &Sandbox, amount: u64) -> Instruction {
+    let mut data = instruction_discriminator("make_offer").to_vec();
+    data.extend(amount.to_le_bytes());
+    data.extend((Utc::now().timestamp() + OFFER_TTL_SECS).to_le_bytes());
+    data.extend(0u64.to_le_bytes()); // offer_nonce
+
+    Instruction {
+        program_id: sandbox.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(sandbox.marketplace, false),
+            AccountMeta::new(sandbox.property, false),
+            AccountMeta::new_readonly(sandbox.offer_limits, false),
+            AccountMeta::new(sandbox.offer, false),
+            AccountMeta::new(sandbox.escrow, false),
+            AccountMeta::new(sandbox.buyer.pubkey(), true),
+            // Unused optional price_feed
+            AccountMeta::new_readonly(sandbox.program_id, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Creates the escrow's NFT account, then accepts the offer
+fn accept_offer_ixs(sandbox: &Sandbox) -> Vec<Instruction> {
+    let seller = sandbox.seller.pubkey();
+    let mint = sandbox.nft_mint.pubkey();
+
+    // accept = true, no seller concession
+    let mut data = instruction_discriminator("respond_to_offer").to_vec();
+    data.extend([1, 0]);
+
+    vec![
+        create_associated_token_account(&seller, &sandbox.escrow, &mint, &spl_token::id()),
+        Instruction {
+            program_id: sandbox.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(sandbox.marketplace, false),
+                AccountMeta::new(sandbox.property, false),
+                AccountMeta::new(sandbox.offer, false),
+                AccountMeta::new(sandbox.escrow, false),
+                AccountMeta::new(seller, true),
+                AccountMeta::new(sandbox.buyer.pubkey(), false),
+                AccountMeta::new(get_associated_token_address(&seller, &mint), false),
+                AccountMeta::new(get_associated_token_address(&sandbox.escrow, &mint), false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                // Unused optional accounts: co_ownership, seller_concession, system_program
+                AccountMeta::new_readonly(sandbox.program_id, false),
+                AccountMeta::new_readonly(sandbox.program_id, false),
+                AccountMeta::new_readonly(sandbox.program_id, false),
+                AccountMeta::new_readonly(sandbox.property_governance, false),
+                AccountMeta::new(sandbox.sale_authorization, false),
+            ],
+            data,
+        },
+    ]
+}
+
+fn snapshot_accounts(rpc_client: &RpcClient, sandbox: &Sandbox) -> Result<Vec<SimulatedAccount>, anyhow::Error> {
+    sandbox
+        .tracked_accounts()
+        .into_iter()
+        .map(|(name, address)| {
+            let account = rpc_client
+                .get_account_with_commitment(&address, rpc_client.commitment())?
+                .value
+                .map(|account| AccountSnapshot {
+                    lamports: account.lamports,
+                    owner: account.owner.to_string(),
+                    data: general_purpose::STANDARD.encode(&account.data),
+                });
+            Ok::<_, anyhow::Error>(SimulatedAccount { name, address: address.to_string(), account })
+        })
+        .collect()
+}
+
+/// Simulates the step for its compute units, then sends it. Returns false when it failed.
+fn run_step(
+    rpc_client: &RpcClient,
+    sandbox: &Sandbox,
+    name: &'static str,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    steps: &mut Vec<SimulatedStep>,
+) -> Result<bool, anyhow::Error> {
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx = SolanaTransaction::new_signed_with_payer(instructions, Some(&signers[0].pubkey()), signers, blockhash);
+    let fee = rpc_client.get_fee_for_message(&tx.message)?;
+
+    let simulation = rpc_client.simulate_transaction(&tx)?.value;
+    let logs = simulation.logs.unwrap_or_default();
+    let mut step = SimulatedStep {
+        name,
+        signature: None,
+        compute_units: simulation.units_consumed,
+        fee,
+        error: simulation.err.map(|e| e.to_string()),
+        logs,
+        accounts: Vec::new(),
+    };
+
+    if step.error.is_none() {
+        match rpc_client.send_and_confirm_transaction(&tx) {
+            Ok(signature) => step.signature = Some(signature.to_string()),
+            Err(e) => step.error = Some(e.to_string()),
+        }
+    }
+    step.accounts = snapshot_accounts(rpc_client, sandbox)?;
+
+    let succeeded = step.error.is_none();
+    steps.push(step);
+    Ok(succeeded)
+}
+
+fn simulate_sale(rpc_url: &str, request: &SimulateSaleRequest) -> Result<SimulateSaleResponse, anyhow::Error> {
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+    let sandbox = Sandbox::new(Pubkey::from_str(PROGRAM_ID)?);
+
+    for wallet in [&sandbox.authority, &sandbox.seller, &sandbox.buyer] {
+        let signature = rpc_client.request_airdrop(&wallet.pubkey(), AIRDROP_SOL * LAMPORTS_PER_SOL)?;
+        rpc_client.poll_for_signature(&signature)?;
+    }
+
+    let price = request.price.unwrap_or(DEFAULT_PRICE);
+    let mut steps = Vec::new();
+    let completed = run_step(
+        &rpc_client,
+        &sandbox,
+        "initialize_marketplace",
+        &[initialize_marketplace_ix(&sandbox, request.fee_bps.unwrap_or(250))],
+        &[&sandbox.authority],
+        &mut steps,
+    )? && run_step(
+        &rpc_client,
+        &sandbox,
+        "create_nft_mint",
+        &create_nft_mint_ixs(&rpc_client, &sandbox)?,
+        &[&sandbox.seller, &sandbox.nft_mint],
+        &mut steps,
+    )? && run_step(
+        &rpc_client,
+        &sandbox,
+        "list_property",
+        &[list_property_ix(&sandbox, request)],
+        &[&sandbox.seller],
+        &mut steps,
+    )? && run_step(
+        &rpc_client,
+        &sandbox,
+        "make_offer",
+        &[make_offer_ix(&sandbox, request.offer_amount.unwrap_or(price))],
+        &[&sandbox.buyer],
+        &mut steps,
+    )? && run_step(
+        &rpc_client,
+        &sandbox,
+        "accept_offer",
+        &accept_offer_ixs(&sandbox),
+        &[&sandbox.seller],
+        &mut steps,
+    )?;
+
+    Ok(SimulateSaleResponse {
+        success: completed,
+        message: if completed {
+            "Sale simulated".to_string()
+        } else {
+            format!("Simulation stopped at {}", steps.last().map(|s| s.name).unwrap_or("setup"))
+        },
+        marketplace_authority: sandbox.authority.pubkey().to_string(),
+        seller: sandbox.seller.pubkey().to_string(),
+        buyer: sandbox.buyer.pubkey().to_string(),
+        steps,
+    })
+}
+
+/// Walks list → offer → accept with generated wallets on the validator at SANDBOX_RPC_URL, which
+/// must have the program deployed. Each step reports compute units, fees and the program
+/// accounts afterwards, so integrators can check their parameters before touching devnet.
+pub async fn simulate_sale_endpoint(
+    req: HttpRequest,
+    data: web::Json<SimulateSaleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let rpc_url = env::var("SANDBOX_RPC_URL")
+        .map_err(|_| ApiError::Unavailable("Sandbox validator is not configured".to_string()))?;
+
+    let request = data.into_inner();
+    let response = web::block(move || simulate_sale(&rpc_url, &request))
+        .await?
+        .map_err(|e| ApiError::Unavailable(format!("Sandbox run failed: {}", e)))?;
+
+    info!("Sandbox sale simulation for {}: {}", wallet_address, response.message);
+    Ok(HttpResponse::Ok().json(response))
+}
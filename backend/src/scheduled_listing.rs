@@ -0,0 +1,496 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::Transaction as SolanaTransaction};
+use std::str::FromStr;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::ApiError;
+use crate::models::ScheduledListing;
+use crate::risk;
+use crate::schema::{properties, scheduled_listings};
+use crate::transaction::{
+    authenticated_wallet, instruction_discriminator, listing_row, record_listed, ListPropertyRequest, PROGRAM_ID,
+};
+
+/// How often the publisher looks for listings that are due
+const POLL_INTERVAL_SECS: u64 = 30;
+/// Furthest ahead a listing can be scheduled
+const MAX_SCHEDULE_DAYS: i64 = 90;
+/// Editing and cancelling are only possible before the publisher claims a listing
+const EDITABLE_STATUSES: [&str; 2] = ["scheduled", "failed"];
+/// A listing left in `publishing` this long was interrupted and is picked up again
+const STALE_PUBLISH_MINS: i64 = 10;
+
+/// Listings in these states still have a hidden property row
+pub const UNPUBLISHED_STATUSES: [&str; 3] = ["scheduled", "publishing", "failed"];
+
+#[derive(Deserialize)]
+pub struct ScheduleListingRequest {
+    /// Base64 list_property transaction, fully signed against a durable nonce
+    pub serialized_transaction: String,
+    /// Same metadata `/api/transactions/submit` takes for a listing
+    pub metadata: String,
+    /// Unix timestamp to publish at
+    pub publish_at: i64,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateScheduledListingRequest {
+    pub publish_at: Option<i64>,
+    /// A re-signed transaction replaces the listing; it must come with its metadata
+    pub serialized_transaction: Option<String>,
+    pub metadata: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ScheduledListingResponse {
+    pub success: bool,
+    pub message: String,
+    pub listing: ScheduledListing,
+}
+
+#[derive(Serialize)]
+pub struct ScheduledListingsResponse {
+    pub success: bool,
+    pub message: String,
+    pub listings: Vec<ScheduledListing>,
+}
+
+fn publish_time(publish_at: i64) -> Result<NaiveDateTime, ApiError> {
+    let publish_at = DateTime::from_timestamp(publish_at, 0)
+        .ok_or_else(|| ApiError::InvalidInput("Invalid publish time".to_string()))?
+        .naive_utc();
+    let now = Utc::now().naive_utc();
+    if publish_at <= now {
+        return Err(ApiError::InvalidInput("Publish time must be in the future".to_string()));
+    }
+    if publish_at > now + Duration::days(MAX_SCHEDULE_DAYS) {
+        return Err(ApiError::InvalidInput(format!(
+            "Listings can be scheduled at most {} days ahead",
+            MAX_SCHEDULE_DAYS
+        )));
+    }
+    Ok(publish_at)
+}
+
+/// `property_id`, the first list_property argument, as a borsh string after the discriminator
+fn listed_property_id(data: &[u8]) -> Option<&str> {
+    let len = u32::from_le_bytes(data.get(8..12)?.try_into().ok()?) as usize;
+    std::str::from_utf8(data.get(12..12usize.checked_add(len)?)?).ok()
+}
+
+/// Decodes a listing transaction that can be held until publication: signed by the wallet as
+/// fee payer, calling list_property for `property_id` only, and using a durable nonce so it
+/// doesn't expire
+fn held_transaction(serialized: &str, wallet_address: &str, property_id: &str) -> Result<SolanaTransaction, ApiError> {
+    let tx_bytes = general_purpose::STANDARD
+        .decode(serialized)
+        .map_err(|_| ApiError::InvalidInput("Invalid serialized transaction".to_string()))?;
+    let tx = bincode::deserialize::<SolanaTransaction>(&tx_bytes)
+        .map_err(|e| ApiError::InvalidInput(format!("Failed to deserialize transaction: {}", e)))?;
+
+    let wallet = Pubkey::from_str(wallet_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid wallet address".to_string()))?;
+    if tx.message.account_keys.first() != Some(&wallet) {
+        return Err(ApiError::InvalidInput("Transaction must be paid for by your wallet".to_string()));
+    }
+    if tx.verify().is_err() {
+        return Err(ApiError::InvalidInput("Transaction must be fully signed".to_string()));
+    }
+    if solana_sdk::transaction::uses_durable_nonce(&tx).is_none() {
+        return Err(ApiError::InvalidInput(
+            "Scheduled listings must use a durable nonce so they don't expire".to_string(),
+        ));
+    }
+
+    let program_id = Pubkey::from_str(PROGRAM_ID)
+        .map_err(|e| ApiError::Internal(format!("Invalid program id: {}", e)))?;
+    let list_property = instruction_discriminator("list_property");
+    let listed: Vec<Option<&str>> = tx
+        .message
+        .instructions
+        .iter()
+        .filter(|ix| {
+            tx.message.account_keys.get(ix.program_id_index as usize) == Some(&program_id)
+                && ix.data.starts_with(&list_property)
+        })
+        .map(|ix| listed_property_id(&ix.data))
+        .collect();
+    if listed.is_empty() {
+        return Err(ApiError::InvalidInput("Transaction does not list a property".to_string()));
+    }
+    // The hidden property row is keyed by the metadata, so the transaction must list the same one
+    if listed.iter().any(|id| *id != Some(property_id)) {
+        return Err(ApiError::InvalidInput(format!(
+            "Transaction does not list property {} from the metadata",
+            property_id
+        )));
+    }
+    Ok(tx)
+}
+
+fn parse_metadata(metadata: &str) -> Result<ListPropertyRequest, ApiError> {
+    serde_json::from_str(metadata).map_err(|e| ApiError::InvalidInput(format!("Failed to parse metadata: {}", e)))
+}
+
+fn load_listing(conn: &mut PgConnection, listing_id: &str, wallet_address: &str) -> Result<ScheduledListing, ApiError> {
+    let listing_uuid = Uuid::parse_str(listing_id)
+        .map_err(|_| ApiError::InvalidInput("Invalid scheduled listing ID".to_string()))?;
+    let listing = scheduled_listings::table
+        .find(listing_uuid)
+        .first::<ScheduledListing>(conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch scheduled listing"))?
+        .ok_or_else(|| ApiError::NotFound("Scheduled listing not found".to_string()))?;
+    if listing.wallet_address != wallet_address {
+        return Err(ApiError::Forbidden("Scheduled listing belongs to another wallet".to_string()));
+    }
+    Ok(listing)
+}
+
+/// Holds a signed listing transaction until `publish_at`. The property row is created right away
+/// but stays hidden until the publisher lands the transaction.
+pub async fn schedule_listing(
+    req: HttpRequest,
+    data: web::Json<ScheduleListingRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let publish_at = publish_time(data.publish_at)?;
+    let metadata = parse_metadata(&data.metadata)?;
+    let tx = held_transaction(&data.serialized_transaction, &wallet_address, &metadata.property_id)?;
+
+    risk::screen_relay(&wallet_address, "schedule_listing", risk::message_addresses(&tx.message)).await?;
+
+    let mut conn = db::establish_connection()?;
+    let existing = properties::table
+        .filter(properties::property_id.eq(&metadata.property_id))
+        .count()
+        .get_result::<i64>(&mut conn)
+        .map_err(ApiError::db("Failed to check property ID"))?;
+    if existing > 0 {
+        return Err(ApiError::InvalidInput("Property ID is already in use".to_string()));
+    }
+
+    let now = Utc::now().naive_utc();
+    let listing = ScheduledListing {
+        id: Uuid::new_v4(),
+        property_id: metadata.property_id.clone(),
+        wallet_address: wallet_address.clone(),
+        serialized_transaction: Some(data.serialized_transaction.clone()),
+        metadata: data.metadata.clone(),
+        publish_at,
+        status: "scheduled".to_string(),
+        signature: None,
+        last_error: None,
+        published_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let hidden_property = listing_row(&mut conn, &wallet_address, &metadata, false);
+
+    let listing = conn
+        .transaction(|conn| {
+            diesel::insert_into(properties::table).values(&hidden_property).execute(conn)?;
+            diesel::insert_into(scheduled_listings::table)
+                .values(&listing)
+                .get_result::<ScheduledListing>(conn)
+        })
+        .map_err(ApiError::db("Failed to schedule listing"))?;
+
+    info!("Scheduled listing of {} for {}", listing.property_id, listing.publish_at);
+    Ok(HttpResponse::Ok().json(ScheduledListingResponse {
+        success: true,
+        message: "Listing scheduled".to_string(),
+        listing,
+    }))
+}
+
+/// The authenticated wallet's scheduled listings, soonest first
+pub async fn get_my_scheduled_listings(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+
+    let listings = scheduled_listings::table
+        .filter(scheduled_listings::wallet_address.eq(&wallet_address))
+        .order(scheduled_listings::publish_at.asc())
+        .load::<ScheduledListing>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch scheduled listings"))?;
+
+    Ok(HttpResponse::Ok().json(ScheduledListingsResponse {
+        success: true,
+        message: format!("Found {} scheduled listings", listings.len()),
+        listings,
+    }))
+}
+
+/// Moves a listing's publish time or replaces its transaction before it is published. A failed
+/// listing is rescheduled by editing it.
+pub async fn update_scheduled_listing(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<UpdateScheduledListingRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let data = data.into_inner();
+
+    let replacement = match (&data.serialized_transaction, &data.metadata) {
+        (Some(serialized), Some(metadata)) => {
+            let metadata = parse_metadata(metadata)?;
+            let tx = held_transaction(serialized, &wallet_address, &metadata.property_id)?;
+            risk::screen_relay(&wallet_address, "schedule_listing", risk::message_addresses(&tx.message)).await?;
+            Some(metadata)
+        }
+        (None, None) => None,
+        _ => {
+            return Err(ApiError::InvalidInput(
+                "A replacement transaction must come with its metadata".to_string(),
+            ))
+        }
+    };
+    let publish_at = data.publish_at.map(publish_time).transpose()?;
+
+    let mut conn = db::establish_connection()?;
+    let listing = load_listing(&mut conn, &path.into_inner(), &wallet_address)?;
+    if !EDITABLE_STATUSES.contains(&listing.status.as_str()) {
+        return Err(ApiError::InvalidInput(format!("Listing is already {}", listing.status)));
+    }
+    if listing.status == "failed" && publish_at.is_none() && listing.publish_at <= Utc::now().naive_utc() {
+        return Err(ApiError::InvalidInput("Choose a new publish time to reschedule".to_string()));
+    }
+    if let Some(metadata) = &replacement {
+        if metadata.property_id != listing.property_id {
+            return Err(ApiError::InvalidInput("Property ID cannot be changed".to_string()));
+        }
+    }
+
+    let now = Utc::now().naive_utc();
+    let updated = conn
+        .transaction(|conn| {
+            // Guarded on the status so a listing the publisher just claimed is left alone
+            let Some(updated) = diesel::update(
+                scheduled_listings::table
+                    .find(listing.id)
+                    .filter(scheduled_listings::status.eq_any(EDITABLE_STATUSES)),
+            )
+            .set((
+                scheduled_listings::publish_at.eq(publish_at.unwrap_or(listing.publish_at)),
+                scheduled_listings::status.eq("scheduled"),
+                scheduled_listings::last_error.eq(None::<String>),
+                scheduled_listings::serialized_transaction
+                    .eq(data.serialized_transaction.clone().or(listing.serialized_transaction.clone())),
+                scheduled_listings::metadata.eq(data.metadata.clone().unwrap_or(listing.metadata.clone())),
+                scheduled_listings::updated_at.eq(now),
+            ))
+            .get_result::<ScheduledListing>(conn)
+            .optional()?
+            else {
+                return Ok(None);
+            };
+
+            if let Some(metadata) = &replacement {
+                diesel::update(
+                    properties::table
+                        .filter(properties::property_id.eq(&listing.property_id))
+                        .filter(properties::is_active.eq(false)),
+                )
+                .set((
+                    properties::price.eq(metadata.price as i64),
+                    properties::metadata_uri.eq(&metadata.metadata_uri),
+                    properties::location.eq(&metadata.location),
+                    properties::square_feet.eq(metadata.square_feet as i64),
+                    properties::bedrooms.eq(metadata.bedrooms as i16),
+                    properties::bathrooms.eq(metadata.bathrooms as i16),
+                    properties::nft_mint_address.eq(&metadata.nft_mint_address),
+                    properties::nft_token_account.eq(&metadata.nft_token_account),
+                    properties::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            }
+            Ok(Some(updated))
+        })
+        .map_err(ApiError::db("Failed to update scheduled listing"))?
+        .ok_or_else(|| ApiError::InvalidInput("Listing is already being published".to_string()))?;
+
+    info!("Updated scheduled listing of {}", updated.property_id);
+    Ok(HttpResponse::Ok().json(ScheduledListingResponse {
+        success: true,
+        message: "Scheduled listing updated".to_string(),
+        listing: updated,
+    }))
+}
+
+/// Drops a listing before it is published, along with its hidden property row. The signed
+/// transaction is discarded here, but a copy the owner kept stays valid until they advance the
+/// nonce.
+pub async fn cancel_scheduled_listing(req: HttpRequest, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+    let listing = load_listing(&mut conn, &path.into_inner(), &wallet_address)?;
+    if !EDITABLE_STATUSES.contains(&listing.status.as_str()) {
+        return Err(ApiError::InvalidInput(format!("Listing is already {}", listing.status)));
+    }
+
+    let cancelled = conn
+        .transaction(|conn| {
+            let Some(cancelled) = diesel::update(
+                scheduled_listings::table
+                    .find(listing.id)
+                    .filter(scheduled_listings::status.eq_any(EDITABLE_STATUSES)),
+            )
+            .set((
+                scheduled_listings::status.eq("cancelled"),
+                scheduled_listings::serialized_transaction.eq(None::<String>),
+                scheduled_listings::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<ScheduledListing>(conn)
+            .optional()?
+            else {
+                return Ok(None);
+            };
+
+            diesel::delete(
+                properties::table
+                    .filter(properties::property_id.eq(&listing.property_id))
+                    .filter(properties::is_active.eq(false)),
+            )
+            .execute(conn)?;
+            Ok(Some(cancelled))
+        })
+        .map_err(ApiError::db("Failed to cancel scheduled listing"))?
+        .ok_or_else(|| ApiError::InvalidInput("Listing is already being published".to_string()))?;
+
+    info!("Cancelled scheduled listing of {}", cancelled.property_id);
+    Ok(HttpResponse::Ok().json(ScheduledListingResponse {
+        success: true,
+        message: "Scheduled listing cancelled".to_string(),
+        listing: cancelled,
+    }))
+}
+
+/// Sends a held transaction. A transaction that already landed, e.g. before a restart cut the
+/// previous attempt short, counts as sent.
+fn send_listing(tx: &SolanaTransaction) -> Result<Signature, anyhow::Error> {
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+    let signature = tx.signatures[0];
+    match rpc_client.send_and_confirm_transaction(tx) {
+        Ok(signature) => Ok(signature),
+        Err(e) => match rpc_client.get_signature_status(&signature)? {
+            Some(Ok(())) => Ok(signature),
+            _ => Err(e.into()),
+        },
+    }
+}
+
+fn publish(conn: &mut PgConnection, listing: &ScheduledListing) -> Result<Signature, anyhow::Error> {
+    let serialized = listing
+        .serialized_transaction
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("No transaction is held for this listing"))?;
+    let tx = bincode::deserialize::<SolanaTransaction>(&general_purpose::STANDARD.decode(serialized)?)?;
+    let metadata: ListPropertyRequest = serde_json::from_str(&listing.metadata)?;
+
+    let signature = send_listing(&tx)?;
+
+    let now = Utc::now().naive_utc();
+    conn.transaction(|conn| {
+        diesel::update(properties::table.filter(properties::property_id.eq(&listing.property_id)))
+            .set((properties::is_active.eq(true), properties::updated_at.eq(now)))
+            .execute(conn)?;
+        diesel::update(scheduled_listings::table.find(listing.id))
+            .set((
+                scheduled_listings::status.eq("published"),
+                scheduled_listings::signature.eq(Some(signature.to_string())),
+                scheduled_listings::serialized_transaction.eq(None::<String>),
+                scheduled_listings::last_error.eq(None::<String>),
+                scheduled_listings::published_at.eq(Some(now)),
+                scheduled_listings::updated_at.eq(now),
+            ))
+            .execute(conn)
+    })?;
+    record_listed(conn, &listing.wallet_address, &metadata);
+    Ok(signature)
+}
+
+/// Publishes every due listing once, returning how many went live
+pub fn process_due(conn: &mut PgConnection) -> QueryResult<usize> {
+    let now = Utc::now().naive_utc();
+    let due = scheduled_listings::table
+        .filter(
+            scheduled_listings::status
+                .eq("scheduled")
+                .and(scheduled_listings::publish_at.le(now))
+                .or(scheduled_listings::status
+                    .eq("publishing")
+                    .and(scheduled_listings::updated_at.le(now - Duration::minutes(STALE_PUBLISH_MINS)))),
+        )
+        .order(scheduled_listings::publish_at.asc())
+        .limit(20)
+        .load::<ScheduledListing>(conn)?;
+
+    let mut published = 0;
+    for listing in due {
+        // Claim the listing so an edit or cancel can't race the submission
+        let claimed = diesel::update(
+            scheduled_listings::table
+                .find(listing.id)
+                .filter(scheduled_listings::status.eq(&listing.status))
+                .filter(scheduled_listings::updated_at.eq(listing.updated_at)),
+        )
+        .set((
+            scheduled_listings::status.eq("publishing"),
+            scheduled_listings::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+        if claimed == 0 {
+            continue;
+        }
+
+        match publish(conn, &listing) {
+            Ok(signature) => {
+                info!("Published scheduled listing of {} in {}", listing.property_id, signature);
+                published += 1;
+            }
+            Err(e) => {
+                warn!("Failed to publish scheduled listing of {}: {}", listing.property_id, e);
+                diesel::update(scheduled_listings::table.find(listing.id))
+                    .set((
+                        scheduled_listings::status.eq("failed"),
+                        scheduled_listings::last_error.eq(Some(e.to_string())),
+                        scheduled_listings::updated_at.eq(Utc::now().naive_utc()),
+                    ))
+                    .execute(conn)?;
+            }
+        }
+    }
+
+    Ok(published)
+}
+
+/// Spawns the background task that publishes scheduled listings when they are due
+pub fn spawn_publisher() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(POLL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let result = tokio::task::spawn_blocking(|| {
+                let mut conn = db::establish_connection().map_err(|e| e.to_string())?;
+                process_due(&mut conn).map_err(|e| e.to_string())
+            })
+            .await;
+
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(count)) => info!("Published {} scheduled listings", count),
+                Ok(Err(e)) => error!("Scheduled listing run failed: {}", e),
+                Err(e) => error!("Scheduled listing publisher panicked: {}", e),
+            }
+        }
+    });
+}
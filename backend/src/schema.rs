@@ -1,5 +1,111 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_usage (wallet_address, bucket_start) {
+        wallet_address -> Text,
+        bucket_start -> Timestamp,
+        requests -> Int8,
+        client_errors -> Int8,
+        server_errors -> Int8,
+    }
+}
+
+diesel::table! {
+    archived_histories (id) {
+        id -> Uuid,
+        property_id -> Text,
+        history_address -> Text,
+        transaction_index -> Int8,
+        seller_wallet -> Text,
+        buyer_wallet -> Text,
+        price -> Int8,
+        seller_concession -> Int8,
+        sold_at -> Timestamp,
+        archived_at -> Timestamp,
+        archive_signature -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    attribute_corrections (id) {
+        id -> Uuid,
+        property_id -> Text,
+        owner_wallet -> Text,
+        square_feet -> Nullable<Int8>,
+        bedrooms -> Nullable<Int2>,
+        bathrooms -> Nullable<Int2>,
+        reason -> Nullable<Text>,
+        document_uris -> Text,
+        status -> Text,
+        verifier_wallet -> Nullable<Text>,
+        review_notes -> Nullable<Text>,
+        serialized_transaction -> Nullable<Text>,
+        signature -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    callback_deliveries (id) {
+        id -> Uuid,
+        callback_id -> Uuid,
+        property_id -> Text,
+        event_type -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        created_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    governance_proposals (id) {
+        id -> Uuid,
+        offer_id -> Uuid,
+        property_id -> Text,
+        governance -> Text,
+        proposal_address -> Nullable<Text>,
+        min_amount -> Int8,
+        authorization_expires_at -> Timestamp,
+        status -> Text,
+        acceptance_transaction -> Nullable<Text>,
+        created_by -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    id_reservations (id) {
+        id -> Uuid,
+        kind -> Text,
+        scope -> Text,
+        value -> Text,
+        reserved_by -> Text,
+        status -> Text,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    invite_codes (id) {
+        id -> Uuid,
+        code -> Text,
+        owner_wallet -> Text,
+        organization_id -> Nullable<Uuid>,
+        max_uses -> Nullable<Int4>,
+        use_count -> Int4,
+        expires_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     marketplace (id) {
         id -> Uuid,
@@ -21,6 +127,74 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         expiration_time -> Timestamp,
+        offer_nonce -> Int8,
+    }
+}
+
+diesel::table! {
+    organization_members (id) {
+        id -> Uuid,
+        organization_id -> Uuid,
+        wallet_address -> Text,
+        role -> Text,
+        status -> Text,
+        invited_by -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    organizations (id) {
+        id -> Uuid,
+        name -> Text,
+        owner_wallet -> Text,
+        multisig_address -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    outbox_events (id) {
+        id -> Uuid,
+        event_type -> Text,
+        aggregate_id -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        created_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    payout_address_changes (id) {
+        id -> Uuid,
+        wallet_address -> Text,
+        payout_address -> Text,
+        previous_payout_address -> Nullable<Text>,
+        message -> Text,
+        wallet_signature -> Text,
+        payout_signature -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    pending_db_effects (id) {
+        id -> Uuid,
+        signature -> Text,
+        effect_type -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -40,6 +214,140 @@ diesel::table! {
         updated_at -> Timestamp,
         nft_mint_address -> Varchar,
         nft_token_account -> Varchar,
+        organization_id -> Nullable<Uuid>,
+        slug -> Text,
+    }
+}
+
+diesel::table! {
+    property_callbacks (id) {
+        id -> Uuid,
+        property_id -> Text,
+        owner_wallet -> Text,
+        url -> Text,
+        secret -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    property_events (id) {
+        id -> Uuid,
+        property_id -> Text,
+        event_type -> Text,
+        price -> Nullable<Int8>,
+        previous_price -> Nullable<Int8>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    property_translations (id) {
+        id -> Uuid,
+        property_id -> Text,
+        locale -> Text,
+        location -> Text,
+        description -> Nullable<Text>,
+        source -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    property_verifications (id) {
+        id -> Uuid,
+        property_id -> Text,
+        cadastral_id -> Text,
+        jurisdiction_code -> Text,
+        adapter -> Text,
+        status -> Text,
+        details -> Nullable<Text>,
+        attestation_signature -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    referral_conversions (id) {
+        id -> Uuid,
+        invite_code_id -> Uuid,
+        wallet_address -> Text,
+        event_type -> Text,
+        property_id -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    risk_screenings (id) {
+        id -> Uuid,
+        wallet_address -> Text,
+        context -> Text,
+        addresses -> Text,
+        provider -> Text,
+        decision -> Text,
+        matched_address -> Nullable<Text>,
+        reason -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    scheduled_listings (id) {
+        id -> Uuid,
+        property_id -> Text,
+        wallet_address -> Text,
+        serialized_transaction -> Nullable<Text>,
+        metadata -> Text,
+        publish_at -> Timestamp,
+        status -> Text,
+        signature -> Nullable<Text>,
+        last_error -> Nullable<Text>,
+        published_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    swap_deals (id) {
+        id -> Uuid,
+        proposer_wallet -> Text,
+        proposer_property_id -> Text,
+        counterparty_wallet -> Text,
+        counterparty_property_id -> Text,
+        cash_difference -> Int8,
+        status -> Text,
+        proposer_leg_status -> Text,
+        proposer_leg_signature -> Nullable<Text>,
+        counterparty_leg_status -> Text,
+        counterparty_leg_signature -> Nullable<Text>,
+        serialized_transaction -> Nullable<Text>,
+        signature -> Nullable<Text>,
+        expires_at -> Timestamp,
+        accepted_at -> Nullable<Timestamp>,
+        completed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    transaction_drafts (id) {
+        id -> Uuid,
+        wallet_address -> Text,
+        fee_payer -> Text,
+        instructions -> Text,
+        nonce_account -> Nullable<Text>,
+        serialized_transaction -> Text,
+        blockhash -> Text,
+        last_valid_block_height -> Nullable<Int8>,
+        refresh_count -> Int4,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -51,6 +359,7 @@ diesel::table! {
         buyer_wallet -> Text,
         price -> Int8,
         timestamp -> Timestamp,
+        signature -> Nullable<Text>,
     }
 }
 
@@ -62,10 +371,38 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(callback_deliveries -> property_callbacks (callback_id));
+diesel::joinable!(governance_proposals -> offers (offer_id));
+diesel::joinable!(invite_codes -> organizations (organization_id));
+diesel::joinable!(organization_members -> organizations (organization_id));
+diesel::joinable!(properties -> organizations (organization_id));
+diesel::joinable!(referral_conversions -> invite_codes (invite_code_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    api_usage,
+    archived_histories,
+    attribute_corrections,
+    callback_deliveries,
+    governance_proposals,
+    id_reservations,
+    invite_codes,
     marketplace,
     offers,
+    organization_members,
+    organizations,
+    outbox_events,
+    payout_address_changes,
+    pending_db_effects,
     properties,
+    property_callbacks,
+    property_events,
+    property_translations,
+    property_verifications,
+    referral_conversions,
+    risk_screenings,
+    scheduled_listings,
+    swap_deals,
+    transaction_drafts,
     transactions,
     users,
 );
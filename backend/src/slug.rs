@@ -0,0 +1,126 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::env;
+use tracing::{info, error};
+
+use crate::db;
+use crate::market_status;
+use crate::models::Property;
+use crate::schema::properties;
+use crate::translation;
+
+/// Lowercase ASCII words joined by single hyphens; everything else is dropped
+pub fn slugify(text: &str) -> String {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Slug for a new listing, `<location>-<property id>`. Property ids are unique, so a numeric
+/// suffix is only needed when two ids slugify the same way.
+pub fn unique_slug(conn: &mut PgConnection, location: &str, prop_id: &str) -> String {
+    let base = [slugify(location), slugify(prop_id)]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    let mut candidate = base.clone();
+    for n in 2.. {
+        let taken = diesel::select(diesel::dsl::exists(
+            properties::table.filter(properties::slug.eq(&candidate)),
+        ))
+        .get_result::<bool>(conn);
+        match taken {
+            Ok(false) => break,
+            Ok(true) => candidate = format!("{}-{}", base, n),
+            Err(e) => {
+                // The insert itself will surface a genuine collision
+                error!("Failed to check slug {}: {}", candidate, e);
+                break;
+            }
+        }
+    }
+    candidate
+}
+
+/// Fetches an active property by its slug, localized to the request locale
+pub async fn get_property_by_slug(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let slug_param = path.into_inner().to_ascii_lowercase();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    info!("Fetching property with slug: {}", slug_param);
+
+    match properties::table
+        .filter(properties::slug.eq(&slug_param))
+        .filter(properties::is_active.eq(true))
+        .first::<Property>(&mut conn)
+    {
+        Ok(prop) => {
+            let mut props = [prop];
+            if let Some(locale) = translation::request_locale(&req) {
+                translation::localize_properties(&mut conn, &mut props, &locale);
+            }
+            let listing = market_status::with_market_status(&mut conn, props.into());
+            HttpResponse::Ok().json(&listing[0])
+        }
+        Err(diesel::result::Error::NotFound) => HttpResponse::NotFound().body("Property not found"),
+        Err(e) => {
+            error!("Failed to fetch property by slug: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch property: {}", e))
+        }
+    }
+}
+
+/// XML sitemap of active listings at their canonical slug URLs under SITE_URL
+pub async fn get_sitemap() -> impl Responder {
+    let site_url = env::var("SITE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let site_url = site_url.trim_end_matches('/');
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let listings = match properties::table
+        .filter(properties::is_active.eq(true))
+        .order_by(properties::updated_at.desc())
+        .select((properties::slug, properties::updated_at))
+        .load::<(String, chrono::NaiveDateTime)>(&mut conn)
+    {
+        Ok(listings) => listings,
+        Err(e) => {
+            error!("Failed to fetch listings for sitemap: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to build sitemap: {}", e));
+        }
+    };
+
+    // Slugs are plain [a-z0-9-], so they need no XML escaping
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (listing_slug, updated_at) in listings {
+        xml.push_str(&format!(
+            "  <url><loc>{}/properties/{}</loc><lastmod>{}</lastmod></url>\n",
+            site_url,
+            listing_slug,
+            updated_at.format("%Y-%m-%d"),
+        ));
+    }
+    xml.push_str("</urlset>\n");
+
+    HttpResponse::Ok().content_type("application/xml").body(xml)
+}
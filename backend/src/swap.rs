@@ -0,0 +1,646 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose, Engine};
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_program,
+    transaction::Transaction as SolanaTransaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::str::FromStr;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::ApiError;
+use crate::models::{Property, SwapDeal};
+use crate::organization;
+use crate::risk;
+use crate::schema::{offers, properties, swap_deals};
+use crate::transaction::{authenticated_wallet, get_property_pubkey, instruction_discriminator, PROGRAM_ID};
+
+// Deal lifecycle
+pub const STATUS_PROPOSED: &str = "proposed";
+pub const STATUS_ACCEPTED: &str = "accepted";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+// Each side's signature on the swap transaction
+pub const LEG_PENDING: &str = "pending";
+pub const LEG_SIGNED: &str = "signed";
+
+/// How long the counterparty has to accept a proposal
+const SWAP_TTL_DAYS: i64 = 7;
+
+#[derive(Deserialize)]
+pub struct ProposeSwapRequest {
+    /// The proposer's property
+    pub property_id: String,
+    /// The property the proposer wants in return
+    pub counterparty_property_id: String,
+    /// Lamports the proposer adds to the deal; negative asks the counterparty to add them
+    pub cash_difference: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct SignSwapRequest {
+    /// Base58 signature of the prepared transaction message
+    pub signature: String,
+}
+
+/// One side of the swap, for progress tracking
+#[derive(Serialize, Clone)]
+pub struct SwapLeg {
+    pub wallet: String,
+    pub gives_property_id: String,
+    pub receives_property_id: String,
+    /// Cash sent to the other party in the swap transaction
+    pub pays_lamports: u64,
+    pub status: String,
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SwapDealView {
+    #[serde(flatten)]
+    pub deal: SwapDeal,
+    pub legs: Vec<SwapLeg>,
+}
+
+#[derive(Serialize)]
+pub struct SwapDealResponse {
+    pub success: bool,
+    pub message: String,
+    pub swap: SwapDealView,
+}
+
+#[derive(Serialize)]
+pub struct SwapDealsResponse {
+    pub success: bool,
+    pub message: String,
+    pub swaps: Vec<SwapDealView>,
+}
+
+#[derive(Serialize)]
+pub struct PreparedSwapResponse {
+    pub success: bool,
+    pub message: String,
+    pub swap: SwapDealView,
+    /// Base64 unsigned transaction for both parties to sign
+    pub serialized_transaction: String,
+}
+
+fn view(deal: SwapDeal) -> SwapDealView {
+    let legs = vec![
+        SwapLeg {
+            wallet: deal.proposer_wallet.clone(),
+            gives_property_id: deal.proposer_property_id.clone(),
+            receives_property_id: deal.counterparty_property_id.clone(),
+            pays_lamports: deal.cash_difference.max(0) as u64,
+            status: deal.proposer_leg_status.clone(),
+            signature: deal.proposer_leg_signature.clone(),
+        },
+        SwapLeg {
+            wallet: deal.counterparty_wallet.clone(),
+            gives_property_id: deal.counterparty_property_id.clone(),
+            receives_property_id: deal.proposer_property_id.clone(),
+            pays_lamports: deal.cash_difference.min(0).unsigned_abs(),
+            status: deal.counterparty_leg_status.clone(),
+            signature: deal.counterparty_leg_signature.clone(),
+        },
+    ];
+    SwapDealView { deal, legs }
+}
+
+fn swap_response(message: &str, deal: SwapDeal) -> HttpResponse {
+    HttpResponse::Ok().json(SwapDealResponse {
+        success: true,
+        message: message.to_string(),
+        swap: view(deal),
+    })
+}
+
+fn load_property(conn: &mut PgConnection, property_id: &str) -> Result<Property, ApiError> {
+    properties::table
+        .filter(properties::property_id.eq(property_id))
+        .first::<Property>(conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch property"))?
+        .ok_or_else(|| ApiError::NotFound(format!("Property {} not found", property_id)))
+}
+
+/// The deal, if the wallet is one of its two parties
+fn load_swap(conn: &mut PgConnection, swap_id: &str, wallet: &str) -> Result<SwapDeal, ApiError> {
+    let swap_uuid = Uuid::parse_str(swap_id)
+        .map_err(|_| ApiError::InvalidInput("Invalid swap ID format".to_string()))?;
+    let deal = swap_deals::table
+        .find(swap_uuid)
+        .first::<SwapDeal>(conn)
+        .optional()
+        .map_err(ApiError::db("Failed to fetch swap"))?
+        .ok_or_else(|| ApiError::NotFound("Swap not found".to_string()))?;
+    if deal.proposer_wallet != wallet && deal.counterparty_wallet != wallet {
+        return Err(ApiError::Forbidden("Only the parties to a swap can view or act on it".to_string()));
+    }
+    Ok(deal)
+}
+
+/// Records a leg's progress on the caller's side of the deal
+fn set_leg(
+    conn: &mut PgConnection,
+    deal: &SwapDeal,
+    wallet: &str,
+    leg_status: &str,
+    signature: Option<&str>,
+) -> QueryResult<SwapDeal> {
+    let target = swap_deals::table.find(deal.id);
+    let now = Utc::now().naive_utc();
+    if deal.proposer_wallet == wallet {
+        diesel::update(target)
+            .set((
+                swap_deals::proposer_leg_status.eq(leg_status),
+                swap_deals::proposer_leg_signature.eq(signature),
+                swap_deals::updated_at.eq(now),
+            ))
+            .get_result(conn)
+    } else {
+        diesel::update(target)
+            .set((
+                swap_deals::counterparty_leg_status.eq(leg_status),
+                swap_deals::counterparty_leg_signature.eq(signature),
+                swap_deals::updated_at.eq(now),
+            ))
+            .get_result(conn)
+    }
+}
+
+/// Proposes trading one of the caller's properties for someone else's, optionally with cash
+/// to balance the values. The other property's owner becomes the counterparty.
+pub async fn propose_swap(
+    req: HttpRequest,
+    data: web::Json<ProposeSwapRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    if data.property_id == data.counterparty_property_id {
+        return Err(ApiError::InvalidInput("A property can't be swapped for itself".to_string()));
+    }
+
+    let mut conn = db::establish_connection()?;
+    let offered = load_property(&mut conn, &data.property_id)?;
+    let wanted = load_property(&mut conn, &data.counterparty_property_id)?;
+    if offered.owner_wallet != wallet_address {
+        return Err(ApiError::Forbidden("You can only offer a property you own".to_string()));
+    }
+    if wanted.owner_wallet == wallet_address {
+        return Err(ApiError::InvalidInput("You already own the requested property".to_string()));
+    }
+
+    let property_ids = [offered.property_id.clone(), wanted.property_id.clone()];
+    let open_deals = swap_deals::table
+        .filter(swap_deals::status.eq_any([STATUS_PROPOSED, STATUS_ACCEPTED]))
+        .filter(
+            swap_deals::proposer_property_id
+                .eq_any(&property_ids)
+                .or(swap_deals::counterparty_property_id.eq_any(&property_ids)),
+        )
+        .count()
+        .get_result::<i64>(&mut conn)
+        .map_err(ApiError::db("Failed to check open swaps"))?;
+    if open_deals > 0 {
+        return Err(ApiError::InvalidInput("One of these properties is already in an open swap".to_string()));
+    }
+
+    let now = Utc::now().naive_utc();
+    let deal = diesel::insert_into(swap_deals::table)
+        .values(&SwapDeal {
+            id: Uuid::new_v4(),
+            proposer_wallet: wallet_address.clone(),
+            proposer_property_id: offered.property_id,
+            counterparty_wallet: wanted.owner_wallet,
+            counterparty_property_id: wanted.property_id,
+            cash_difference: data.cash_difference.unwrap_or(0),
+            status: STATUS_PROPOSED.to_string(),
+            proposer_leg_status: LEG_PENDING.to_string(),
+            proposer_leg_signature: None,
+            counterparty_leg_status: LEG_PENDING.to_string(),
+            counterparty_leg_signature: None,
+            serialized_transaction: None,
+            signature: None,
+            expires_at: now + Duration::days(SWAP_TTL_DAYS),
+            accepted_at: None,
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .get_result::<SwapDeal>(&mut conn)
+        .map_err(ApiError::db("Failed to create swap"))?;
+
+    info!("Swap {} proposed by {}", deal.id, wallet_address);
+    Ok(swap_response("Swap proposed", deal))
+}
+
+/// Swaps the authenticated wallet is party to, newest first
+pub async fn get_my_swaps(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+
+    let deals = swap_deals::table
+        .filter(
+            swap_deals::proposer_wallet
+                .eq(&wallet_address)
+                .or(swap_deals::counterparty_wallet.eq(&wallet_address)),
+        )
+        .order(swap_deals::created_at.desc())
+        .load::<SwapDeal>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch swaps"))?;
+
+    Ok(HttpResponse::Ok().json(SwapDealsResponse {
+        success: true,
+        message: "Swaps retrieved".to_string(),
+        swaps: deals.into_iter().map(view).collect(),
+    }))
+}
+
+pub async fn get_swap(req: HttpRequest, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+    let deal = load_swap(&mut conn, &path.into_inner(), &wallet_address)?;
+    Ok(swap_response("Swap retrieved", deal))
+}
+
+/// Counterparty agrees to the proposed terms; the swap transaction can then be prepared
+pub async fn accept_swap(req: HttpRequest, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+    let deal = load_swap(&mut conn, &path.into_inner(), &wallet_address)?;
+
+    if deal.counterparty_wallet != wallet_address {
+        return Err(ApiError::Forbidden("Only the counterparty can accept a swap".to_string()));
+    }
+    let now = Utc::now().naive_utc();
+    if deal.expires_at <= now {
+        return Err(ApiError::InvalidInput("Swap proposal has expired".to_string()));
+    }
+
+    // Either property may have changed hands since the proposal
+    let offered = load_property(&mut conn, &deal.proposer_property_id)?;
+    let wanted = load_property(&mut conn, &deal.counterparty_property_id)?;
+    if offered.owner_wallet != deal.proposer_wallet || wanted.owner_wallet != deal.counterparty_wallet {
+        return Err(ApiError::InvalidInput("A property in this swap has changed owner".to_string()));
+    }
+
+    let deal = diesel::update(
+        swap_deals::table
+            .find(deal.id)
+            .filter(swap_deals::status.eq(STATUS_PROPOSED)),
+    )
+    .set((
+        swap_deals::status.eq(STATUS_ACCEPTED),
+        swap_deals::accepted_at.eq(now),
+        swap_deals::updated_at.eq(now),
+    ))
+    .get_result::<SwapDeal>(&mut conn)
+    .optional()
+    .map_err(ApiError::db("Failed to accept swap"))?
+    .ok_or_else(|| ApiError::InvalidInput("Swap is no longer open for acceptance".to_string()))?;
+
+    info!("Swap {} accepted by {}", deal.id, wallet_address);
+    Ok(swap_response("Swap accepted", deal))
+}
+
+/// Either party can back out until the swap transaction has been sent
+pub async fn cancel_swap(req: HttpRequest, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+    let deal = load_swap(&mut conn, &path.into_inner(), &wallet_address)?;
+
+    let now = Utc::now().naive_utc();
+    let deal = diesel::update(
+        swap_deals::table
+            .find(deal.id)
+            .filter(swap_deals::status.eq_any([STATUS_PROPOSED, STATUS_ACCEPTED]))
+            .filter(swap_deals::signature.is_null()),
+    )
+    .set((
+        swap_deals::status.eq(STATUS_CANCELLED),
+        swap_deals::updated_at.eq(now),
+    ))
+    .get_result::<SwapDeal>(&mut conn)
+    .optional()
+    .map_err(ApiError::db("Failed to cancel swap"))?
+    .ok_or_else(|| ApiError::InvalidInput("Swap can no longer be cancelled".to_string()))?;
+
+    info!("Swap {} cancelled by {}", deal.id, wallet_address);
+    Ok(swap_response("Swap cancelled", deal))
+}
+
+/// Reads `owner` and `transaction_count` from the on-chain `Property`
+fn fetch_owner_and_count(rpc_client: &RpcClient, property_pda: &Pubkey) -> Result<(Pubkey, u64), anyhow::Error> {
+    let data = rpc_client.get_account_data(property_pda)?;
+    let owner = Pubkey::try_from(
+        data.get(40..72)
+            .ok_or_else(|| anyhow::anyhow!("Property account data is truncated"))?,
+    )?;
+    let mut offset = 8 + 32 + 32; // discriminator, marketplace, owner
+
+    let take = |offset: &mut usize, len: usize| -> Result<&[u8], anyhow::Error> {
+        let slice = data
+            .get(*offset..*offset + len)
+            .ok_or_else(|| anyhow::anyhow!("Property account data is truncated"))?;
+        *offset += len;
+        Ok(slice)
+    };
+    let skip_string = |offset: &mut usize| -> Result<(), anyhow::Error> {
+        let len = u32::from_le_bytes(take(offset, 4)?.try_into()?) as usize;
+        take(offset, len)?;
+        Ok(())
+    };
+
+    skip_string(&mut offset)?; // property_id
+    take(&mut offset, 8)?; // price
+    skip_string(&mut offset)?; // metadata_uri
+    skip_string(&mut offset)?; // location
+    // square_feet, bedrooms, bathrooms, is_active, created_at, updated_at
+    take(&mut offset, 8 + 1 + 1 + 1 + 8 + 8)?;
+
+    Ok((owner, u64::from_le_bytes(take(&mut offset, 8)?.try_into()?)))
+}
+
+/// Unsigned `swap_properties` transaction exchanging the two properties, with the cash
+/// difference paid in the same instruction. The proposer is `owner_a` and pays the fee.
+fn build_swap_transaction(
+    proposer_property: &Property,
+    counterparty_property: &Property,
+    cash_difference: i64,
+) -> Result<SolanaTransaction, anyhow::Error> {
+    let program_id = Pubkey::from_str(PROGRAM_ID)?;
+    let owner_a = Pubkey::from_str(&proposer_property.owner_wallet)?;
+    let owner_b = Pubkey::from_str(&counterparty_property.owner_wallet)?;
+    let nft_mint_a = Pubkey::from_str(&proposer_property.nft_mint_address)?;
+    let nft_mint_b = Pubkey::from_str(&counterparty_property.nft_mint_address)?;
+    let property_a = get_property_pubkey(&proposer_property.property_id, &program_id)?;
+    let property_b = get_property_pubkey(&counterparty_property.property_id, &program_id)?;
+
+    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+    let mut history_accounts = Vec::new();
+    let mut governance_accounts = Vec::new();
+    for (property_pda, owner, property_id) in [
+        (property_a, owner_a, &proposer_property.property_id),
+        (property_b, owner_b, &counterparty_property.property_id),
+    ] {
+        let (chain_owner, transaction_count) = fetch_owner_and_count(&rpc_client, &property_pda)?;
+        if chain_owner != owner {
+            return Err(anyhow::anyhow!("Property {} is not owned by {} on-chain", property_id, owner));
+        }
+        let (transaction_history_pda, _) = Pubkey::find_program_address(
+            &[b"transaction", property_pda.as_ref(), &(transaction_count + 1).to_le_bytes()],
+            &program_id,
+        );
+        let (history_index_pda, _) =
+            Pubkey::find_program_address(&[b"history_index", property_pda.as_ref()], &program_id);
+        let (property_governance_pda, _) = Pubkey::find_program_address(
+            &[b"property_governance", property_pda.as_ref()],
+            &program_id,
+        );
+        history_accounts.push((transaction_history_pda, history_index_pda));
+        governance_accounts.push(property_governance_pda);
+    }
+
+    let accounts = vec![
+        AccountMeta::new(property_a, false),
+        AccountMeta::new(property_b, false),
+        AccountMeta::new(history_accounts[0].0, false),
+        AccountMeta::new(history_accounts[1].0, false),
+        AccountMeta::new(history_accounts[0].1, false),
+        AccountMeta::new(history_accounts[1].1, false),
+        AccountMeta::new_readonly(governance_accounts[0], false),
+        AccountMeta::new_readonly(governance_accounts[1], false),
+        AccountMeta::new_readonly(nft_mint_a, false),
+        AccountMeta::new_readonly(nft_mint_b, false),
+        AccountMeta::new(get_associated_token_address(&owner_a, &nft_mint_a), false),
+        AccountMeta::new(get_associated_token_address(&owner_b, &nft_mint_a), false),
+        AccountMeta::new(get_associated_token_address(&owner_b, &nft_mint_b), false),
+        AccountMeta::new(get_associated_token_address(&owner_a, &nft_mint_b), false),
+        AccountMeta::new(owner_a, true),
+        AccountMeta::new(owner_b, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let mut data = instruction_discriminator("swap_properties").to_vec();
+    data.extend_from_slice(&cash_difference.to_le_bytes());
+
+    let instruction = Instruction { program_id, accounts, data };
+    let mut tx = SolanaTransaction::new_unsigned(Message::new(&[instruction], Some(&owner_a)));
+    tx.message.recent_blockhash = rpc_client.get_latest_blockhash()?;
+    Ok(tx)
+}
+
+fn decode_transaction(serialized: &str) -> Result<SolanaTransaction, anyhow::Error> {
+    let bytes = general_purpose::STANDARD.decode(serialized)?;
+    Ok(bincode::deserialize::<SolanaTransaction>(&bytes)?)
+}
+
+fn encode_transaction(tx: &SolanaTransaction) -> Result<String, anyhow::Error> {
+    Ok(general_purpose::STANDARD.encode(bincode::serialize(tx)?))
+}
+
+/// Builds the swap transaction for an accepted deal with a fresh blockhash. Signatures collected
+/// for an earlier preparation no longer match, so both legs go back to pending.
+pub async fn prepare_swap(req: HttpRequest, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let mut conn = db::establish_connection()?;
+    let deal = load_swap(&mut conn, &path.into_inner(), &wallet_address)?;
+
+    if deal.status != STATUS_ACCEPTED || deal.signature.is_some() {
+        return Err(ApiError::InvalidInput("Only accepted swaps not yet sent can be prepared".to_string()));
+    }
+    let proposer_property = load_property(&mut conn, &deal.proposer_property_id)?;
+    let counterparty_property = load_property(&mut conn, &deal.counterparty_property_id)?;
+    if proposer_property.owner_wallet != deal.proposer_wallet
+        || counterparty_property.owner_wallet != deal.counterparty_wallet
+    {
+        return Err(ApiError::InvalidInput("A property in this swap has changed owner".to_string()));
+    }
+
+    // swap_properties refuses properties with open offers rather than refunding them
+    let property_ids = [deal.proposer_property_id.clone(), deal.counterparty_property_id.clone()];
+    let open_offers = offers::table
+        .filter(offers::property_id.eq_any(&property_ids))
+        .filter(offers::status.eq_any(["pending", "accepted"]))
+        .count()
+        .get_result::<i64>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch offers"))?;
+    if open_offers > 0 {
+        return Err(ApiError::InvalidInput(
+            "Offers on the swapped properties must settle, be rejected or be withdrawn first".to_string(),
+        ));
+    }
+
+    let cash_difference = deal.cash_difference;
+    let serialized_transaction = web::block(move || {
+        let tx = build_swap_transaction(&proposer_property, &counterparty_property, cash_difference)?;
+        encode_transaction(&tx)
+    })
+    .await?
+    .map_err(|e| ApiError::InvalidInput(format!("Failed to build swap transaction: {}", e)))?;
+
+    let deal = diesel::update(
+        swap_deals::table
+            .find(deal.id)
+            .filter(swap_deals::status.eq(STATUS_ACCEPTED))
+            .filter(swap_deals::signature.is_null()),
+    )
+    .set((
+        swap_deals::serialized_transaction.eq(Some(&serialized_transaction)),
+        swap_deals::proposer_leg_status.eq(LEG_PENDING),
+        swap_deals::proposer_leg_signature.eq(None::<String>),
+        swap_deals::counterparty_leg_status.eq(LEG_PENDING),
+        swap_deals::counterparty_leg_signature.eq(None::<String>),
+        swap_deals::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .get_result::<SwapDeal>(&mut conn)
+    .optional()
+    .map_err(ApiError::db("Failed to update swap"))?
+    .ok_or_else(|| ApiError::InvalidInput("Swap is no longer open for preparation".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(PreparedSwapResponse {
+        success: true,
+        message: "Swap transaction prepared for signing".to_string(),
+        swap: view(deal),
+        serialized_transaction,
+    }))
+}
+
+/// Adds the caller's signature to the prepared swap transaction. Once both parties have signed
+/// it is sent, and both properties change hands in the database together with the deal.
+pub async fn sign_swap(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<SignSwapRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let signer = Pubkey::from_str(&wallet_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid wallet address".to_string()))?;
+    let signer_signature = Signature::from_str(&data.signature)
+        .map_err(|_| ApiError::InvalidInput("Invalid signature".to_string()))?;
+    let mut conn = db::establish_connection()?;
+    let deal = load_swap(&mut conn, &path.into_inner(), &wallet_address)?;
+
+    if deal.status != STATUS_ACCEPTED || deal.signature.is_some() {
+        return Err(ApiError::InvalidInput("Only accepted swaps not yet sent can be signed".to_string()));
+    }
+    let mut tx = deal
+        .serialized_transaction
+        .as_deref()
+        .ok_or_else(|| ApiError::InvalidInput("Swap transaction has not been prepared".to_string()))
+        .and_then(|serialized| {
+            decode_transaction(serialized)
+                .map_err(|e| ApiError::Internal(format!("Stored swap transaction is invalid: {}", e)))
+        })?;
+
+    let position = tx
+        .get_signing_keypair_positions(&[signer])
+        .map_err(|e| ApiError::Internal(format!("Stored swap transaction is invalid: {}", e)))?
+        .into_iter()
+        .next()
+        .flatten()
+        .ok_or_else(|| ApiError::Forbidden("Wallet is not a signer of this swap".to_string()))?;
+    if !signer_signature.verify(signer.as_ref(), &tx.message_data()) {
+        return Err(ApiError::InvalidInput("Signature does not match the prepared transaction".to_string()));
+    }
+    tx.signatures[position] = signer_signature;
+
+    let serialized = encode_transaction(&tx)
+        .map_err(|e| ApiError::Internal(format!("Failed to store signature: {}", e)))?;
+    let fully_signed = tx.signatures.iter().all(|sig| *sig != Signature::default());
+    if fully_signed {
+        risk::screen_relay(&wallet_address, "swap", risk::message_addresses(&tx.message)).await?;
+    }
+
+    // Claiming the send with the transaction signature keeps a cancel or a second send out
+    let tx_signature = tx.signatures[0].to_string();
+    let deal = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let deal = set_leg(conn, &deal, &wallet_address, LEG_SIGNED, Some(&data.signature))?;
+            diesel::update(
+                swap_deals::table
+                    .find(deal.id)
+                    .filter(swap_deals::status.eq(STATUS_ACCEPTED))
+                    .filter(swap_deals::signature.is_null()),
+            )
+            .set((
+                swap_deals::serialized_transaction.eq(Some(&serialized)),
+                swap_deals::signature.eq(fully_signed.then_some(&tx_signature)),
+            ))
+            .get_result::<SwapDeal>(conn)
+        })
+        .optional()
+        .map_err(ApiError::db("Failed to record swap signature"))?
+        .ok_or_else(|| ApiError::InvalidInput("Swap is no longer open for signing".to_string()))?;
+    if !fully_signed {
+        info!("Swap {} signed by {}", deal.id, wallet_address);
+        return Ok(swap_response("Signature recorded", deal));
+    }
+
+    let sent = web::block(move || {
+        let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
+        rpc_client.send_and_confirm_transaction(&tx).map_err(Box::new)
+    })
+    .await?;
+    if let Err(e) = sent {
+        // The parties prepare and sign again; the old signatures can't be reused
+        diesel::update(swap_deals::table.find(deal.id))
+            .set((
+                swap_deals::signature.eq(None::<String>),
+                swap_deals::serialized_transaction.eq(None::<String>),
+                swap_deals::proposer_leg_status.eq(LEG_PENDING),
+                swap_deals::proposer_leg_signature.eq(None::<String>),
+                swap_deals::counterparty_leg_status.eq(LEG_PENDING),
+                swap_deals::counterparty_leg_signature.eq(None::<String>),
+                swap_deals::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(&mut conn)
+            .map_err(ApiError::db("Failed to reset swap"))?;
+        return Err(ApiError::Internal(format!("Swap transaction failed: {}", e)));
+    }
+
+    let deal = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let now = Utc::now().naive_utc();
+            // The swap leaves both properties unlisted with their new owners
+            for (property_id, new_owner) in [
+                (&deal.proposer_property_id, &deal.counterparty_wallet),
+                (&deal.counterparty_property_id, &deal.proposer_wallet),
+            ] {
+                let new_organization = organization::organization_for_owner(conn, new_owner);
+                diesel::update(properties::table.filter(properties::property_id.eq(property_id)))
+                    .set((
+                        properties::owner_wallet.eq(new_owner),
+                        properties::organization_id.eq(new_organization),
+                        properties::is_active.eq(false),
+                        properties::updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+            }
+            diesel::update(swap_deals::table.find(deal.id))
+                .set((
+                    swap_deals::status.eq(STATUS_COMPLETED),
+                    swap_deals::completed_at.eq(now),
+                    swap_deals::updated_at.eq(now),
+                ))
+                .get_result::<SwapDeal>(conn)
+        })
+        .map_err(ApiError::db("Failed to record swap"))?;
+
+    info!("Swap {} completed: {}", deal.id, tx_signature);
+    Ok(swap_response("Swap completed", deal))
+}
@@ -0,0 +1,381 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+use tracing::{info, error, warn};
+
+use crate::db;
+use crate::models::{CallbackDelivery, PropertyCallback};
+use crate::outbox;
+use crate::organization::{self, Permission};
+use crate::schema::{callback_deliveries, property_callbacks};
+use crate::transaction::verify_token;
+
+pub const EVENT_OFFER_ACCEPTED: &str = "offer.accepted";
+pub const EVENT_PROPERTY_SOLD: &str = "property.sold";
+
+/// How often the dispatcher polls for due deliveries
+const POLL_INTERVAL_SECS: u64 = 10;
+/// Deliveries are marked failed after this many attempts
+const MAX_ATTEMPTS: i32 = 12;
+const BATCH_SIZE: i64 = 50;
+
+#[derive(Deserialize)]
+pub struct RegisterCallbackRequest {
+    pub url: String,
+}
+
+#[derive(Serialize)]
+pub struct CallbackResponse {
+    pub success: bool,
+    pub message: String,
+    pub callback: Option<PropertyCallback>,
+    /// Only returned when the callback is registered; used to verify `X-Signature-256`
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeliveriesResponse {
+    pub success: bool,
+    pub message: String,
+    pub deliveries: Vec<CallbackDelivery>,
+}
+
+/// Queues a signed delivery to the title company registered by `seller_wallet`, if any.
+/// Call inside the same `conn.transaction` as the state change, like `outbox::record`.
+pub fn record<T: Serialize>(
+    conn: &mut PgConnection,
+    prop_id: &str,
+    seller_wallet: &str,
+    kind: &str,
+    payload: &T,
+) -> QueryResult<()> {
+    use crate::schema::property_callbacks::dsl::*;
+
+    // A callback only covers the deal of the owner who registered it
+    let callback = property_callbacks
+        .filter(property_id.eq(prop_id))
+        .filter(owner_wallet.eq(seller_wallet))
+        .first::<PropertyCallback>(conn)
+        .optional()?;
+    let Some(callback) = callback else {
+        return Ok(());
+    };
+
+    let payload = serde_json::to_string(payload)
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+    let now = Utc::now().naive_utc();
+
+    let delivery = CallbackDelivery {
+        id: Uuid::new_v4(),
+        callback_id: callback.id,
+        property_id: prop_id.to_string(),
+        event_type: kind.to_string(),
+        payload,
+        status: "pending".to_string(),
+        attempts: 0,
+        last_error: None,
+        next_attempt_at: now,
+        created_at: now,
+        delivered_at: None,
+    };
+
+    diesel::insert_into(callback_deliveries::table)
+        .values(&delivery)
+        .execute(conn)?;
+    Ok(())
+}
+
+fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn deliver(
+    client: &reqwest::blocking::Client,
+    callback: &PropertyCallback,
+    delivery: &CallbackDelivery,
+) -> Result<(), String> {
+    let payload: serde_json::Value = serde_json::from_str(&delivery.payload).map_err(|e| e.to_string())?;
+    let body = serde_json::to_vec(&serde_json::json!({
+        "id": delivery.id,
+        "event_type": delivery.event_type,
+        "property_id": delivery.property_id,
+        "payload": payload,
+        "created_at": delivery.created_at,
+    }))
+    .map_err(|e| e.to_string())?;
+
+    // Receivers recompute HMAC-SHA256("{timestamp}.{body}") with their secret
+    let timestamp = Utc::now().timestamp();
+    let response = client
+        .post(&callback.url)
+        .header("Content-Type", "application/json")
+        .header("X-Delivery-Id", delivery.id.to_string())
+        .header("X-Signature-Timestamp", timestamp.to_string())
+        .header("X-Signature-256", format!("sha256={}", sign(&callback.secret, timestamp, &body)))
+        .body(body)
+        .send()
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Callback responded with status {}", response.status()))
+    }
+}
+
+/// Delivers every due payload once, returning how many were delivered
+pub fn dispatch_due(conn: &mut PgConnection, client: &reqwest::blocking::Client) -> QueryResult<usize> {
+    use crate::schema::callback_deliveries::dsl::*;
+
+    let now = Utc::now().naive_utc();
+    let due = callback_deliveries
+        .inner_join(property_callbacks::table)
+        .filter(status.eq("pending"))
+        .filter(next_attempt_at.le(now))
+        .order_by(created_at.asc())
+        .limit(BATCH_SIZE)
+        .load::<(CallbackDelivery, PropertyCallback)>(conn)?;
+
+    let mut delivered = 0;
+    for (delivery, callback) in due {
+        let now = Utc::now().naive_utc();
+        let tries = delivery.attempts + 1;
+        match deliver(client, &callback, &delivery) {
+            Ok(()) => {
+                diesel::update(callback_deliveries.find(delivery.id))
+                    .set((
+                        status.eq("delivered"),
+                        attempts.eq(tries),
+                        last_error.eq(None::<String>),
+                        delivered_at.eq(Some(now)),
+                    ))
+                    .execute(conn)?;
+                delivered += 1;
+            }
+            Err(e) => {
+                let next_status = if tries >= MAX_ATTEMPTS { "failed" } else { "pending" };
+                diesel::update(callback_deliveries.find(delivery.id))
+                    .set((
+                        status.eq(next_status),
+                        attempts.eq(tries),
+                        last_error.eq(Some(e.clone())),
+                        next_attempt_at.eq(now + outbox::next_backoff(tries)),
+                    ))
+                    .execute(conn)?;
+                warn!("Delivery {} of callback payload {} failed: {}", tries, delivery.id, e);
+            }
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Spawns the poller delivering title-company payloads
+pub fn spawn_dispatcher() {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(POLL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let result = tokio::task::spawn_blocking(move || {
+                let client = reqwest::blocking::Client::builder()
+                    .timeout(std::time::Duration::from_secs(10))
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                let mut conn = db::establish_connection().map_err(|e| e.to_string())?;
+                dispatch_due(&mut conn, &client).map_err(|e| e.to_string())
+            })
+            .await;
+
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(count)) => info!("Delivered {} title callback payloads", count),
+                Ok(Err(e)) => error!("Title callback dispatch failed: {}", e),
+                Err(e) => error!("Title callback dispatcher panicked: {}", e),
+            }
+        }
+    });
+}
+
+/// Returns the on-chain owner the callback is registered under, which is the multisig for org properties
+fn require_owner(conn: &mut PgConnection, prop_id: &str, wallet_address: &str) -> Result<String, HttpResponse> {
+    organization::require_property_permission(
+        conn,
+        prop_id,
+        wallet_address,
+        Permission::List,
+        "Only the property owner can manage its title callback",
+    )
+}
+
+/// Registers or replaces the title-company endpoint for a property and issues a new signing secret
+pub async fn put_title_callback(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<RegisterCallbackRequest>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+    let property_id_param = path.into_inner();
+
+    match reqwest::Url::parse(&data.url) {
+        Ok(parsed) if parsed.scheme() == "https" => {}
+        _ => return HttpResponse::BadRequest().body("Callback URL must be a valid https URL"),
+    }
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let owner = match require_owner(&mut conn, &property_id_param, &wallet_address) {
+        Ok(owner) => owner,
+        Err(resp) => return resp,
+    };
+
+    use crate::schema::property_callbacks::dsl::*;
+
+    let now = Utc::now().naive_utc();
+    let new_secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let callback = PropertyCallback {
+        id: Uuid::new_v4(),
+        property_id: property_id_param.clone(),
+        owner_wallet: owner,
+        url: data.url.clone(),
+        secret: new_secret.clone(),
+        created_at: now,
+        updated_at: now,
+    };
+
+    match diesel::insert_into(property_callbacks)
+        .values(&callback)
+        .on_conflict((property_id, owner_wallet))
+        .do_update()
+        .set((
+            url.eq(&data.url),
+            secret.eq(&new_secret),
+            updated_at.eq(now),
+        ))
+        .get_result::<PropertyCallback>(&mut conn)
+    {
+        Ok(saved) => {
+            info!("Registered title callback for {}", property_id_param);
+            HttpResponse::Ok().json(CallbackResponse {
+                success: true,
+                message: "Title callback registered".to_string(),
+                callback: Some(saved),
+                secret: Some(new_secret),
+            })
+        }
+        Err(e) => {
+            error!("Failed to register title callback: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to register title callback: {}", e))
+        }
+    }
+}
+
+/// Removes the owner's callback for a property along with its delivery history
+pub async fn delete_title_callback(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+    let property_id_param = path.into_inner();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    let owner = match require_owner(&mut conn, &property_id_param, &wallet_address) {
+        Ok(owner) => owner,
+        Err(resp) => return resp,
+    };
+
+    use crate::schema::property_callbacks::dsl::*;
+
+    match diesel::delete(
+        property_callbacks
+            .filter(property_id.eq(&property_id_param))
+            .filter(owner_wallet.eq(&owner)),
+    )
+    .execute(&mut conn)
+    {
+        Ok(0) => HttpResponse::NotFound().body("No title callback registered"),
+        Ok(_) => HttpResponse::Ok().json(CallbackResponse {
+            success: true,
+            message: "Title callback removed".to_string(),
+            callback: None,
+            secret: None,
+        }),
+        Err(e) => {
+            error!("Failed to remove title callback: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to remove title callback: {}", e))
+        }
+    }
+}
+
+/// Delivery status of each payload sent to the caller's title company, newest first.
+/// Sellers keep access after the sale since deliveries follow the callback they registered.
+pub async fn get_title_callback_deliveries(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+    let property_id_param = path.into_inner();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    // Members of an owning org see the deliveries registered under its multisig
+    let mut registrants = vec![wallet_address.clone()];
+    if let Ok(owner) = organization::require_property_permission(
+        &mut conn,
+        &property_id_param,
+        &wallet_address,
+        Permission::View,
+        "",
+    ) {
+        registrants.push(owner);
+    }
+
+    match callback_deliveries::table
+        .inner_join(property_callbacks::table)
+        .filter(callback_deliveries::property_id.eq(&property_id_param))
+        .filter(property_callbacks::owner_wallet.eq_any(&registrants))
+        .order_by(callback_deliveries::created_at.desc())
+        .select(callback_deliveries::all_columns)
+        .load::<CallbackDelivery>(&mut conn)
+    {
+        Ok(deliveries) => HttpResponse::Ok().json(DeliveriesResponse {
+            success: true,
+            message: format!("Found {} deliveries", deliveries.len()),
+            deliveries,
+        }),
+        Err(e) => {
+            error!("Failed to fetch callback deliveries: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch callback deliveries: {}", e))
+        }
+    }
+}
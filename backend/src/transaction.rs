@@ -1,7 +1,7 @@
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
 use base64::{engine::general_purpose, Engine};
-use bincode;
 use chrono::Utc;
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use solana_client::rpc_client::RpcClient;
@@ -19,10 +19,23 @@ use uuid::Uuid;
 use anyhow::Result;
 use tracing::{info, error};
 
+use crate::allocation;
 use crate::auth;
 use crate::db;
+use crate::error::ApiError;
+use crate::invite;
 use crate::models::Property;
-use crate::schema::properties;
+use crate::outbox;
+use crate::retry_queue::{self, DbEffect, EffectOutcome};
+use crate::risk;
+use crate::slug;
+use crate::title_callback;
+use crate::translation;
+use crate::market_status;
+use crate::organization;
+
+/// Deployed address of the real estate marketplace program
+pub const PROGRAM_ID: &str = "E7v7RResymJU5XvvPA9uwxGSEEsdSE6XvaP7BTV2GGoQ";
 
 #[derive(Debug, Deserialize)]
 pub struct SubmitTransactionRequest {
@@ -36,21 +49,21 @@ pub struct SubmitInstructionsRequest {
     pub metadata: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SerializedInstruction {
     pub program_id: String,
     pub accounts: Vec<SerializedAccountMeta>,
     pub data: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SerializedAccountMeta {
     pub pubkey: String,
     pub is_signer: bool,
     pub is_writable: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ListPropertyRequest {
     pub property_id: String,
     pub price: u64,
@@ -66,6 +79,8 @@ pub struct ListPropertyRequest {
 #[derive(Debug, Serialize)]
 pub struct TransactionResponse {
     pub signature: String,
+    /// The transaction confirmed but its database write was queued for replay
+    pub queued: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -76,29 +91,36 @@ pub struct BlockhashResponse {
 #[derive(Debug, thiserror::Error)]
 pub enum TransactionError {
     #[error("RPC error: {0}")]
-    RpcError(#[from] solana_client::client_error::ClientError),
+    Rpc(Box<solana_client::client_error::ClientError>),
     #[error("Serialization error: {0}")]
-    SerializationError(#[from] bincode::Error),
+    Serialization(#[from] bincode::Error),
     #[error("Database error: {0}")]
-    DatabaseError(#[from] diesel::result::Error),
+    Database(#[from] diesel::result::Error),
 }
 
-pub async fn verify_token(req: &HttpRequest) -> Result<String, HttpResponse> {
+// Boxed so the RPC client's large error doesn't bloat every Result carrying a TransactionError
+impl From<solana_client::client_error::ClientError> for TransactionError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        TransactionError::Rpc(Box::new(e))
+    }
+}
+
+/// Wallet address from the request's bearer JWT
+pub async fn authenticated_wallet(req: &HttpRequest) -> Result<String, ApiError> {
     // Extract the authorization header
-    let auth_header = match req.headers().get("Authorization") {
-        Some(header) => header,
-        None => return Err(HttpResponse::Unauthorized().body("No authorization header")),
-    };
+    let auth_header = req
+        .headers()
+        .get("Authorization")
+        .ok_or_else(|| ApiError::Unauthorized("No authorization header".to_string()))?;
 
     // Extract the token from the header
-    let auth_str = match auth_header.to_str() {
-        Ok(s) => s,
-        Err(_) => return Err(HttpResponse::Unauthorized().body("Invalid authorization header")),
-    };
+    let auth_str = auth_header
+        .to_str()
+        .map_err(|_| ApiError::Unauthorized("Invalid authorization header".to_string()))?;
 
     // Check if the header is a bearer token
     if !auth_str.starts_with("Bearer ") {
-        return Err(HttpResponse::Unauthorized().body("Invalid token format"));
+        return Err(ApiError::Unauthorized("Invalid token format".to_string()));
     }
 
     // Extract the JWT
@@ -107,14 +129,12 @@ pub async fn verify_token(req: &HttpRequest) -> Result<String, HttpResponse> {
     // Verify and extract wallet address from JWT
     let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
     let validation = jsonwebtoken::Validation::default();
-    let token_data = match jsonwebtoken::decode::<auth::Claims>(
+    let token_data = jsonwebtoken::decode::<auth::Claims>(
         token,
         &jsonwebtoken::DecodingKey::from_secret(jwt_secret.as_bytes()),
         &validation,
-    ) {
-        Ok(data) => data,
-        Err(e) => return Err(HttpResponse::Unauthorized().body(format!("Invalid token: {}", e))),
-    };
+    )
+    .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?;
 
     // Add some debug logging to see what wallet address is being returned
     info!("Token verified for wallet: {}", token_data.claims.sub);
@@ -122,193 +142,187 @@ pub async fn verify_token(req: &HttpRequest) -> Result<String, HttpResponse> {
     Ok(token_data.claims.sub)
 }
 
+/// `authenticated_wallet` for handlers that still build their own responses
+pub async fn verify_token(req: &HttpRequest) -> Result<String, HttpResponse> {
+    authenticated_wallet(req).await.map_err(|e| e.error_response())
+}
+
 // New endpoint to get a recent blockhash
-pub async fn get_recent_blockhash(req: HttpRequest) -> HttpResponse {
+pub async fn get_recent_blockhash(req: HttpRequest) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let _wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    authenticated_wallet(&req).await?;
 
     // Get recent blockhash from Solana
-    let blockhash = match web::block(move || {
+    let blockhash = web::block(move || {
         let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
         let blockhash = rpc_client.get_latest_blockhash()?;
-        Ok::<Hash, solana_client::client_error::ClientError>(blockhash)
-    }).await {
-        Ok(Ok(hash)) => hash,
-        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Failed to get blockhash: {}", e)),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
-    };
+        Ok::<Hash, TransactionError>(blockhash)
+    }).await??;
 
-    HttpResponse::Ok().json(BlockhashResponse {
+    Ok(HttpResponse::Ok().json(BlockhashResponse {
         blockhash: blockhash.to_string(),
-    })
+    }))
 }
 
 pub async fn submit_transaction(
     req: HttpRequest,
     data: web::Json<SubmitTransactionRequest>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
-    let _owner = match Pubkey::from_str(&wallet_address) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid wallet address"),
-    };
+    Pubkey::from_str(&wallet_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid wallet address".to_string()))?;
 
     // Decode the base64 serialized transaction
-    let tx_bytes = match general_purpose::STANDARD.decode(&data.serialized_transaction) {
-        Ok(bytes) => bytes,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid serialized transaction"),
-    };
+    let tx_bytes = general_purpose::STANDARD
+        .decode(&data.serialized_transaction)
+        .map_err(|_| ApiError::InvalidInput("Invalid serialized transaction".to_string()))?;
 
     // Deserialize the transaction
-    let tx = match bincode::deserialize::<SolanaTransaction>(&tx_bytes) {
-        Ok(transaction) => transaction,
-        Err(e) => return HttpResponse::BadRequest().body(format!("Failed to deserialize transaction: {}", e)),
-    };
+    let tx = bincode::deserialize::<SolanaTransaction>(&tx_bytes)
+        .map_err(|e| ApiError::InvalidInput(format!("Failed to deserialize transaction: {}", e)))?;
+
+    risk::screen_relay(&wallet_address, "submit_transaction", risk::message_addresses(&tx.message)).await?;
 
     // Offload blocking RPC call to a separate thread
-    let tx_signature = match web::block(move || {
+    let tx_signature = web::block(move || {
         let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
         let signature = rpc_client.send_and_confirm_transaction(&tx)?;
         Ok::<Signature, TransactionError>(signature)
-    }).await {
-        Ok(Ok(sig)) => sig,
-        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Transaction failed: {}", e)),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
-    };
+    }).await??;
 
     // Parse the property metadata
-    let metadata: ListPropertyRequest = match serde_json::from_str(&data.metadata) {
-        Ok(meta) => meta,
-        Err(e) => return HttpResponse::BadRequest().body(format!("Failed to parse metadata: {}", e)),
-    };
+    let metadata: ListPropertyRequest = serde_json::from_str(&data.metadata)
+        .map_err(|e| ApiError::InvalidInput(format!("Failed to parse metadata: {}", e)))?;
+
+    let signature = tx_signature.to_string();
+    let queued = store_listing(&wallet_address, metadata, &signature)?;
+    Ok(HttpResponse::Ok().json(TransactionResponse { signature, queued }))
+}
 
-    // Store property in database
+/// Records a confirmed listing, returning whether the write was queued rather than applied. The
+/// transaction is already confirmed, so a failed insert is queued for replay and a missing
+/// database connection spools the listing to disk instead of reporting a failure.
+fn store_listing(wallet_address: &str, metadata: ListPropertyRequest, signature: &str) -> Result<bool, ApiError> {
     let mut conn = match db::establish_connection() {
         Ok(conn) => conn,
         Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
+            let property_id = metadata.property_id.clone();
+            let effect = DbEffect::RecordListing { wallet_address: wallet_address.to_string(), metadata };
+            retry_queue::spool(signature, effect, &e.to_string()).map_err(|spool_err| {
+                ApiError::Internal(format!(
+                    "Transaction {} confirmed but the listing could not be recorded: {}",
+                    signature, spool_err
+                ))
+            })?;
+            info!("Property {} queued for the database after transaction {}", property_id, signature);
+            return Ok(true);
         }
     };
-    
+
+    let new_property = listing_row(&mut conn, wallet_address, &metadata, true);
+    let outcome = retry_queue::apply_or_enqueue(&mut conn, signature, DbEffect::InsertProperty(new_property))
+        .map_err(ApiError::db("Failed to record listing"))?;
+    match outcome {
+        EffectOutcome::Applied => info!("Property {} successfully added to database", metadata.property_id),
+        EffectOutcome::Queued => {
+            info!("Property {} queued for the database after transaction {}", metadata.property_id, signature)
+        }
+    }
+    record_listed(&mut conn, wallet_address, &metadata);
+    Ok(outcome == EffectOutcome::Queued)
+}
+
+/// Database row for the property described by a listing transaction's metadata
+pub(crate) fn listing_row(
+    conn: &mut PgConnection,
+    wallet_address: &str,
+    metadata: &ListPropertyRequest,
+    is_active: bool,
+) -> Property {
     let now = Utc::now().naive_utc();
-    let new_property = Property {
+    Property {
         id: Uuid::new_v4(),
         property_id: metadata.property_id.clone(),
-        owner_wallet: wallet_address,
+        owner_wallet: wallet_address.to_string(),
         price: metadata.price as i64,
-        metadata_uri: metadata.metadata_uri,
-        location: metadata.location,
+        metadata_uri: metadata.metadata_uri.clone(),
+        location: metadata.location.clone(),
         square_feet: metadata.square_feet as i64,
         bedrooms: metadata.bedrooms as i16,
         bathrooms: metadata.bathrooms as i16,
-        is_active: true,
+        is_active,
         created_at: now,
         updated_at: now,
-        nft_mint_address: metadata.nft_mint_address,  // New field
-        nft_token_account: metadata.nft_token_account, // New field
-    };
-
-    match diesel::insert_into(properties::table)
-        .values(&new_property)
-        .execute(&mut conn)
-    {
-        Ok(_) => {
-            info!("Property {} successfully added to database", metadata.property_id);
-            HttpResponse::Ok().json(TransactionResponse {
-                signature: tx_signature.to_string(),
-            })
-        }
-        Err(e) => {
-            error!("Failed to insert property into database: {}", e);
-            HttpResponse::InternalServerError().body(format!("Database error: {}", e))
-        }
+        nft_mint_address: metadata.nft_mint_address.clone(),
+        nft_token_account: metadata.nft_token_account.clone(),
+        organization_id: organization::organization_for_owner(conn, wallet_address),
+        slug: slug::unique_slug(conn, &metadata.location, &metadata.property_id),
     }
 }
 
+/// Bookkeeping for a listing that has just gone live on-chain
+pub(crate) fn record_listed(conn: &mut PgConnection, wallet_address: &str, metadata: &ListPropertyRequest) {
+    allocation::consume_property_id(conn, &metadata.property_id);
+    market_status::record_event(
+        conn,
+        &metadata.property_id,
+        market_status::EVENT_LISTED,
+        Some(metadata.price as i64),
+        None,
+    );
+    invite::record_first_listing(conn, wallet_address, &metadata.property_id);
+    translation::schedule_translation(metadata.property_id.clone(), metadata.location.clone());
+}
+
 pub async fn submit_transaction_no_update(
     req: HttpRequest,
     data: web::Json<SubmitTransactionRequest>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
-    let _owner = match Pubkey::from_str(&wallet_address) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid wallet address"),
-    };
+    Pubkey::from_str(&wallet_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid wallet address".to_string()))?;
 
     // Decode the base64 serialized transaction
-    let tx_bytes = match general_purpose::STANDARD.decode(&data.serialized_transaction) {
-        Ok(bytes) => bytes,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid serialized transaction"),
-    };
+    let tx_bytes = general_purpose::STANDARD
+        .decode(&data.serialized_transaction)
+        .map_err(|_| ApiError::InvalidInput("Invalid serialized transaction".to_string()))?;
 
     // Deserialize the transaction
-    let tx = match bincode::deserialize::<SolanaTransaction>(&tx_bytes) {
-        Ok(transaction) => transaction,
-        Err(e) => return HttpResponse::BadRequest().body(format!("Failed to deserialize transaction: {}", e)),
-    };
+    let tx = bincode::deserialize::<SolanaTransaction>(&tx_bytes)
+        .map_err(|e| ApiError::InvalidInput(format!("Failed to deserialize transaction: {}", e)))?;
+
+    risk::screen_relay(&wallet_address, "submit_transaction_no_update", risk::message_addresses(&tx.message)).await?;
 
     // Offload blocking RPC call to a separate thread
-    let tx_signature = match web::block(move || {
+    let tx_signature = web::block(move || {
         let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
         let signature = rpc_client.send_and_confirm_transaction(&tx)?;
         Ok::<Signature, TransactionError>(signature)
-    }).await {
-        Ok(Ok(sig)) => sig,
-        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Transaction failed: {}", e)),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
-    };
+    }).await??;
 
     // Return transaction signature without updating the database
     info!("Transaction submitted successfully without database update");
-    HttpResponse::Ok().json(TransactionResponse {
+    Ok(HttpResponse::Ok().json(TransactionResponse {
         signature: tx_signature.to_string(),
-    })
+        queued: false,
+    }))
 }
 
-// New endpoint to submit transaction instructions
-pub async fn submit_instructions(
-    req: HttpRequest,
-    data: web::Json<SubmitInstructionsRequest>,
-) -> HttpResponse {
-    // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
-
-    let owner_pubkey = match Pubkey::from_str(&wallet_address) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid wallet address"),
-    };
-
-    // Parse instructions
+/// Converts client-supplied instructions, reporting the first invalid field
+pub fn parse_instructions(serialized: &[SerializedInstruction]) -> Result<Vec<Instruction>, String> {
     let mut instructions = Vec::new();
-    for serialized_instruction in &data.instructions {
-        let program_id = match Pubkey::from_str(&serialized_instruction.program_id) {
-            Ok(pubkey) => pubkey,
-            Err(_) => return HttpResponse::BadRequest().body(format!("Invalid program ID: {}", serialized_instruction.program_id)),
-        };
+    for serialized_instruction in serialized {
+        let program_id = Pubkey::from_str(&serialized_instruction.program_id)
+            .map_err(|_| format!("Invalid program ID: {}", serialized_instruction.program_id))?;
 
         let mut accounts = Vec::new();
         for account_meta in &serialized_instruction.accounts {
-            let pubkey = match Pubkey::from_str(&account_meta.pubkey) {
-                Ok(pubkey) => pubkey,
-                Err(_) => return HttpResponse::BadRequest().body(format!("Invalid account pubkey: {}", account_meta.pubkey)),
-            };
+            let pubkey = Pubkey::from_str(&account_meta.pubkey)
+                .map_err(|_| format!("Invalid account pubkey: {}", account_meta.pubkey))?;
 
             accounts.push(solana_sdk::instruction::AccountMeta {
                 pubkey,
@@ -317,10 +331,9 @@ pub async fn submit_instructions(
             });
         }
 
-        let instruction_data = match general_purpose::STANDARD.decode(&serialized_instruction.data) {
-            Ok(data) => data,
-            Err(_) => return HttpResponse::BadRequest().body(format!("Invalid instruction data")),
-        };
+        let instruction_data = general_purpose::STANDARD
+            .decode(&serialized_instruction.data)
+            .map_err(|_| "Invalid instruction data".to_string())?;
 
         instructions.push(Instruction {
             program_id,
@@ -328,6 +341,25 @@ pub async fn submit_instructions(
             data: instruction_data,
         });
     }
+    Ok(instructions)
+}
+
+// New endpoint to submit transaction instructions
+pub async fn submit_instructions(
+    req: HttpRequest,
+    data: web::Json<SubmitInstructionsRequest>,
+) -> Result<HttpResponse, ApiError> {
+    // Verify authentication token
+    let wallet_address = authenticated_wallet(&req).await?;
+
+    let owner_pubkey = Pubkey::from_str(&wallet_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid wallet address".to_string()))?;
+
+    // Parse instructions
+    let instructions = parse_instructions(&data.instructions).map_err(ApiError::InvalidInput)?;
+
+    let involved = risk::message_addresses(&Message::new(&instructions, Some(&owner_pubkey)));
+    risk::screen_relay(&wallet_address, "submit_instructions", involved).await?;
 
     // Create keypair for the primary signer
     // In a real implementation, you might load this from secure storage
@@ -335,7 +367,7 @@ pub async fn submit_instructions(
     let primary_signer = Keypair::new();
 
     // Build and send the transaction
-    let tx_signature = match web::block(move || {
+    let tx_signature = web::block(move || {
         let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
         
         // Get a fresh blockhash
@@ -355,60 +387,15 @@ pub async fn submit_instructions(
         // Send and confirm the transaction
         let signature = rpc_client.send_and_confirm_transaction(&transaction)?;
         Ok::<Signature, TransactionError>(signature)
-    }).await {
-        Ok(Ok(sig)) => sig,
-        Ok(Err(e)) => return HttpResponse::InternalServerError().body(format!("Transaction failed: {}", e)),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Thread pool error: {}", e)),
-    };
+    }).await??;
 
     // Parse the property metadata
-    let metadata: ListPropertyRequest = match serde_json::from_str(&data.metadata) {
-        Ok(meta) => meta,
-        Err(e) => return HttpResponse::BadRequest().body(format!("Failed to parse metadata: {}", e)),
-    };
+    let metadata: ListPropertyRequest = serde_json::from_str(&data.metadata)
+        .map_err(|e| ApiError::InvalidInput(format!("Failed to parse metadata: {}", e)))?;
 
-    // Store property in database
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
-    
-    let now = Utc::now().naive_utc();
-    let new_property = Property {
-        id: Uuid::new_v4(),
-        property_id: metadata.property_id.clone(),
-        owner_wallet: wallet_address,
-        price: metadata.price as i64,
-        metadata_uri: metadata.metadata_uri,
-        location: metadata.location,
-        square_feet: metadata.square_feet as i64,
-        bedrooms: metadata.bedrooms as i16,
-        bathrooms: metadata.bathrooms as i16,
-        is_active: true,
-        created_at: now,
-        updated_at: now,
-        nft_mint_address: metadata.nft_mint_address,  // New field
-        nft_token_account: metadata.nft_token_account, // New field
-    };
-
-    match diesel::insert_into(properties::table)
-        .values(&new_property)
-        .execute(&mut conn)
-    {
-        Ok(_) => {
-            info!("Property {} successfully added to database", metadata.property_id);
-            HttpResponse::Ok().json(TransactionResponse {
-                signature: tx_signature.to_string(),
-            })
-        }
-        Err(e) => {
-            error!("Failed to insert property into database: {}", e);
-            HttpResponse::InternalServerError().body(format!("Database error: {}", e))
-        }
-    }
+    let signature = tx_signature.to_string();
+    let queued = store_listing(&wallet_address, metadata, &signature)?;
+    Ok(HttpResponse::Ok().json(TransactionResponse { signature, queued }))
 }
 
 // Define the Transaction struct for database interaction
@@ -421,6 +408,7 @@ pub struct DbTransaction {
     pub buyer_wallet: String,
     pub price: i64,
     pub timestamp: chrono::NaiveDateTime,
+    pub signature: Option<String>,
 }
 
 // New request struct for recording a property sale
@@ -430,6 +418,7 @@ pub struct RecordPropertySaleRequest {
     pub seller_wallet: String,
     pub buyer_wallet: String,
     pub price: i64,
+    pub transaction_signature: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -443,25 +432,16 @@ pub struct PropertySaleResponse {
 pub async fn record_property_sale(
     req: HttpRequest,
     data: web::Json<RecordPropertySaleRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     // Check that the requester is either the buyer or seller
     if wallet_address != data.buyer_wallet && wallet_address != data.seller_wallet {
-        return HttpResponse::Forbidden().body("Only the buyer or seller can record this transaction");
+        return Err(ApiError::Forbidden("Only the buyer or seller can record this transaction".to_string()));
     }
 
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     // Create new transaction record
     let transaction_id = Uuid::new_v4();
@@ -474,81 +454,91 @@ pub async fn record_property_sale(
         buyer_wallet: data.buyer_wallet.clone(),
         price: data.price,
         timestamp: now,
-    };
+        signature: data.transaction_signature.clone(),
+    };
+
+    // Insert transaction into database together with its notification intent
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::insert_into(crate::schema::transactions::table)
+            .values(&new_transaction)
+            .execute(conn)?;
+        title_callback::record(
+            conn,
+            &new_transaction.property_id,
+            &new_transaction.seller_wallet,
+            title_callback::EVENT_PROPERTY_SOLD,
+            &new_transaction,
+        )?;
+        outbox::record(conn, "property.sold", &new_transaction.property_id, &new_transaction)
+    })
+    .map_err(ApiError::db("Failed to record property sale"))?;
 
-    // Insert transaction into database
-    match diesel::insert_into(crate::schema::transactions::table)
-        .values(&new_transaction)
-        .execute(&mut conn)
+    info!(
+        "Property sale recorded: {} sold to {}",
+        data.property_id, data.buyer_wallet
+    );
+    market_status::record_event(
+        &mut conn,
+        &data.property_id,
+        market_status::EVENT_SOLD,
+        Some(data.price),
+        None,
+    );
+    
+    // Update property ownership in the properties table
     {
-        Ok(_) => {
-            info!(
-                "Property sale recorded: {} sold to {}",
-                data.property_id, data.buyer_wallet
-            );
-            
-            // Update property ownership in the properties table
-            {
-                use crate::schema::properties::dsl::{properties, property_id as prop_id, owner_wallet, is_active, updated_at as prop_updated_at};
-                
-                match diesel::update(properties.filter(prop_id.eq(&data.property_id)))
-                    .set((
-                        owner_wallet.eq(&data.buyer_wallet),
-                        is_active.eq(true), // Set is_active to true for active properties
-                        prop_updated_at.eq(now),
-                    ))
-                    .execute(&mut conn)
-                {
-                    Ok(_) => {
-                        info!("Property ownership transferred to {}", data.buyer_wallet);
-                    },
-                    Err(e) => {
-                        error!("Failed to update property ownership: {}", e);
-                        // Continue anyway since the transaction was recorded
-                    }
-                }
+        use crate::schema::properties::dsl::{properties, property_id as prop_id, owner_wallet, organization_id, is_active, updated_at as prop_updated_at};
+        
+        let buyer_organization = organization::organization_for_owner(&mut conn, &data.buyer_wallet);
+        match diesel::update(properties.filter(prop_id.eq(&data.property_id)))
+            .set((
+                owner_wallet.eq(&data.buyer_wallet),
+                organization_id.eq(buyer_organization),
+                is_active.eq(true), // Set is_active to true for active properties
+                prop_updated_at.eq(now),
+            ))
+            .execute(&mut conn)
+        {
+            Ok(_) => {
+                info!("Property ownership transferred to {}", data.buyer_wallet);
+            },
+            Err(e) => {
+                error!("Failed to update property ownership: {}", e);
+                // Continue anyway since the transaction was recorded
             }
-            
-            // Update the status of the accepted offer to 'completed'
-            {
-                use crate::schema::offers::dsl::{offers, property_id as offer_property_id, buyer_wallet as offer_buyer_wallet, status, updated_at as offer_updated_at};
-                
-                match diesel::update(offers.filter(
-                    offer_property_id.eq(&data.property_id)
-                        .and(offer_buyer_wallet.eq(&data.buyer_wallet))
-                        .and(status.eq("accepted"))
-                ))
-                    .set((
-                        status.eq("completed"),
-                        offer_updated_at.eq(now),
-                    ))
-                    .execute(&mut conn)
-                {
-                    Ok(_) => {
-                        info!("Offer status updated to completed");
-                    },
-                    Err(e) => {
-                        error!("Failed to update offer status: {}", e);
-                        // Continue anyway since the transaction was recorded
-                    }
-                }
+        }
+    }
+    
+    // Update the status of the accepted offer to 'completed'
+    {
+        use crate::schema::offers::dsl::{offers, property_id as offer_property_id, buyer_wallet as offer_buyer_wallet, status, updated_at as offer_updated_at};
+        
+        match diesel::update(offers.filter(
+            offer_property_id.eq(&data.property_id)
+                .and(offer_buyer_wallet.eq(&data.buyer_wallet))
+                .and(status.eq("accepted"))
+        ))
+            .set((
+                status.eq("completed"),
+                offer_updated_at.eq(now),
+            ))
+            .execute(&mut conn)
+        {
+            Ok(_) => {
+                info!("Offer status updated to completed");
+            },
+            Err(e) => {
+                error!("Failed to update offer status: {}", e);
+                // Continue anyway since the transaction was recorded
             }
-            
-            HttpResponse::Ok().json(PropertySaleResponse {
-                success: true,
-                message: "Property sale transaction recorded successfully".to_string(),
-                transaction_id: Some(transaction_id),
-            })
-        },
-        Err(e) => {
-            error!("Failed to record property sale: {}", e);
-            HttpResponse::InternalServerError().json(PropertySaleResponse {
-                success: false,
-                message: format!("Failed to record property sale: {}", e),
-                transaction_id: None,
-            })
         }
     }
+    
+    Ok(HttpResponse::Ok().json(PropertySaleResponse {
+        success: true,
+        message: "Property sale transaction recorded successfully".to_string(),
+        transaction_id: Some(transaction_id),
+    }))
 }
 
 #[derive(Debug, Serialize)]
@@ -559,44 +549,24 @@ pub struct TransactionsResponse {
 }
 
 /// Retrieves the transaction history
-pub async fn get_transactions(req: HttpRequest) -> impl Responder {
+pub async fn get_transactions(req: HttpRequest) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let _wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    authenticated_wallet(&req).await?;
 
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     // Fetch all transactions ordered by timestamp (most recent first)
-    let transactions_result = crate::schema::transactions::table
+    let transactions = crate::schema::transactions::table
         .order_by(crate::schema::transactions::timestamp.desc())
-        .load::<DbTransaction>(&mut conn);
+        .load::<DbTransaction>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch transactions"))?;
 
-    match transactions_result {
-        Ok(transactions) => {
-            info!("Successfully retrieved {} transactions", transactions.len());
-            HttpResponse::Ok().json(TransactionsResponse {
-                success: true,
-                message: format!("Successfully retrieved {} transactions", transactions.len()),
-                transactions,
-            })
-        },
-        Err(e) => {
-            error!("Failed to fetch transactions: {}", e);
-            HttpResponse::InternalServerError().json(TransactionsResponse {
-                success: false,
-                message: format!("Failed to fetch transactions: {}", e),
-                transactions: vec![],
-            })
-        }
-    }
+    info!("Successfully retrieved {} transactions", transactions.len());
+    Ok(HttpResponse::Ok().json(TransactionsResponse {
+        success: true,
+        message: format!("Successfully retrieved {} transactions", transactions.len()),
+        transactions,
+    }))
 }
 
 // Add after the get_transactions function
@@ -635,16 +605,13 @@ pub struct CreateEscrowTokenAccountResponse {
 pub async fn complete_nft_transfer(
     req: HttpRequest,
     data: web::Json<CompleteNFTTransferRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     // Verify that the requester is the buyer
     if wallet_address != data.buyer_wallet {
-        return HttpResponse::Forbidden().body("Only the buyer can request NFT transfer completion");
+        return Err(ApiError::Forbidden("Only the buyer can request NFT transfer completion".to_string()));
     }
 
     info!(
@@ -666,11 +633,11 @@ pub async fn complete_nft_transfer(
 
     // Here you would use the admin keypair to sign and submit the NFT transfer transaction
     
-    HttpResponse::Ok().json(CompleteNFTTransferResponse {
+    Ok(HttpResponse::Ok().json(CompleteNFTTransferResponse {
         success: true,
         message: "NFT transfer request processed successfully. In production, this would transfer the NFT.".to_string(),
         nft_transaction_signature: Some("simulated_nft_tx_signature".to_string()),
-    })
+    }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -691,12 +658,9 @@ pub struct UpdatePropertyOwnershipResponse {
 pub async fn update_property_ownership(
     req: HttpRequest,
     data: web::Json<UpdatePropertyOwnershipRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    let wallet_address = authenticated_wallet(&req).await?;
 
     // Log detailed information for debugging
     info!("Updating property ownership: property_id={}, new_owner={}, offer_id={}, transaction_signature={}, caller={}", 
@@ -714,154 +678,118 @@ pub async fn update_property_ownership(
     */
 
     // Parse offer_id string to UUID
-    let offer_uuid = match Uuid::parse_str(&data.offer_id) {
-        Ok(uuid) => {
-            info!("Successfully parsed offer UUID: {}", uuid);
-            uuid
-        },
-        Err(e) => {
-            error!("Invalid offer UUID format: {} for value {}", e, data.offer_id);
-            return HttpResponse::BadRequest().json(UpdatePropertyOwnershipResponse {
-                success: false,
-                message: format!("Invalid offer ID format: {}", e),
-            });
-        }
-    };
+    let offer_uuid = Uuid::parse_str(&data.offer_id)
+        .map_err(|e| ApiError::InvalidInput(format!("Invalid offer ID format: {}", e)))?;
+    info!("Successfully parsed offer UUID: {}", offer_uuid);
 
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     // Get the seller info and price from the offer
     use crate::schema::offers::dsl::{offers, id as offer_id, amount};
-    let offer_result = offers
+    let price = offers
         .filter(offer_id.eq(offer_uuid))
-        .select((amount))
-        .first::<i64>(&mut conn);
-    
-    let price = match offer_result {
-        Ok(offer_amount) => {
-            info!("Found offer with amount: {}", offer_amount);
-            offer_amount
-        },
-        Err(e) => {
-            error!("Error fetching offer amount: {} for offer_id: {}", e, offer_uuid);
-            return HttpResponse::InternalServerError().json(UpdatePropertyOwnershipResponse {
-                success: false,
-                message: format!("Error fetching offer details: {}", e),
-            });
-        }
-    };
+        .select(amount)
+        .first::<i64>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Error fetching offer details"))?
+        .ok_or_else(|| ApiError::NotFound("Offer not found".to_string()))?;
+    info!("Found offer with amount: {}", price);
 
     // Get the current property owner (seller) from the properties table
     use crate::schema::properties::dsl::{properties, property_id as prop_id, owner_wallet};
-    let seller_result = properties
+    let seller = properties
         .filter(prop_id.eq(&data.property_id))
         .select(owner_wallet)
-        .first::<String>(&mut conn);
-    
-    let seller = match seller_result {
-        Ok(current_owner) => {
-            info!("Found current property owner: {}", current_owner);
-            current_owner
-        },
-        Err(e) => {
-            error!("Error fetching property owner: {} for property_id: {}", e, data.property_id);
-            return HttpResponse::InternalServerError().json(UpdatePropertyOwnershipResponse {
-                success: false,
-                message: format!("Error fetching property owner: {}", e),
-            });
-        }
-    };
+        .first::<String>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Error fetching property owner"))?
+        .ok_or_else(|| ApiError::NotFound("Property not found".to_string()))?;
+    info!("Found current property owner: {}", seller);
 
     let now = Utc::now().naive_utc();
     
     // Update property ownership in the properties table
-    let property_update_result = {
-        use crate::schema::properties::dsl::{properties, property_id as prop_id, owner_wallet, is_active, updated_at as prop_updated_at};
+    {
+        use crate::schema::properties::dsl::{properties, property_id as prop_id, owner_wallet, organization_id, is_active, updated_at as prop_updated_at};
         
         info!("Updating property {} ownership from {} to {}", data.property_id, seller, data.new_owner);
         
         // Set is_active to true for new owner
+        let new_owner_organization = organization::organization_for_owner(&mut conn, &data.new_owner);
         diesel::update(properties.filter(prop_id.eq(&data.property_id)))
             .set((
                 owner_wallet.eq(&data.new_owner),
+                organization_id.eq(new_owner_organization),
                 is_active.eq(true), // Set is_active to true for active properties
                 prop_updated_at.eq(now),
             ))
             .execute(&mut conn)
+            .map_err(ApiError::db("Failed to update property ownership"))?;
+    }
+
+    info!("Property ownership transferred to {}", data.new_owner);
+    
+    // Record the transaction in the transactions table
+    let transaction_id = Uuid::new_v4();
+    let new_transaction = DbTransaction {
+        id: transaction_id,
+        property_id: data.property_id.clone(),
+        seller_wallet: seller,
+        buyer_wallet: data.new_owner.clone(),
+        price,
+        timestamp: now,
+        signature: Some(data.transaction_signature.clone()),
+    };
+    
+    // Insert transaction into database
+    let transaction_result = diesel::insert_into(crate::schema::transactions::table)
+        .values(&new_transaction)
+        .execute(&mut conn);
+        
+    if let Err(e) = transaction_result {
+        error!("Failed to record property sale transaction: {}", e);
+        // Continue anyway since the property ownership was updated
+    } else {
+        info!("Property sale transaction recorded successfully");
+        market_status::record_event(
+            &mut conn,
+            &data.property_id,
+            market_status::EVENT_SOLD,
+            Some(price),
+            None,
+        );
+    }
+    
+    // Update the status of the associated offer to 'completed'
+    let offer_update_result = {
+        use crate::schema::offers::dsl::{offers, id as offer_id, status, updated_at as offer_updated_at};
+        
+        info!("Updating offer {} status to completed", offer_uuid);
+        
+        // Use the parsed UUID instead of the string
+        diesel::update(offers.filter(offer_id.eq(offer_uuid)))
+            .set((
+                status.eq("completed"),
+                offer_updated_at.eq(now),
+            ))
+            .execute(&mut conn)
     };
 
-    match property_update_result {
+    match offer_update_result {
         Ok(_) => {
-            info!("Property ownership transferred to {}", data.new_owner);
-            
-            // Record the transaction in the transactions table
-            let transaction_id = Uuid::new_v4();
-            let new_transaction = DbTransaction {
-                id: transaction_id,
-                property_id: data.property_id.clone(),
-                seller_wallet: seller,
-                buyer_wallet: data.new_owner.clone(),
-                price: price,
-                timestamp: now,
-            };
-            
-            // Insert transaction into database
-            let transaction_result = diesel::insert_into(crate::schema::transactions::table)
-                .values(&new_transaction)
-                .execute(&mut conn);
-                
-            if let Err(e) = transaction_result {
-                error!("Failed to record property sale transaction: {}", e);
-                // Continue anyway since the property ownership was updated
-            } else {
-                info!("Property sale transaction recorded successfully");
-            }
-            
-            // Update the status of the associated offer to 'completed'
-            let offer_update_result = {
-                use crate::schema::offers::dsl::{offers, id as offer_id, status, updated_at as offer_updated_at};
-                
-                info!("Updating offer {} status to completed", offer_uuid);
-                
-                // Use the parsed UUID instead of the string
-                diesel::update(offers.filter(offer_id.eq(offer_uuid)))
-                    .set((
-                        status.eq("completed"),
-                        offer_updated_at.eq(now),
-                    ))
-                    .execute(&mut conn)
-            };
-
-            match offer_update_result {
-                Ok(_) => {
-                    info!("Offer status updated to completed");
-                    HttpResponse::Ok().json(UpdatePropertyOwnershipResponse {
-                        success: true,
-                        message: "Property ownership updated successfully".to_string(),
-                    })
-                },
-                Err(e) => {
-                    error!("Failed to update offer status: {}", e);
-                    // Continue anyway since the property ownership was updated
-                    HttpResponse::Ok().json(UpdatePropertyOwnershipResponse {
-                        success: true,
-                        message: "Property ownership updated but offer status update failed".to_string(),
-                    })
-                }
-            }
+            info!("Offer status updated to completed");
+            Ok(HttpResponse::Ok().json(UpdatePropertyOwnershipResponse {
+                success: true,
+                message: "Property ownership updated successfully".to_string(),
+            }))
         },
         Err(e) => {
-            error!("Failed to update property ownership: {}", e);
-            HttpResponse::InternalServerError().json(UpdatePropertyOwnershipResponse {
-                success: false,
-                message: format!("Failed to update property ownership: {}", e),
-            })
+            error!("Failed to update offer status: {}", e);
+            // Continue anyway since the property ownership was updated
+            Ok(HttpResponse::Ok().json(UpdatePropertyOwnershipResponse {
+                success: true,
+                message: "Property ownership updated but offer status update failed".to_string(),
+            }))
         }
     }
 }
@@ -870,81 +798,53 @@ pub async fn update_property_ownership(
 pub async fn create_escrow_token_account(
     req: HttpRequest,
     data: web::Json<CreateEscrowTokenAccountRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Verify authentication token
-    let _wallet_address = match verify_token(&req).await {
-        Ok(wallet) => wallet,
-        Err(resp) => return resp,
-    };
+    authenticated_wallet(&req).await?;
 
     info!("Creating escrow token account for offer ID: {}", &data.offer_id);
 
-    let marketplace_program_id = match Pubkey::from_str("E7v7RResymJU5XvvPA9uwxGSEEsdSE6XvaP7BTV2GGoQ") {
-        Ok(pubkey) => pubkey,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid program ID"),
-    };
+    let marketplace_program_id = Pubkey::from_str(PROGRAM_ID)
+        .map_err(|_| ApiError::Internal("Invalid program ID".to_string()))?;
 
-    let nft_mint = match Pubkey::from_str(&data.nft_mint_address) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid NFT mint address"),
-    };
+    let nft_mint = Pubkey::from_str(&data.nft_mint_address)
+        .map_err(|_| ApiError::InvalidInput("Invalid NFT mint address".to_string()))?;
 
     // Derive the offer PDA
-    let property_pubkey = match get_property_pubkey(&data.property_id, &marketplace_program_id) {
-        Ok(pubkey) => pubkey,
-        Err(e) => return HttpResponse::BadRequest().body(format!("Error deriving property PDA: {}", e)),
-    };
+    let property_pubkey = get_property_pubkey(&data.property_id, &marketplace_program_id)
+        .map_err(|e| ApiError::InvalidInput(format!("Error deriving property PDA: {}", e)))?;
 
     // Get the offer from database to find the buyer's wallet
-    let mut conn = match db::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            error!("Failed to connect to database: {}", e);
-            return HttpResponse::InternalServerError().body("Database connection failed");
-        }
-    };
+    let mut conn = db::establish_connection()?;
 
     // Parse offer_id string to UUID
-    let offer_uuid = match Uuid::parse_str(&data.offer_id) {
-        Ok(uuid) => uuid,
-        Err(e) => {
-            error!("Invalid offer UUID format: {}", e);
-            return HttpResponse::BadRequest().body(format!("Invalid offer ID format: {}", e));
-        }
-    };
+    let offer_uuid = Uuid::parse_str(&data.offer_id)
+        .map_err(|e| ApiError::InvalidInput(format!("Invalid offer ID format: {}", e)))?;
 
     // Get the offer from the database
-    use crate::schema::offers::dsl::{offers, id, buyer_wallet as offer_buyer_wallet};
-    let offer_result = offers
+    use crate::schema::offers::dsl::{offers, id, buyer_wallet as offer_buyer_wallet, offer_nonce};
+    let (buyer_wallet_address, nonce) = offers
         .filter(id.eq(offer_uuid))
-        .select(offer_buyer_wallet)
-        .first::<String>(&mut conn);
-
-    let buyer_wallet_address = match offer_result {
-        Ok(wallet) => wallet,
-        Err(e) => {
-            error!("Error fetching offer buyer wallet: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Error fetching offer: {}", e));
-        }
-    };
+        .select((offer_buyer_wallet, offer_nonce))
+        .first::<(String, i64)>(&mut conn)
+        .optional()
+        .map_err(ApiError::db("Error fetching offer"))?
+        .ok_or_else(|| ApiError::NotFound("Offer not found".to_string()))?;
 
     let buyer_pubkey = if let Some(buyer_wallet) = &data.buyer_wallet {
-        match Pubkey::from_str(buyer_wallet) {
-            Ok(pubkey) => pubkey,
-            Err(_) => return HttpResponse::BadRequest().body("Invalid buyer wallet address in request"),
-        }
+        Pubkey::from_str(buyer_wallet)
+            .map_err(|_| ApiError::InvalidInput("Invalid buyer wallet address in request".to_string()))?
     } else {
-        match Pubkey::from_str(&buyer_wallet_address) {
-            Ok(pubkey) => pubkey,
-            Err(_) => return HttpResponse::BadRequest().body("Invalid buyer wallet address"),
-        }
+        Pubkey::from_str(&buyer_wallet_address)
+            .map_err(|_| ApiError::InvalidInput("Invalid buyer wallet address".to_string()))?
     };
 
     let (offer_pda, _) = Pubkey::find_program_address(
         &[
             b"offer", 
             property_pubkey.as_ref(), 
-            buyer_pubkey.as_ref()
+            buyer_pubkey.as_ref(),
+            &(nonce as u64).to_le_bytes()
         ],
         &marketplace_program_id,
     );
@@ -956,7 +856,7 @@ pub async fn create_escrow_token_account(
     );
 
     // Offload blocking RPC call to a separate thread
-    let escrow_token_account = match web::block(move || {
+    let escrow_token_account = web::block(move || {
         // Create a connection to Solana devnet
         let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
         
@@ -981,7 +881,7 @@ pub async fn create_escrow_token_account(
         );
         
         // Check if the token account already exists
-        if let Ok(_) = rpc_client.get_account(&escrow_token_account) {
+        if rpc_client.get_account(&escrow_token_account).is_ok() {
             // Account already exists, return it
             info!("Escrow token account already exists: {}", escrow_token_account);
             return Ok::<Pubkey, anyhow::Error>(escrow_token_account);
@@ -1005,35 +905,18 @@ pub async fn create_escrow_token_account(
         info!("Created escrow token account: {} with signature: {}", escrow_token_account, signature);
         
         Ok::<Pubkey, anyhow::Error>(escrow_token_account)
-    }).await {
-        Ok(Ok(account)) => account,
-        Ok(Err(e)) => {
-            error!("Error creating escrow token account: {}", e);
-            return HttpResponse::InternalServerError().json(CreateEscrowTokenAccountResponse {
-                success: false,
-                message: format!("Failed to create escrow token account: {}", e),
-                escrow_token_account: None,
-            });
-        },
-        Err(e) => {
-            error!("Thread pool error: {}", e);
-            return HttpResponse::InternalServerError().json(CreateEscrowTokenAccountResponse {
-                success: false,
-                message: format!("Thread pool error: {}", e),
-                escrow_token_account: None,
-            });
-        },
-    };
+    }).await?
+    .map_err(|e| ApiError::Internal(format!("Failed to create escrow token account: {}", e)))?;
 
-    HttpResponse::Ok().json(CreateEscrowTokenAccountResponse {
+    Ok(HttpResponse::Ok().json(CreateEscrowTokenAccountResponse {
         success: true,
         message: "Escrow token account created successfully".to_string(),
         escrow_token_account: Some(escrow_token_account.to_string()),
-    })
+    }))
 }
 
 // Create a new function that gets the marketplace PDA and the marketplace account's authority
-fn get_marketplace_info(program_id: &Pubkey) -> Result<(Pubkey, Pubkey), anyhow::Error> {
+pub(crate) fn get_marketplace_info(program_id: &Pubkey) -> Result<(Pubkey, Pubkey), anyhow::Error> {
     // First try with the connected wallet we observed
     let authority = match Pubkey::from_str("A9xYe8XDnCRyPdy7B75B5PT7JP9ktLtxi6xMBVa7C4Xd") {
         Ok(pubkey) => pubkey,
@@ -1052,7 +935,7 @@ fn get_marketplace_info(program_id: &Pubkey) -> Result<(Pubkey, Pubkey), anyhow:
 }
 
 // Helper function to derive property PDA
-fn get_property_pubkey(property_id: &str, program_id: &Pubkey) -> Result<Pubkey, anyhow::Error> {
+pub(crate) fn get_property_pubkey(property_id: &str, program_id: &Pubkey) -> Result<Pubkey, anyhow::Error> {
     let (marketplace_pda, _) = get_marketplace_info(program_id)?;
     
     let (property_pda, _) = Pubkey::find_program_address(
@@ -1061,4 +944,11 @@ fn get_property_pubkey(property_id: &str, program_id: &Pubkey) -> Result<Pubkey,
     );
     
     Ok(property_pda)
-} 
\ No newline at end of file
+} 
+// Computes the Anchor discriminator for a program instruction
+pub(crate) fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(format!("global:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
\ No newline at end of file
@@ -0,0 +1,348 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::env;
+use uuid::Uuid;
+use tracing::{info, error, warn};
+
+use crate::db;
+use crate::models::{Property, PropertyTranslation};
+use crate::organization::{self, Permission};
+use crate::transaction::verify_token;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TranslationError {
+    #[error("Translation request failed: {0}")]
+    Request(String),
+    #[error("Translation configuration error: {0}")]
+    Config(String),
+}
+
+/// A machine translation backend
+pub trait TranslationProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn translate(&self, text: &str, target_locale: &str) -> Result<String, TranslationError>;
+}
+
+/// LibreTranslate-compatible provider at `TRANSLATION_API_URL`
+pub struct HttpTranslationProvider {
+    base_url: String,
+    api_key: Option<String>,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Deserialize)]
+struct HttpTranslationResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+}
+
+impl HttpTranslationProvider {
+    pub fn from_env() -> Result<Self, TranslationError> {
+        let base_url = env::var("TRANSLATION_API_URL")
+            .map_err(|_| TranslationError::Config("TRANSLATION_API_URL must be set".to_string()))?;
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(15))
+            .build()
+            .map_err(|e| TranslationError::Config(e.to_string()))?;
+
+        Ok(HttpTranslationProvider {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: env::var("TRANSLATION_API_KEY").ok(),
+            client,
+        })
+    }
+}
+
+impl TranslationProvider for HttpTranslationProvider {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn translate(&self, text: &str, target_locale: &str) -> Result<String, TranslationError> {
+        let response = self
+            .client
+            .post(format!("{}/translate", self.base_url))
+            .json(&serde_json::json!({
+                "q": text,
+                "source": "auto",
+                "target": target_locale,
+                "format": "text",
+                "api_key": self.api_key,
+            }))
+            .send()
+            .map_err(|e| TranslationError::Request(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(TranslationError::Request(format!("Provider responded with status {}", response.status())));
+        }
+
+        let body: HttpTranslationResponse = response
+            .json()
+            .map_err(|e| TranslationError::Request(e.to_string()))?;
+        Ok(body.translated_text)
+    }
+}
+
+/// Picks the provider configured by `TRANSLATION_PROVIDER`; `None` disables auto translation
+pub fn provider_from_env() -> Result<Option<Box<dyn TranslationProvider>>, TranslationError> {
+    match env::var("TRANSLATION_PROVIDER").as_deref() {
+        Ok("http") => Ok(Some(Box::new(HttpTranslationProvider::from_env()?))),
+        Ok("none") | Err(_) => Ok(None),
+        Ok(other) => Err(TranslationError::Config(format!("Unknown translation provider: {}", other))),
+    }
+}
+
+/// Locales listings are translated into, from `TRANSLATION_LOCALES` (comma separated)
+pub fn configured_locales() -> Vec<String> {
+    env::var("TRANSLATION_LOCALES")
+        .map(|locales| {
+            locales
+                .split(',')
+                .map(|l| l.trim().to_lowercase())
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Resolves the requested locale from `?locale=` or the first `Accept-Language` tag
+pub fn request_locale(req: &HttpRequest) -> Option<String> {
+    let from_query = web::Query::<LocaleQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|q| q.into_inner().locale);
+
+    let raw = from_query.or_else(|| {
+        req.headers()
+            .get("Accept-Language")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(|tag| tag.split(';').next().unwrap_or(tag).to_string())
+    })?;
+
+    let primary = raw.trim().split(['-', '_']).next()?.to_lowercase();
+    if primary.is_empty() || primary == "*" {
+        None
+    } else {
+        Some(primary)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LocaleQuery {
+    pub locale: Option<String>,
+}
+
+fn upsert_auto_translation(
+    conn: &mut PgConnection,
+    prop_id: &str,
+    target_locale: &str,
+    translated_location: &str,
+) -> QueryResult<()> {
+    use crate::schema::property_translations::dsl::*;
+    use diesel::query_dsl::methods::FilterDsl;
+
+    let now = Utc::now().naive_utc();
+    let translation = PropertyTranslation {
+        id: Uuid::new_v4(),
+        property_id: prop_id.to_string(),
+        locale: target_locale.to_string(),
+        location: translated_location.to_string(),
+        description: None,
+        source: "auto".to_string(),
+        created_at: now,
+        updated_at: now,
+    };
+
+    // Manual overrides by the owner always win over machine translations
+    diesel::insert_into(property_translations)
+        .values(&translation)
+        .on_conflict((property_id, locale))
+        .do_update()
+        .set((location.eq(translated_location), updated_at.eq(now)))
+        .filter(source.eq("auto"))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Translates a listing into every configured locale in the background
+pub fn schedule_translation(prop_id: String, source_location: String) {
+    let locales = configured_locales();
+    if locales.is_empty() {
+        return;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let provider = match provider_from_env() {
+            Ok(Some(provider)) => provider,
+            Ok(None) => return,
+            Err(e) => {
+                error!("Translation provider unavailable: {}", e);
+                return;
+            }
+        };
+
+        let mut conn = match db::establish_connection() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to connect to database: {}", e);
+                return;
+            }
+        };
+
+        for target in locales {
+            match provider.translate(&source_location, &target) {
+                Ok(translated) => {
+                    if let Err(e) = upsert_auto_translation(&mut conn, &prop_id, &target, &translated) {
+                        error!("Failed to store {} translation for {}: {}", target, prop_id, e);
+                    }
+                }
+                Err(e) => warn!("{} translation of {} into {} failed: {}", provider.name(), prop_id, target, e),
+            }
+        }
+        info!("Finished translating property {}", prop_id);
+    });
+}
+
+/// Replaces listing text with the translation for `target_locale` where one exists
+pub fn localize_properties(conn: &mut PgConnection, props: &mut [Property], target_locale: &str) {
+    use crate::schema::property_translations::dsl::*;
+
+    let ids: Vec<String> = props.iter().map(|p| p.property_id.clone()).collect();
+    let translations = match property_translations
+        .filter(locale.eq(target_locale))
+        .filter(property_id.eq_any(&ids))
+        .load::<PropertyTranslation>(conn)
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            error!("Failed to load translations: {}", e);
+            return;
+        }
+    };
+
+    for prop in props.iter_mut() {
+        if let Some(t) = translations.iter().find(|t| t.property_id == prop.property_id) {
+            prop.location = t.location.clone();
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TranslationOverrideRequest {
+    pub location: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TranslationsResponse {
+    pub success: bool,
+    pub message: String,
+    pub translations: Vec<PropertyTranslation>,
+}
+
+/// Lists all stored translations of a property
+pub async fn get_property_translations(path: web::Path<String>) -> impl Responder {
+    let property_id_param = path.into_inner();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    use crate::schema::property_translations::dsl::*;
+
+    match property_translations
+        .filter(property_id.eq(&property_id_param))
+        .order_by(locale.asc())
+        .load::<PropertyTranslation>(&mut conn)
+    {
+        Ok(translations) => HttpResponse::Ok().json(TranslationsResponse {
+            success: true,
+            message: format!("Successfully retrieved {} translations", translations.len()),
+            translations,
+        }),
+        Err(e) => {
+            error!("Failed to fetch translations: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to fetch translations: {}", e))
+        }
+    }
+}
+
+/// Lets the owner set the text for a locale manually; auto translation never overwrites it
+pub async fn put_property_translation(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    data: web::Json<TranslationOverrideRequest>,
+) -> impl Responder {
+    let wallet_address = match verify_token(&req).await {
+        Ok(wallet) => wallet,
+        Err(resp) => return resp,
+    };
+
+    let (property_id_param, locale_param) = path.into_inner();
+    let locale_param = locale_param.to_lowercase();
+
+    let mut conn = match db::establish_connection() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Failed to connect to database: {}", e);
+            return HttpResponse::InternalServerError().body("Database connection failed");
+        }
+    };
+
+    if let Err(resp) = organization::require_property_permission(
+        &mut conn,
+        &property_id_param,
+        &wallet_address,
+        Permission::List,
+        "Only the property owner can edit translations",
+    ) {
+        return resp;
+    }
+
+    use crate::schema::property_translations::dsl::*;
+
+    let now = Utc::now().naive_utc();
+    let translation = PropertyTranslation {
+        id: Uuid::new_v4(),
+        property_id: property_id_param.clone(),
+        locale: locale_param.clone(),
+        location: data.location.clone(),
+        description: data.description.clone(),
+        source: "manual".to_string(),
+        created_at: now,
+        updated_at: now,
+    };
+
+    match diesel::insert_into(property_translations)
+        .values(&translation)
+        .on_conflict((property_id, locale))
+        .do_update()
+        .set((
+            location.eq(&data.location),
+            description.eq(&data.description),
+            source.eq("manual"),
+            updated_at.eq(now),
+        ))
+        .execute(&mut conn)
+    {
+        Ok(_) => {
+            info!("Owner override stored for {} ({})", property_id_param, locale_param);
+            HttpResponse::Ok().json(TranslationsResponse {
+                success: true,
+                message: "Translation saved".to_string(),
+                translations: vec![translation],
+            })
+        }
+        Err(e) => {
+            error!("Failed to save translation: {}", e);
+            HttpResponse::InternalServerError().body(format!("Failed to save translation: {}", e))
+        }
+    }
+}
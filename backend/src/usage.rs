@@ -0,0 +1,217 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use chrono::{Duration, DurationRound, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::warn;
+
+use crate::auth;
+use crate::db;
+use crate::error::ApiError;
+use crate::models::ApiUsage;
+use crate::schema::{api_usage, callback_deliveries, property_callbacks, risk_screenings};
+use crate::transaction::authenticated_wallet;
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    /// 24h, 7d or 30d; defaults to 7d
+    pub period: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct RequestUsage {
+    pub total: i64,
+    pub client_errors: i64,
+    pub server_errors: i64,
+}
+
+#[derive(Serialize)]
+pub struct UsageBucket {
+    pub start: NaiveDateTime,
+    pub requests: i64,
+    pub client_errors: i64,
+    pub server_errors: i64,
+}
+
+/// Relayed transactions, from the compliance screening log. Only recorded while screening is on.
+#[derive(Serialize, Default)]
+pub struct RelayUsage {
+    pub relayed: i64,
+    pub blocked: i64,
+}
+
+/// Title company callbacks queued for properties the wallet registered callbacks on
+#[derive(Serialize, Default)]
+pub struct WebhookUsage {
+    pub total: i64,
+    pub delivered: i64,
+    pub pending: i64,
+    pub failed: i64,
+    /// Delivery attempts, including retries
+    pub attempts: i64,
+}
+
+#[derive(Serialize)]
+pub struct UsageResponse {
+    pub success: bool,
+    pub message: String,
+    pub period: String,
+    pub since: NaiveDateTime,
+    pub requests: RequestUsage,
+    /// Hourly for 24h, daily otherwise
+    pub series: Vec<UsageBucket>,
+    pub relays: RelayUsage,
+    pub webhooks: WebhookUsage,
+}
+
+/// Counts each authenticated request against the wallet's hourly usage. Wrapped around the app
+/// with `middleware::from_fn(usage::track_usage)`.
+pub async fn track_usage(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let wallet = auth::bearer_wallet(req.headers());
+    let result = next.call(req).await;
+
+    if let Some(wallet) = wallet {
+        let status = match &result {
+            Ok(res) => res.status().as_u16(),
+            Err(e) => e.as_response_error().status_code().as_u16(),
+        };
+        // Recorded off the request path; a lost count is not worth failing or slowing a request
+        tokio::task::spawn_blocking(move || {
+            let recorded = db::establish_connection()
+                .map_err(|e| e.to_string())
+                .and_then(|mut conn| record_request(&mut conn, &wallet, status).map_err(|e| e.to_string()));
+            if let Err(e) = recorded {
+                warn!("Failed to record API usage for {}: {}", wallet, e);
+            }
+        });
+    }
+
+    result
+}
+
+fn record_request(conn: &mut PgConnection, wallet: &str, status: u16) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    let client_error = i64::from((400..500).contains(&status));
+    let server_error = i64::from(status >= 500);
+
+    diesel::insert_into(api_usage::table)
+        .values(&ApiUsage {
+            wallet_address: wallet.to_string(),
+            bucket_start: now.duration_trunc(Duration::hours(1)).unwrap_or(now),
+            requests: 1,
+            client_errors: client_error,
+            server_errors: server_error,
+        })
+        .on_conflict((api_usage::wallet_address, api_usage::bucket_start))
+        .do_update()
+        .set((
+            api_usage::requests.eq(api_usage::requests + 1),
+            api_usage::client_errors.eq(api_usage::client_errors + client_error),
+            api_usage::server_errors.eq(api_usage::server_errors + server_error),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Period length and the width of its series buckets
+fn parse_period(period: &str) -> Option<(Duration, Duration)> {
+    match period {
+        "24h" => Some((Duration::hours(24), Duration::hours(1))),
+        "7d" => Some((Duration::days(7), Duration::days(1))),
+        "30d" => Some((Duration::days(30), Duration::days(1))),
+        _ => None,
+    }
+}
+
+/// Returns the authenticated wallet's request counts, relayed transactions and webhook
+/// deliveries over the selected period
+pub async fn get_my_usage(
+    req: HttpRequest,
+    query: web::Query<UsageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let wallet_address = authenticated_wallet(&req).await?;
+    let period = query.period.clone().unwrap_or_else(|| "7d".to_string());
+    let (length, step) = parse_period(&period)
+        .ok_or_else(|| ApiError::InvalidInput("Period must be 24h, 7d or 30d".to_string()))?;
+
+    let now = Utc::now().naive_utc();
+    let since = (now - length).duration_trunc(step).unwrap_or(now - length);
+
+    let mut conn = db::establish_connection()?;
+
+    let rows = api_usage::table
+        .filter(api_usage::wallet_address.eq(&wallet_address))
+        .filter(api_usage::bucket_start.ge(since))
+        .order(api_usage::bucket_start.asc())
+        .load::<ApiUsage>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch API usage"))?;
+
+    let mut requests = RequestUsage::default();
+    let mut series: BTreeMap<NaiveDateTime, UsageBucket> = BTreeMap::new();
+    for row in rows {
+        requests.total += row.requests;
+        requests.client_errors += row.client_errors;
+        requests.server_errors += row.server_errors;
+
+        let start = row.bucket_start.duration_trunc(step).unwrap_or(row.bucket_start);
+        let bucket = series.entry(start).or_insert(UsageBucket {
+            start,
+            requests: 0,
+            client_errors: 0,
+            server_errors: 0,
+        });
+        bucket.requests += row.requests;
+        bucket.client_errors += row.client_errors;
+        bucket.server_errors += row.server_errors;
+    }
+
+    let decisions = risk_screenings::table
+        .filter(risk_screenings::wallet_address.eq(&wallet_address))
+        .filter(risk_screenings::created_at.ge(since))
+        .select(risk_screenings::decision)
+        .load::<String>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch relay usage"))?;
+    let mut relays = RelayUsage::default();
+    for decision in decisions {
+        match decision.as_str() {
+            "allowed" | "allowed_unscreened" => relays.relayed += 1,
+            _ => relays.blocked += 1,
+        }
+    }
+
+    let deliveries = callback_deliveries::table
+        .inner_join(property_callbacks::table)
+        .filter(property_callbacks::owner_wallet.eq(&wallet_address))
+        .filter(callback_deliveries::created_at.ge(since))
+        .select((callback_deliveries::status, callback_deliveries::attempts))
+        .load::<(String, i32)>(&mut conn)
+        .map_err(ApiError::db("Failed to fetch webhook deliveries"))?;
+    let mut webhooks = WebhookUsage::default();
+    for (status, attempts) in deliveries {
+        webhooks.total += 1;
+        webhooks.attempts += i64::from(attempts);
+        match status.as_str() {
+            "delivered" => webhooks.delivered += 1,
+            "failed" => webhooks.failed += 1,
+            _ => webhooks.pending += 1,
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(UsageResponse {
+        success: true,
+        message: "Usage retrieved".to_string(),
+        period,
+        since,
+        requests,
+        series: series.into_values().collect(),
+        relays,
+        webhooks,
+    }))
+}
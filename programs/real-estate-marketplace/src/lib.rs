@@ -1,11 +1,21 @@
 #![allow(unused_imports)]
 #![allow(unexpected_cfgs)]
+// `usize::is_multiple_of` is newer than the Solana platform toolchain
+#![allow(clippy::manual_is_multiple_of)]
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{self, MintTo, Transfer, Mint, TokenAccount, Token},
+    metadata::{
+        create_master_edition_v3, create_metadata_accounts_v3,
+        mpl_token_metadata::types::{Collection, CollectionDetails, DataV2},
+        burn_nft, update_metadata_accounts_v2, verify_sized_collection_item, BurnNft,
+        CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata, UpdateMetadataAccountsV2,
+        VerifySizedCollectionItem,
+    },
+    token::{self, Approve, Burn, CloseAccount, MintTo, Revoke, Transfer, Mint, TokenAccount, Token},
 };
-use std::mem::size_of;
+use pyth_sdk_solana::state::SolanaPriceAccount;
 
 declare_id!("E7v7RResymJU5XvvPA9uwxGSEEsdSE6XvaP7BTV2GGoQ");
 
@@ -23,9 +33,51 @@ pub mod real_estate_marketplace {
         marketplace.authority = ctx.accounts.authority.key();
         marketplace.properties_count = 0;
         marketplace.fee_percentage = marketplace_fee;
+        marketplace.sol_usd_price_feed = Pubkey::default();
+        marketplace.max_price_age_secs = DEFAULT_MAX_PRICE_AGE_SECS;
+        marketplace.max_confidence_bps = DEFAULT_MAX_CONFIDENCE_BPS;
+        marketplace.version = MARKETPLACE_VERSION;
+        marketplace.paused = false;
+        marketplace.timelock_secs = 0;
+        marketplace.change_count = 0;
+        marketplace.listing_fee_lamports = 0;
+        marketplace.bump = ctx.bumps.marketplace;
+
+        emit!(MarketplaceInitialized {
+            marketplace: marketplace.key(),
+            authority: marketplace.authority,
+            fee_percentage: marketplace.fee_percentage,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the protocol's cut of every marketplace's settlement fee, shared by all marketplaces
+    /// on this program. Only the program's upgrade authority can call this.
+    pub fn configure_protocol(
+        ctx: Context<ConfigureProtocol>,
+        protocol_fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require!(protocol_fee_bps <= 10000, ErrorCode::InvalidProtocolConfig);
+
+        let protocol_config = &mut ctx.accounts.protocol_config;
+        let clock = Clock::get()?;
+        protocol_config.protocol_fee_bps = protocol_fee_bps;
+        protocol_config.treasury = treasury;
+        protocol_config.updated_at = clock.unix_timestamp;
+
+        emit!(ProtocolConfigured {
+            protocol_fee_bps,
+            treasury,
+            timestamp: clock.unix_timestamp,
+        });
+
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn list_property(
         ctx: Context<ListProperty>,
         property_id: String,
@@ -35,16 +87,43 @@ pub mod real_estate_marketplace {
         square_feet: u64,
         bedrooms: u8,
         bathrooms: u8,
+        expires_at: Option<i64>,
+        property_type: PropertyType,
+        amenities: u64,
+        geohash: String,
+        latitude_e6: Option<i32>,
+        longitude_e6: Option<i32>,
+        content_hashes: Option<ContentHashes>,
     ) -> Result<()> {
-        require!(property_id.len() <= 32, ErrorCode::PropertyIdTooLong);
-        require!(metadata_uri.len() <= 100, ErrorCode::MetadataUriTooLong);
-        require!(location.len() <= 50, ErrorCode::LocationTooLong);
-        require!(price > 0, ErrorCode::InvalidPrice);
+        require!(!ctx.accounts.marketplace.paused, ErrorCode::MarketplacePaused);
 
         let marketplace = &mut ctx.accounts.marketplace;
         let property = &mut ctx.accounts.property;
         let clock = Clock::get()?;
 
+        let listing = new_listing(
+            ListingArgs {
+                property_id,
+                price,
+                metadata_uri,
+                location,
+                square_feet,
+                bedrooms,
+                bathrooms,
+                expires_at,
+                property_type,
+                amenities,
+                geohash,
+                latitude_e6,
+                longitude_e6,
+                content_hashes,
+            },
+            marketplace.key(),
+            ctx.accounts.owner.key(),
+            ctx.accounts.property_nft_mint.key(),
+            clock.unix_timestamp,
+        )?;
+
         // Mint NFT for the property
         token::mint_to(
             CpiContext::new(
@@ -58,124 +137,183 @@ pub mod real_estate_marketplace {
             1,
         )?;
 
-        // Initialize property account
-        property.owner = ctx.accounts.owner.key();
-        property.property_id = property_id;
-        property.price = price;
-        property.metadata_uri = metadata_uri;
-        property.location = location;
-        property.square_feet = square_feet;
-        property.bedrooms = bedrooms;
-        property.bathrooms = bathrooms;
-        property.is_active = true;
-        property.created_at = clock.unix_timestamp;
-        property.updated_at = clock.unix_timestamp;
-        property.transaction_count = 0;
-        property.marketplace = marketplace.key();
-        property.nft_mint = ctx.accounts.property_nft_mint.key();
+        property.set_inner(listing);
+        property.bump = ctx.bumps.property;
+
+        take_listing_slot(
+            &ctx.accounts.listing_limits,
+            &mut ctx.accounts.owner_listings,
+            ctx.accounts.listing_slot.as_mut(),
+            marketplace.key(),
+            ctx.accounts.owner.key(),
+            property.key(),
+            clock.unix_timestamp,
+        )?;
+
+        // The marketplace's listing bond is locked until the listing ends
+        let bond_lamports = listing_bond_lamports(&ctx.accounts.listing_bond_config)?;
+        if bond_lamports > 0 {
+            let listing_bond = ctx
+                .accounts
+                .listing_bond
+                .as_mut()
+                .ok_or(ErrorCode::ListingBondRequired)?;
+            listing_bond.property = property.key();
+            listing_bond.owner = ctx.accounts.owner.key();
+            listing_bond.amount = bond_lamports;
+            listing_bond.locked_at = clock.unix_timestamp;
+
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: listing_bond.to_account_info(),
+                    },
+                ),
+                bond_lamports,
+            )?;
+
+            emit!(ListingBondLocked {
+                listing_bond: listing_bond.key(),
+                property: property.key(),
+                owner: listing_bond.owner,
+                amount: bond_lamports,
+                timestamp: clock.unix_timestamp,
+            });
+        }
 
         marketplace.properties_count = marketplace
             .properties_count
             .checked_add(1)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-        emit!(PropertyListed {
-            property: property.key(),
-            owner: property.owner,
-            property_id: property.property_id.clone(),
-            price: property.price,
-            nft_mint: property.nft_mint,
-            timestamp: clock.unix_timestamp,
-        });
+        let listing_fee = collect_listing_fee(
+            marketplace,
+            ctx.accounts.treasury.as_mut(),
+            &ctx.accounts.owner,
+            &ctx.accounts.system_program,
+        )?;
+
+        emit_listed(property.key(), property, listing_fee, clock.unix_timestamp);
 
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_property(
         ctx: Context<UpdateProperty>,
         price: Option<u64>,
         metadata_uri: Option<String>,
         is_active: Option<bool>,
+        min_offer_bps: Option<u16>,
+        expires_at: Option<i64>,
+        property_type: Option<PropertyType>,
+        amenities: Option<u64>,
     ) -> Result<()> {
         let property = &mut ctx.accounts.property;
         let clock = Clock::get()?;
 
-        msg!("DEBUG: Starting update_property for property ID: {}", property.property_id);
-        msg!("DEBUG: Current property owner: {}", property.owner.to_string());
-        msg!("DEBUG: Transaction signer: {}", ctx.accounts.owner.key().to_string());
-        msg!("DEBUG: NFT mint from property: {}", property.nft_mint.to_string());
-        msg!("DEBUG: NFT mint from transaction: {}", ctx.accounts.property_nft_mint.key().to_string());
-        msg!("DEBUG: Token account provided: {}", ctx.accounts.owner_nft_account.key().to_string());
-
-        // Log ownership constraint check
-        if property.owner != ctx.accounts.owner.key() {
-            msg!("ERROR: Property owner mismatch!");
-            msg!("DEBUG: Property owner: {}", property.owner.to_string());
-            msg!("DEBUG: Signer: {}", ctx.accounts.owner.key().to_string());
-            return Err(ErrorCode::NotPropertyOwner.into());
-        }
-        msg!("DEBUG: Owner check passed");
-
-        // Log NFT mint constraint check
-        if property.nft_mint != ctx.accounts.property_nft_mint.key() {
-            msg!("ERROR: NFT mint mismatch!");
-            msg!("DEBUG: Property NFT mint: {}", property.nft_mint.to_string());
-            msg!("DEBUG: Transaction NFT mint: {}", ctx.accounts.property_nft_mint.key().to_string());
-            return Err(ErrorCode::InvalidNFTMint.into());
-        }
-        msg!("DEBUG: NFT mint check passed");
-
-        // Deserialize the token account to check ownership
-        msg!("DEBUG: Attempting to deserialize token account");
-        let owner_nft_account = match TokenAccount::try_deserialize(&mut &ctx.accounts.owner_nft_account.data.borrow()[..]) {
-            Ok(account) => account,
-            Err(err) => {
-                msg!("ERROR: Failed to deserialize token account: {:?}", err);
-                return Err(ErrorCode::InvalidTokenAccount.into());
-            }
-        };
-        
-        msg!("DEBUG: Token account deserialized successfully");
-        msg!("DEBUG: Token account owner: {}", owner_nft_account.owner.to_string());
-        msg!("DEBUG: Token account mint: {}", owner_nft_account.mint.to_string());
-        msg!("DEBUG: Token account amount: {}", owner_nft_account.amount);
-
-        // Modified the check to use >= instead of == to allow multiple tokens
-        if owner_nft_account.amount < 1 {
-            msg!("ERROR: Token account has insufficient tokens");
-            msg!("DEBUG: Token amount: {}", owner_nft_account.amount);
-            return Err(ErrorCode::NotNFTOwner.into());
-        }
-        msg!("DEBUG: Token amount check passed");
+        let property_key = property.key();
+        consume_co_owner_approval(
+            property,
+            ctx.accounts.co_ownership.as_deref_mut(),
+            property_key,
+        )?;
 
         if let Some(new_price) = price {
             require!(new_price > 0, ErrorCode::InvalidPrice);
             property.price = new_price;
-            msg!("DEBUG: Updated price to: {}", new_price);
         }
 
         if let Some(new_metadata_uri) = metadata_uri {
-            require!(
-                new_metadata_uri.len() <= 200,
-                ErrorCode::MetadataUriTooLong
-            );
-            property.metadata_uri = new_metadata_uri.clone();
-            msg!("DEBUG: Updated metadata_uri to: {}", new_metadata_uri);
+            require!(property.content_hashes.is_none(), ErrorCode::ContentHashedProperty);
+            validate_metadata_uri(&new_metadata_uri, MAX_METADATA_URI_LEN)?;
+            property.metadata_uri = new_metadata_uri;
         }
 
         if let Some(new_is_active) = is_active {
+            // A subdivided parent is retired in favour of its parcels
+            require!(
+                !(new_is_active && property.child_count > 0),
+                ErrorCode::PropertySubdivided
+            );
+            require!(
+                !(new_is_active && property.merged_into.is_some()),
+                ErrorCode::PropertyMerged
+            );
+            // Relisting counts against the owner like a new listing
+            if new_is_active && !property.is_active {
+                take_listing_slot(
+                    &ctx.accounts.listing_limits,
+                    &mut ctx.accounts.owner_listings,
+                    ctx.accounts.listing_slot.as_mut(),
+                    property.marketplace,
+                    ctx.accounts.owner.key(),
+                    property_key,
+                    clock.unix_timestamp,
+                )?;
+            }
+            if !new_is_active && property.is_active {
+                if let Some(listing_slot) = &ctx.accounts.listing_slot {
+                    free_listing_slot(
+                        &listing_slot.to_account_info(),
+                        &mut ctx.accounts.owner_listings,
+                        &ctx.accounts.owner.to_account_info(),
+                        clock.unix_timestamp,
+                    )?;
+                }
+                refund_listing_bond(
+                    &ctx.accounts.listing_bond,
+                    &ctx.accounts.owner.to_account_info(),
+                    clock.unix_timestamp,
+                )?;
+            }
             property.is_active = new_is_active;
-            msg!("DEBUG: Updated is_active to: {}", new_is_active);
+        }
+
+        if let Some(new_min_offer_bps) = min_offer_bps {
+            require!(new_min_offer_bps <= 10000, ErrorCode::InvalidMinOfferBps);
+            property.min_offer_bps = new_min_offer_bps;
+        }
+
+        // An expiry of 0 clears the listing deadline
+        if let Some(new_expires_at) = expires_at {
+            if new_expires_at == 0 {
+                property.expires_at = None;
+            } else {
+                require!(
+                    new_expires_at > clock.unix_timestamp,
+                    ErrorCode::InvalidExpirationTime
+                );
+                property.expires_at = Some(new_expires_at);
+            }
+        }
+
+        if property_type.is_some() || amenities.is_some() {
+            let new_property_type = property_type.unwrap_or(property.property_type);
+            let new_amenities = amenities.unwrap_or(property.amenities);
+            validate_amenities(new_property_type, new_amenities)?;
+            property.property_type = new_property_type;
+            property.amenities = new_amenities;
         }
 
         property.updated_at = clock.unix_timestamp;
-        msg!("DEBUG: Property updated successfully");
+
+        // Listings sized for shorter URIs must be grown with resize_property first
+        require!(
+            8 + property.try_to_vec()?.len() <= property.to_account_info().data_len(),
+            ErrorCode::PropertyAccountTooSmall
+        );
 
         emit!(PropertyUpdated {
             property: property.key(),
             owner: property.owner,
             price: property.price,
             is_active: property.is_active,
+            min_offer_bps: property.min_offer_bps,
+            property_type: property.property_type,
+            amenities: property.amenities,
             timestamp: clock.unix_timestamp,
         });
 
@@ -186,18 +324,22 @@ pub mod real_estate_marketplace {
         ctx: Context<MakeOffer>,
         offer_amount: u64,
         expiration_time: i64,
+        offer_nonce: u64,
     ) -> Result<()> {
-        let property = &ctx.accounts.property;
+        let property = &mut ctx.accounts.property;
         let offer = &mut ctx.accounts.offer;
         let escrow = &mut ctx.accounts.escrow;
         let clock = Clock::get()?;
 
-        require!(property.is_active, ErrorCode::PropertyNotActive);
-        require!(offer_amount > 0, ErrorCode::InvalidOfferAmount);
-        require!(
-            expiration_time > clock.unix_timestamp,
-            ErrorCode::InvalidExpirationTime
-        );
+        check_offer_terms(
+            property,
+            &ctx.accounts.marketplace,
+            ctx.accounts.price_feed.as_deref(),
+            &clock,
+            offer_amount,
+            expiration_time,
+        )?;
+        open_offer(property, &ctx.accounts.offer_limits)?;
 
         // Transfer SOL from buyer to escrow account
         let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
@@ -224,6 +366,9 @@ pub mod real_estate_marketplace {
         offer.updated_at = clock.unix_timestamp;
         offer.expiration_time = expiration_time;
         offer.escrow = escrow.key();
+        offer.nonce = offer_nonce;
+        offer.version = OFFER_VERSION;
+        offer.bump = ctx.bumps.offer;
 
         // Initialize escrow account data
         escrow.offer = offer.key();
@@ -233,19 +378,28 @@ pub mod real_estate_marketplace {
         escrow.amount = offer_amount;
         escrow.nft_held = false;
         escrow.created_at = clock.unix_timestamp;
+        escrow.bump = ctx.bumps.escrow;
 
         emit!(OfferCreated {
             offer: offer.key(),
+            marketplace: property.marketplace,
             property: property.key(),
             buyer: offer.buyer,
             amount: offer_amount,
+            nonce: offer_nonce,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
-    pub fn respond_to_offer(ctx: Context<RespondToOffer>, accept: bool) -> Result<()> {
+    /// `seller_concession` is credited back to the buyer from the seller's proceeds at settlement
+    /// and needs the `seller_concession` account; it can only accompany an acceptance.
+    pub fn respond_to_offer(
+        ctx: Context<RespondToOffer>,
+        accept: bool,
+        seller_concession: Option<u64>,
+    ) -> Result<()> {
         let property = &mut ctx.accounts.property;
         let offer = &mut ctx.accounts.offer;
         let escrow = &mut ctx.accounts.escrow;
@@ -256,63 +410,59 @@ pub mod real_estate_marketplace {
             ErrorCode::OfferNotPending
         );
 
-        require!(
-            escrow.key() == offer.escrow,
-            ErrorCode::EscrowMismatch
-        );
-
-        // Verify the seller is the property owner
-        require!(
-            property.owner == ctx.accounts.owner.key(),
-            ErrorCode::NotPropertyOwner
-        );
+        let concession = seller_concession.unwrap_or(0);
+        require!(concession < offer.amount, ErrorCode::InvalidSellerConcession);
+        match ctx.accounts.seller_concession.as_mut() {
+            Some(seller_concession) => {
+                require!(accept, ErrorCode::InvalidSellerConcession);
+                seller_concession.offer = offer.key();
+                seller_concession.amount = concession;
+            }
+            None => require!(concession == 0, ErrorCode::InvalidSellerConcession),
+        }
 
-        // Verify the buyer account matches the offer
+        // Expired offers are refunded through `expire_offer`
         require!(
-            offer.buyer == ctx.accounts.buyer.key(),
-            ErrorCode::NotOfferBuyer
+            offer.expiration_time > clock.unix_timestamp,
+            ErrorCode::OfferExpired
         );
 
-        // Check if offer has expired
-        if offer.expiration_time <= clock.unix_timestamp {
-            // Return funds to buyer if offer expired
-            let escrow_lamports = escrow.to_account_info().lamports();
-            require!(
-                escrow_lamports >= offer.amount,
-                ErrorCode::InsufficientEscrowFunds
-            );
-
-            // Transfer SOL back to buyer
-            **escrow.to_account_info().try_borrow_mut_lamports()? = escrow_lamports
-                .checked_sub(offer.amount)
-                .ok_or(ErrorCode::ArithmeticOverflow)?;
-            
-            **ctx.accounts.buyer.try_borrow_mut_lamports()? = ctx
-                .accounts.buyer
-                .lamports()
-                .checked_add(offer.amount)
-                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        if accept {
+            consume_co_owner_approval(
+                property,
+                ctx.accounts.co_ownership.as_deref_mut(),
+                offer.key(),
+            )?;
 
-            offer.status = OfferStatus::Expired;
-            offer.updated_at = clock.unix_timestamp;
-            
-            emit!(OfferExpired {
-                offer: offer.key(),
-                property: property.key(),
-                buyer: offer.buyer,
-                seller: property.owner,
-                amount: offer.amount,
-                timestamp: clock.unix_timestamp,
-            });
-            
-            return Err(ErrorCode::OfferExpired.into());
-        }
+            // A governed property needs a sale authorization from a passed proposal covering
+            // this amount. It is single use.
+            if let Some(governance) =
+                property_governance(&property.key(), &ctx.accounts.property_governance)?
+            {
+                let authorization = ctx
+                    .accounts
+                    .sale_authorization
+                    .as_ref()
+                    .ok_or(ErrorCode::SaleAuthorizationRequired)?;
+                require_keys_eq!(
+                    authorization.governance,
+                    governance.governance,
+                    ErrorCode::SaleAuthorizationRequired
+                );
+                require!(
+                    authorization.expires_at > clock.unix_timestamp,
+                    ErrorCode::SaleAuthorizationExpired
+                );
+                require!(
+                    offer.amount >= authorization.min_amount,
+                    ErrorCode::OfferBelowAuthorizedAmount
+                );
+                authorization.close(ctx.accounts.owner.to_account_info())?;
+            }
 
-        if accept {
             // Verify the seller has the NFT
-            let seller_nft_account = TokenAccount::try_deserialize(&mut &ctx.accounts.seller_nft_account.data.borrow()[..])?;
             require!(
-                seller_nft_account.amount >= 1,
+                ctx.accounts.seller_nft_account.amount >= 1,
                 ErrorCode::NotNFTOwner
             );
             
@@ -334,6 +484,7 @@ pub mod real_estate_marketplace {
 
             emit!(OfferAccepted {
                 offer: offer.key(),
+                marketplace: property.marketplace,
                 property: property.key(),
                 buyer: offer.buyer,
                 seller: property.owner,
@@ -341,28 +492,15 @@ pub mod real_estate_marketplace {
                 timestamp: clock.unix_timestamp,
             });
         } else {
-            // Verify escrow has the funds
-            let escrow_lamports = escrow.to_account_info().lamports();
-            require!(
-                escrow_lamports >= offer.amount,
-                ErrorCode::InsufficientEscrowFunds
-            );
-
             // Reject offer and return funds to buyer
-            **escrow.to_account_info().try_borrow_mut_lamports()? = escrow_lamports
-                .checked_sub(offer.amount)
-                .ok_or(ErrorCode::ArithmeticOverflow)?;
-            
-            **ctx.accounts.buyer.try_borrow_mut_lamports()? = ctx
-                .accounts.buyer
-                .lamports()
-                .checked_add(offer.amount)
-                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            release_escrow(&escrow.to_account_info(), &ctx.accounts.buyer.to_account_info(), escrow.amount)?;
 
             offer.status = OfferStatus::Rejected;
+            close_offer(property);
             
             emit!(OfferRejected {
                 offer: offer.key(),
+                marketplace: property.marketplace,
                 property: property.key(),
                 buyer: offer.buyer,
                 seller: property.owner,
@@ -375,7 +513,34 @@ pub mod real_estate_marketplace {
         Ok(())
     }
 
-    pub fn execute_sale(ctx: Context<ExecuteSale>) -> Result<()> {
+    pub fn reject_remaining_offers<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RejectRemainingOffers<'info>>,
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let accepted_offer = &ctx.accounts.accepted_offer;
+        let clock = Clock::get()?;
+
+        // Competing offers are passed as (offer, escrow, buyer) triples
+        require!(
+            ctx.remaining_accounts.len() % 3 == 0,
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            require!(
+                accounts[0].key() != accepted_offer.key(),
+                ErrorCode::CannotSweepAcceptedOffer
+            );
+            reject_pending_offer(property, accounts, clock.unix_timestamp)?;
+        }
+
+        Ok(())
+    }
+
+    /// Settles an offer the seller has accepted, which already moved the NFT into escrow. Only
+    /// the program-owned escrows are touched, so anyone can settle; the payer just covers rent
+    /// for the new accounts.
+    pub fn execute_sale<'info>(ctx: Context<'_, '_, 'info, 'info, ExecuteSale<'info>>) -> Result<()> {
         let property = &mut ctx.accounts.property;
         let offer = &mut ctx.accounts.offer;
         let escrow = &mut ctx.accounts.escrow;
@@ -395,14 +560,70 @@ pub mod real_estate_marketplace {
             ErrorCode::EscrowMismatch
         );
         require!(
-            escrow.nft_held == true,
+            escrow.nft_held,
             ErrorCode::NFTNotInEscrow
         );
+        require!(
+            escrow.amount >= offer.amount,
+            ErrorCode::OfferNotFunded
+        );
+
+        // An agreed closing date holds settlement until it arrives; the schedule is closed with
+        // the sale, returning its rent to the buyer
+        let closing_info = ctx.accounts.closing_schedule.to_account_info();
+        if closing_info.owner == &crate::ID {
+            let schedule = {
+                let data = closing_info.try_borrow_data()?;
+                ClosingSchedule::try_deserialize(&mut &data[..])?
+            };
+            require!(
+                clock.unix_timestamp >= schedule.closing_date,
+                ErrorCode::ClosingDateNotReached
+            );
+
+            let rent = closing_info.lamports();
+            **closing_info.try_borrow_mut_lamports()? = 0;
+            **ctx.accounts.buyer.to_account_info().try_borrow_mut_lamports()? = ctx
+                .accounts.buyer
+                .lamports()
+                .checked_add(rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            closing_info.assign(&anchor_lang::system_program::ID);
+            closing_info.realloc(0, false)?;
+        }
+
+        // Properties under an association need its sign-off on this buyer and price
+        let transfer_approved_by = match property.approval_authority {
+            Some(approval_authority) => {
+                let approval_info = ctx.accounts.transfer_approval.to_account_info();
+                require!(approval_info.owner == &crate::ID, ErrorCode::TransferNotApproved);
+                let approval = {
+                    let data = approval_info.try_borrow_data()?;
+                    TransferApproval::try_deserialize(&mut &data[..])?
+                };
+                require!(
+                    approval.authority == approval_authority
+                        && approval.buyer == offer.buyer
+                        && approval.price == offer.amount,
+                    ErrorCode::TransferNotApproved
+                );
+                Some(approval_authority)
+            }
+            None => None,
+        };
+
+        let payout = payout_destination(property, &ctx.accounts.payout_address)?;
+        require_keys_eq!(ctx.accounts.payout.key(), payout, ErrorCode::PayoutAddressMismatch);
 
-        // Calculate fees
+        // Calculate fees, discounted by either party's stake
+        let fee_bps = settlement_fee_bps(
+            marketplace,
+            &ctx.accounts.staking_config,
+            [&ctx.accounts.buyer_stake, &ctx.accounts.seller_stake],
+        )?;
         let fee_amount = offer
             .amount
-            .checked_mul(marketplace.fee_percentage)
+            .checked_mul(fee_bps)
             .ok_or(ErrorCode::ArithmeticOverflow)?
             .checked_div(10000)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
@@ -412,6 +633,17 @@ pub mod real_estate_marketplace {
             .checked_sub(fee_amount)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+        // Transfer tax for the property's jurisdiction also comes out of the seller's share
+        let tax_amount = transfer_tax(
+            property,
+            &ctx.accounts.tax_config,
+            ctx.accounts.tax_collector.as_deref(),
+            offer.amount,
+        )?;
+        let seller_amount = seller_amount
+            .checked_sub(tax_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
         // Transfer NFT from escrow to buyer
         token::transfer(
             CpiContext::new_with_signer(
@@ -424,37 +656,251 @@ pub mod real_estate_marketplace {
                 &[&[
                     b"escrow", 
                     offer.key().as_ref(), 
-                    &[ctx.bumps.escrow]
+                    &[escrow.bump]
                 ]],
             ),
             1,
         )?;
 
-        // Transfer SOL from escrow to seller
-        **ctx.accounts.seller.try_borrow_mut_lamports()? = ctx
-            .accounts.seller
+        // Remaining accounts: co-owner wallets first, then a (lien, lienholder) pair per active lien
+        let owner_count = if property.co_owned {
+            ctx.accounts
+                .co_ownership
+                .as_ref()
+                .ok_or(ErrorCode::CoOwnershipRequired)?
+                .owner_count as usize
+        } else {
+            0
+        };
+        let lien_count = property.lien_count as usize;
+        require!(
+            ctx.remaining_accounts.len() == owner_count + lien_count * 2,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let (payee_accounts, lien_accounts) = ctx.remaining_accounts.split_at(owner_count);
+
+        // Liens are paid off from the seller's proceeds before anything reaches the owners
+        let mut released_liens = Vec::with_capacity(lien_count);
+        let mut lien_total: u64 = 0;
+        for pair in lien_accounts.chunks(2) {
+            let (lien_info, lienholder_info) = (&pair[0], &pair[1]);
+            require!(
+                lien_info.is_writable && lienholder_info.is_writable,
+                ErrorCode::InvalidRemainingAccounts
+            );
+
+            let lien: Account<'info, Lien> = Account::try_from(lien_info)?;
+            require!(lien.property == property.key(), ErrorCode::InvalidLien);
+            require!(lien.lienholder == lienholder_info.key(), ErrorCode::InvalidLien);
+            require!(
+                !released_liens.contains(&lien_info.key()),
+                ErrorCode::InvalidRemainingAccounts
+            );
+
+            **lienholder_info.try_borrow_mut_lamports()? = lienholder_info
+                .lamports()
+                .checked_add(lien.amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            lien_total = lien_total
+                .checked_add(lien.amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            emit!(LienReleased {
+                lien: lien_info.key(),
+                property: property.key(),
+                lienholder: lien.lienholder,
+                amount: lien.amount,
+                paid_off: true,
+                timestamp: clock.unix_timestamp,
+            });
+
+            released_liens.push(lien_info.key());
+            lien.close(lienholder_info.clone())?;
+        }
+        let seller_amount = seller_amount
+            .checked_sub(lien_total)
+            .ok_or(ErrorCode::LiensExceedProceeds)?;
+
+        // A concession agreed at acceptance comes out of the seller's proceeds and goes back to the buyer
+        let concession_info = ctx.accounts.seller_concession.to_account_info();
+        let seller_concession = if concession_info.owner == &crate::ID {
+            let amount = {
+                let data = concession_info.try_borrow_data()?;
+                SellerConcession::try_deserialize(&mut &data[..])?.amount
+            };
+            // Close it, returning the rent to the seller who paid for it at acceptance
+            let rent = concession_info.lamports();
+            **concession_info.try_borrow_mut_lamports()? = 0;
+            **ctx.accounts.seller.try_borrow_mut_lamports()? = ctx
+                .accounts.seller
+                .lamports()
+                .checked_add(rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            concession_info.assign(&anchor_lang::system_program::ID);
+            concession_info.realloc(0, false)?;
+            amount
+        } else {
+            0
+        };
+        let seller_amount = seller_amount
+            .checked_sub(seller_concession)
+            .ok_or(ErrorCode::InvalidSellerConcession)?;
+        **ctx.accounts.buyer.to_account_info().try_borrow_mut_lamports()? = ctx
+            .accounts.buyer
             .lamports()
-            .checked_add(seller_amount)
+            .checked_add(seller_concession)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-        // Transfer fee to marketplace
-        **ctx.accounts.marketplace_authority.try_borrow_mut_lamports()? = ctx
-            .accounts.marketplace_authority
+        // Co-owned proceeds are split by share, with rounding dust going to the seller
+        let mut seller_payout = seller_amount;
+        if property.co_owned {
+            let co_ownership = ctx
+                .accounts
+                .co_ownership
+                .as_ref()
+                .ok_or(ErrorCode::CoOwnershipRequired)?;
+
+            for (i, payee) in payee_accounts.iter().enumerate() {
+                require_keys_eq!(
+                    payee.key(),
+                    co_ownership.owners[i],
+                    ErrorCode::InvalidRemainingAccounts
+                );
+                let share = (seller_amount as u128)
+                    .checked_mul(co_ownership.shares_bps[i] as u128)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?
+                    .checked_div(10000)
+                    .ok_or(ErrorCode::ArithmeticOverflow)? as u64;
+
+                **payee.try_borrow_mut_lamports()? = payee
+                    .lamports()
+                    .checked_add(share)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+                seller_payout = seller_payout
+                    .checked_sub(share)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+            }
+        }
+
+        // With a release schedule the seller's payout stays in escrow until milestones are confirmed
+        let schedule_info = ctx.accounts.release_schedule.to_account_info();
+        let held = if schedule_info.owner == &crate::ID {
+            require!(!property.co_owned, ErrorCode::InvalidReleaseSchedule);
+            let mut schedule = {
+                let data = schedule_info.try_borrow_data()?;
+                ReleaseSchedule::try_deserialize(&mut &data[..])?
+            };
+            schedule.seller = payout;
+            schedule.total_amount = seller_payout;
+            schedule.funded = true;
+            let mut data = schedule_info.try_borrow_mut_data()?;
+            schedule.try_serialize(&mut &mut data[..])?;
+            seller_payout
+        } else {
+            0
+        };
+
+        // Transfer SOL from escrow to the seller's payout wallet
+        **ctx.accounts.payout.try_borrow_mut_lamports()? = ctx
+            .accounts.payout
             .lamports()
-            .checked_add(fee_amount)
+            .checked_add(seller_payout - held)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-        // Reduce escrow lamports
-        **escrow.to_account_info().try_borrow_mut_lamports()? = escrow
+        // Transfer fee to the marketplace treasury, less the protocol's share
+        let protocol_fee = protocol_fee_share(
+            &ctx.accounts.protocol_config,
+            ctx.accounts.protocol_treasury.as_deref(),
+            fee_amount,
+        )?;
+        let marketplace_fee = fee_amount - protocol_fee;
+        **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? = ctx
+            .accounts.treasury
             .to_account_info()
             .lamports()
-            .checked_sub(offer.amount)
+            .checked_add(marketplace_fee)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        record_fee(&mut ctx.accounts.treasury, marketplace.key(), marketplace_fee)?;
+        if protocol_fee > 0 {
+            let protocol_treasury = ctx
+                .accounts
+                .protocol_treasury
+                .as_ref()
+                .ok_or(ErrorCode::ProtocolTreasuryMismatch)?;
+            **protocol_treasury.try_borrow_mut_lamports()? = protocol_treasury
+                .lamports()
+                .checked_add(protocol_fee)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+
+        // Transfer tax to the jurisdiction's collector
+        if tax_amount > 0 {
+            let collector = ctx
+                .accounts
+                .tax_collector
+                .as_ref()
+                .ok_or(ErrorCode::TaxCollectorMismatch)?;
+            **collector.try_borrow_mut_lamports()? = collector
+                .lamports()
+                .checked_add(tax_amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+
+        // Take everything paid out above from escrow, leaving the held payout and its rent
+        let escrow_info = escrow.to_account_info();
+        require!(
+            escrowed_lamports(&escrow_info)? >= offer.amount - held,
+            ErrorCode::InsufficientEscrowFunds
+        );
+        **escrow_info.try_borrow_mut_lamports()? = escrow_info
+            .lamports()
+            .checked_sub(offer.amount - held)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+        // A title insurance premium the buyer added goes to the insurer and the policy is recorded
+        // with the sale. The policy account is closed, returning its rent to the buyer.
+        let insurance_info = ctx.accounts.title_insurance.to_account_info();
+        let title_policy_hash = if insurance_info.owner == &crate::ID {
+            let policy = {
+                let data = insurance_info.try_borrow_data()?;
+                TitleInsurance::try_deserialize(&mut &data[..])?
+            };
+            let insurer = ctx.accounts.insurer.as_ref().ok_or(ErrorCode::InsurerMismatch)?;
+            require_keys_eq!(insurer.key(), policy.insurer, ErrorCode::InsurerMismatch);
+            release_escrow(&insurance_info, &insurer.to_account_info(), policy.premium)?;
+
+            let rent = insurance_info.lamports();
+            **insurance_info.try_borrow_mut_lamports()? = 0;
+            **ctx.accounts.buyer.to_account_info().try_borrow_mut_lamports()? = ctx
+                .accounts.buyer
+                .lamports()
+                .checked_add(rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            insurance_info.assign(&anchor_lang::system_program::ID);
+            insurance_info.realloc(0, false)?;
+
+            emit!(TitlePremiumReleased {
+                offer: offer.key(),
+                insurer: policy.insurer,
+                premium: policy.premium,
+                policy_hash: policy.policy_hash,
+                timestamp: clock.unix_timestamp,
+            });
+            Some(policy.policy_hash)
+        } else {
+            None
+        };
+
         let previous_owner = property.owner;
         property.owner = offer.buyer;
         property.price = offer.amount;
+        property.price_currency = PriceCurrency::Lamports;
+        property.price_schedule = None;
         property.is_active = false;
+        // The seller's NFT delegation doesn't carry over to the buyer
+        property.auto_accept = false;
+        property.co_owned = false;
+        property.lien_count = 0;
         property.updated_at = clock.unix_timestamp;
         property.transaction_count = property
             .transaction_count
@@ -468,360 +914,11668 @@ pub mod real_estate_marketplace {
         transaction_history.price = offer.amount;
         transaction_history.timestamp = clock.unix_timestamp;
         transaction_history.transaction_index = property.transaction_count;
+        transaction_history.seller_concession = seller_concession;
+        transaction_history.version = TRANSACTION_HISTORY_VERSION;
+        transaction_history.title_policy_hash = title_policy_hash;
+        transaction_history.transfer_approved_by = transfer_approved_by;
+
+        // The approval is spent on this sale, so it's closed to whoever settled it
+        if transfer_approved_by.is_some() {
+            close_pda(
+                &ctx.accounts.transfer_approval.to_account_info(),
+                &ctx.accounts.payer.to_account_info(),
+            )?;
+        }
+        free_listing_slot_unchecked(
+            &ctx.accounts.listing_slot,
+            &ctx.accounts.owner_listings,
+            &ctx.accounts.seller,
+            clock.unix_timestamp,
+        )?;
+        refund_listing_bond(
+            &ctx.accounts.listing_bond,
+            &ctx.accounts.seller,
+            clock.unix_timestamp,
+        )?;
+
+        let history_index = &mut ctx.accounts.history_index;
+        history_index.property = property.key();
+        history_index.count = property.transaction_count;
+        history_index.latest_history = transaction_history.key();
+        history_index.updated_at = clock.unix_timestamp;
 
         offer.status = OfferStatus::Completed;
         offer.updated_at = clock.unix_timestamp;
+        close_offer(property);
 
         emit!(PropertySold {
             property: property.key(),
+            marketplace: property.marketplace,
             transaction_history: transaction_history.key(),
             previous_owner,
             new_owner: property.owner,
             price: offer.amount,
+            seller_concession,
+            fee_bps,
+            marketplace_fee,
+            protocol_fee,
+            transfer_tax: tax_amount,
+            lien_payoff: lien_total,
+            seller_proceeds: seller_amount,
+            payment_mint: None,
             nft_mint: property.nft_mint,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(marketplace_fee: u64)]
-pub struct InitializeMarketplace<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + size_of::<Marketplace>(),
-        seeds = [b"marketplace", authority.key().as_ref()],
-        bump
-    )]
-    pub marketplace: Account<'info, Marketplace>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    pub fn leave_review(
+        ctx: Context<LeaveReview>,
+        rating: u8,
+        comment_hash: [u8; 32],
+    ) -> Result<()> {
+        require!((1..=5).contains(&rating), ErrorCode::InvalidRating);
 
-#[derive(Accounts)]
-#[instruction(
-    property_id: String,
-    price: u64,
-    metadata_uri: String,
-    location: String,
-    square_feet: u64,
-    bedrooms: u8,
-    bathrooms: u8
-)]
-pub struct ListProperty<'info> {
-    #[account(mut)]
-    pub marketplace: Account<'info, Marketplace>,
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + size_of::<Property>() + 
-                32 + // property_id max length
-                100 + // metadata_uri max length
-                50, // location max length
-        seeds = [b"property", marketplace.key().as_ref(), property_id.as_bytes()],
-        bump
-    )]
-    pub property: Account<'info, Property>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// CHECK: This is the NFT mint account, initialized by the token program
-    #[account(
-        mut,
-        constraint = property_nft_mint.owner == &token::ID
-    )]
-    pub property_nft_mint: AccountInfo<'info>,
-    /// CHECK: This is the owner's NFT token account, managed by the associated token program
-    #[account(mut)]
-    pub owner_nft_account: AccountInfo<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        let transaction_history = &ctx.accounts.transaction_history;
+        let reviewer = ctx.accounts.reviewer.key();
+        let review = &mut ctx.accounts.review;
+        let clock = Clock::get()?;
+
+        // Each party reviews the other side of the settled sale
+        let reviewee = if reviewer == transaction_history.buyer {
+            transaction_history.seller
+        } else if reviewer == transaction_history.seller {
+            transaction_history.buyer
+        } else {
+            return Err(ErrorCode::NotTransactionParty.into());
+        };
+
+        review.transaction_history = transaction_history.key();
+        review.property = transaction_history.property;
+        review.reviewer = reviewer;
+        review.reviewee = reviewee;
+        review.rating = rating;
+        review.comment_hash = comment_hash;
+        review.created_at = clock.unix_timestamp;
+
+        emit!(ReviewSubmitted {
+            review: review.key(),
+            transaction_history: transaction_history.key(),
+            property: review.property,
+            reviewer,
+            reviewee,
+            rating,
+            comment_hash,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn register_verifier(ctx: Context<RegisterVerifier>, verifier: Pubkey) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+        let verifier_account = &mut ctx.accounts.verifier_account;
+        let clock = Clock::get()?;
+
+        verifier_account.marketplace = ctx.accounts.marketplace.key();
+        verifier_account.verifier = verifier;
+        verifier_account.registered_at = clock.unix_timestamp;
+
+        emit!(VerifierRegistered {
+            marketplace: verifier_account.marketplace,
+            verifier,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_verifier(ctx: Context<RemoveVerifier>) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+        let clock = Clock::get()?;
+
+        emit!(VerifierRemoved {
+            marketplace: ctx.accounts.marketplace.key(),
+            verifier: ctx.accounts.verifier_account.verifier,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Registers a title insurer buyers can buy a policy from at settlement
+    pub fn register_insurer(ctx: Context<RegisterInsurer>, insurer: Pubkey) -> Result<()> {
+        let insurer_account = &mut ctx.accounts.insurer_account;
+        let clock = Clock::get()?;
+
+        insurer_account.marketplace = ctx.accounts.marketplace.key();
+        insurer_account.insurer = insurer;
+        insurer_account.registered_at = clock.unix_timestamp;
+
+        emit!(InsurerRegistered {
+            marketplace: insurer_account.marketplace,
+            insurer,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Policies already added to offers still pay out to the removed insurer
+    pub fn remove_insurer(ctx: Context<RemoveInsurer>) -> Result<()> {
+        emit!(InsurerRemoved {
+            marketplace: ctx.accounts.marketplace.key(),
+            insurer: ctx.accounts.insurer_account.insurer,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Buyer opts into title insurance on their offer, up to and including acceptance. The
+    /// premium is escrowed in the policy account and paid to the insurer at settlement.
+    pub fn add_title_insurance(
+        ctx: Context<AddTitleInsurance>,
+        premium: u64,
+        policy_hash: [u8; 32],
+    ) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let clock = Clock::get()?;
+
+        require!(
+            offer.status == OfferStatus::Pending || offer.status == OfferStatus::Accepted,
+            ErrorCode::OfferNotPending
+        );
+        require!(premium > 0, ErrorCode::InvalidTitleInsurance);
+
+        let title_insurance = &mut ctx.accounts.title_insurance;
+        title_insurance.offer = offer.key();
+        title_insurance.buyer = offer.buyer;
+        title_insurance.insurer = ctx.accounts.insurer_account.insurer;
+        title_insurance.premium = premium;
+        title_insurance.policy_hash = policy_hash;
+        title_insurance.created_at = clock.unix_timestamp;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: title_insurance.to_account_info(),
+                },
+            ),
+            premium,
+        )?;
+
+        emit!(TitleInsuranceAdded {
+            offer: offer.key(),
+            property: offer.property,
+            buyer: offer.buyer,
+            insurer: title_insurance.insurer,
+            premium,
+            policy_hash,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the premium and rent to the buyer. Once the seller has accepted, the policy is
+    /// locked in until the sale settles or the offer is unwound.
+    pub fn cancel_title_insurance(ctx: Context<CancelTitleInsurance>) -> Result<()> {
+        require!(
+            ctx.accounts.offer.status != OfferStatus::Accepted,
+            ErrorCode::TitleInsuranceLocked
+        );
+
+        emit!(TitleInsuranceCancelled {
+            offer: ctx.accounts.offer.key(),
+            buyer: ctx.accounts.buyer.key(),
+            premium: ctx.accounts.title_insurance.premium,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Links the property to its land registry parcel and claims the parcel's global
+    /// PropertyRegistry, so the same parcel cannot be listed by another property in any
+    /// marketplace. Resubmitting a property's existing reference claims it for properties that
+    /// were linked before the registry existed.
+    pub fn set_land_registry_info(
+        ctx: Context<SetLandRegistryInfo>,
+        cadastral_id: [u8; 32],
+        jurisdiction_code: [u8; 8],
+        parcel_key: [u8; 32],
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let property_registry = &mut ctx.accounts.property_registry;
+        let clock = Clock::get()?;
+
+        // Registry references are write-once so a verified link cannot be swapped later
+        let unset = property.cadastral_id == [0u8; 32] && property.jurisdiction_code == [0u8; 8];
+        require!(
+            unset
+                || (property.cadastral_id == cadastral_id
+                    && property.jurisdiction_code == jurisdiction_code),
+            ErrorCode::LandRegistryInfoAlreadySet
+        );
+        require!(
+            cadastral_id != [0u8; 32] && jurisdiction_code != [0u8; 8],
+            ErrorCode::InvalidLandRegistryInfo
+        );
+        require!(
+            parcel_key == normalized_parcel_key(&cadastral_id, &jurisdiction_code),
+            ErrorCode::InvalidParcelKey
+        );
+        require!(
+            property_registry.property == Pubkey::default()
+                || property_registry.property == property.key(),
+            ErrorCode::ParcelAlreadyClaimed
+        );
+
+        if property_registry.property == Pubkey::default() {
+            property_registry.parcel_key = parcel_key;
+            property_registry.property = property.key();
+            property_registry.marketplace = property.marketplace;
+            property_registry.claimant = ctx.accounts.owner.key();
+            property_registry.claimed_at = clock.unix_timestamp;
+
+            emit!(ParcelClaimed {
+                property_registry: property_registry.key(),
+                parcel_key,
+                property: property.key(),
+                marketplace: property.marketplace,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        if !unset {
+            return Ok(());
+        }
+
+        property.cadastral_id = cadastral_id;
+        property.jurisdiction_code = jurisdiction_code;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(LandRegistryInfoSet {
+            property: property.key(),
+            owner: property.owner,
+            cadastral_id,
+            jurisdiction_code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn verify_property(ctx: Context<VerifyProperty>) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        require!(
+            property.cadastral_id != [0u8; 32],
+            ErrorCode::InvalidLandRegistryInfo
+        );
+
+        property.registry_verified = true;
+        property.registry_verifier = ctx.accounts.verifier.key();
+        property.registry_verified_at = clock.unix_timestamp;
+
+        emit!(PropertyVerified {
+            property: property.key(),
+            verifier: property.registry_verifier,
+            cadastral_id: property.cadastral_id,
+            jurisdiction_code: property.jurisdiction_code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the property's energy rating and certificate hashes. Only a registered verifier can,
+    /// since green-building funds filter on them; attesting again replaces the earlier values.
+    pub fn attest_energy_rating(
+        ctx: Context<AttestEnergyRating>,
+        energy_rating: EnergyRating,
+        leed_certificate_hash: Option<[u8; 32]>,
+        epc_certificate_hash: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        require!(
+            leed_certificate_hash != Some([0; 32]) && epc_certificate_hash != Some([0; 32]),
+            ErrorCode::InvalidEnergyAttestation
+        );
+
+        property.energy_rating = energy_rating;
+        property.leed_certificate_hash = leed_certificate_hash;
+        property.epc_certificate_hash = epc_certificate_hash;
+        property.energy_verifier = Some(ctx.accounts.verifier.key());
+        property.energy_attested_at = clock.unix_timestamp;
+
+        emit!(EnergyRatingAttested {
+            property: property.key(),
+            verifier: ctx.accounts.verifier.key(),
+            energy_rating,
+            leed_certificate_hash,
+            epc_certificate_hash,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_auto_accept(ctx: Context<SetAutoAccept>, enabled: bool) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        // Instant settlement would bypass co-owner, association and governance approvals
+        require!(!(enabled && property.co_owned), ErrorCode::CoOwnedProperty);
+        require!(
+            !(enabled && property.approval_authority.is_some()),
+            ErrorCode::TransferNotApproved
+        );
+        require!(
+            !(enabled
+                && property_governance(&property.key(), &ctx.accounts.property_governance)?.is_some()),
+            ErrorCode::GovernedProperty
+        );
+
+        // The property PDA is delegated the NFT so buy_now can settle without the seller online
+        if enabled {
+            token::approve(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Approve {
+                        to: ctx.accounts.owner_nft_account.to_account_info(),
+                        delegate: property.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+        } else {
+            token::revoke(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Revoke {
+                    source: ctx.accounts.owner_nft_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ))?;
+        }
+
+        property.auto_accept = enabled;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(AutoAcceptUpdated {
+            property: property.key(),
+            owner: property.owner,
+            auto_accept: enabled,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn buy_now(ctx: Context<BuyNow>, offer_amount: u64) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let marketplace = &ctx.accounts.marketplace;
+        let clock = Clock::get()?;
+
+        require!(!marketplace.paused, ErrorCode::MarketplacePaused);
+        require!(property.is_active, ErrorCode::PropertyNotActive);
+        require!(property.auto_accept, ErrorCode::AutoAcceptDisabled);
+        require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+        require!(property.approval_authority.is_none(), ErrorCode::TransferNotApproved);
+        require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+        if let Some(listing_expiry) = property.expires_at {
+            require!(
+                clock.unix_timestamp < listing_expiry,
+                ErrorCode::ListingExpired
+            );
+        }
+        let asking_price = listing_price_in_lamports(
+            property,
+            marketplace,
+            ctx.accounts.price_feed.as_deref(),
+            &clock,
+        )?;
+        require!(
+            offer_amount >= asking_price,
+            ErrorCode::OfferBelowAskingPrice
+        );
+
+        let payout = payout_destination(property, &ctx.accounts.payout_address)?;
+        require_keys_eq!(ctx.accounts.payout.key(), payout, ErrorCode::PayoutAddressMismatch);
+
+        // Calculate fees, discounted by either party's stake
+        let fee_bps = settlement_fee_bps(
+            marketplace,
+            &ctx.accounts.staking_config,
+            [&ctx.accounts.buyer_stake, &ctx.accounts.seller_stake],
+        )?;
+        let fee_amount = offer_amount
+            .checked_mul(fee_bps)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        let tax_amount = transfer_tax(
+            property,
+            &ctx.accounts.tax_config,
+            ctx.accounts.tax_collector.as_deref(),
+            offer_amount,
+        )?;
+
+        let seller_amount = offer_amount
+            .checked_sub(fee_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_sub(tax_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        // Transfer SOL from buyer to the seller's payout wallet
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.buyer.key(),
+                &payout,
+                seller_amount,
+            ),
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                ctx.accounts.payout.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // Transfer fee to the marketplace treasury, less the protocol's share
+        let protocol_fee = protocol_fee_share(
+            &ctx.accounts.protocol_config,
+            ctx.accounts.protocol_treasury.as_deref(),
+            fee_amount,
+        )?;
+        let marketplace_fee = fee_amount - protocol_fee;
+        if marketplace_fee > 0 {
+            anchor_lang::solana_program::program::invoke(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.buyer.key(),
+                    &ctx.accounts.treasury.key(),
+                    marketplace_fee,
+                ),
+                &[
+                    ctx.accounts.buyer.to_account_info(),
+                    ctx.accounts.treasury.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        record_fee(&mut ctx.accounts.treasury, ctx.accounts.marketplace.key(), marketplace_fee)?;
+        if protocol_fee > 0 {
+            let protocol_treasury = ctx
+                .accounts
+                .protocol_treasury
+                .as_ref()
+                .ok_or(ErrorCode::ProtocolTreasuryMismatch)?;
+            anchor_lang::solana_program::program::invoke(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.buyer.key(),
+                    &protocol_treasury.key(),
+                    protocol_fee,
+                ),
+                &[
+                    ctx.accounts.buyer.to_account_info(),
+                    protocol_treasury.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        // Transfer tax to the jurisdiction's collector
+        if tax_amount > 0 {
+            let collector = ctx
+                .accounts
+                .tax_collector
+                .as_ref()
+                .ok_or(ErrorCode::TaxCollectorMismatch)?;
+            anchor_lang::solana_program::program::invoke(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.buyer.key(),
+                    &collector.key(),
+                    tax_amount,
+                ),
+                &[
+                    ctx.accounts.buyer.to_account_info(),
+                    collector.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        // Transfer NFT from seller to buyer using the property PDA's delegation
+        let marketplace_key = property.marketplace;
+        let property_id_bytes = property.property_id.clone().into_bytes();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.seller_nft_account.to_account_info(),
+                    to: ctx.accounts.buyer_nft_account.to_account_info(),
+                    authority: property.to_account_info(),
+                },
+                &[&[
+                    b"property",
+                    marketplace_key.as_ref(),
+                    &property_id_bytes,
+                    &[property.bump],
+                ]],
+            ),
+            1,
+        )?;
+
+        let previous_owner = property.owner;
+        property.owner = ctx.accounts.buyer.key();
+        property.price = offer_amount;
+        property.price_currency = PriceCurrency::Lamports;
+        property.price_schedule = None;
+        property.is_active = false;
+        property.auto_accept = false;
+        property.updated_at = clock.unix_timestamp;
+        property.transaction_count = property
+            .transaction_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        free_listing_slot_unchecked(
+            &ctx.accounts.listing_slot,
+            &ctx.accounts.owner_listings,
+            &ctx.accounts.seller,
+            clock.unix_timestamp,
+        )?;
+        refund_listing_bond(
+            &ctx.accounts.listing_bond,
+            &ctx.accounts.seller,
+            clock.unix_timestamp,
+        )?;
+
+        let transaction_history = &mut ctx.accounts.transaction_history;
+        transaction_history.property = property.key();
+        transaction_history.seller = previous_owner;
+        transaction_history.buyer = property.owner;
+        transaction_history.price = offer_amount;
+        transaction_history.timestamp = clock.unix_timestamp;
+        transaction_history.transaction_index = property.transaction_count;
+        transaction_history.seller_concession = 0;
+        transaction_history.version = TRANSACTION_HISTORY_VERSION;
+
+        let history_index = &mut ctx.accounts.history_index;
+        history_index.property = property.key();
+        history_index.count = property.transaction_count;
+        history_index.latest_history = transaction_history.key();
+        history_index.updated_at = clock.unix_timestamp;
+
+        emit!(PropertySold {
+            property: property.key(),
+            marketplace: property.marketplace,
+            transaction_history: transaction_history.key(),
+            previous_owner,
+            new_owner: property.owner,
+            price: offer_amount,
+            seller_concession: 0,
+            fee_bps,
+            marketplace_fee,
+            protocol_fee,
+            transfer_tax: tax_amount,
+            lien_payoff: 0,
+            seller_proceeds: seller_amount,
+            payment_mint: None,
+            nft_mint: property.nft_mint,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn expire_listing(ctx: Context<ExpireListing>) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        let listing_expiry = property.expires_at.ok_or(ErrorCode::ListingHasNoExpiry)?;
+        require!(property.is_active, ErrorCode::PropertyNotActive);
+        require!(
+            clock.unix_timestamp >= listing_expiry,
+            ErrorCode::ListingNotExpired
+        );
+
+        property.is_active = false;
+        property.updated_at = clock.unix_timestamp;
+        free_listing_slot_unchecked(
+            &ctx.accounts.listing_slot,
+            &ctx.accounts.owner_listings,
+            &ctx.accounts.owner,
+            clock.unix_timestamp,
+        )?;
+        refund_listing_bond(&ctx.accounts.listing_bond, &ctx.accounts.owner, clock.unix_timestamp)?;
+
+        emit!(ListingExpired {
+            property: property.key(),
+            owner: property.owner,
+            expires_at: listing_expiry,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn configure_price_oracle(
+        ctx: Context<ConfigurePriceOracle>,
+        sol_usd_price_feed: Pubkey,
+        max_price_age_secs: u64,
+        max_confidence_bps: u16,
+    ) -> Result<()> {
+        require!(max_price_age_secs > 0, ErrorCode::InvalidOracleConfig);
+        require!(max_confidence_bps <= 10000, ErrorCode::InvalidOracleConfig);
+
+        let marketplace = &mut ctx.accounts.marketplace;
+        let clock = Clock::get()?;
+
+        marketplace.sol_usd_price_feed = sol_usd_price_feed;
+        marketplace.max_price_age_secs = max_price_age_secs;
+        marketplace.max_confidence_bps = max_confidence_bps;
+
+        emit!(PriceOracleConfigured {
+            marketplace: marketplace.key(),
+            sol_usd_price_feed,
+            max_price_age_secs,
+            max_confidence_bps,
+            timestamp: clock.unix_timestamp,
+        });
+        emit_config_updated(marketplace, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    pub fn set_price_currency(
+        ctx: Context<SetPriceCurrency>,
+        price_currency: PriceCurrency,
+        price: u64,
+    ) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        // Currency and price change together so the stored amount is never misread
+        property.price_currency = price_currency;
+        property.price = price;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(PriceCurrencyUpdated {
+            property: property.key(),
+            owner: property.owner,
+            price_currency,
+            price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_property_attributes(
+        ctx: Context<UpdatePropertyAttributes>,
+        square_feet: Option<u64>,
+        bedrooms: Option<u8>,
+        bathrooms: Option<u8>,
+    ) -> Result<()> {
+        require!(
+            square_feet.is_some() || bedrooms.is_some() || bathrooms.is_some(),
+            ErrorCode::NoAttributeChanges
+        );
+
+        let property = &mut ctx.accounts.property;
+        let attribute_change = &mut ctx.accounts.attribute_change;
+        let clock = Clock::get()?;
+
+        // Keep the previous values for the audit trail before applying the correction
+        attribute_change.property = property.key();
+        attribute_change.owner = ctx.accounts.owner.key();
+        attribute_change.verifier = ctx.accounts.verifier.key();
+        attribute_change.change_index = property.attribute_change_count;
+        attribute_change.old_square_feet = property.square_feet;
+        attribute_change.old_bedrooms = property.bedrooms;
+        attribute_change.old_bathrooms = property.bathrooms;
+
+        if let Some(new_square_feet) = square_feet {
+            property.square_feet = new_square_feet;
+        }
+        if let Some(new_bedrooms) = bedrooms {
+            property.bedrooms = new_bedrooms;
+        }
+        if let Some(new_bathrooms) = bathrooms {
+            property.bathrooms = new_bathrooms;
+        }
+
+        attribute_change.new_square_feet = property.square_feet;
+        attribute_change.new_bedrooms = property.bedrooms;
+        attribute_change.new_bathrooms = property.bathrooms;
+        attribute_change.timestamp = clock.unix_timestamp;
+
+        property.attribute_change_count = property
+            .attribute_change_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(PropertyAttributesUpdated {
+            property: property.key(),
+            attribute_change: attribute_change.key(),
+            verifier: attribute_change.verifier,
+            square_feet: property.square_feet,
+            bedrooms: property.bedrooms,
+            bathrooms: property.bathrooms,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_price_schedule(
+        ctx: Context<SetPriceSchedule>,
+        start_price: u64,
+        drop_amount: u64,
+        interval_secs: i64,
+        floor_price: u64,
+    ) -> Result<()> {
+        require!(start_price > 0 && floor_price > 0, ErrorCode::InvalidPrice);
+        require!(
+            floor_price <= start_price && drop_amount > 0 && interval_secs > 0,
+            ErrorCode::InvalidPriceSchedule
+        );
+
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        property.price = start_price;
+        property.price_schedule = Some(PriceSchedule {
+            start_price,
+            drop_amount,
+            interval_secs,
+            floor_price,
+            start_time: clock.unix_timestamp,
+        });
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(PriceScheduleUpdated {
+            property: property.key(),
+            owner: property.owner,
+            price_schedule: property.price_schedule,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn clear_price_schedule(ctx: Context<SetPriceSchedule>) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        // Freeze the listing at whatever the schedule had reached
+        property.price = effective_listing_price(property, clock.unix_timestamp)?;
+        property.price_schedule = None;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(PriceScheduleUpdated {
+            property: property.key(),
+            owner: property.owner,
+            price_schedule: None,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_co_ownership(
+        ctx: Context<CreateCoOwnership>,
+        owners: Vec<Pubkey>,
+        shares_bps: Vec<u16>,
+        threshold: u8,
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        require!(!property.co_owned, ErrorCode::CoOwnedProperty);
+        require!(!property.auto_accept, ErrorCode::CoOwnedProperty);
+        require!(
+            owners.len() >= 2 && owners.len() <= MAX_CO_OWNERS && owners.len() == shares_bps.len(),
+            ErrorCode::InvalidCoOwnership
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= owners.len(),
+            ErrorCode::InvalidCoOwnership
+        );
+        require!(
+            shares_bps.iter().all(|share| *share > 0)
+                && shares_bps.iter().map(|share| *share as u32).sum::<u32>() == 10000,
+            ErrorCode::InvalidCoOwnership
+        );
+        for (i, owner) in owners.iter().enumerate() {
+            require!(!owners[..i].contains(owner), ErrorCode::InvalidCoOwnership);
+        }
+        // The NFT holder settles on behalf of the group, so it must be one of the owners
+        require!(owners.contains(&property.owner), ErrorCode::InvalidCoOwnership);
+
+        let co_ownership = &mut ctx.accounts.co_ownership;
+        co_ownership.property = property.key();
+        co_ownership.owners = [Pubkey::default(); MAX_CO_OWNERS];
+        co_ownership.shares_bps = [0; MAX_CO_OWNERS];
+        co_ownership.owners[..owners.len()].copy_from_slice(&owners);
+        co_ownership.shares_bps[..shares_bps.len()].copy_from_slice(&shares_bps);
+        co_ownership.owner_count = owners.len() as u8;
+        co_ownership.threshold = threshold;
+        co_ownership.approval_target = Pubkey::default();
+        co_ownership.approvals = 0;
+        co_ownership.created_at = clock.unix_timestamp;
+
+        property.co_owned = true;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(CoOwnershipCreated {
+            property: property.key(),
+            co_ownership: co_ownership.key(),
+            owners,
+            shares_bps,
+            threshold,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Records a co-owner's approval of `target`: an offer to accept, or the property itself
+    /// for listing changes. Approving a different target restarts the tally.
+    pub fn approve_sale(ctx: Context<ApproveSale>, target: Pubkey) -> Result<()> {
+        let co_ownership = &mut ctx.accounts.co_ownership;
+        let clock = Clock::get()?;
+
+        let index = co_ownership.owners[..co_ownership.owner_count as usize]
+            .iter()
+            .position(|owner| *owner == ctx.accounts.co_owner.key())
+            .ok_or(ErrorCode::NotCoOwner)?;
+
+        if co_ownership.approval_target != target {
+            co_ownership.approval_target = target;
+            co_ownership.approvals = 0;
+        }
+        co_ownership.approvals |= 1 << index;
+
+        emit!(SaleApproved {
+            property: co_ownership.property,
+            co_owner: ctx.accounts.co_owner.key(),
+            target,
+            approvals: co_ownership.approvals.count_ones() as u8,
+            threshold: co_ownership.threshold,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Groups the seller's properties, passed as writable remaining accounts, into a bundle
+    /// that can only be sold as a whole
+    pub fn create_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateBundle<'info>>,
+        bundle_id: u64,
+        price: u64,
+    ) -> Result<()> {
+        let bundle = &mut ctx.accounts.bundle;
+        let seller = ctx.accounts.seller.key();
+        let clock = Clock::get()?;
+
+        require!(price > 0, ErrorCode::InvalidPrice);
+
+        // Remaining accounts: a (property, property governance PDA) pair per property
+        require!(
+            ctx.remaining_accounts.len() % 2 == 0,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let property_count = ctx.remaining_accounts.len() / 2;
+        require!(
+            (2..=MAX_BUNDLE_PROPERTIES).contains(&property_count),
+            ErrorCode::InvalidBundle
+        );
+
+        bundle.properties = [Pubkey::default(); MAX_BUNDLE_PROPERTIES];
+        for (i, pair) in ctx.remaining_accounts.chunks(2).enumerate() {
+            let (property_info, governance_info) = (&pair[0], &pair[1]);
+            require!(property_info.is_writable, ErrorCode::InvalidRemainingAccounts);
+            // Governed properties only sell through an authorized offer acceptance
+            require!(
+                property_governance(property_info.key, governance_info)?.is_none(),
+                ErrorCode::GovernedProperty
+            );
+            require!(
+                !bundle.properties[..i].contains(&property_info.key()),
+                ErrorCode::InvalidBundle
+            );
+
+            let mut property: Account<'info, Property> = Account::try_from(property_info)?;
+            require!(property.owner == seller, ErrorCode::NotPropertyOwner);
+            require!(
+                property.marketplace == ctx.accounts.marketplace.key(),
+                ErrorCode::MarketplaceMismatch
+            );
+            require!(property.is_active, ErrorCode::PropertyNotActive);
+            require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+            require!(!property.co_owned, ErrorCode::CoOwnedProperty);
+            require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+
+            property.bundle = Some(bundle.key());
+            property.updated_at = clock.unix_timestamp;
+            property.exit(&crate::ID)?;
+
+            bundle.properties[i] = property_info.key();
+        }
+
+        bundle.marketplace = ctx.accounts.marketplace.key();
+        bundle.seller = seller;
+        bundle.bundle_id = bundle_id;
+        bundle.property_count = property_count as u8;
+        bundle.price = price;
+        bundle.is_active = true;
+        bundle.created_at = clock.unix_timestamp;
+        bundle.updated_at = clock.unix_timestamp;
+
+        emit!(BundleCreated {
+            bundle: bundle.key(),
+            seller,
+            properties: bundle.properties[..bundle.property_count as usize].to_vec(),
+            price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Releases every property of an unsold bundle, passed in bundle order
+    pub fn cancel_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelBundle<'info>>,
+    ) -> Result<()> {
+        let bundle = &mut ctx.accounts.bundle;
+        let clock = Clock::get()?;
+
+        require!(bundle.is_active, ErrorCode::BundleNotActive);
+        release_bundle_properties(bundle, ctx.remaining_accounts, clock.unix_timestamp)?;
+
+        bundle.is_active = false;
+        bundle.updated_at = clock.unix_timestamp;
+
+        emit!(BundleCancelled {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Escrows the full bundle offer in the bundle offer account itself
+    pub fn make_bundle_offer(
+        ctx: Context<MakeBundleOffer>,
+        offer_amount: u64,
+        expiration_time: i64,
+        offer_nonce: u64,
+    ) -> Result<()> {
+        let bundle = &ctx.accounts.bundle;
+        let bundle_offer = &mut ctx.accounts.bundle_offer;
+        let clock = Clock::get()?;
+
+        require!(offer_amount > 0, ErrorCode::InvalidOfferAmount);
+        require!(
+            expiration_time > clock.unix_timestamp,
+            ErrorCode::InvalidExpirationTime
+        );
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.buyer.key(),
+            &bundle_offer.key(),
+            offer_amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                bundle_offer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        bundle_offer.bundle = bundle.key();
+        bundle_offer.buyer = ctx.accounts.buyer.key();
+        bundle_offer.amount = offer_amount;
+        bundle_offer.status = OfferStatus::Pending;
+        bundle_offer.nonce = offer_nonce;
+        bundle_offer.created_at = clock.unix_timestamp;
+        bundle_offer.expiration_time = expiration_time;
+
+        emit!(BundleOfferCreated {
+            bundle_offer: bundle_offer.key(),
+            bundle: bundle.key(),
+            buyer: bundle_offer.buyer,
+            amount: offer_amount,
+            nonce: offer_nonce,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Seller declines a bundle offer; closing the offer account refunds the buyer in full
+    pub fn reject_bundle_offer(ctx: Context<RejectBundleOffer>) -> Result<()> {
+        let bundle_offer = &ctx.accounts.bundle_offer;
+        let clock = Clock::get()?;
+
+        require!(
+            bundle_offer.status == OfferStatus::Pending,
+            ErrorCode::OfferNotPending
+        );
+
+        emit!(BundleOfferClosed {
+            bundle_offer: bundle_offer.key(),
+            bundle: bundle_offer.bundle,
+            buyer: bundle_offer.buyer,
+            status: OfferStatus::Rejected,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Buyer reclaims an offer once it has expired or the bundle is no longer for sale
+    pub fn withdraw_bundle_offer(ctx: Context<WithdrawBundleOffer>) -> Result<()> {
+        let bundle_offer = &ctx.accounts.bundle_offer;
+        let clock = Clock::get()?;
+
+        require!(
+            bundle_offer.expiration_time <= clock.unix_timestamp || !ctx.accounts.bundle.is_active,
+            ErrorCode::OfferNotExpired
+        );
+
+        emit!(BundleOfferClosed {
+            bundle_offer: bundle_offer.key(),
+            bundle: bundle_offer.bundle,
+            buyer: bundle_offer.buyer,
+            status: OfferStatus::Expired,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Settles a bundle atomically: pays the seller, marketplace and any transfer tax from the offer
+    /// escrow, transfers every NFT and records a history entry per property. Remaining accounts are
+    /// (property, seller_nft_account, buyer_nft_account, transaction_history, history_index,
+    /// tax_config, tax_collector, payout_address, payout, listing_slot, listing_bond) in bundle
+    /// order. The collector can be any account when the property's jurisdiction has no tax
+    /// config. Each property's proceeds go to its payout address, as in `execute_sale`.
+    pub fn accept_bundle_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AcceptBundleOffer<'info>>,
+    ) -> Result<()> {
+        let bundle = &mut ctx.accounts.bundle;
+        let bundle_offer = &mut ctx.accounts.bundle_offer;
+        let marketplace = &ctx.accounts.marketplace;
+        let clock = Clock::get()?;
+
+        require!(bundle.is_active, ErrorCode::BundleNotActive);
+        require!(
+            bundle_offer.status == OfferStatus::Pending,
+            ErrorCode::OfferNotPending
+        );
+        require!(
+            bundle_offer.expiration_time > clock.unix_timestamp,
+            ErrorCode::OfferExpired
+        );
+
+        let property_count = bundle.property_count as usize;
+        require!(
+            ctx.remaining_accounts.len() == property_count * 11,
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let fee_bps = settlement_fee_bps(
+            marketplace,
+            &ctx.accounts.staking_config,
+            [&ctx.accounts.buyer_stake, &ctx.accounts.seller_stake],
+        )?;
+        let fee_amount = bundle_offer
+            .amount
+            .checked_mul(fee_bps)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        let seller_amount = bundle_offer
+            .amount
+            .checked_sub(fee_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        let protocol_fee = protocol_fee_share(
+            &ctx.accounts.protocol_config,
+            ctx.accounts.protocol_treasury.as_deref(),
+            fee_amount,
+        )?;
+        let marketplace_fee = fee_amount - protocol_fee;
+
+        let offer_info = bundle_offer.to_account_info();
+        release_escrow(&offer_info, &ctx.accounts.treasury.to_account_info(), marketplace_fee)?;
+        record_fee(&mut ctx.accounts.treasury, marketplace.key(), marketplace_fee)?;
+        if protocol_fee > 0 {
+            let protocol_treasury = ctx
+                .accounts
+                .protocol_treasury
+                .as_ref()
+                .ok_or(ErrorCode::ProtocolTreasuryMismatch)?;
+            release_escrow(&offer_info, protocol_treasury, protocol_fee)?;
+        }
+
+        // History records the bundle price split evenly, with the remainder on the last parcel.
+        // The fee and the protocol's share of it are attributed the same way.
+        let per_property_price = bundle_offer.amount / property_count as u64;
+        let per_property_fee = fee_amount / property_count as u64;
+        let per_property_protocol_fee = protocol_fee / property_count as u64;
+        let history_space = 8 + TransactionHistory::INIT_SPACE;
+        let history_rent = Rent::get()?.minimum_balance(history_space);
+        let mut tax_total: u64 = 0;
+
+        for (i, accounts) in ctx.remaining_accounts.chunks(11).enumerate() {
+            let (property_info, seller_nft_info, buyer_nft_info, history_info, history_index_info) =
+                (&accounts[0], &accounts[1], &accounts[2], &accounts[3], &accounts[4]);
+            let (tax_config_info, tax_collector_info) = (&accounts[5], &accounts[6]);
+            let (payout_address_info, payout_info) = (&accounts[7], &accounts[8]);
+            let (listing_slot_info, listing_bond_info) = (&accounts[9], &accounts[10]);
+
+            require!(
+                property_info.key() == bundle.properties[i],
+                ErrorCode::InvalidRemainingAccounts
+            );
+            let mut property: Account<'info, Property> = Account::try_from(property_info)?;
+            require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+            require!(property.approval_authority.is_none(), ErrorCode::TransferNotApproved);
+            require!(
+                property.owner == bundle.seller && property.bundle == Some(bundle.key()),
+                ErrorCode::InvalidBundle
+            );
+            let payout = payout_destination(&property, payout_address_info)?;
+            require_keys_eq!(payout_info.key(), payout, ErrorCode::PayoutAddressMismatch);
+            require!(payout_info.is_writable, ErrorCode::InvalidRemainingAccounts);
+
+            let buyer_nft_account: Account<'info, TokenAccount> = Account::try_from(buyer_nft_info)?;
+            require!(
+                buyer_nft_account.mint == property.nft_mint
+                    && buyer_nft_account.owner == bundle_offer.buyer,
+                ErrorCode::InvalidTokenAccount
+            );
+
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: seller_nft_info.clone(),
+                        to: buyer_nft_info.clone(),
+                        authority: ctx.accounts.seller.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+
+            let transaction_index = property
+                .transaction_count
+                .checked_add(1)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            let index_bytes = transaction_index.to_le_bytes();
+            let (history_key, history_bump) = Pubkey::find_program_address(
+                &[b"transaction", property_info.key.as_ref(), &index_bytes],
+                &crate::ID,
+            );
+            require!(
+                history_info.key() == history_key,
+                ErrorCode::InvalidRemainingAccounts
+            );
+
+            anchor_lang::system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::CreateAccount {
+                        from: ctx.accounts.seller.to_account_info(),
+                        to: history_info.clone(),
+                    },
+                    &[&[
+                        b"transaction",
+                        property_info.key.as_ref(),
+                        &index_bytes,
+                        &[history_bump],
+                    ]],
+                ),
+                history_rent,
+                history_space as u64,
+                &crate::ID,
+            )?;
+
+            let (price, property_fee, property_protocol_fee) = if i + 1 == property_count {
+                let others = property_count as u64 - 1;
+                (
+                    bundle_offer.amount - per_property_price * others,
+                    fee_amount - per_property_fee * others,
+                    protocol_fee - per_property_protocol_fee * others,
+                )
+            } else {
+                (per_property_price, per_property_fee, per_property_protocol_fee)
+            };
+
+            let property_tax =
+                transfer_tax(&property, tax_config_info, Some(tax_collector_info), price)?;
+            if property_tax > 0 {
+                require!(tax_collector_info.is_writable, ErrorCode::InvalidRemainingAccounts);
+                release_escrow(&offer_info, tax_collector_info, property_tax)?;
+                tax_total = tax_total
+                    .checked_add(property_tax)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+            }
+            let seller_proceeds = price
+                .checked_sub(property_fee)
+                .and_then(|proceeds| proceeds.checked_sub(property_tax))
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            release_escrow(&offer_info, payout_info, seller_proceeds)?;
+
+            let history = TransactionHistory {
+                property: property.key(),
+                seller: bundle.seller,
+                buyer: bundle_offer.buyer,
+                price,
+                timestamp: clock.unix_timestamp,
+                transaction_index,
+                seller_concession: 0,
+                version: TRANSACTION_HISTORY_VERSION,
+                title_policy_hash: None,
+                transfer_approved_by: None,
+            };
+            let mut history_data = history_info.try_borrow_mut_data()?;
+            history.try_serialize(&mut &mut history_data[..])?;
+            drop(history_data);
+            write_history_index(
+                history_index_info,
+                &ctx.accounts.seller.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                property_info.key,
+                transaction_index,
+                history_key,
+                clock.unix_timestamp,
+            )?;
+
+            property.owner = bundle_offer.buyer;
+            property.price = price;
+            property.price_currency = PriceCurrency::Lamports;
+            property.price_schedule = None;
+            property.is_active = false;
+            property.auto_accept = false;
+            property.bundle = None;
+            property.updated_at = clock.unix_timestamp;
+            property.transaction_count = transaction_index;
+            property.exit(&crate::ID)?;
+
+            let (listing_slot_key, _) = Pubkey::find_program_address(
+                &[b"listing_slot", property_info.key.as_ref()],
+                &crate::ID,
+            );
+            require!(
+                listing_slot_info.key() == listing_slot_key && listing_slot_info.is_writable,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            free_listing_slot_unchecked(
+                listing_slot_info,
+                &ctx.accounts.owner_listings,
+                &ctx.accounts.seller,
+                clock.unix_timestamp,
+            )?;
+
+            let (listing_bond_key, _) = Pubkey::find_program_address(
+                &[b"listing_bond", property_info.key.as_ref()],
+                &crate::ID,
+            );
+            require!(
+                listing_bond_info.key() == listing_bond_key && listing_bond_info.is_writable,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            refund_listing_bond(listing_bond_info, &ctx.accounts.seller, clock.unix_timestamp)?;
+
+            emit!(PropertySold {
+                property: property.key(),
+                marketplace: property.marketplace,
+                transaction_history: history_key,
+                previous_owner: bundle.seller,
+                new_owner: bundle_offer.buyer,
+                price,
+                seller_concession: 0,
+                fee_bps,
+                marketplace_fee: property_fee - property_protocol_fee,
+                protocol_fee: property_protocol_fee,
+                transfer_tax: property_tax,
+                lien_payoff: 0,
+                seller_proceeds,
+                payment_mint: None,
+                nft_mint: property.nft_mint,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        let seller_amount = seller_amount
+            .checked_sub(tax_total)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        bundle.is_active = false;
+        bundle.updated_at = clock.unix_timestamp;
+        bundle_offer.status = OfferStatus::Completed;
+
+        emit!(BundleSold {
+            bundle: bundle.key(),
+            bundle_offer: bundle_offer.key(),
+            seller: bundle.seller,
+            buyer: bundle_offer.buyer,
+            price: bundle_offer.amount,
+            fee_bps,
+            marketplace_fee,
+            protocol_fee,
+            transfer_tax: tax_total,
+            seller_proceeds: seller_amount,
+            payment_mint: None,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Splits a property into parcels. The parent is deactivated, its NFT burned, and each child
+    /// gets its own Property account and NFT, linked back to the parent. Remaining accounts are
+    /// (child_property, child_nft_mint, child_owner_nft_account) per parcel; the mints are
+    /// created beforehand with the owner as mint authority, as for list_property.
+    pub fn subdivide<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Subdivide<'info>>,
+        child_property_ids: Vec<String>,
+        child_square_feet: Vec<u64>,
+    ) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        let parent = &mut ctx.accounts.parent_property;
+        let clock = Clock::get()?;
+
+        let child_count = child_property_ids.len();
+        require!(
+            (2..=MAX_SUBDIVISION_PARCELS).contains(&child_count)
+                && child_square_feet.len() == child_count
+                && ctx.remaining_accounts.len() == child_count * 3,
+            ErrorCode::InvalidSubdivision
+        );
+        require!(parent.child_count == 0, ErrorCode::PropertySubdivided);
+        require!(parent.merged_into.is_none(), ErrorCode::PropertyMerged);
+        require!(parent.bundle.is_none(), ErrorCode::PropertyInBundle);
+        require!(!parent.co_owned, ErrorCode::CoOwnedProperty);
+        require!(parent.lien_count == 0, ErrorCode::PropertyHasLiens);
+        require!(!parent.auto_accept, ErrorCode::InvalidSubdivision);
+        // An accepted offer or a loan holds the NFT, and the buyer or lender is owed this parcel
+        require!(parent.open_offer_count == 0, ErrorCode::OpenOffersRemain);
+
+        let total_square_feet = child_square_feet
+            .iter()
+            .try_fold(0u64, |sum, sqft| sum.checked_add(*sqft))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            child_square_feet.iter().all(|sqft| *sqft > 0) && total_square_feet == parent.square_feet,
+            ErrorCode::InvalidSubdivision
+        );
+
+        let property_space = listing_space(&parent.content_hashes, parent.metadata_uri.len());
+        let property_rent = Rent::get()?.minimum_balance(property_space);
+        let mut children = Vec::with_capacity(child_count);
+
+        for (i, accounts) in ctx.remaining_accounts.chunks(3).enumerate() {
+            let (child_info, mint_info, owner_nft_info) = (&accounts[0], &accounts[1], &accounts[2]);
+            let child_id = &child_property_ids[i];
+            validate_property_id(child_id)?;
+            require!(
+                child_info.is_writable && mint_info.is_writable && owner_nft_info.is_writable,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            require!(mint_info.owner == &token::ID, ErrorCode::InvalidNFTMint);
+
+            let marketplace_key = marketplace.key();
+            let (child_key, child_bump) = Pubkey::find_program_address(
+                &[b"property", marketplace_key.as_ref(), child_id.as_bytes()],
+                &crate::ID,
+            );
+            require!(child_info.key() == child_key, ErrorCode::InvalidRemainingAccounts);
+
+            anchor_lang::system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::CreateAccount {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: child_info.clone(),
+                    },
+                    &[&[
+                        b"property",
+                        marketplace_key.as_ref(),
+                        child_id.as_bytes(),
+                        &[child_bump],
+                    ]],
+                ),
+                property_rent,
+                property_space as u64,
+                &crate::ID,
+            )?;
+
+            token::mint_to(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: mint_info.clone(),
+                        to: owner_nft_info.clone(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+
+            // Parcels start unlisted, priced pro rata by area until the owner lists them
+            let price = (parent.price as u128)
+                .checked_mul(child_square_feet[i] as u128)
+                .ok_or(ErrorCode::ArithmeticOverflow)?
+                .checked_div(parent.square_feet as u128)
+                .ok_or(ErrorCode::ArithmeticOverflow)?
+                .max(1) as u64;
+
+            let child = Property {
+                marketplace: marketplace_key,
+                owner: parent.owner,
+                property_id: child_id.clone(),
+                price,
+                metadata_uri: parent.metadata_uri.clone(),
+                location: parent.location.clone(),
+                square_feet: child_square_feet[i],
+                bedrooms: 0,
+                bathrooms: 0,
+                is_active: false,
+                created_at: clock.unix_timestamp,
+                updated_at: clock.unix_timestamp,
+                transaction_count: 0,
+                nft_mint: mint_info.key(),
+                min_offer_bps: 0,
+                cadastral_id: [0; 32],
+                jurisdiction_code: [0; 8],
+                registry_verified: false,
+                registry_verifier: Pubkey::default(),
+                registry_verified_at: 0,
+                auto_accept: false,
+                expires_at: None,
+                price_currency: parent.price_currency,
+                attribute_change_count: 0,
+                price_schedule: None,
+                co_owned: false,
+                bundle: None,
+                parent: Some(parent.key()),
+                child_count: 0,
+                merged_into: None,
+                lien_count: 0,
+                document_count: 0,
+                property_type: parent.property_type,
+                amenities: 0,
+                geohash: parent.geohash,
+                latitude_e6: None,
+                longitude_e6: None,
+                version: PROPERTY_VERSION,
+                open_offer_count: 0,
+                approval_authority: parent.approval_authority,
+                energy_rating: EnergyRating::Unrated,
+                leed_certificate_hash: None,
+                epc_certificate_hash: None,
+                energy_verifier: None,
+                energy_attested_at: 0,
+                bump: child_bump,
+                content_hashes: parent.content_hashes,
+            };
+            let mut child_data = child_info.try_borrow_mut_data()?;
+            child.try_serialize(&mut &mut child_data[..])?;
+            drop(child_data);
+
+            children.push(child_key);
+        }
+
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.parent_nft_mint.to_account_info(),
+                    from: ctx.accounts.owner_nft_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        parent.is_active = false;
+        parent.child_count = child_count as u8;
+        parent.updated_at = clock.unix_timestamp;
+
+        marketplace.properties_count = marketplace
+            .properties_count
+            .checked_add(child_count as u64)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(PropertySubdivided {
+            parent: parent.key(),
+            owner: parent.owner,
+            children,
+            child_property_ids,
+            child_square_feet,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Combines parcels owned by the signer into one new property. Each source NFT is burned
+    /// and its Property retired with a pointer to the result; the sources are kept in a
+    /// MergeRecord. Remaining accounts are (property, property_governance, nft_mint,
+    /// owner_nft_account) per parcel.
+    pub fn merge_parcels<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MergeParcels<'info>>,
+        merged_property_id: String,
+    ) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        let merged = &mut ctx.accounts.merged_property;
+        let merge_record = &mut ctx.accounts.merge_record;
+        let owner = ctx.accounts.owner.key();
+        let clock = Clock::get()?;
+
+        validate_property_id(&merged_property_id)?;
+        let source_count = ctx.remaining_accounts.len() / 4;
+        require!(
+            ctx.remaining_accounts.len() % 4 == 0
+                && (2..=MAX_MERGE_PARCELS).contains(&source_count),
+            ErrorCode::InvalidMerge
+        );
+
+        let mut sources = [Pubkey::default(); MAX_MERGE_PARCELS];
+        let mut square_feet: u64 = 0;
+        let mut bedrooms: u8 = 0;
+        let mut bathrooms: u8 = 0;
+        let mut price: u64 = 0;
+        let mut price_currency = PriceCurrency::Lamports;
+        let mut property_type = PropertyType::Residential;
+        let mut amenities: u64 = 0;
+        let mut geohash = [0u8; GEOHASH_LEN];
+        let mut metadata_uri = String::new();
+        let mut location = String::new();
+        let mut approval_authority = None;
+        let mut content_hashes = None;
+
+        for (i, accounts) in ctx.remaining_accounts.chunks(4).enumerate() {
+            let (property_info, governance_info, mint_info, owner_nft_info) =
+                (&accounts[0], &accounts[1], &accounts[2], &accounts[3]);
+            require!(
+                property_info.is_writable && mint_info.is_writable && owner_nft_info.is_writable,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            require!(!sources[..i].contains(property_info.key), ErrorCode::InvalidMerge);
+            // Burning a governed parcel would bypass its governance
+            require!(
+                property_governance(property_info.key, governance_info)?.is_none(),
+                ErrorCode::GovernedProperty
+            );
+
+            let mut property: Account<'info, Property> = Account::try_from(property_info)?;
+            require!(property.owner == owner, ErrorCode::NotPropertyOwner);
+            require!(
+                property.marketplace == marketplace.key(),
+                ErrorCode::MarketplaceMismatch
+            );
+            require!(property.nft_mint == mint_info.key(), ErrorCode::InvalidNFTMint);
+            require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+            require!(!property.co_owned, ErrorCode::CoOwnedProperty);
+            require!(!property.auto_accept, ErrorCode::InvalidMerge);
+            require!(property.child_count == 0, ErrorCode::PropertySubdivided);
+            require!(property.merged_into.is_none(), ErrorCode::PropertyMerged);
+            require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+            // A pending offer's escrow would be stranded on a burned parcel
+            require!(property.open_offer_count == 0, ErrorCode::OpenOffersRemain);
+
+            if i == 0 {
+                price_currency = property.price_currency;
+                property_type = property.property_type;
+                geohash = property.geohash;
+                metadata_uri = property.metadata_uri.clone();
+                location = property.location.clone();
+                approval_authority = property.approval_authority;
+                content_hashes = property.content_hashes;
+            }
+            require!(property.price_currency == price_currency, ErrorCode::InvalidMerge);
+            require!(property.property_type == property_type, ErrorCode::InvalidMerge);
+            require!(property.approval_authority == approval_authority, ErrorCode::InvalidMerge);
+            amenities |= property.amenities;
+
+            square_feet = square_feet
+                .checked_add(property.square_feet)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            bedrooms = bedrooms.saturating_add(property.bedrooms);
+            bathrooms = bathrooms.saturating_add(property.bathrooms);
+            price = price
+                .checked_add(property.price)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            token::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: mint_info.clone(),
+                        from: owner_nft_info.clone(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+
+            property.is_active = false;
+            property.merged_into = Some(merged.key());
+            property.updated_at = clock.unix_timestamp;
+            property.exit(&crate::ID)?;
+
+            sources[i] = property_info.key();
+        }
+
+        token::mint_to(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.merged_nft_mint.to_account_info(),
+                    to: ctx.accounts.owner_nft_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        // The merged property starts unlisted at the combined price of its sources
+        merged.marketplace = marketplace.key();
+        merged.owner = owner;
+        merged.property_id = merged_property_id;
+        merged.price = price;
+        merged.metadata_uri = metadata_uri;
+        merged.location = location;
+        merged.square_feet = square_feet;
+        merged.bedrooms = bedrooms;
+        merged.bathrooms = bathrooms;
+        merged.is_active = false;
+        merged.created_at = clock.unix_timestamp;
+        merged.updated_at = clock.unix_timestamp;
+        merged.transaction_count = 0;
+        merged.nft_mint = ctx.accounts.merged_nft_mint.key();
+        merged.min_offer_bps = 0;
+        merged.auto_accept = false;
+        merged.expires_at = None;
+        merged.price_currency = price_currency;
+        merged.attribute_change_count = 0;
+        merged.price_schedule = None;
+        merged.co_owned = false;
+        merged.bundle = None;
+        merged.parent = None;
+        merged.child_count = 0;
+        merged.merged_into = None;
+        merged.lien_count = 0;
+        merged.document_count = 0;
+        merged.property_type = property_type;
+        merged.amenities = amenities;
+        merged.geohash = geohash;
+        merged.latitude_e6 = None;
+        merged.longitude_e6 = None;
+        merged.version = PROPERTY_VERSION;
+        merged.approval_authority = approval_authority;
+        merged.energy_rating = EnergyRating::Unrated;
+        merged.leed_certificate_hash = None;
+        merged.epc_certificate_hash = None;
+        merged.energy_verifier = None;
+        merged.energy_attested_at = 0;
+        merged.bump = ctx.bumps.merged_property;
+        merged.content_hashes = content_hashes;
+
+        merge_record.merged_property = merged.key();
+        merge_record.sources = sources;
+        merge_record.source_count = source_count as u8;
+        merge_record.merged_at = clock.unix_timestamp;
+
+        marketplace.properties_count = marketplace
+            .properties_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(ParcelsMerged {
+            merged_property: merged.key(),
+            owner,
+            sources: sources[..source_count].to_vec(),
+            square_feet,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Creates the program-owned collection that settlement receipts are minted into
+    pub fn create_receipt_collection(
+        ctx: Context<CreateReceiptCollection>,
+        base_uri: String,
+    ) -> Result<()> {
+        require!(base_uri.len() <= MAX_RECEIPT_BASE_URI_LEN, ErrorCode::MetadataUriTooLong);
+
+        let marketplace_key = ctx.accounts.marketplace.key();
+        let authority_seeds: &[&[u8]] = &[
+            b"receipt_authority",
+            marketplace_key.as_ref(),
+            &[ctx.bumps.receipt_authority],
+        ];
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.collection_mint.to_account_info(),
+                    to: ctx.accounts.collection_token_account.to_account_info(),
+                    authority: ctx.accounts.receipt_authority.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            1,
+        )?;
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    mint: ctx.accounts.collection_mint.to_account_info(),
+                    mint_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    payer: ctx.accounts.authority.to_account_info(),
+                    update_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            DataV2 {
+                name: "Property Sale Receipts".to_string(),
+                symbol: RECEIPT_SYMBOL.to_string(),
+                uri: base_uri.clone(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            Some(CollectionDetails::V1 { size: 0 }),
+        )?;
+
+        create_master_edition_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: ctx.accounts.collection_master_edition.to_account_info(),
+                    mint: ctx.accounts.collection_mint.to_account_info(),
+                    update_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    mint_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    payer: ctx.accounts.authority.to_account_info(),
+                    metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            Some(0),
+        )?;
+
+        let receipt_config = &mut ctx.accounts.receipt_config;
+        receipt_config.marketplace = marketplace_key;
+        receipt_config.collection_mint = ctx.accounts.collection_mint.key();
+        receipt_config.base_uri = base_uri;
+        receipt_config.authority_bump = ctx.bumps.receipt_authority;
+
+        emit!(ReceiptCollectionCreated {
+            marketplace: marketplace_key,
+            collection_mint: receipt_config.collection_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Mints a one-of-one receipt NFT for a completed sale to its buyer. Optional: clients
+    /// append it to the settlement transaction when the buyer wants a certificate.
+    pub fn mint_settlement_receipt(ctx: Context<MintSettlementReceipt>) -> Result<()> {
+        let history = &ctx.accounts.transaction_history;
+        let receipt_config = &ctx.accounts.receipt_config;
+        let clock = Clock::get()?;
+
+        let marketplace_key = ctx.accounts.marketplace.key();
+        let authority_seeds: &[&[u8]] = &[
+            b"receipt_authority",
+            marketplace_key.as_ref(),
+            &[receipt_config.authority_bump],
+        ];
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.buyer_receipt_account.to_account_info(),
+                    authority: ctx.accounts.receipt_authority.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            1,
+        )?;
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.receipt_metadata.to_account_info(),
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    mint_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    payer: ctx.accounts.buyer.to_account_info(),
+                    update_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            DataV2 {
+                name: format!("Sale Receipt #{}", history.transaction_index),
+                symbol: RECEIPT_SYMBOL.to_string(),
+                uri: format!(
+                    "{}?history={}&price={}&date={}",
+                    receipt_config.base_uri,
+                    history.key(),
+                    history.price,
+                    history.timestamp
+                ),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: Some(Collection {
+                    verified: false,
+                    key: receipt_config.collection_mint,
+                }),
+                uses: None,
+            },
+            false,
+            true,
+            None,
+        )?;
+
+        create_master_edition_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: ctx.accounts.receipt_master_edition.to_account_info(),
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    update_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    mint_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    payer: ctx.accounts.buyer.to_account_info(),
+                    metadata: ctx.accounts.receipt_metadata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            Some(0),
+        )?;
+
+        verify_sized_collection_item(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                VerifySizedCollectionItem {
+                    payer: ctx.accounts.buyer.to_account_info(),
+                    metadata: ctx.accounts.receipt_metadata.to_account_info(),
+                    collection_authority: ctx.accounts.receipt_authority.to_account_info(),
+                    collection_mint: ctx.accounts.collection_mint.to_account_info(),
+                    collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    collection_master_edition: ctx.accounts.collection_master_edition.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            None,
+        )?;
+
+        emit!(SettlementReceiptMinted {
+            transaction_history: history.key(),
+            property: history.property,
+            buyer: history.buyer,
+            receipt_mint: ctx.accounts.receipt_mint.key(),
+            price: history.price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn register_lienholder(ctx: Context<RegisterLienholder>, lienholder: Pubkey) -> Result<()> {
+        let lienholder_account = &mut ctx.accounts.lienholder_account;
+        let clock = Clock::get()?;
+
+        lienholder_account.marketplace = ctx.accounts.marketplace.key();
+        lienholder_account.lienholder = lienholder;
+        lienholder_account.registered_at = clock.unix_timestamp;
+
+        emit!(LienholderRegistered {
+            marketplace: lienholder_account.marketplace,
+            lienholder,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_lienholder(ctx: Context<RemoveLienholder>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        emit!(LienholderRemoved {
+            marketplace: ctx.accounts.marketplace.key(),
+            lienholder: ctx.accounts.lienholder_account.lienholder,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn attach_lien(
+        ctx: Context<AttachLien>,
+        amount: u64,
+        document_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidLien);
+        require!(document_hash != [0; 32], ErrorCode::InvalidLien);
+
+        let property = &mut ctx.accounts.property;
+        let lien = &mut ctx.accounts.lien;
+        let clock = Clock::get()?;
+
+        require!(property.merged_into.is_none(), ErrorCode::PropertyMerged);
+        require!(property.child_count == 0, ErrorCode::PropertySubdivided);
+
+        lien.property = property.key();
+        lien.lienholder = ctx.accounts.lienholder.key();
+        lien.amount = amount;
+        lien.document_hash = document_hash;
+        lien.created_at = clock.unix_timestamp;
+
+        property.lien_count = property
+            .lien_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(LienAttached {
+            lien: lien.key(),
+            property: property.key(),
+            lienholder: lien.lienholder,
+            amount,
+            document_hash,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Lienholder discharges a lien that was satisfied off-chain
+    pub fn release_lien(ctx: Context<ReleaseLien>) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let lien = &ctx.accounts.lien;
+        let clock = Clock::get()?;
+
+        property.lien_count = property
+            .lien_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(LienReleased {
+            lien: lien.key(),
+            property: property.key(),
+            lienholder: lien.lienholder,
+            amount: lien.amount,
+            paid_off: false,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn register_document(
+        ctx: Context<RegisterDocument>,
+        doc_type: DocumentType,
+        sha256_hash: [u8; 32],
+        uri: String,
+    ) -> Result<()> {
+        require!(sha256_hash != [0; 32], ErrorCode::InvalidDocument);
+        require!(!uri.is_empty(), ErrorCode::InvalidDocument);
+        require!(uri.len() <= MAX_DOCUMENT_URI_LEN, ErrorCode::MetadataUriTooLong);
+
+        let property = &mut ctx.accounts.property;
+        let document = &mut ctx.accounts.document;
+        let clock = Clock::get()?;
+
+        require!(property.merged_into.is_none(), ErrorCode::PropertyMerged);
+
+        document.property = property.key();
+        document.doc_type = doc_type;
+        document.sha256_hash = sha256_hash;
+        document.uri = uri;
+        document.registered_by = ctx.accounts.owner.key();
+        document.created_at = clock.unix_timestamp;
+
+        property.document_count = property
+            .document_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(DocumentRegistered {
+            document: document.key(),
+            property: property.key(),
+            doc_type,
+            sha256_hash,
+            uri: document.uri.clone(),
+            registered_by: document.registered_by,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Adds a photo, floor plan, tour or other media file to the property's gallery, identified by
+    /// the hash of its URI
+    pub fn add_media_item(
+        ctx: Context<AddMediaItem>,
+        kind: MediaKind,
+        uri_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(uri_hash != [0; 32], ErrorCode::InvalidMediaItem);
+
+        let property = &ctx.accounts.property;
+        let media = &mut ctx.accounts.property_media;
+        let clock = Clock::get()?;
+
+        require!(property.merged_into.is_none(), ErrorCode::PropertyMerged);
+        require!(
+            !media.items.iter().any(|item| item.uri_hash == uri_hash),
+            ErrorCode::InvalidMediaItem
+        );
+        require!(media.items.len() < MAX_MEDIA_ITEMS, ErrorCode::MediaLimitReached);
+
+        media.property = property.key();
+        media.items.push(MediaItem { kind, uri_hash });
+        media.updated_at = clock.unix_timestamp;
+
+        emit!(PropertyMediaUpdated {
+            property: property.key(),
+            owner: property.owner,
+            items: media.items.clone(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_media_item(ctx: Context<RemoveMediaItem>, uri_hash: [u8; 32]) -> Result<()> {
+        let property = &ctx.accounts.property;
+        let media = &mut ctx.accounts.property_media;
+        let clock = Clock::get()?;
+
+        let position = media
+            .items
+            .iter()
+            .position(|item| item.uri_hash == uri_hash)
+            .ok_or(ErrorCode::MediaItemNotFound)?;
+        media.items.remove(position);
+        media.updated_at = clock.unix_timestamp;
+
+        emit!(PropertyMediaUpdated {
+            property: property.key(),
+            owner: property.owner,
+            items: media.items.clone(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Grows the property account, paid by the owner, so it can hold a metadata URI of
+    /// up to `metadata_uri_capacity` bytes along with every field of the current layout
+    pub fn resize_property(ctx: Context<ResizeProperty>, metadata_uri_capacity: u16) -> Result<()> {
+        let property = &ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        emit!(PropertyResized {
+            property: property.key(),
+            owner: property.owner,
+            metadata_uri_capacity,
+            space: property.to_account_info().data_len() as u64,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stores content hashes in place of the metadata URI and location. On a full listing this
+    /// converts it to slim, shrinking the account and refunding the freed rent to the owner; on a
+    /// slim one it replaces the hashes after the off-chain metadata changes.
+    pub fn set_content_hashes(
+        ctx: Context<SetContentHashes>,
+        content_hashes: ContentHashes,
+    ) -> Result<()> {
+        validate_content_hashes(&content_hashes)?;
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        property.metadata_uri = String::new();
+        property.location = String::new();
+        property.content_hashes = Some(content_hashes);
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(ContentHashesSet {
+            property: property.key(),
+            owner: property.owner,
+            metadata_hash: content_hashes.metadata_hash,
+            location_hash: content_hashes.location_hash,
+            space: property.to_account_info().data_len() as u64,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrades a marketplace account to the current layout. Permissionless; the payer
+    /// covers any extra rent. `creator` is the authority the marketplace was initialized by, which
+    /// its address was derived from.
+    pub fn migrate_marketplace(ctx: Context<MigrateMarketplace>, creator: Pubkey) -> Result<()> {
+        let info = ctx.accounts.marketplace.to_account_info();
+        let (from_version, mut upgraded) = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Marketplace::DISCRIMINATOR,
+                ErrorCode::InvalidMigration
+            );
+            // Older accounts end before the listing fee or the bump; pad them so those read as zero
+            let mut padded = data.to_vec();
+            padded.extend_from_slice(&[0u8; 9]);
+            match Marketplace::try_deserialize(&mut &padded[..]) {
+                Ok(current) => {
+                    require!(current.version < MARKETPLACE_VERSION, ErrorCode::AlreadyMigrated);
+                    (current.version, Marketplace { version: MARKETPLACE_VERSION, ..current })
+                }
+                Err(_) => match MarketplaceV1::deserialize(&mut &data[8..]) {
+                    Ok(v1) if v1.version == 1 => (1, v1.upgrade()),
+                    _ => (0, LegacyMarketplace::deserialize(&mut &data[8..])?.upgrade()),
+                },
+            }
+        };
+        upgraded.bump = pda_bump(&[b"marketplace", creator.as_ref()], info.key)?;
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            &upgraded,
+            8 + Marketplace::INIT_SPACE,
+        )?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: MARKETPLACE_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_property(ctx: Context<MigrateProperty>) -> Result<()> {
+        let info = ctx.accounts.property.to_account_info();
+        let (from_version, mut upgraded) = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Property::DISCRIMINATOR,
+                ErrorCode::InvalidMigration
+            );
+            // Older accounts end before the open offer counter, the approval authority, the
+            // energy fields, the bump or the content hashes; pad them so those read as zero, None
+            // and unrated
+            let mut padded = data.to_vec();
+            padded.extend_from_slice(&[0u8; 17]);
+            match Property::try_deserialize(&mut &padded[..]) {
+                Ok(current) => {
+                    require!(current.version < PROPERTY_VERSION, ErrorCode::AlreadyMigrated);
+                    (current.version, Property { version: PROPERTY_VERSION, ..current })
+                }
+                Err(_) => (0, LegacyProperty::deserialize(&mut &data[8..])?.upgrade()),
+            }
+        };
+        upgraded.bump = pda_bump(
+            &[b"property", upgraded.marketplace.as_ref(), upgraded.property_id.as_bytes()],
+            info.key,
+        )?;
+
+        let space = property_space(upgraded.metadata_uri.len().max(INITIAL_METADATA_URI_LEN));
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            &upgraded,
+            space,
+        )?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: PROPERTY_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_offer(ctx: Context<MigrateOffer>) -> Result<()> {
+        let info = ctx.accounts.offer.to_account_info();
+        let (from_version, mut upgraded) = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Offer::DISCRIMINATOR,
+                ErrorCode::InvalidMigration
+            );
+            // Version 1 accounts end before the bump; pad them so it reads as zero
+            let mut padded = data.to_vec();
+            padded.push(0);
+            match Offer::try_deserialize(&mut &padded[..]) {
+                Ok(current) => {
+                    require!(current.version < OFFER_VERSION, ErrorCode::AlreadyMigrated);
+                    (current.version, Offer { version: OFFER_VERSION, ..current })
+                }
+                Err(_) => (0, LegacyOffer::deserialize(&mut &data[8..])?.upgrade()),
+            }
+        };
+        // Offers made before nonces were seeded without one, including those already upgraded
+        let nonce_bytes = upgraded.nonce.to_le_bytes();
+        let (nonce_key, nonce_bump) = Pubkey::find_program_address(
+            &[b"offer", upgraded.property.as_ref(), upgraded.buyer.as_ref(), &nonce_bytes],
+            &crate::ID,
+        );
+        upgraded.bump = if nonce_key == info.key() {
+            nonce_bump
+        } else {
+            pda_bump(
+                &[b"offer", upgraded.property.as_ref(), upgraded.buyer.as_ref()],
+                info.key,
+            )?
+        };
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            &upgraded,
+            8 + Offer::INIT_SPACE,
+        )?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: OFFER_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stores the bump on an escrow created before escrows kept it. Escrows carry no version, so
+    /// the old layout is recognized by its length.
+    pub fn migrate_escrow(ctx: Context<MigrateEscrow>) -> Result<()> {
+        let info = ctx.accounts.escrow.to_account_info();
+        let mut upgraded = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Escrow::DISCRIMINATOR,
+                ErrorCode::InvalidMigration
+            );
+            require!(data.len() < 8 + Escrow::INIT_SPACE, ErrorCode::AlreadyMigrated);
+            let mut padded = data.to_vec();
+            padded.push(0);
+            Escrow::try_deserialize(&mut &padded[..])?
+        };
+        upgraded.bump = pda_bump(&[b"escrow", upgraded.offer.as_ref()], info.key)?;
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            &upgraded,
+            8 + Escrow::INIT_SPACE,
+        )?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version: 0,
+            to_version: 1,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets how long sellers have to answer an offer and the bond they forfeit to the buyer if they don't.
+    /// A zero window disables the rule.
+    pub fn set_response_rule(
+        ctx: Context<SetResponseRule>,
+        response_window_secs: i64,
+        bond_lamports: u64,
+    ) -> Result<()> {
+        require!(response_window_secs >= 0, ErrorCode::InvalidResponseRule);
+        require!(
+            response_window_secs > 0 || bond_lamports == 0,
+            ErrorCode::InvalidResponseRule
+        );
+
+        let rule = &mut ctx.accounts.response_rule;
+        let clock = Clock::get()?;
+
+        rule.marketplace = ctx.accounts.marketplace.key();
+        rule.response_window_secs = response_window_secs;
+        rule.bond_lamports = bond_lamports;
+        rule.updated_at = clock.unix_timestamp;
+
+        emit!(ResponseRuleUpdated {
+            marketplace: rule.marketplace,
+            response_window_secs,
+            bond_lamports,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_seller_bond(ctx: Context<DepositSellerBond>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidSellerBond);
+
+        let seller_bond = &mut ctx.accounts.seller_bond;
+        let clock = Clock::get()?;
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.owner.key(),
+            &seller_bond.key(),
+            amount,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                seller_bond.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        seller_bond.property = ctx.accounts.property.key();
+        seller_bond.owner = ctx.accounts.owner.key();
+        seller_bond.updated_at = clock.unix_timestamp;
+
+        emit!(SellerBondUpdated {
+            seller_bond: seller_bond.key(),
+            property: seller_bond.property,
+            owner: seller_bond.owner,
+            available: seller_bond_available(seller_bond)?,
+            reserved: seller_bond.reserved,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns bond lamports that are not reserved for offers still inside their response window
+    pub fn withdraw_seller_bond(ctx: Context<WithdrawSellerBond>, amount: u64) -> Result<()> {
+        let seller_bond = &mut ctx.accounts.seller_bond;
+        let clock = Clock::get()?;
+
+        require!(amount > 0, ErrorCode::InvalidSellerBond);
+        require!(
+            amount <= seller_bond_available(seller_bond)?,
+            ErrorCode::SellerBondReserved
+        );
+
+        **seller_bond.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.owner.to_account_info().try_borrow_mut_lamports()? += amount;
+        seller_bond.updated_at = clock.unix_timestamp;
+
+        emit!(SellerBondUpdated {
+            seller_bond: seller_bond.key(),
+            property: seller_bond.property,
+            owner: seller_bond.owner,
+            available: seller_bond_available(seller_bond)?,
+            reserved: seller_bond.reserved,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Starts the seller's response clock for a pending offer and reserves up to the rule's bond
+    /// from the seller's bond account. Buyers send this alongside make_offer.
+    pub fn lock_response_bond(ctx: Context<LockResponseBond>) -> Result<()> {
+        let rule = &ctx.accounts.response_rule;
+        let offer = &ctx.accounts.offer;
+        let response_bond = &mut ctx.accounts.response_bond;
+        let clock = Clock::get()?;
+
+        require!(rule.response_window_secs > 0, ErrorCode::ResponseRuleDisabled);
+        require!(offer.status == OfferStatus::Pending, ErrorCode::OfferNotPending);
+
+        let deadline = offer
+            .created_at
+            .checked_add(rule.response_window_secs)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(clock.unix_timestamp < deadline, ErrorCode::ResponseWindowClosed);
+
+        let (seller_bond_key, amount) = match ctx.accounts.seller_bond.as_mut() {
+            Some(seller_bond) => {
+                let amount = rule.bond_lamports.min(seller_bond_available(seller_bond)?);
+                seller_bond.reserved = seller_bond
+                    .reserved
+                    .checked_add(amount)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+                seller_bond.updated_at = clock.unix_timestamp;
+                (seller_bond.key(), amount)
+            }
+            None => (Pubkey::default(), 0),
+        };
+
+        response_bond.offer = offer.key();
+        response_bond.buyer = offer.buyer;
+        response_bond.seller_bond = seller_bond_key;
+        response_bond.amount = amount;
+        response_bond.deadline = deadline;
+
+        emit!(ResponseBondLocked {
+            offer: offer.key(),
+            property: offer.property,
+            buyer: offer.buyer,
+            amount,
+            deadline,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Frees the seller's reservation once the offer has been answered
+    pub fn release_response_bond(ctx: Context<ReleaseResponseBond>) -> Result<()> {
+        require!(
+            ctx.accounts.offer.status != OfferStatus::Pending,
+            ErrorCode::OfferStillPending
+        );
+
+        unreserve_seller_bond(
+            &ctx.accounts.response_bond,
+            ctx.accounts.seller_bond.as_mut(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Cancels an offer the seller left unanswered past the response deadline. The buyer gets the
+    /// escrowed amount back plus the reserved seller bond.
+    pub fn claim_unanswered_offer(ctx: Context<ClaimUnansweredOffer>) -> Result<()> {
+        let offer = &mut ctx.accounts.offer;
+        let escrow = &ctx.accounts.escrow;
+        let response_bond = &ctx.accounts.response_bond;
+        let clock = Clock::get()?;
+
+        require!(offer.status == OfferStatus::Pending, ErrorCode::OfferNotPending);
+        require!(
+            clock.unix_timestamp >= response_bond.deadline,
+            ErrorCode::ResponseWindowOpen
+        );
+
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.buyer.to_account_info(), escrow.amount)?;
+
+        unreserve_seller_bond(response_bond, ctx.accounts.seller_bond.as_mut())?;
+        if let Some(seller_bond) = ctx.accounts.seller_bond.as_ref() {
+            **seller_bond.to_account_info().try_borrow_mut_lamports()? -= response_bond.amount;
+            **ctx.accounts.buyer.to_account_info().try_borrow_mut_lamports()? += response_bond.amount;
+        }
+
+        offer.status = OfferStatus::Expired;
+        offer.updated_at = clock.unix_timestamp;
+        close_offer(&mut ctx.accounts.property);
+
+        emit!(UnansweredOfferClaimed {
+            offer: offer.key(),
+            property: offer.property,
+            buyer: offer.buyer,
+            refunded: escrow.amount,
+            bond_paid: response_bond.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Brings a property's history index up to date for sales that don't carry it, such as bundle
+    /// settlements and sales made before the index existed. Anyone can pay for this.
+    pub fn sync_history_index(ctx: Context<SyncHistoryIndex>) -> Result<()> {
+        let property = &ctx.accounts.property;
+        let history_index = &mut ctx.accounts.history_index;
+
+        history_index.property = property.key();
+        history_index.count = property.transaction_count;
+        history_index.latest_history = if property.transaction_count == 0 {
+            Pubkey::default()
+        } else {
+            Pubkey::find_program_address(
+                &[
+                    b"transaction",
+                    property.key().as_ref(),
+                    &property.transaction_count.to_le_bytes(),
+                ],
+                &crate::ID,
+            )
+            .0
+        };
+        history_index.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Refunds a pending offer once it has passed its expiration time. Anyone can run this, so
+    /// keepers can clear expired offers without the seller.
+    pub fn expire_offer(ctx: Context<ExpireOffer>) -> Result<()> {
+        let offer = &mut ctx.accounts.offer;
+        let escrow = &ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(offer.status == OfferStatus::Pending, ErrorCode::OfferNotPending);
+        require!(
+            offer.expiration_time <= clock.unix_timestamp,
+            ErrorCode::OfferNotExpired
+        );
+
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.buyer.to_account_info(), escrow.amount)?;
+
+        offer.status = OfferStatus::Expired;
+        offer.updated_at = clock.unix_timestamp;
+        close_offer(&mut ctx.accounts.property);
+
+        emit!(OfferExpired {
+            offer: offer.key(),
+            property: offer.property,
+            buyer: offer.buyer,
+            seller: ctx.accounts.property.owner,
+            amount: offer.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Assigns an accepted offer to `new_buyer` before closing, as wholesalers assign contracts.
+    /// The assignee reimburses the original buyer's escrowed deposit plus `assignment_fee` and
+    /// takes over the escrow, so settlement delivers the NFT to them. Offers carrying title
+    /// insurance can't be assigned since the policy is the original buyer's.
+    pub fn assign_offer(
+        ctx: Context<AssignOffer>,
+        new_buyer: Pubkey,
+        assignment_fee: u64,
+    ) -> Result<()> {
+        let offer = &mut ctx.accounts.offer;
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            offer.status == OfferStatus::Accepted,
+            ErrorCode::OfferNotAccepted
+        );
+        require!(
+            new_buyer != offer.buyer && new_buyer != ctx.accounts.property.owner,
+            ErrorCode::InvalidAssignment
+        );
+        require!(
+            ctx.accounts.title_insurance.owner != &crate::ID,
+            ErrorCode::InvalidAssignment
+        );
+
+        let reimbursed = escrow
+            .amount
+            .checked_add(assignment_fee)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.assignee.to_account_info(),
+                    to: ctx.accounts.buyer.to_account_info(),
+                },
+            ),
+            reimbursed,
+        )?;
+
+        // Milestone confirmations move to the assignee along with the escrow
+        let schedule_info = ctx.accounts.release_schedule.to_account_info();
+        if schedule_info.owner == &crate::ID {
+            let mut schedule = {
+                let data = schedule_info.try_borrow_data()?;
+                ReleaseSchedule::try_deserialize(&mut &data[..])?
+            };
+            schedule.buyer = new_buyer;
+            let mut data = schedule_info.try_borrow_mut_data()?;
+            schedule.try_serialize(&mut &mut data[..])?;
+        }
+
+        let original_buyer = offer.buyer;
+        offer.buyer = new_buyer;
+        offer.updated_at = clock.unix_timestamp;
+        escrow.buyer = new_buyer;
+
+        let assignment = &mut ctx.accounts.offer_assignment;
+        assignment.offer = offer.key();
+        assignment.original_buyer = original_buyer;
+        assignment.assignee = new_buyer;
+        assignment.reimbursed = escrow.amount;
+        assignment.assignment_fee = assignment_fee;
+        assignment.assigned_at = clock.unix_timestamp;
+
+        emit!(OfferAssigned {
+            offer: offer.key(),
+            property: offer.property,
+            original_buyer,
+            new_buyer,
+            reimbursed: escrow.amount,
+            assignment_fee,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Owners opt a property into association approval; from then on only the association can
+    /// change or drop the requirement
+    pub fn set_approval_authority(
+        ctx: Context<SetApprovalAuthority>,
+        approval_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let signer = ctx.accounts.authority.key();
+        let clock = Clock::get()?;
+
+        match property.approval_authority {
+            Some(current) => require_keys_eq!(signer, current, ErrorCode::NotApprovalAuthority),
+            None => require_keys_eq!(signer, property.owner, ErrorCode::NotPropertyOwner),
+        }
+        require!(
+            !(approval_authority.is_some() && property.auto_accept),
+            ErrorCode::TransferNotApproved
+        );
+
+        let previous = property.approval_authority;
+        property.approval_authority = approval_authority;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(ApprovalAuthorityUpdated {
+            property: property.key(),
+            previous,
+            approval_authority,
+            updated_by: signer,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Association sign-off on selling to the offer's buyer at the offer's amount. Approving again
+    /// after a counter or an assignment replaces the earlier approval.
+    pub fn approve_transfer(ctx: Context<ApproveTransfer>) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let clock = Clock::get()?;
+
+        require!(
+            ctx.accounts.property.approval_authority == Some(ctx.accounts.authority.key()),
+            ErrorCode::NotApprovalAuthority
+        );
+        require!(
+            offer.status == OfferStatus::Pending || offer.status == OfferStatus::Accepted,
+            ErrorCode::OfferNotPending
+        );
+
+        let approval = &mut ctx.accounts.transfer_approval;
+        approval.property = offer.property;
+        approval.offer = offer.key();
+        approval.authority = ctx.accounts.authority.key();
+        approval.buyer = offer.buyer;
+        approval.price = offer.amount;
+        approval.approved_at = clock.unix_timestamp;
+
+        emit!(TransferApproved {
+            property: offer.property,
+            offer: offer.key(),
+            authority: approval.authority,
+            buyer: offer.buyer,
+            price: offer.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Marketplace authority override for offers nobody else can unwind, e.g. after bad metadata
+    /// or a compromised seller wallet. The buyer gets back whatever the escrow holds, an escrowed
+    /// NFT goes back to the property owner, and the offer is cancelled.
+    pub fn force_cancel_offer(ctx: Context<ForceCancelOffer>, reason: InterventionReason) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let offer = &mut ctx.accounts.offer;
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            offer.status == OfferStatus::Pending || offer.status == OfferStatus::Accepted,
+            ErrorCode::OfferNotCancellable
+        );
+
+        // A short escrow is exactly the kind of incident this unwinds, so refund what is there
+        let refunded = escrowed_lamports(&escrow.to_account_info())?.min(escrow.amount);
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.buyer.to_account_info(), refunded)?;
+        escrow.amount = 0;
+
+        let nft_returned = escrow.nft_held;
+        if escrow.nft_held {
+            let escrow_nft_account = ctx
+                .accounts
+                .escrow_nft_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidTokenAccount)?;
+            let seller_nft_account = ctx
+                .accounts
+                .seller_nft_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidTokenAccount)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_nft_account.to_account_info(),
+                        to: seller_nft_account.to_account_info(),
+                        authority: escrow.to_account_info(),
+                    },
+                    &[&[b"escrow", offer.key().as_ref(), &[escrow.bump]]],
+                ),
+                1,
+            )?;
+            escrow.nft_held = false;
+        }
+
+        offer.status = OfferStatus::Cancelled;
+        offer.updated_at = clock.unix_timestamp;
+        close_offer(property);
+
+        emit!(AdminIntervention {
+            marketplace: ctx.accounts.marketplace.key(),
+            authority: ctx.accounts.authority.key(),
+            offer: offer.key(),
+            property: property.key(),
+            buyer: offer.buyer,
+            seller: property.owner,
+            refunded,
+            nft_returned,
+            reason,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the share of earnest money a buyer forfeits to the seller when financing falls through
+    pub fn set_financing_penalty(ctx: Context<SetFinancingRule>, penalty_bps: u16) -> Result<()> {
+        require!(penalty_bps <= 10000, ErrorCode::InvalidFinancingPenalty);
+
+        let rule = &mut ctx.accounts.financing_rule;
+        let clock = Clock::get()?;
+
+        rule.marketplace = ctx.accounts.marketplace.key();
+        rule.penalty_bps = penalty_bps;
+        rule.updated_at = clock.unix_timestamp;
+
+        emit!(FinancingRuleUpdated {
+            marketplace: rule.marketplace,
+            penalty_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Makes an offer backed only by earnest money. The buyer must escrow the rest of the price
+    /// through `fund_financed_offer` before `financing_deadline` or the sale can be cancelled.
+    pub fn make_financed_offer(
+        ctx: Context<MakeFinancedOffer>,
+        offer_amount: u64,
+        earnest_amount: u64,
+        expiration_time: i64,
+        offer_nonce: u64,
+        financing_deadline: i64,
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let offer = &mut ctx.accounts.offer;
+        let escrow = &mut ctx.accounts.escrow;
+        let financing = &mut ctx.accounts.financing;
+        let clock = Clock::get()?;
+
+        check_offer_terms(
+            property,
+            &ctx.accounts.marketplace,
+            ctx.accounts.price_feed.as_deref(),
+            &clock,
+            offer_amount,
+            expiration_time,
+        )?;
+        open_offer(property, &ctx.accounts.offer_limits)?;
+        require!(
+            earnest_amount > 0 && earnest_amount < offer_amount,
+            ErrorCode::InvalidEarnestAmount
+        );
+        require!(
+            financing_deadline > clock.unix_timestamp,
+            ErrorCode::InvalidFinancingDeadline
+        );
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.buyer.key(),
+            &escrow.key(),
+            earnest_amount,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        offer.buyer = ctx.accounts.buyer.key();
+        offer.property = property.key();
+        offer.amount = offer_amount;
+        offer.status = OfferStatus::Pending;
+        offer.created_at = clock.unix_timestamp;
+        offer.updated_at = clock.unix_timestamp;
+        offer.expiration_time = expiration_time;
+        offer.escrow = escrow.key();
+        offer.nonce = offer_nonce;
+        offer.version = OFFER_VERSION;
+        offer.bump = ctx.bumps.offer;
+
+        escrow.offer = offer.key();
+        escrow.property = property.key();
+        escrow.buyer = ctx.accounts.buyer.key();
+        escrow.seller = property.owner;
+        escrow.amount = earnest_amount;
+        escrow.nft_held = false;
+        escrow.created_at = clock.unix_timestamp;
+        escrow.bump = ctx.bumps.escrow;
+
+        financing.offer = offer.key();
+        financing.buyer = offer.buyer;
+        financing.earnest_amount = earnest_amount;
+        financing.financing_deadline = financing_deadline;
+        // Snapshot the penalty so later rule changes don't alter agreed terms
+        financing.penalty_bps = ctx
+            .accounts
+            .financing_rule
+            .as_ref()
+            .map_or(0, |rule| rule.penalty_bps);
+        financing.funded = false;
+
+        emit!(OfferCreated {
+            offer: offer.key(),
+            marketplace: property.marketplace,
+            property: property.key(),
+            buyer: offer.buyer,
+            amount: offer_amount,
+            nonce: offer_nonce,
+            timestamp: clock.unix_timestamp,
+        });
+        emit!(FinancingContingencyCreated {
+            offer: offer.key(),
+            earnest_amount,
+            financing_deadline,
+            penalty_bps: financing.penalty_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Escrows the financed balance of an offer, clearing its financing contingency
+    pub fn fund_financed_offer(ctx: Context<FundFinancedOffer>) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let escrow = &mut ctx.accounts.escrow;
+        let financing = &mut ctx.accounts.financing;
+        let clock = Clock::get()?;
+
+        require!(
+            offer.status == OfferStatus::Pending || offer.status == OfferStatus::Accepted,
+            ErrorCode::OfferNotPending
+        );
+        require!(!financing.funded, ErrorCode::OfferAlreadyFunded);
+        require!(
+            clock.unix_timestamp < financing.financing_deadline,
+            ErrorCode::FinancingDeadlinePassed
+        );
+
+        let balance = offer
+            .amount
+            .checked_sub(escrow.amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.buyer.key(),
+            &escrow.key(),
+            balance,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        escrow.amount = offer.amount;
+        financing.funded = true;
+
+        emit!(FinancedOfferFunded {
+            offer: offer.key(),
+            amount: balance,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Unwinds an accepted offer whose buyer missed the financing deadline. Either party can run
+    /// it: the NFT goes back to the seller, the seller keeps the penalty share of the earnest
+    /// money and the buyer gets the rest.
+    pub fn cancel_for_financing(ctx: Context<CancelForFinancing>) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let offer = &mut ctx.accounts.offer;
+        let escrow = &mut ctx.accounts.escrow;
+        let financing = &ctx.accounts.financing;
+        let clock = Clock::get()?;
+
+        let caller = ctx.accounts.caller.key();
+        require!(
+            caller == offer.buyer || caller == property.owner,
+            ErrorCode::NotOfferParty
+        );
+        require!(offer.status == OfferStatus::Accepted, ErrorCode::OfferNotAccepted);
+        require!(!financing.funded, ErrorCode::OfferAlreadyFunded);
+        require!(
+            clock.unix_timestamp >= financing.financing_deadline,
+            ErrorCode::FinancingDeadlineNotReached
+        );
+
+        if escrow.nft_held {
+            let offer_key = offer.key();
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.escrow_nft_account.to_account_info(),
+                        to: ctx.accounts.seller_nft_account.to_account_info(),
+                        authority: escrow.to_account_info(),
+                    },
+                    &[&[b"escrow", offer_key.as_ref(), &[escrow.bump]]],
+                ),
+                1,
+            )?;
+            escrow.nft_held = false;
+        }
+
+        let penalty = (escrow.amount as u128)
+            .checked_mul(financing.penalty_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::ArithmeticOverflow)? as u64;
+        let refund = escrow
+            .amount
+            .checked_sub(penalty)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.seller.to_account_info(), penalty)?;
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.buyer.to_account_info(), refund)?;
+        escrow.amount = 0;
+
+        offer.status = OfferStatus::Rejected;
+        offer.updated_at = clock.unix_timestamp;
+        close_offer(property);
+
+        emit!(FinancingCancelled {
+            offer: offer.key(),
+            property: property.key(),
+            cancelled_by: caller,
+            penalty,
+            refunded: refund,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_transaction_history(ctx: Context<MigrateTransactionHistory>) -> Result<()> {
+        let info = ctx.accounts.transaction_history.to_account_info();
+        let (from_version, upgraded) = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == TransactionHistory::DISCRIMINATOR,
+                ErrorCode::InvalidMigration
+            );
+            // Older records end before the title policy hash or the transfer approval; pad them so
+            // those read as None
+            let mut padded = data.to_vec();
+            padded.extend_from_slice(&[0u8; 2]);
+            match TransactionHistory::try_deserialize(&mut &padded[..]) {
+                Ok(current) => {
+                    require!(
+                        current.version < TRANSACTION_HISTORY_VERSION,
+                        ErrorCode::AlreadyMigrated
+                    );
+                    (
+                        current.version,
+                        TransactionHistory { version: TRANSACTION_HISTORY_VERSION, ..current },
+                    )
+                }
+                Err(_) => (0, LegacyTransactionHistory::deserialize(&mut &data[8..])?.upgrade()),
+            }
+        };
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            &upgraded,
+            8 + TransactionHistory::INIT_SPACE,
+        )?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: TRANSACTION_HISTORY_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Splits the seller's payout into milestone tranches released by the buyer or an arbiter,
+    /// e.g. `[3000, 3000, 4000]` for 30/30/40. Set by the buyer while the offer is pending, so the
+    /// seller accepts on these terms.
+    pub fn set_release_schedule(
+        ctx: Context<SetReleaseSchedule>,
+        tranche_bps: Vec<u16>,
+        arbiter: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.offer.status == OfferStatus::Pending,
+            ErrorCode::OfferNotPending
+        );
+        require!(
+            !tranche_bps.is_empty()
+                && tranche_bps.len() <= MAX_MILESTONES
+                && tranche_bps.iter().all(|&bps| bps > 0)
+                && tranche_bps.iter().map(|&bps| bps as u32).sum::<u32>() == 10000,
+            ErrorCode::InvalidReleaseSchedule
+        );
+
+        let schedule = &mut ctx.accounts.release_schedule;
+        schedule.escrow = ctx.accounts.escrow.key();
+        schedule.buyer = ctx.accounts.buyer.key();
+        schedule.seller = Pubkey::default();
+        schedule.arbiter = arbiter;
+        schedule.tranche_bps = tranche_bps.clone();
+        schedule.total_amount = 0;
+        schedule.released_amount = 0;
+        schedule.released_count = 0;
+        schedule.funded = false;
+
+        emit!(ReleaseScheduleSet {
+            offer: ctx.accounts.offer.key(),
+            escrow: schedule.escrow,
+            tranche_bps,
+            arbiter,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the seller the next tranche of a settled sale held under a release schedule
+    pub fn release_milestone(ctx: Context<ReleaseMilestone>) -> Result<()> {
+        let schedule = &mut ctx.accounts.release_schedule;
+        let escrow = &ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        let approver = ctx.accounts.approver.key();
+        require!(
+            approver == schedule.buyer || schedule.arbiter == Some(approver),
+            ErrorCode::NotMilestoneApprover
+        );
+        require!(schedule.funded, ErrorCode::ReleaseScheduleNotFunded);
+        let milestone = schedule.released_count as usize;
+        require!(
+            milestone < schedule.tranche_bps.len(),
+            ErrorCode::AllMilestonesReleased
+        );
+
+        // The last tranche takes the rounding remainder
+        let amount = if milestone + 1 == schedule.tranche_bps.len() {
+            schedule
+                .total_amount
+                .checked_sub(schedule.released_amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?
+        } else {
+            (schedule.total_amount as u128)
+                .checked_mul(schedule.tranche_bps[milestone] as u128)
+                .ok_or(ErrorCode::ArithmeticOverflow)?
+                .checked_div(10000)
+                .ok_or(ErrorCode::ArithmeticOverflow)? as u64
+        };
+
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.seller.to_account_info(), amount)?;
+
+        schedule.released_amount = schedule
+            .released_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        schedule.released_count += 1;
+
+        emit!(MilestoneReleased {
+            escrow: schedule.escrow,
+            milestone: milestone as u8,
+            amount,
+            released_by: approver,
+            remaining: schedule.total_amount - schedule.released_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Moves accrued fees out of the treasury. The treasury always keeps its rent-exempt minimum.
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64, recipient: Pubkey) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        let treasury_info = treasury.to_account_info();
+
+        let rent = Rent::get()?.minimum_balance(treasury_info.data_len());
+        let available = treasury_info.lamports().saturating_sub(rent);
+        require!(amount > 0 && amount <= available, ErrorCode::InsufficientTreasuryFunds);
+
+        **treasury_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.destination.try_borrow_mut_lamports()? = ctx
+            .accounts.destination
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        treasury.total_withdrawn = treasury
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(FeesWithdrawn {
+            marketplace: treasury.marketplace,
+            destination: recipient,
+            amount,
+            remaining: available - amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Queues a marketplace parameter change that anyone can execute once the timelock has passed.
+    /// The authority may be an SPL Governance PDA, in which case proposals call this.
+    pub fn queue_parameter_change(
+        ctx: Context<QueueParameterChange>,
+        change: ParameterChange,
+    ) -> Result<()> {
+        match change {
+            ParameterChange::SetFee { fee_bps } => {
+                require!(fee_bps <= 10000, ErrorCode::InvalidFeePercentage)
+            }
+            ParameterChange::SetTimelock { timelock_secs } => require!(
+                (0..=MAX_TIMELOCK_SECS).contains(&timelock_secs),
+                ErrorCode::InvalidTimelock
+            ),
+            _ => {}
+        }
+
+        let marketplace = &mut ctx.accounts.marketplace;
+        let queued_change = &mut ctx.accounts.queued_change;
+        let clock = Clock::get()?;
+
+        queued_change.marketplace = marketplace.key();
+        queued_change.index = marketplace.change_count;
+        queued_change.change = change.clone();
+        queued_change.queued_at = clock.unix_timestamp;
+        queued_change.eta = clock
+            .unix_timestamp
+            .checked_add(marketplace.timelock_secs)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        marketplace.change_count = marketplace
+            .change_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(ParameterChangeQueued {
+            marketplace: queued_change.marketplace,
+            index: queued_change.index,
+            change,
+            eta: queued_change.eta,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_parameter_change(ctx: Context<CancelParameterChange>) -> Result<()> {
+        emit!(ParameterChangeCancelled {
+            marketplace: ctx.accounts.marketplace.key(),
+            index: ctx.accounts.queued_change.index,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Applies a queued change once its timelock has passed. Verifier changes need `verifier_account`.
+    pub fn execute_parameter_change(ctx: Context<ExecuteParameterChange>) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        let queued_change = &ctx.accounts.queued_change;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= queued_change.eta,
+            ErrorCode::TimelockNotElapsed
+        );
+
+        match queued_change.change.clone() {
+            ParameterChange::SetFee { fee_bps } => marketplace.fee_percentage = fee_bps,
+            ParameterChange::SetPaused { paused } => marketplace.paused = paused,
+            ParameterChange::SetTimelock { timelock_secs } => marketplace.timelock_secs = timelock_secs,
+            ParameterChange::SetAuthority { authority } => marketplace.authority = authority,
+            ParameterChange::SetListingFee { listing_fee_lamports } => {
+                marketplace.listing_fee_lamports = listing_fee_lamports
+            }
+            ParameterChange::AddVerifier { verifier } => {
+                let verifier_account = ctx
+                    .accounts
+                    .verifier_account
+                    .as_mut()
+                    .ok_or(ErrorCode::VerifierAccountRequired)?;
+                verifier_account.marketplace = marketplace.key();
+                verifier_account.verifier = verifier;
+                verifier_account.registered_at = clock.unix_timestamp;
+
+                emit!(VerifierRegistered {
+                    marketplace: marketplace.key(),
+                    verifier,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+            ParameterChange::RemoveVerifier { verifier } => {
+                ctx.accounts
+                    .verifier_account
+                    .as_ref()
+                    .ok_or(ErrorCode::VerifierAccountRequired)?
+                    .close(ctx.accounts.authority.to_account_info())?;
+
+                emit!(VerifierRemoved {
+                    marketplace: marketplace.key(),
+                    verifier,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        emit!(ParameterChangeExecuted {
+            marketplace: marketplace.key(),
+            index: queued_change.index,
+            change: queued_change.change.clone(),
+            timestamp: clock.unix_timestamp,
+        });
+        if queued_change.change.verifier() == Pubkey::default() {
+            emit_config_updated(marketplace, clock.unix_timestamp);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the SPL token users stake for fee discounts and the discount tiers, lowest stake first
+    pub fn configure_staking(
+        ctx: Context<ConfigureStaking>,
+        tiers: Vec<StakeTier>,
+        min_lock_secs: i64,
+    ) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+        require!(tiers.len() <= MAX_STAKE_TIERS, ErrorCode::InvalidStakingConfig);
+        require!(min_lock_secs >= 0, ErrorCode::InvalidStakingConfig);
+        require!(
+            tiers.iter().all(|tier| tier.min_amount > 0 && tier.discount_bps <= 10000),
+            ErrorCode::InvalidStakingConfig
+        );
+        require!(
+            tiers.windows(2).all(|pair| pair[0].min_amount < pair[1].min_amount),
+            ErrorCode::InvalidStakingConfig
+        );
+
+        let staking_config = &mut ctx.accounts.staking_config;
+        let stake_mint = ctx.accounts.stake_mint.key();
+        // Stakes are held in the original mint, so it can't change once set
+        require!(
+            staking_config.stake_mint == Pubkey::default() || staking_config.stake_mint == stake_mint,
+            ErrorCode::StakeMintMismatch
+        );
+
+        staking_config.marketplace = ctx.accounts.marketplace.key();
+        staking_config.stake_mint = stake_mint;
+        staking_config.tiers = tiers.clone();
+        staking_config.min_lock_secs = min_lock_secs;
+
+        emit!(StakingConfigured {
+            marketplace: staking_config.marketplace,
+            stake_mint,
+            tiers,
+            min_lock_secs,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidStakeAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let stake_account = &mut ctx.accounts.stake_account;
+        let staking_config = &mut ctx.accounts.staking_config;
+        let clock = Clock::get()?;
+
+        stake_account.marketplace = staking_config.marketplace;
+        stake_account.owner = ctx.accounts.owner.key();
+        stake_account.amount = stake_account
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        // Topping up restarts the lock so a stake can't be raised just for one settlement
+        stake_account.staked_at = clock.unix_timestamp;
+        staking_config.total_staked = staking_config
+            .total_staked
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(Staked {
+            marketplace: stake_account.marketplace,
+            owner: stake_account.owner,
+            amount,
+            total: stake_account.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(amount > 0 && amount <= stake_account.amount, ErrorCode::InvalidStakeAmount);
+        require!(
+            clock.unix_timestamp
+                >= stake_account
+                    .staked_at
+                    .checked_add(ctx.accounts.staking_config.min_lock_secs)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?,
+            ErrorCode::StakeLocked
+        );
+
+        let marketplace_key = ctx.accounts.marketplace.key();
+        let config_seeds: &[&[u8]] = &[
+            b"staking_config",
+            marketplace_key.as_ref(),
+            &[ctx.bumps.staking_config],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.staking_config.to_account_info(),
+                },
+                &[config_seeds],
+            ),
+            amount,
+        )?;
+
+        stake_account.amount -= amount;
+        let staking_config = &mut ctx.accounts.staking_config;
+        staking_config.total_staked = staking_config
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(Unstaked {
+            marketplace: marketplace_key,
+            owner: stake_account.owner,
+            amount,
+            total: stake_account.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Locks the property NFT in a loan vault and opens a loan request for lenders to fund
+    pub fn request_loan(
+        ctx: Context<RequestLoan>,
+        principal: u64,
+        interest: u64,
+        duration_secs: i64,
+    ) -> Result<()> {
+        let property = &ctx.accounts.property;
+        require!(!property.is_active, ErrorCode::PropertyStillListed);
+        require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+        require!(!property.co_owned, ErrorCode::CoOwnedProperty);
+        require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+        require!(
+            principal > 0 && duration_secs > 0 && duration_secs <= MAX_LOAN_DURATION_SECS,
+            ErrorCode::InvalidLoanTerms
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.borrower_nft_account.to_account_info(),
+                    to: ctx.accounts.loan_nft_account.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let loan = &mut ctx.accounts.loan;
+        let clock = Clock::get()?;
+        loan.property = property.key();
+        loan.borrower = ctx.accounts.borrower.key();
+        loan.lender = Pubkey::default();
+        loan.nft_mint = property.nft_mint;
+        loan.principal = principal;
+        loan.interest = interest;
+        loan.duration_secs = duration_secs;
+        loan.requested_at = clock.unix_timestamp;
+        loan.funded_at = 0;
+        loan.due_at = 0;
+        loan.status = LoanStatus::Requested;
+
+        emit!(LoanRequested {
+            loan: loan.key(),
+            property: loan.property,
+            borrower: loan.borrower,
+            principal,
+            interest,
+            duration_secs,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraws an unfunded loan request and returns the NFT
+    pub fn cancel_loan_request(ctx: Context<CancelLoanRequest>) -> Result<()> {
+        require!(ctx.accounts.loan.status == LoanStatus::Requested, ErrorCode::LoanNotRequested);
+
+        release_loan_collateral(
+            &ctx.accounts.loan,
+            ctx.bumps.loan,
+            &ctx.accounts.loan_nft_account,
+            &ctx.accounts.borrower_nft_account,
+            ctx.accounts.borrower.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(LoanCancelled {
+            loan: ctx.accounts.loan.key(),
+            property: ctx.accounts.loan.property,
+            borrower: ctx.accounts.loan.borrower,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the principal to the borrower and starts the loan term
+    pub fn fund_loan(ctx: Context<FundLoan>) -> Result<()> {
+        let loan = &mut ctx.accounts.loan;
+        require!(loan.status == LoanStatus::Requested, ErrorCode::LoanNotRequested);
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.lender.to_account_info(),
+                    to: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            loan.principal,
+        )?;
+
+        let clock = Clock::get()?;
+        loan.lender = ctx.accounts.lender.key();
+        loan.funded_at = clock.unix_timestamp;
+        loan.due_at = clock
+            .unix_timestamp
+            .checked_add(loan.duration_secs)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        loan.status = LoanStatus::Active;
+
+        emit!(LoanFunded {
+            loan: loan.key(),
+            property: loan.property,
+            lender: loan.lender,
+            due_at: loan.due_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Repays principal plus interest to the lender and releases the NFT. Allowed until the
+    /// lender claims the collateral, even after the due date.
+    pub fn repay_loan(ctx: Context<RepayLoan>) -> Result<()> {
+        let loan = &ctx.accounts.loan;
+        require!(loan.status == LoanStatus::Active, ErrorCode::LoanNotActive);
+
+        let amount = loan
+            .principal
+            .checked_add(loan.interest)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.borrower.to_account_info(),
+                    to: ctx.accounts.lender.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        release_loan_collateral(
+            loan,
+            ctx.bumps.loan,
+            &ctx.accounts.loan_nft_account,
+            &ctx.accounts.borrower_nft_account,
+            ctx.accounts.borrower.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(LoanRepaid {
+            loan: loan.key(),
+            property: loan.property,
+            borrower: loan.borrower,
+            lender: loan.lender,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Transfers the collateral to the lender once an active loan is past due
+    pub fn claim_loan_collateral(ctx: Context<ClaimLoanCollateral>) -> Result<()> {
+        let loan = &ctx.accounts.loan;
+        let clock = Clock::get()?;
+        require!(loan.status == LoanStatus::Active, ErrorCode::LoanNotActive);
+        require!(clock.unix_timestamp > loan.due_at, ErrorCode::LoanNotDue);
+
+        release_loan_collateral(
+            loan,
+            ctx.bumps.loan,
+            &ctx.accounts.loan_nft_account,
+            &ctx.accounts.lender_nft_account,
+            ctx.accounts.borrower.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        let property = &mut ctx.accounts.property;
+        property.owner = loan.lender;
+        property.auto_accept = false;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(LoanCollateralClaimed {
+            loan: loan.key(),
+            property: property.key(),
+            borrower: loan.borrower,
+            lender: loan.lender,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Opens a time-boxed auction for the collateral of a past-due loan. Once liquidating, the
+    /// loan can no longer be repaid or claimed directly by the lender.
+    pub fn liquidate_loan(ctx: Context<LiquidateLoan>) -> Result<()> {
+        let loan = &mut ctx.accounts.loan;
+        let clock = Clock::get()?;
+        require!(loan.status == LoanStatus::Active, ErrorCode::LoanNotActive);
+        require!(clock.unix_timestamp > loan.due_at, ErrorCode::LoanNotDue);
+        loan.status = LoanStatus::Liquidating;
+
+        let auction = &mut ctx.accounts.auction;
+        auction.loan = loan.key();
+        auction.opened_by = ctx.accounts.caller.key();
+        auction.started_at = clock.unix_timestamp;
+        auction.ends_at = clock
+            .unix_timestamp
+            .checked_add(LIQUIDATION_AUCTION_SECS)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        auction.highest_bid = 0;
+        auction.highest_bidder = None;
+
+        // Snapshot the anti-sniping settings so a config change can't alter a running auction
+        let config_info = ctx.accounts.auction_config.to_account_info();
+        let (snipe_window_secs, extension_secs) = if config_info.owner == &crate::ID {
+            let data = config_info.try_borrow_data()?;
+            let config = AuctionConfig::try_deserialize(&mut &data[..])?;
+            (config.snipe_window_secs, config.extension_secs)
+        } else {
+            (DEFAULT_SNIPE_WINDOW_SECS, DEFAULT_AUCTION_EXTENSION_SECS)
+        };
+        auction.snipe_window_secs = snipe_window_secs;
+        auction.extension_secs = extension_secs;
+
+        emit!(LiquidationStarted {
+            auction: auction.key(),
+            loan: loan.key(),
+            property: loan.property,
+            ends_at: auction.ends_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Escrows a bid in the auction account and refunds the bidder it displaces
+    pub fn place_liquidation_bid(ctx: Context<PlaceLiquidationBid>, amount: u64) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp < auction.ends_at, ErrorCode::AuctionEnded);
+
+        let min_bid = auction
+            .highest_bid
+            .checked_mul(10000 + MIN_BID_INCREMENT_BPS)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / 10000;
+        require!(amount > 0 && amount > min_bid, ErrorCode::BidTooLow);
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.bidder.to_account_info(),
+                    to: ctx.accounts.auction.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        if let Some(previous_bidder) = auction.highest_bidder {
+            require_keys_eq!(
+                ctx.accounts.previous_bidder.key(),
+                previous_bidder,
+                ErrorCode::PreviousBidderMismatch
+            );
+            let refund = auction.highest_bid;
+            let auction_info = ctx.accounts.auction.to_account_info();
+            **auction_info.try_borrow_mut_lamports()? = auction_info
+                .lamports()
+                .checked_sub(refund)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            **ctx.accounts.previous_bidder.try_borrow_mut_lamports()? = ctx
+                .accounts
+                .previous_bidder
+                .lamports()
+                .checked_add(refund)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+
+        let auction = &mut ctx.accounts.auction;
+        auction.highest_bid = amount;
+        auction.highest_bidder = Some(ctx.accounts.bidder.key());
+
+        // A bid in the closing window pushes the end back so others get a chance to respond
+        if auction.ends_at - clock.unix_timestamp <= auction.snipe_window_secs {
+            let previous_ends_at = auction.ends_at;
+            auction.ends_at = auction
+                .ends_at
+                .checked_add(auction.extension_secs)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            emit!(AuctionExtended {
+                auction: auction.key(),
+                previous_ends_at,
+                ends_at: auction.ends_at,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        emit!(LiquidationBidPlaced {
+            auction: auction.key(),
+            bidder: ctx.accounts.bidder.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Settles an ended liquidation auction. The winning bid repays the lender's principal and
+    /// interest first and any surplus goes to the borrower; with no bids the lender takes the NFT.
+    pub fn settle_liquidation(ctx: Context<SettleLiquidation>) -> Result<()> {
+        let loan = &ctx.accounts.loan;
+        let auction = &ctx.accounts.auction;
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp >= auction.ends_at, ErrorCode::AuctionNotEnded);
+
+        let owed = loan
+            .principal
+            .checked_add(loan.interest)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        let lender_amount = auction.highest_bid.min(owed);
+        let borrower_amount = auction.highest_bid - lender_amount;
+
+        let auction_info = auction.to_account_info();
+        **auction_info.try_borrow_mut_lamports()? = auction_info
+            .lamports()
+            .checked_sub(auction.highest_bid)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        **ctx.accounts.lender.try_borrow_mut_lamports()? = ctx
+            .accounts
+            .lender
+            .lamports()
+            .checked_add(lender_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        **ctx.accounts.borrower.try_borrow_mut_lamports()? = ctx
+            .accounts
+            .borrower
+            .lamports()
+            .checked_add(borrower_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        release_loan_collateral(
+            loan,
+            ctx.bumps.loan,
+            &ctx.accounts.loan_nft_account,
+            &ctx.accounts.recipient_nft_account,
+            ctx.accounts.borrower.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        let property = &mut ctx.accounts.property;
+        property.owner = ctx.accounts.recipient.key();
+        property.auto_accept = false;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(LiquidationSettled {
+            auction: auction.key(),
+            loan: loan.key(),
+            property: property.key(),
+            winner: auction.highest_bidder,
+            winning_bid: auction.highest_bid,
+            lender_amount,
+            borrower_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Routes the seller's sale proceeds for a property to a wallet other than the signing owner
+    pub fn set_payout_address(ctx: Context<SetPayoutAddress>, beneficiary: Pubkey) -> Result<()> {
+        require!(beneficiary != Pubkey::default(), ErrorCode::InvalidPayoutAddress);
+
+        let payout_address = &mut ctx.accounts.payout_address;
+        let clock = Clock::get()?;
+        payout_address.property = ctx.accounts.property.key();
+        payout_address.owner = ctx.accounts.owner.key();
+        payout_address.beneficiary = beneficiary;
+        payout_address.updated_at = clock.unix_timestamp;
+
+        emit!(PayoutAddressSet {
+            property: payout_address.property,
+            owner: payout_address.owner,
+            beneficiary,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sends future sale proceeds back to the owner wallet
+    pub fn clear_payout_address(ctx: Context<ClearPayoutAddress>) -> Result<()> {
+        emit!(PayoutAddressCleared {
+            property: ctx.accounts.payout_address.property,
+            owner: ctx.accounts.owner.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Hands the NFT's metadata update authority to the property PDA, so it follows ownership
+    /// instead of staying with a seller after the sale. Buyers can require this before offering.
+    pub fn delegate_metadata_authority(ctx: Context<DelegateMetadataAuthority>) -> Result<()> {
+        let property = &ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        update_metadata_accounts_v2(
+            CpiContext::new(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                UpdateMetadataAccountsV2 {
+                    metadata: ctx.accounts.nft_metadata.to_account_info(),
+                    update_authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            Some(property.key()),
+            None,
+            None,
+            None,
+        )?;
+
+        let custody = &mut ctx.accounts.metadata_custody;
+        custody.property = property.key();
+        custody.delegated_by = ctx.accounts.owner.key();
+        custody.delegated_at = clock.unix_timestamp;
+
+        emit!(MetadataAuthorityTransferred {
+            property: property.key(),
+            nft_mint: property.nft_mint,
+            new_update_authority: property.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Passes update authority held by the property PDA to the current owner. Anyone can run it
+    /// once the property has changed hands since delegation; before that only the owner can take
+    /// it back.
+    pub fn hand_over_metadata_authority(ctx: Context<HandOverMetadataAuthority>) -> Result<()> {
+        let property = &ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        require!(
+            property.owner != ctx.accounts.metadata_custody.delegated_by
+                || ctx.accounts.caller.key() == property.owner,
+            ErrorCode::MetadataAuthorityNotTransferable
+        );
+
+        let marketplace_key = property.marketplace;
+        let property_id_bytes = property.property_id.clone().into_bytes();
+        update_metadata_accounts_v2(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                UpdateMetadataAccountsV2 {
+                    metadata: ctx.accounts.nft_metadata.to_account_info(),
+                    update_authority: property.to_account_info(),
+                },
+                &[&[
+                    b"property",
+                    marketplace_key.as_ref(),
+                    &property_id_bytes,
+                    &[property.bump],
+                ]],
+            ),
+            Some(property.owner),
+            None,
+            None,
+            None,
+        )?;
+
+        emit!(MetadataAuthorityTransferred {
+            property: property.key(),
+            nft_mint: property.nft_mint,
+            new_update_authority: property.owner,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Leases a property to a tenant for `term_periods` monthly periods starting at `start_at`.
+    /// Both parties sign to agree the terms, and the tenant's security deposit goes into escrow.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_lease(
+        ctx: Context<CreateLease>,
+        monthly_rent: u64,
+        start_at: i64,
+        term_periods: u16,
+        late_fee_bps: u16,
+        grace_period_secs: i64,
+        deposit_amount: u64,
+        arbiter: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            monthly_rent > 0
+                && term_periods > 0
+                && late_fee_bps <= MAX_LATE_FEE_BPS
+                && (0..RENT_PERIOD_SECS).contains(&grace_period_secs),
+            ErrorCode::InvalidLeaseTerms
+        );
+
+        let lease = &mut ctx.accounts.lease;
+        let clock = Clock::get()?;
+        lease.property = ctx.accounts.property.key();
+        lease.landlord = ctx.accounts.landlord.key();
+        lease.tenant = ctx.accounts.tenant.key();
+        lease.monthly_rent = monthly_rent;
+        lease.late_fee_bps = late_fee_bps;
+        lease.grace_period_secs = grace_period_secs;
+        lease.start_at = start_at;
+        lease.term_periods = term_periods;
+        lease.paid_through = start_at;
+        lease.periods_paid = 0;
+        lease.created_at = clock.unix_timestamp;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.tenant.to_account_info(),
+                    to: ctx.accounts.security_deposit.to_account_info(),
+                },
+            ),
+            deposit_amount,
+        )?;
+
+        let deposit = &mut ctx.accounts.security_deposit;
+        deposit.lease = lease.key();
+        deposit.landlord = lease.landlord;
+        deposit.tenant = lease.tenant;
+        deposit.arbiter = arbiter;
+        deposit.amount = deposit_amount;
+        deposit.deductions = Vec::new();
+        deposit.status = DepositStatus::Held;
+        deposit.lease_ended_at = 0;
+        deposit.submitted_at = 0;
+
+        emit!(LeaseCreated {
+            lease: lease.key(),
+            property: lease.property,
+            landlord: lease.landlord,
+            tenant: lease.tenant,
+            monthly_rent,
+            start_at,
+            term_periods,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the next month of rent to the landlord, with a late fee once the period has been due
+    /// for longer than the grace period
+    pub fn pay_rent(ctx: Context<PayRent>) -> Result<()> {
+        let lease = &mut ctx.accounts.lease;
+        let clock = Clock::get()?;
+        require!(lease.periods_paid < lease.term_periods, ErrorCode::LeaseFullyPaid);
+        require!(
+            lease.paid_through < clock.unix_timestamp + RENT_PERIOD_SECS * MAX_PREPAID_PERIODS,
+            ErrorCode::RentPaidTooFarAhead
+        );
+
+        let due_at = lease.paid_through;
+        let late_fee = if clock.unix_timestamp > due_at + lease.grace_period_secs {
+            lease
+                .monthly_rent
+                .checked_mul(lease.late_fee_bps as u64)
+                .ok_or(ErrorCode::ArithmeticOverflow)?
+                / 10000
+        } else {
+            0
+        };
+        let amount = lease
+            .monthly_rent
+            .checked_add(late_fee)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.tenant.to_account_info(),
+                    to: ctx.accounts.landlord.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        lease.paid_through = due_at
+            .checked_add(RENT_PERIOD_SECS)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        lease.periods_paid += 1;
+
+        emit!(RentReceipt {
+            lease: lease.key(),
+            period: lease.periods_paid,
+            due_at,
+            paid_through: lease.paid_through,
+            rent: lease.monthly_rent,
+            late_fee,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Ends a lease, returning its rent to the landlord. Before the term is over the tenant has
+    /// to sign as well. Prepaid periods that haven't started are refunded to the tenant.
+    pub fn end_lease(ctx: Context<EndLease>) -> Result<()> {
+        let lease = &ctx.accounts.lease;
+        let clock = Clock::get()?;
+
+        let term_ends_at = RENT_PERIOD_SECS
+            .checked_mul(lease.term_periods as i64)
+            .and_then(|term_secs| lease.start_at.checked_add(term_secs))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        let term_over =
+            lease.periods_paid == lease.term_periods || clock.unix_timestamp >= term_ends_at;
+        require!(
+            term_over || ctx.accounts.tenant.is_signer,
+            ErrorCode::LeaseTermNotOver
+        );
+
+        // The period under way when the lease ends isn't refunded
+        let unused_periods = lease
+            .paid_through
+            .saturating_sub(clock.unix_timestamp.max(lease.start_at))
+            .max(0)
+            / RENT_PERIOD_SECS;
+        let rent_refund = lease
+            .monthly_rent
+            .checked_mul(unused_periods as u64)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        if rent_refund > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.landlord.to_account_info(),
+                        to: ctx.accounts.tenant.to_account_info(),
+                    },
+                ),
+                rent_refund,
+            )?;
+        }
+
+        ctx.accounts.security_deposit.lease_ended_at = clock.unix_timestamp;
+
+        emit!(LeaseEnded {
+            lease: lease.key(),
+            property: lease.property,
+            periods_paid: lease.periods_paid,
+            rent_refund,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Puts a property under a governance (e.g. an SPL Governance account of a DAO or charity
+    /// realm). From then on accepting an offer needs a sale authorization signed by the governance,
+    /// which it can only sign by executing a passed proposal.
+    pub fn attach_governance(ctx: Context<AttachGovernance>, governance: Pubkey) -> Result<()> {
+        let property = &ctx.accounts.property;
+        require!(governance != Pubkey::default(), ErrorCode::InvalidGovernance);
+        require!(!property.auto_accept, ErrorCode::GovernedProperty);
+        require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+
+        let property_governance = &mut ctx.accounts.property_governance;
+        let clock = Clock::get()?;
+        property_governance.property = property.key();
+        property_governance.governance = governance;
+        property_governance.attached_at = clock.unix_timestamp;
+
+        emit!(GovernanceAttached {
+            property: property.key(),
+            governance,
+            owner: property.owner,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Releases a property from its governance. Only the governance itself can do this.
+    pub fn detach_governance(ctx: Context<DetachGovernance>) -> Result<()> {
+        emit!(GovernanceDetached {
+            property: ctx.accounts.property.key(),
+            governance: ctx.accounts.governance.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Authorizes accepting one offer of at least `min_amount` before `expires_at`. Signed by the
+    /// governance, so it is only reachable through proposal execution; replaces any unused one.
+    pub fn authorize_governed_sale(
+        ctx: Context<AuthorizeGovernedSale>,
+        min_amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            min_amount > 0 && expires_at > clock.unix_timestamp,
+            ErrorCode::InvalidSaleAuthorization
+        );
+
+        let authorization = &mut ctx.accounts.sale_authorization;
+        authorization.property = ctx.accounts.property.key();
+        authorization.governance = ctx.accounts.governance.key();
+        authorization.min_amount = min_amount;
+        authorization.expires_at = expires_at;
+        authorization.authorized_at = clock.unix_timestamp;
+
+        emit!(GovernedSaleAuthorized {
+            property: authorization.property,
+            governance: authorization.governance,
+            min_amount,
+            expires_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Itemizes what the landlord keeps from the security deposit after the lease has ended.
+    /// No deductions is a full refund once the tenant accepts or the response window passes.
+    pub fn submit_deposit_deductions(
+        ctx: Context<SubmitDepositDeductions>,
+        deductions: Vec<DepositDeduction>,
+    ) -> Result<()> {
+        let deposit = &mut ctx.accounts.security_deposit;
+        let clock = Clock::get()?;
+        require!(deposit.lease_ended_at != 0, ErrorCode::LeaseNotEnded);
+        require!(deposit.status == DepositStatus::Held, ErrorCode::InvalidDepositStatus);
+        require!(
+            deductions.len() <= MAX_DEPOSIT_DEDUCTIONS
+                && deductions.iter().all(|d| d.amount > 0 && d.document_hash != [0; 32]),
+            ErrorCode::InvalidDeductions
+        );
+        let total = total_deductions(&deductions)?;
+        require!(total <= deposit.amount, ErrorCode::InvalidDeductions);
+
+        deposit.deductions = deductions;
+        deposit.status = DepositStatus::DeductionsSubmitted;
+        deposit.submitted_at = clock.unix_timestamp;
+
+        emit!(DepositDeductionsSubmitted {
+            security_deposit: deposit.key(),
+            lease: deposit.lease,
+            total,
+            deduction_count: deposit.deductions.len() as u8,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Tenant contests the submitted deductions, leaving them for the arbiter to resolve
+    pub fn dispute_deposit_deductions(ctx: Context<DisputeDepositDeductions>) -> Result<()> {
+        let deposit = &mut ctx.accounts.security_deposit;
+        require!(
+            deposit.status == DepositStatus::DeductionsSubmitted,
+            ErrorCode::InvalidDepositStatus
+        );
+        require!(deposit.arbiter.is_some(), ErrorCode::NoDepositArbiter);
+        deposit.status = DepositStatus::Disputed;
+
+        emit!(DepositDisputed {
+            security_deposit: deposit.key(),
+            lease: deposit.lease,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays out the deposit: the landlord gets the accepted deductions and the tenant the rest.
+    /// The tenant can settle at any time by accepting the deductions; anyone can settle once the
+    /// tenant's response window passes, or with a full refund if the landlord never submitted any.
+    pub fn settle_security_deposit(ctx: Context<SettleSecurityDeposit>) -> Result<()> {
+        let deposit = &ctx.accounts.security_deposit;
+        let clock = Clock::get()?;
+        require!(deposit.lease_ended_at != 0, ErrorCode::LeaseNotEnded);
+        let by_tenant = ctx.accounts.caller.key() == deposit.tenant;
+
+        let landlord_amount = match deposit.status {
+            DepositStatus::DeductionsSubmitted => {
+                require!(
+                    by_tenant
+                        || clock.unix_timestamp >= deposit.submitted_at + DEPOSIT_RESPONSE_SECS,
+                    ErrorCode::DepositWindowOpen
+                );
+                total_deductions(&deposit.deductions)?
+            }
+            // The landlord let the claim window pass without submitting deductions
+            DepositStatus::Held => {
+                require!(
+                    clock.unix_timestamp >= deposit.lease_ended_at + DEPOSIT_CLAIM_SECS,
+                    ErrorCode::DepositWindowOpen
+                );
+                0
+            }
+            DepositStatus::Disputed => return err!(ErrorCode::InvalidDepositStatus),
+        };
+
+        pay_out_security_deposit(
+            deposit,
+            landlord_amount,
+            &ctx.accounts.landlord,
+            &ctx.accounts.tenant,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Arbiter decides how much of a disputed deposit the landlord keeps, up to the amount claimed
+    pub fn resolve_deposit_dispute(
+        ctx: Context<ResolveDepositDispute>,
+        landlord_amount: u64,
+    ) -> Result<()> {
+        let deposit = &ctx.accounts.security_deposit;
+        require!(deposit.status == DepositStatus::Disputed, ErrorCode::InvalidDepositStatus);
+        require!(
+            deposit.arbiter == Some(ctx.accounts.arbiter.key()),
+            ErrorCode::NotDepositArbiter
+        );
+        require!(
+            landlord_amount <= total_deductions(&deposit.deductions)?,
+            ErrorCode::InvalidDeductions
+        );
+
+        pay_out_security_deposit(
+            deposit,
+            landlord_amount,
+            &ctx.accounts.landlord,
+            &ctx.accounts.tenant,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    /// Caps how many offers can be open on a property at once. Zero removes the cap.
+    pub fn configure_offer_limits(ctx: Context<ConfigureOfferLimits>, max_open_offers: u16) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+
+        let offer_limits = &mut ctx.accounts.offer_limits;
+        offer_limits.marketplace = ctx.accounts.marketplace.key();
+        offer_limits.max_open_offers = max_open_offers;
+
+        emit!(OfferLimitsConfigured {
+            marketplace: offer_limits.marketplace,
+            max_open_offers,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets how close to the end a liquidation bid must land to extend the auction, and by how
+    /// much. A zero window turns extensions off. Applies to auctions opened afterwards.
+    pub fn configure_auctions(
+        ctx: Context<ConfigureAuctions>,
+        snipe_window_secs: i64,
+        extension_secs: i64,
+    ) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+        require!(
+            snipe_window_secs >= 0
+                && (0..=MAX_AUCTION_EXTENSION_SECS).contains(&extension_secs)
+                && (snipe_window_secs == 0 || extension_secs > 0),
+            ErrorCode::InvalidAuctionConfig
+        );
+
+        let auction_config = &mut ctx.accounts.auction_config;
+        auction_config.marketplace = ctx.accounts.marketplace.key();
+        auction_config.snipe_window_secs = snipe_window_secs;
+        auction_config.extension_secs = extension_secs;
+
+        emit!(AuctionConfigured {
+            marketplace: auction_config.marketplace,
+            snipe_window_secs,
+            extension_secs,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets how many years a sale record must be kept before `archive_history` can close it
+    pub fn configure_history_retention(
+        ctx: Context<ConfigureHistoryRetention>,
+        retention_years: u16,
+    ) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+        require!(
+            retention_years >= MIN_HISTORY_RETENTION_YEARS,
+            ErrorCode::InvalidRetentionPeriod
+        );
+
+        let retention = &mut ctx.accounts.history_retention;
+        retention.marketplace = ctx.accounts.marketplace.key();
+        retention.retention_years = retention_years;
+
+        emit!(HistoryRetentionConfigured {
+            marketplace: retention.marketplace,
+            retention_years,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the transfer tax collected at settlement on properties in a jurisdiction. Taken from
+    /// the seller's proceeds and paid to `collector`.
+    pub fn set_tax_config(
+        ctx: Context<SetTaxConfig>,
+        jurisdiction_code: [u8; 8],
+        tax_bps: u16,
+        collector: Pubkey,
+    ) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+        require!(
+            jurisdiction_code != [0u8; 8] && tax_bps <= MAX_TRANSFER_TAX_BPS,
+            ErrorCode::InvalidTaxConfig
+        );
+
+        let tax_config = &mut ctx.accounts.tax_config;
+        let clock = Clock::get()?;
+        tax_config.marketplace = ctx.accounts.marketplace.key();
+        tax_config.jurisdiction_code = jurisdiction_code;
+        tax_config.tax_bps = tax_bps;
+        tax_config.collector = collector;
+        tax_config.updated_at = clock.unix_timestamp;
+
+        emit!(TaxConfigUpdated {
+            marketplace: tax_config.marketplace,
+            jurisdiction_code,
+            tax_bps,
+            collector,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stops collecting transfer tax in a jurisdiction
+    pub fn remove_tax_config(ctx: Context<RemoveTaxConfig>) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+
+        emit!(TaxConfigRemoved {
+            marketplace: ctx.accounts.marketplace.key(),
+            jurisdiction_code: ctx.accounts.tax_config.jurisdiction_code,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the lamport bond sellers lock when listing a property. Zero turns bonds off for new
+    /// listings; bonds already locked are unaffected.
+    pub fn configure_listing_bond(
+        ctx: Context<ConfigureListingBond>,
+        bond_lamports: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+
+        let config = &mut ctx.accounts.listing_bond_config;
+        config.marketplace = ctx.accounts.marketplace.key();
+        config.bond_lamports = bond_lamports;
+
+        emit!(ListingBondConfigured {
+            marketplace: config.marketplace,
+            bond_lamports,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns a listing bond to the seller who locked it when the listing ended outside a sale or
+    /// delisting, which refund it themselves: the property was retired, merged, subdivided or
+    /// transferred. Anyone can call this.
+    pub fn release_listing_bond(ctx: Context<ReleaseListingBond>) -> Result<()> {
+        let listing_bond = &ctx.accounts.listing_bond;
+        let property_info = ctx.accounts.property.to_account_info();
+
+        if property_info.owner == &crate::ID {
+            let property = {
+                let data = property_info.try_borrow_data()?;
+                Property::try_deserialize(&mut &data[..])?
+            };
+            require!(
+                property.merged_into.is_some()
+                    || property.child_count > 0
+                    || property.owner != listing_bond.owner,
+                ErrorCode::ListingStillActive
+            );
+        }
+
+        emit!(ListingBondReleased {
+            listing_bond: listing_bond.key(),
+            property: listing_bond.property,
+            owner: listing_bond.owner,
+            amount: listing_bond.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Forfeits the bond of a listing flagged as fraudulent to the treasury and takes the
+    /// listing down. The account's rent goes back to the seller.
+    pub fn slash_listing_bond(
+        ctx: Context<SlashListingBond>,
+        reason: InterventionReason,
+    ) -> Result<()> {
+        let listing_bond = &ctx.accounts.listing_bond;
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        let amount = listing_bond.amount;
+        release_escrow(
+            &listing_bond.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            amount,
+        )?;
+        record_fee(&mut ctx.accounts.treasury, ctx.accounts.marketplace.key(), amount)?;
+
+        property.is_active = false;
+        property.updated_at = clock.unix_timestamp;
+        free_listing_slot_unchecked(
+            &ctx.accounts.listing_slot,
+            &ctx.accounts.owner_listings,
+            &ctx.accounts.owner,
+            clock.unix_timestamp,
+        )?;
+
+        emit!(ListingBondSlashed {
+            listing_bond: listing_bond.key(),
+            property: property.key(),
+            owner: listing_bond.owner,
+            authority: ctx.accounts.authority.key(),
+            amount,
+            reason,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Closes a sale record older than the retention period and returns its rent to the treasury.
+    /// The full record is emitted first so indexers keep it. A property's latest sale is never
+    /// archived.
+    pub fn archive_history(ctx: Context<ArchiveHistory>) -> Result<()> {
+        let history = &ctx.accounts.transaction_history;
+        let clock = Clock::get()?;
+
+        require!(
+            history.transaction_index < ctx.accounts.property.transaction_count,
+            ErrorCode::CannotArchiveLatestHistory
+        );
+        let retention_secs = i64::from(ctx.accounts.history_retention.retention_years)
+            .checked_mul(SECONDS_PER_YEAR)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            clock.unix_timestamp.saturating_sub(history.timestamp) >= retention_secs,
+            ErrorCode::HistoryRetentionActive
+        );
+
+        emit!(HistoryArchived {
+            transaction_history: history.key(),
+            property: history.property,
+            seller: history.seller,
+            buyer: history.buyer,
+            price: history.price,
+            sold_at: history.timestamp,
+            transaction_index: history.transaction_index,
+            seller_concession: history.seller_concession,
+            version: history.version,
+            rent_recovered: history.to_account_info().lamports(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Gives the property to another wallet without a sale, e.g. an inheritance or a move to a DAO.
+    /// Every open offer must be passed as (offer, escrow, buyer) triples so it can be refunded,
+    /// and the transfer is recorded as a zero-price sale.
+    pub fn transfer_property<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TransferProperty<'info>>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        require!(new_owner != property.owner, ErrorCode::InvalidTransferRecipient);
+        require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+        require!(!property.co_owned, ErrorCode::CoOwnedProperty);
+        require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+        require!(property.approval_authority.is_none(), ErrorCode::TransferNotApproved);
+        require!(
+            property_governance(&property.key(), &ctx.accounts.property_governance)?.is_none(),
+            ErrorCode::GovernedProperty
+        );
+
+        require!(
+            ctx.remaining_accounts.len() % 3 == 0,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            reject_pending_offer(property, accounts, clock.unix_timestamp)?;
+        }
+        require!(property.open_offer_count == 0, ErrorCode::OpenOffersRemain);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_nft_account.to_account_info(),
+                    to: ctx.accounts.recipient_nft_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let previous_owner = property.owner;
+        hand_over(
+            property,
+            &mut ctx.accounts.transaction_history,
+            &mut ctx.accounts.history_index,
+            new_owner,
+            0,
+            clock.unix_timestamp,
+        )?;
+
+        emit!(PropertyTransferred {
+            property: property.key(),
+            transaction_history: ctx.accounts.transaction_history.key(),
+            previous_owner,
+            new_owner,
+            nft_mint: property.nft_mint,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Exchanges two properties between their owners in one transaction, so a swap can't end
+    /// half-completed. Both owners sign. A positive `cash_difference` is paid by `owner_a` to
+    /// `owner_b` alongside the exchange, a negative one the other way. Each property gets a
+    /// history record priced at the cash its new owner paid.
+    pub fn swap_properties(ctx: Context<SwapProperties>, cash_difference: i64) -> Result<()> {
+        let clock = Clock::get()?;
+        let property_a = &mut ctx.accounts.property_a;
+        let property_b = &mut ctx.accounts.property_b;
+
+        require!(
+            property_a.marketplace == property_b.marketplace,
+            ErrorCode::MarketplaceMismatch
+        );
+        require!(
+            ctx.accounts.owner_a.key() != ctx.accounts.owner_b.key(),
+            ErrorCode::InvalidTransferRecipient
+        );
+        for (property, governance) in [
+            (&*property_a, &ctx.accounts.property_governance_a),
+            (&*property_b, &ctx.accounts.property_governance_b),
+        ] {
+            require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+            require!(!property.co_owned, ErrorCode::CoOwnedProperty);
+            require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+            require!(property.open_offer_count == 0, ErrorCode::OpenOffersRemain);
+            require!(property.approval_authority.is_none(), ErrorCode::TransferNotApproved);
+            require!(
+                property_governance(&property.key(), governance)?.is_none(),
+                ErrorCode::GovernedProperty
+            );
+        }
+
+        let (payer, payee) = if cash_difference >= 0 {
+            (&ctx.accounts.owner_a, &ctx.accounts.owner_b)
+        } else {
+            (&ctx.accounts.owner_b, &ctx.accounts.owner_a)
+        };
+        let cash = cash_difference.unsigned_abs();
+        if cash > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: payee.to_account_info(),
+                    },
+                ),
+                cash,
+            )?;
+        }
+
+        for (from, to, authority) in [
+            (
+                &ctx.accounts.owner_a_nft_a,
+                &ctx.accounts.owner_b_nft_a,
+                &ctx.accounts.owner_a,
+            ),
+            (
+                &ctx.accounts.owner_b_nft_b,
+                &ctx.accounts.owner_a_nft_b,
+                &ctx.accounts.owner_b,
+            ),
+        ] {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: authority.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+        }
+
+        // Whoever paid the cash bought the other property for it
+        let (price_a, price_b) = if cash_difference >= 0 { (0, cash) } else { (cash, 0) };
+        let owner_a = ctx.accounts.owner_a.key();
+        let owner_b = ctx.accounts.owner_b.key();
+        hand_over(
+            property_a,
+            &mut ctx.accounts.transaction_history_a,
+            &mut ctx.accounts.history_index_a,
+            owner_b,
+            price_a,
+            clock.unix_timestamp,
+        )?;
+        hand_over(
+            property_b,
+            &mut ctx.accounts.transaction_history_b,
+            &mut ctx.accounts.history_index_b,
+            owner_a,
+            price_b,
+            clock.unix_timestamp,
+        )?;
+
+        emit!(PropertiesSwapped {
+            property_a: property_a.key(),
+            property_b: property_b.key(),
+            transaction_history_a: ctx.accounts.transaction_history_a.key(),
+            transaction_history_b: ctx.accounts.transaction_history_b.key(),
+            owner_a,
+            owner_b,
+            cash_difference,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Lists several properties in one transaction, e.g. the units of a building. Remaining
+    /// accounts are (property, nft_mint, owner_nft_account) per listing, in the same order.
+    pub fn list_properties_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ListPropertiesBatch<'info>>,
+        listings: Vec<ListingArgs>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.marketplace.paused, ErrorCode::MarketplacePaused);
+        require!(
+            !listings.is_empty()
+                && listings.len() <= MAX_BATCH_LISTINGS
+                && ctx.remaining_accounts.len() == listings.len() * 3,
+            ErrorCode::InvalidBatchListing
+        );
+
+        // Bonded or capped listings go through list_property, one bond or slot per property
+        require!(
+            listing_bond_lamports(&ctx.accounts.listing_bond_config)? == 0,
+            ErrorCode::ListingBondRequired
+        );
+        let owner_listings = &mut ctx.accounts.owner_listings;
+        owner_listings.marketplace = ctx.accounts.marketplace.key();
+        owner_listings.owner = ctx.accounts.owner.key();
+        require!(
+            listing_cap(&ctx.accounts.listing_limits, owner_listings)? == 0,
+            ErrorCode::ListingSlotRequired
+        );
+
+        let marketplace = &mut ctx.accounts.marketplace;
+        let marketplace_key = marketplace.key();
+        let clock = Clock::get()?;
+        let rent = Rent::get()?;
+
+        for (args, accounts) in listings.into_iter().zip(ctx.remaining_accounts.chunks(3)) {
+            let (property_info, mint_info, owner_nft_info) = (&accounts[0], &accounts[1], &accounts[2]);
+            require!(
+                property_info.is_writable && mint_info.is_writable && owner_nft_info.is_writable,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            require!(mint_info.owner == &token::ID, ErrorCode::InvalidNFTMint);
+            let space = listing_space(&args.content_hashes, 0);
+
+            let mut property = new_listing(
+                args,
+                marketplace_key,
+                ctx.accounts.owner.key(),
+                mint_info.key(),
+                clock.unix_timestamp,
+            )?;
+            let (property_key, property_bump) = Pubkey::find_program_address(
+                &[b"property", marketplace_key.as_ref(), property.property_id.as_bytes()],
+                &crate::ID,
+            );
+            require!(property_info.key() == property_key, ErrorCode::InvalidRemainingAccounts);
+            property.bump = property_bump;
+
+            anchor_lang::system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::CreateAccount {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: property_info.clone(),
+                    },
+                    &[&[
+                        b"property",
+                        marketplace_key.as_ref(),
+                        property.property_id.as_bytes(),
+                        &[property_bump],
+                    ]],
+                ),
+                rent.minimum_balance(space),
+                space as u64,
+                &crate::ID,
+            )?;
+
+            token::mint_to(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: mint_info.clone(),
+                        to: owner_nft_info.clone(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+
+            let mut property_data = property_info.try_borrow_mut_data()?;
+            property.try_serialize(&mut &mut property_data[..])?;
+            drop(property_data);
+
+            marketplace.properties_count = marketplace
+                .properties_count
+                .checked_add(1)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            let listing_fee = collect_listing_fee(
+                marketplace,
+                ctx.accounts.treasury.as_mut(),
+                &ctx.accounts.owner,
+                &ctx.accounts.system_program,
+            )?;
+
+            emit_listed(property_key, &property, listing_fee, clock.unix_timestamp);
+        }
+
+        Ok(())
+    }
+
+    /// Frees a parcel claimed by a property that has since been merged or subdivided, so the
+    /// property that now represents the land can claim it. Rent goes back to the original
+    /// claimant. Anyone can call this.
+    pub fn release_parcel(ctx: Context<ReleaseParcel>) -> Result<()> {
+        let property = &ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        require!(
+            property.merged_into.is_some() || property.child_count > 0,
+            ErrorCode::ParcelStillListed
+        );
+
+        emit!(ParcelReleased {
+            property_registry: ctx.accounts.property_registry.key(),
+            parcel_key: ctx.accounts.property_registry.parcel_key,
+            property: property.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Tears a property down for good, e.g. after demolition or a listing made in error: burns
+    /// the NFT, closes its metadata when the metadata accounts are passed, releases the parcel
+    /// claim and closes the property and its history index. Sale records stay on-chain.
+    /// Remaining accounts are (offer, escrow, buyer) triples for every pending offer, which are
+    /// rejected and refunded first.
+    pub fn retire_property<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RetireProperty<'info>>,
+    ) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let clock = Clock::get()?;
+
+        require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+        require!(!property.co_owned, ErrorCode::CoOwnedProperty);
+        require!(property.lien_count == 0, ErrorCode::PropertyHasLiens);
+        require!(property.child_count == 0, ErrorCode::PropertySubdivided);
+        require!(property.merged_into.is_none(), ErrorCode::PropertyMerged);
+        require!(
+            property_governance(&property.key(), &ctx.accounts.property_governance)?.is_none(),
+            ErrorCode::GovernedProperty
+        );
+
+        require!(
+            ctx.remaining_accounts.len() % 3 == 0,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            reject_pending_offer(property, accounts, clock.unix_timestamp)?;
+        }
+        require!(property.open_offer_count == 0, ErrorCode::OpenOffersRemain);
+
+        // Burning through the metadata program also closes the metadata and edition accounts
+        match (
+            &ctx.accounts.nft_metadata,
+            &ctx.accounts.nft_master_edition,
+            &ctx.accounts.token_metadata_program,
+        ) {
+            (Some(metadata), Some(edition), Some(token_metadata_program)) => {
+                burn_nft(
+                    CpiContext::new(
+                        token_metadata_program.to_account_info(),
+                        BurnNft {
+                            metadata: metadata.to_account_info(),
+                            owner: ctx.accounts.owner.to_account_info(),
+                            mint: ctx.accounts.nft_mint.to_account_info(),
+                            token: ctx.accounts.owner_nft_account.to_account_info(),
+                            edition: edition.to_account_info(),
+                            spl_token: ctx.accounts.token_program.to_account_info(),
+                        },
+                    ),
+                    None,
+                )?;
+            }
+            _ => {
+                token::burn(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Burn {
+                            mint: ctx.accounts.nft_mint.to_account_info(),
+                            from: ctx.accounts.owner_nft_account.to_account_info(),
+                            authority: ctx.accounts.owner.to_account_info(),
+                        },
+                    ),
+                    1,
+                )?;
+                token::close_account(CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    CloseAccount {
+                        account: ctx.accounts.owner_nft_account.to_account_info(),
+                        destination: ctx.accounts.owner.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ))?;
+            }
+        }
+
+        // A parcel claim held by this property would otherwise stay claimed forever, since
+        // release_parcel needs the property account
+        if property.cadastral_id != [0u8; 32] {
+            let parcel_key = normalized_parcel_key(&property.cadastral_id, &property.jurisdiction_code);
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"property_registry", parcel_key.as_ref()],
+                &crate::ID,
+            );
+            let registry_info = ctx.accounts.property_registry.to_account_info();
+            require_keys_eq!(registry_info.key(), expected, ErrorCode::InvalidParcelRegistry);
+
+            if registry_info.owner == &crate::ID {
+                let registry = {
+                    let data = registry_info.try_borrow_data()?;
+                    PropertyRegistry::try_deserialize(&mut &data[..])?
+                };
+                if registry.property == property.key() {
+                    let claimant = ctx
+                        .accounts
+                        .registry_claimant
+                        .as_ref()
+                        .ok_or(ErrorCode::InvalidParcelRegistry)?;
+                    require_keys_eq!(
+                        claimant.key(),
+                        registry.claimant,
+                        ErrorCode::InvalidParcelRegistry
+                    );
+
+                    let rent = registry_info.lamports();
+                    **registry_info.try_borrow_mut_lamports()? = 0;
+                    **claimant.try_borrow_mut_lamports()? = claimant
+                        .lamports()
+                        .checked_add(rent)
+                        .ok_or(ErrorCode::ArithmeticOverflow)?;
+                    registry_info.assign(&anchor_lang::system_program::ID);
+                    registry_info.realloc(0, false)?;
+
+                    emit!(ParcelReleased {
+                        property_registry: registry_info.key(),
+                        parcel_key,
+                        property: property.key(),
+                        timestamp: clock.unix_timestamp,
+                    });
+                }
+            }
+        }
+
+        emit!(PropertyRetired {
+            property: property.key(),
+            marketplace: property.marketplace,
+            owner: property.owner,
+            property_id: property.property_id.clone(),
+            nft_mint: property.nft_mint,
+            transaction_count: property.transaction_count,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Proposes the date the sale closes on. Set by the buyer while the offer is pending, so the
+    /// seller agrees to it by accepting; neither party can settle before it.
+    pub fn set_closing_date(ctx: Context<SetClosingDate>, closing_date: i64) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let clock = Clock::get()?;
+
+        require!(offer.status == OfferStatus::Pending, ErrorCode::OfferNotPending);
+        require!(closing_date > clock.unix_timestamp, ErrorCode::InvalidClosingDate);
+
+        let schedule = &mut ctx.accounts.closing_schedule;
+        schedule.offer = offer.key();
+        schedule.closing_date = closing_date;
+        schedule.scheduled_at = clock.unix_timestamp;
+
+        emit!(ClosingScheduled {
+            offer: offer.key(),
+            property: offer.property,
+            buyer: offer.buyer,
+            closing_date,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Unwinds an accepted offer that still can't settle a grace period after its closing date.
+    /// Anyone can run it. Escrow that is ready settles through `execute_sale` instead, which is
+    /// equally permissionless. Otherwise the NFT goes back to the seller and the buyer is
+    /// refunded, less the financing penalty when the buyer never funded a financed offer.
+    pub fn release_closing_escrow(ctx: Context<ReleaseClosingEscrow>) -> Result<()> {
+        let property = &mut ctx.accounts.property;
+        let offer = &mut ctx.accounts.offer;
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(offer.status == OfferStatus::Accepted, ErrorCode::OfferNotAccepted);
+        let release_at = ctx
+            .accounts
+            .closing_schedule
+            .closing_date
+            .checked_add(CLOSING_GRACE_SECS)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(clock.unix_timestamp >= release_at, ErrorCode::ClosingDateNotReached);
+
+        let escrowed = escrowed_lamports(&escrow.to_account_info())?.min(escrow.amount);
+        require!(
+            !(escrow.nft_held && escrowed >= offer.amount),
+            ErrorCode::ClosingReadyToSettle
+        );
+
+        // The seller delivered but the buyer never funded the balance
+        let financing_info = ctx.accounts.financing.to_account_info();
+        let penalty_bps = if escrow.nft_held && financing_info.owner == &crate::ID {
+            let financing = {
+                let data = financing_info.try_borrow_data()?;
+                FinancingContingency::try_deserialize(&mut &data[..])?
+            };
+            financing.penalty_bps
+        } else {
+            0
+        };
+        let penalty = (escrowed as u128)
+            .checked_mul(penalty_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::ArithmeticOverflow)? as u64;
+        let refunded = escrowed - penalty;
+
+        let nft_returned = escrow.nft_held;
+        if escrow.nft_held {
+            let escrow_nft_account = ctx
+                .accounts
+                .escrow_nft_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidTokenAccount)?;
+            let seller_nft_account = ctx
+                .accounts
+                .seller_nft_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidTokenAccount)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_nft_account.to_account_info(),
+                        to: seller_nft_account.to_account_info(),
+                        authority: escrow.to_account_info(),
+                    },
+                    &[&[b"escrow", offer.key().as_ref(), &[escrow.bump]]],
+                ),
+                1,
+            )?;
+            escrow.nft_held = false;
+        }
+
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.seller.to_account_info(), penalty)?;
+        release_escrow(&escrow.to_account_info(), &ctx.accounts.buyer.to_account_info(), refunded)?;
+        escrow.amount = 0;
+
+        offer.status = OfferStatus::Cancelled;
+        offer.updated_at = clock.unix_timestamp;
+        close_offer(property);
+
+        emit!(ClosingEscrowReleased {
+            offer: offer.key(),
+            property: property.key(),
+            marketplace: property.marketplace,
+            buyer: offer.buyer,
+            seller: property.owner,
+            closing_date: ctx.accounts.closing_schedule.closing_date,
+            refunded,
+            penalty,
+            nft_returned,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Caps how many active listings one wallet can hold in the marketplace. Zero removes the
+    /// cap. Applies as owners list; listings made while uncapped aren't counted.
+    pub fn configure_listing_limits(
+        ctx: Context<ConfigureListingLimits>,
+        max_listings_per_owner: u16,
+    ) -> Result<()> {
+        require!(ctx.accounts.marketplace.timelock_secs == 0, ErrorCode::TimelockActive);
+
+        let listing_limits = &mut ctx.accounts.listing_limits;
+        listing_limits.marketplace = ctx.accounts.marketplace.key();
+        listing_limits.max_listings_per_owner = max_listings_per_owner;
+
+        emit!(ListingLimitsConfigured {
+            marketplace: listing_limits.marketplace,
+            max_listings_per_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Overrides the listing cap for one wallet, e.g. to let a brokerage list more. Zero makes
+    /// the wallet unlimited and `None` puts it back on the marketplace's cap.
+    pub fn set_owner_listing_limit(
+        ctx: Context<SetOwnerListingLimit>,
+        owner: Pubkey,
+        max_listings: Option<u16>,
+    ) -> Result<()> {
+        let owner_listings = &mut ctx.accounts.owner_listings;
+        owner_listings.marketplace = ctx.accounts.marketplace.key();
+        owner_listings.owner = owner;
+        owner_listings.max_listings = max_listings;
+
+        emit!(OwnerListingLimitSet {
+            marketplace: owner_listings.marketplace,
+            owner,
+            max_listings,
+            active_listings: owner_listings.active_listings,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Gives a listing's slot back to the owner who took it when the listing ended outside a sale
+    /// or delisting, which free it themselves: the property was retired, merged, subdivided or
+    /// transferred. Anyone can call this.
+    pub fn release_listing_slot(ctx: Context<ReleaseListingSlot>) -> Result<()> {
+        let listing_slot = &ctx.accounts.listing_slot;
+        let property_info = ctx.accounts.property.to_account_info();
+
+        if property_info.owner == &crate::ID {
+            let property = {
+                let data = property_info.try_borrow_data()?;
+                Property::try_deserialize(&mut &data[..])?
+            };
+            require!(
+                property.merged_into.is_some()
+                    || property.child_count > 0
+                    || property.owner != listing_slot.owner,
+                ErrorCode::ListingStillActive
+            );
+        }
+
+        let owner_listings = &mut ctx.accounts.owner_listings;
+        owner_listings.active_listings = owner_listings
+            .active_listings
+            .checked_sub(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit!(ListingSlotReleased {
+            marketplace: listing_slot.marketplace,
+            property: listing_slot.property,
+            owner: listing_slot.owner,
+            active_listings: owner_listings.active_listings,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Records an improvement to the property, e.g. to back a price increase. `date` is when the
+    /// work was completed and `description_hash` the SHA-256 of its off-chain write-up. Records
+    /// stay with the property across sales and can't be edited.
+    pub fn add_renovation(
+        ctx: Context<AddRenovation>,
+        description_hash: [u8; 32],
+        cost: u64,
+        date: i64,
+    ) -> Result<()> {
+        let property = &ctx.accounts.property;
+        let renovations = &mut ctx.accounts.renovations;
+        let clock = Clock::get()?;
+
+        require!(
+            description_hash != [0; 32] && date > 0 && date <= clock.unix_timestamp,
+            ErrorCode::InvalidRenovation
+        );
+        require!(property.merged_into.is_none(), ErrorCode::PropertyMerged);
+        require!(
+            renovations.records.len() < MAX_RENOVATIONS,
+            ErrorCode::RenovationLimitReached
+        );
+
+        renovations.property = property.key();
+        renovations.records.push(RenovationRecord {
+            description_hash,
+            cost,
+            completed_at: date,
+            recorded_by: property.owner,
+            recorded_at: clock.unix_timestamp,
+        });
+        renovations.updated_at = clock.unix_timestamp;
+
+        emit!(RenovationAdded {
+            property: property.key(),
+            owner: property.owner,
+            index: (renovations.records.len() - 1) as u8,
+            description_hash,
+            cost,
+            completed_at: date,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub const DEFAULT_MAX_PRICE_AGE_SECS: u64 = 60;
+pub const DEFAULT_MAX_CONFIDENCE_BPS: u16 = 200;
+/// Current account layouts; `migrate_*` upgrades anything older in place
+pub const MARKETPLACE_VERSION: u8 = 4;
+pub const PROPERTY_VERSION: u8 = 6;
+pub const OFFER_VERSION: u8 = 2;
+pub const TRANSACTION_HISTORY_VERSION: u8 = 3;
+
+/// Lamports in a seller bond account that are neither rent nor reserved for open offers
+fn seller_bond_available(seller_bond: &Account<SellerBond>) -> Result<u64> {
+    let info = seller_bond.to_account_info();
+    let rent = Rent::get()?.minimum_balance(info.data_len());
+    Ok(info
+        .lamports()
+        .saturating_sub(rent)
+        .saturating_sub(seller_bond.reserved))
+}
+
+fn unreserve_seller_bond(
+    response_bond: &ResponseBond,
+    seller_bond: Option<&mut Account<SellerBond>>,
+) -> Result<()> {
+    if response_bond.amount == 0 {
+        return Ok(());
+    }
+    let seller_bond = seller_bond.ok_or(ErrorCode::InvalidSellerBond)?;
+    require_keys_eq!(seller_bond.key(), response_bond.seller_bond, ErrorCode::InvalidSellerBond);
+    seller_bond.reserved = seller_bond
+        .reserved
+        .checked_sub(response_bond.amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+pub const MAX_CO_OWNERS: usize = 10;
+pub const MAX_MILESTONES: usize = 10;
+pub const MAX_TIMELOCK_SECS: i64 = 30 * 24 * 60 * 60;
+pub const MAX_STAKE_TIERS: usize = 5;
+pub const MAX_LOAN_DURATION_SECS: i64 = 5 * 365 * 24 * 60 * 60;
+pub const LIQUIDATION_AUCTION_SECS: i64 = 3 * 24 * 60 * 60;
+/// Each liquidation bid must beat the current highest by at least this much
+pub const MIN_BID_INCREMENT_BPS: u64 = 100;
+/// Bids this close to the end extend the auction, unless the marketplace configures otherwise
+pub const DEFAULT_SNIPE_WINDOW_SECS: i64 = 10 * 60;
+pub const DEFAULT_AUCTION_EXTENSION_SECS: i64 = 10 * 60;
+pub const MAX_AUCTION_EXTENSION_SECS: i64 = 24 * 60 * 60;
+pub const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+/// Sale records are kept on-chain for at least this long before they can be archived
+pub const MIN_HISTORY_RETENTION_YEARS: u16 = 1;
+pub const RENT_PERIOD_SECS: i64 = 30 * 24 * 60 * 60;
+pub const MAX_PREPAID_PERIODS: i64 = 12;
+pub const MAX_LATE_FEE_BPS: u16 = 2000;
+pub const MAX_TRANSFER_TAX_BPS: u16 = 1000;
+pub const MAX_DEPOSIT_DEDUCTIONS: usize = 10;
+/// Time the landlord has after the lease ends to submit deductions
+pub const DEPOSIT_CLAIM_SECS: i64 = 30 * 24 * 60 * 60;
+/// Time the tenant has to dispute submitted deductions
+pub const DEPOSIT_RESPONSE_SECS: i64 = 14 * 24 * 60 * 60;
+/// Time past an agreed closing date before its escrow can be released without the parties
+pub const CLOSING_GRACE_SECS: i64 = 7 * 24 * 60 * 60;
+/// Four accounts per parcel at settlement, plus its jurisdiction's tax accounts which parcels in
+/// the same jurisdiction share, keeps a full bundle within one transaction
+pub const MAX_BUNDLE_PROPERTIES: usize = 5;
+pub const MAX_BATCH_LISTINGS: usize = 5;
+/// Three accounts per parcel keeps a subdivision within one transaction
+pub const MAX_SUBDIVISION_PARCELS: usize = 5;
+pub const MAX_MERGE_PARCELS: usize = 5;
+pub const MAX_RECEIPT_BASE_URI_LEN: usize = 96;
+pub const RECEIPT_SYMBOL: &str = "RCPT";
+pub const MAX_DOCUMENT_URI_LEN: usize = 200;
+pub const MAX_MEDIA_ITEMS: usize = 20;
+pub const MAX_RENOVATIONS: usize = 32;
+pub const MAX_PROPERTY_ID_LEN: usize = 32;
+pub const MAX_LOCATION_LEN: usize = 50;
+/// Metadata URI room allocated at listing, resize_property grows it up to MAX_METADATA_URI_LEN
+pub const INITIAL_METADATA_URI_LEN: usize = 100;
+pub const MAX_METADATA_URI_LEN: usize = 200;
+
+// Amenity bits for `Property::amenities`
+pub const AMENITY_GARAGE: u64 = 1 << 0;
+pub const AMENITY_POOL: u64 = 1 << 1;
+pub const AMENITY_SOLAR: u64 = 1 << 2;
+pub const AMENITY_ELEVATOR: u64 = 1 << 3;
+pub const AMENITY_GARDEN: u64 = 1 << 4;
+pub const AMENITY_AIR_CONDITIONING: u64 = 1 << 5;
+pub const AMENITY_SECURITY: u64 = 1 << 6;
+pub const AMENITY_LOADING_DOCK: u64 = 1 << 7;
+pub const AMENITY_WATER_ACCESS: u64 = 1 << 8;
+pub const AMENITY_UTILITIES: u64 = 1 << 9;
+pub const GEOHASH_LEN: usize = 10;
+pub const MIN_GEOHASH_LEN: usize = 8;
+const GEOHASH_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+pub const KNOWN_AMENITIES: u64 = (1 << 10) - 1;
+/// Building amenities that make no sense on bare land
+pub const BUILDING_AMENITIES: u64 =
+    AMENITY_GARAGE | AMENITY_POOL | AMENITY_ELEVATOR | AMENITY_AIR_CONDITIONING | AMENITY_LOADING_DOCK;
+
+/// Returns the listing's asking price in lamports, converting USD-cent prices with the
+/// marketplace's Pyth SOL/USD feed after staleness and confidence checks.
+pub fn listing_price_in_lamports(
+    property: &Property,
+    marketplace: &Marketplace,
+    price_feed: Option<&AccountInfo>,
+    clock: &Clock,
+) -> Result<u64> {
+    let listing_price = effective_listing_price(property, clock.unix_timestamp)?;
+
+    match property.price_currency {
+        PriceCurrency::Lamports => Ok(listing_price),
+        PriceCurrency::UsdCents => {
+            let feed_info = price_feed.ok_or(ErrorCode::PriceFeedRequired)?;
+            require_keys_eq!(
+                feed_info.key(),
+                marketplace.sol_usd_price_feed,
+                ErrorCode::InvalidPriceFeed
+            );
+
+            let feed = SolanaPriceAccount::account_info_to_feed(feed_info)
+                .map_err(|_| ErrorCode::InvalidPriceFeed)?;
+            let sol_usd = feed
+                .get_price_no_older_than(clock.unix_timestamp, marketplace.max_price_age_secs)
+                .ok_or(ErrorCode::StalePrice)?;
+            require!(sol_usd.price > 0, ErrorCode::InvalidPriceFeed);
+
+            let confidence_bps = (sol_usd.conf as u128)
+                .checked_mul(10000)
+                .ok_or(ErrorCode::ArithmeticOverflow)?
+                .checked_div(sol_usd.price as u128)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            require!(
+                confidence_bps <= marketplace.max_confidence_bps as u128,
+                ErrorCode::PriceConfidenceTooWide
+            );
+
+            usd_cents_to_lamports(listing_price, sol_usd.price as u64, sol_usd.expo)
+        }
+    }
+}
+
+/// Publishes the marketplace's full configuration after any change to it
+fn emit_config_updated(marketplace: &Account<Marketplace>, timestamp: i64) {
+    emit!(MarketplaceConfigUpdated {
+        marketplace: marketplace.key(),
+        authority: marketplace.authority,
+        fee_percentage: marketplace.fee_percentage,
+        paused: marketplace.paused,
+        timelock_secs: marketplace.timelock_secs,
+        sol_usd_price_feed: marketplace.sol_usd_price_feed,
+        max_price_age_secs: marketplace.max_price_age_secs,
+        max_confidence_bps: marketplace.max_confidence_bps,
+        listing_fee_lamports: marketplace.listing_fee_lamports,
+        timestamp,
+    });
+}
+
+/// Listing and pricing checks every new offer must pass
+fn check_offer_terms(
+    property: &Property,
+    marketplace: &Marketplace,
+    price_feed: Option<&AccountInfo>,
+    clock: &Clock,
+    offer_amount: u64,
+    expiration_time: i64,
+) -> Result<()> {
+    require!(!marketplace.paused, ErrorCode::MarketplacePaused);
+    require!(property.is_active, ErrorCode::PropertyNotActive);
+    require!(property.bundle.is_none(), ErrorCode::PropertyInBundle);
+    require!(offer_amount > 0, ErrorCode::InvalidOfferAmount);
+    if let Some(listing_expiry) = property.expires_at {
+        require!(
+            clock.unix_timestamp < listing_expiry,
+            ErrorCode::ListingExpired
+        );
+    }
+
+    let asking_price = listing_price_in_lamports(property, marketplace, price_feed, clock)?;
+
+    // Offers at or above asking on auto-accept listings settle through buy_now
+    require!(
+        !(property.auto_accept && offer_amount >= asking_price),
+        ErrorCode::AutoAcceptAvailable
+    );
+
+    // Reject low-ball offers below the seller's threshold
+    if property.min_offer_bps > 0 {
+        let minimum_offer = (asking_price as u128)
+            .checked_mul(property.min_offer_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            offer_amount as u128 >= minimum_offer,
+            ErrorCode::OfferBelowMinimum
+        );
+    }
+
+    require!(
+        expiration_time > clock.unix_timestamp,
+        ErrorCode::InvalidExpirationTime
+    );
+
+    Ok(())
+}
+
+/// Marketplace fee for a sale after the best staking discount held by either party. Stake
+/// accounts that don't exist yet count as zero.
+fn settlement_fee_bps(
+    marketplace: &Marketplace,
+    staking_config: &AccountInfo,
+    stakes: [&AccountInfo; 2],
+) -> Result<u64> {
+    if staking_config.owner != &crate::ID {
+        return Ok(marketplace.fee_percentage);
+    }
+    let config = {
+        let data = staking_config.try_borrow_data()?;
+        StakingConfig::try_deserialize(&mut &data[..])?
+    };
+
+    let mut staked = 0u64;
+    for stake_info in stakes {
+        if stake_info.owner == &crate::ID {
+            let data = stake_info.try_borrow_data()?;
+            staked = staked.max(StakeAccount::try_deserialize(&mut &data[..])?.amount);
+        }
+    }
+
+    let discount_bps = config
+        .tiers
+        .iter()
+        .filter(|tier| staked >= tier.min_amount)
+        .map(|tier| tier.discount_bps as u64)
+        .max()
+        .unwrap_or(0);
+    Ok(marketplace
+        .fee_percentage
+        .checked_mul(10000 - discount_bps)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / 10000)
+}
+
+/// Moves a loan's NFT out of its vault and closes the vault, returning the rent to the borrower
+fn release_loan_collateral<'info>(
+    loan: &Account<'info, Loan>,
+    loan_bump: u8,
+    loan_nft_account: &Account<'info, TokenAccount>,
+    destination: &Account<'info, TokenAccount>,
+    borrower: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let loan_seeds: &[&[u8]] = &[b"loan", loan.property.as_ref(), &[loan_bump]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: loan_nft_account.to_account_info(),
+                to: destination.to_account_info(),
+                authority: loan.to_account_info(),
+            },
+            &[loan_seeds],
+        ),
+        1,
+    )?;
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: loan_nft_account.to_account_info(),
+            destination: borrower,
+            authority: loan.to_account_info(),
+        },
+        &[loan_seeds],
+    ))
+}
+
+/// Wallet that receives the seller's proceeds: the beneficiary the current owner configured, or the
+/// owner. A payout address set by a previous owner is ignored. Checks the PDA address so it can
+/// also be used on remaining accounts.
+fn payout_destination(
+    property: &Account<Property>,
+    payout_address: &AccountInfo,
+) -> Result<Pubkey> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"payout_address", property.key().as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(payout_address.key(), expected, ErrorCode::InvalidRemainingAccounts);
+    let info = payout_address.to_account_info();
+    if info.owner != &crate::ID {
+        return Ok(property.owner);
+    }
+    let payout_address = {
+        let data = info.try_borrow_data()?;
+        PayoutAddress::try_deserialize(&mut &data[..])?
+    };
+    Ok(if payout_address.owner == property.owner {
+        payout_address.beneficiary
+    } else {
+        property.owner
+    })
+}
+
+/// Reads a property's governance PDA, if one is attached. Checks the address so it can also be used
+/// on remaining accounts.
+fn property_governance(
+    property: &Pubkey,
+    property_governance: &AccountInfo,
+) -> Result<Option<PropertyGovernance>> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"property_governance", property.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(
+        property_governance.key(),
+        expected,
+        ErrorCode::InvalidRemainingAccounts
+    );
+    if property_governance.owner != &crate::ID {
+        return Ok(None);
+    }
+    let data = property_governance.try_borrow_data()?;
+    Ok(Some(PropertyGovernance::try_deserialize(&mut &data[..])?))
+}
+
+/// Bond the marketplace charges per listing, zero when it hasn't configured one
+fn listing_bond_lamports(listing_bond_config: &UncheckedAccount) -> Result<u64> {
+    let info = listing_bond_config.to_account_info();
+    if info.owner != &crate::ID {
+        return Ok(0);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(ListingBondConfig::try_deserialize(&mut &data[..])?.bond_lamports)
+}
+
+/// Most active listings `owner_listings` may hold: the authority's override for the wallet, else
+/// the marketplace's cap. Zero means unlimited.
+fn listing_cap(listing_limits: &UncheckedAccount, owner_listings: &OwnerListings) -> Result<u16> {
+    if let Some(max_listings) = owner_listings.max_listings {
+        return Ok(max_listings);
+    }
+    let info = listing_limits.to_account_info();
+    if info.owner != &crate::ID {
+        return Ok(0);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(ListingLimits::try_deserialize(&mut &data[..])?.max_listings_per_owner)
+}
+
+/// Counts an active listing against its owner, failing once their cap is reached. The slot is
+/// only required while a cap applies; one still held from before a pause is reused. Slots are
+/// given back by `free_listing_slot` when the listing is sold or delisted.
+fn take_listing_slot(
+    listing_limits: &UncheckedAccount,
+    owner_listings: &mut Account<OwnerListings>,
+    listing_slot: Option<&mut Account<ListingSlot>>,
+    marketplace: Pubkey,
+    owner: Pubkey,
+    property: Pubkey,
+    now: i64,
+) -> Result<()> {
+    owner_listings.marketplace = marketplace;
+    owner_listings.owner = owner;
+    let max_listings = listing_cap(listing_limits, owner_listings)?;
+
+    let slot = match listing_slot {
+        Some(slot) => slot,
+        None => {
+            require!(max_listings == 0, ErrorCode::ListingSlotRequired);
+            return Ok(());
+        }
+    };
+    if slot.property == property {
+        require_keys_eq!(slot.owner, owner, ErrorCode::ListingSlotHeld);
+        return Ok(());
+    }
+    require!(
+        max_listings == 0 || owner_listings.active_listings < max_listings,
+        ErrorCode::ListingLimitReached
+    );
+    owner_listings.active_listings = owner_listings
+        .active_listings
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    slot.property = property;
+    slot.marketplace = marketplace;
+    slot.owner = owner;
+    slot.taken_at = now;
+    Ok(())
+}
+
+/// Gives back the slot a listing took once it's sold or delisted, closing it to the owner who
+/// took it. Nothing to do when the listing holds no slot; one taken by an earlier owner is left
+/// to `release_listing_slot`.
+fn free_listing_slot(
+    listing_slot: &AccountInfo,
+    owner_listings: &mut OwnerListings,
+    owner: &AccountInfo,
+    now: i64,
+) -> Result<()> {
+    if listing_slot.owner != &crate::ID {
+        return Ok(());
+    }
+    let slot = {
+        let data = listing_slot.try_borrow_data()?;
+        ListingSlot::try_deserialize(&mut &data[..])?
+    };
+    if slot.owner != owner.key() {
+        return Ok(());
+    }
+    owner_listings.active_listings = owner_listings
+        .active_listings
+        .checked_sub(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    close_pda(listing_slot, owner)?;
+
+    emit!(ListingSlotReleased {
+        marketplace: slot.marketplace,
+        property: slot.property,
+        owner: slot.owner,
+        active_listings: owner_listings.active_listings,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// `free_listing_slot` for settlement paths, which take the owner's listing count unchecked
+fn free_listing_slot_unchecked(
+    listing_slot: &AccountInfo,
+    owner_listings: &AccountInfo,
+    owner: &AccountInfo,
+    now: i64,
+) -> Result<()> {
+    if listing_slot.owner != &crate::ID {
+        return Ok(());
+    }
+    let mut listings = {
+        let data = owner_listings.try_borrow_data()?;
+        OwnerListings::try_deserialize(&mut &data[..])?
+    };
+    free_listing_slot(listing_slot, &mut listings, owner, now)?;
+    let mut data = owner_listings.try_borrow_mut_data()?;
+    listings.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Returns a listing's bond to the seller who locked it once the listing is sold or delisted,
+/// closing the account to them. Nothing to do when the listing has no bond; one locked by an
+/// earlier owner is left to `release_listing_bond`.
+fn refund_listing_bond(listing_bond: &AccountInfo, owner: &AccountInfo, now: i64) -> Result<()> {
+    if listing_bond.owner != &crate::ID {
+        return Ok(());
+    }
+    let bond = {
+        let data = listing_bond.try_borrow_data()?;
+        ListingBond::try_deserialize(&mut &data[..])?
+    };
+    if bond.owner != owner.key() {
+        return Ok(());
+    }
+    close_pda(listing_bond, owner)?;
+
+    emit!(ListingBondReleased {
+        listing_bond: listing_bond.key(),
+        property: bond.property,
+        owner: bond.owner,
+        amount: bond.amount,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Protocol's cut of a settlement fee, checked against the protocol treasury account that will
+/// receive it. Zero until the protocol fee is configured.
+fn protocol_fee_share(
+    protocol_config: &AccountInfo,
+    protocol_treasury: Option<&AccountInfo>,
+    fee_amount: u64,
+) -> Result<u64> {
+    if protocol_config.owner != &crate::ID {
+        return Ok(0);
+    }
+    let protocol_config = {
+        let data = protocol_config.try_borrow_data()?;
+        ProtocolConfig::try_deserialize(&mut &data[..])?
+    };
+    if protocol_config.protocol_fee_bps == 0 {
+        return Ok(0);
+    }
+    let treasury = protocol_treasury.ok_or(ErrorCode::ProtocolTreasuryMismatch)?;
+    require_keys_eq!(treasury.key(), protocol_config.treasury, ErrorCode::ProtocolTreasuryMismatch);
+    Ok((fee_amount as u128)
+        .checked_mul(protocol_config.protocol_fee_bps as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::ArithmeticOverflow)? as u64)
+}
+
+/// Transfer tax owed on selling `property` for `price`, checked against the collector account
+/// that will receive it. Checks the tax config address so it can also be used on remaining
+/// accounts.
+fn transfer_tax(
+    property: &Property,
+    tax_config: &AccountInfo,
+    tax_collector: Option<&AccountInfo>,
+    price: u64,
+) -> Result<u64> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[
+            b"tax_config",
+            property.marketplace.as_ref(),
+            property.jurisdiction_code.as_ref(),
+        ],
+        &crate::ID,
+    );
+    require_keys_eq!(tax_config.key(), expected, ErrorCode::InvalidTaxConfig);
+    if tax_config.owner != &crate::ID {
+        return Ok(0);
+    }
+    let tax_config = {
+        let data = tax_config.try_borrow_data()?;
+        TaxConfig::try_deserialize(&mut &data[..])?
+    };
+    let collector = tax_collector.ok_or(ErrorCode::TaxCollectorMismatch)?;
+    require_keys_eq!(collector.key(), tax_config.collector, ErrorCode::TaxCollectorMismatch);
+    Ok((price as u128)
+        .checked_mul(tax_config.tax_bps as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::ArithmeticOverflow)? as u64)
+}
+
+fn total_deductions(deductions: &[DepositDeduction]) -> Result<u64> {
+    deductions.iter().try_fold(0u64, |total, d| {
+        total
+            .checked_add(d.amount)
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))
+    })
+}
+
+/// Splits an escrowed security deposit between landlord and tenant. The deposit account itself is
+/// closed to the landlord, who paid its rent, by the instruction's `close` constraint.
+fn pay_out_security_deposit<'info>(
+    deposit: &Account<'info, SecurityDeposit>,
+    landlord_amount: u64,
+    landlord: &AccountInfo<'info>,
+    tenant: &AccountInfo<'info>,
+    timestamp: i64,
+) -> Result<()> {
+    let tenant_amount = deposit
+        .amount
+        .checked_sub(landlord_amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    let deposit_info = deposit.to_account_info();
+    **deposit_info.try_borrow_mut_lamports()? = deposit_info
+        .lamports()
+        .checked_sub(deposit.amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    **landlord.try_borrow_mut_lamports()? = landlord
+        .lamports()
+        .checked_add(landlord_amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    **tenant.try_borrow_mut_lamports()? = tenant
+        .lamports()
+        .checked_add(tenant_amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(SecurityDepositSettled {
+        security_deposit: deposit.key(),
+        lease: deposit.lease,
+        landlord_amount,
+        tenant_amount,
+        timestamp,
+    });
+
+    Ok(())
+}
+
+/// Counts a new offer against the property, failing once the marketplace's cap is reached. The
+/// offer limits PDA is optional; without it offers are unlimited.
+fn open_offer(property: &mut Property, offer_limits: &UncheckedAccount) -> Result<()> {
+    let info = offer_limits.to_account_info();
+    if info.owner == &crate::ID {
+        let limits = {
+            let data = info.try_borrow_data()?;
+            OfferLimits::try_deserialize(&mut &data[..])?
+        };
+        require!(
+            limits.max_open_offers == 0 || property.open_offer_count < limits.max_open_offers,
+            ErrorCode::OpenOfferLimitReached
+        );
+    }
+    property.open_offer_count = property
+        .open_offer_count
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Releases an offer's slot once it is rejected, expired or completed. Saturates because offers
+/// made before the counter existed were never counted.
+fn close_offer(property: &mut Property) {
+    property.open_offer_count = property.open_offer_count.saturating_sub(1);
+}
+
+/// Moves a property to `new_owner` outside of a sale and records it as a sale at `price`. The
+/// new owner decides whether and at what price to list.
+fn hand_over(
+    property: &mut Account<Property>,
+    transaction_history: &mut Account<TransactionHistory>,
+    history_index: &mut Account<HistoryIndex>,
+    new_owner: Pubkey,
+    price: u64,
+    now: i64,
+) -> Result<()> {
+    let previous_owner = property.owner;
+    property.owner = new_owner;
+    property.is_active = false;
+    property.auto_accept = false;
+    property.price_schedule = None;
+    property.updated_at = now;
+    property.transaction_count = property
+        .transaction_count
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    transaction_history.property = property.key();
+    transaction_history.seller = previous_owner;
+    transaction_history.buyer = new_owner;
+    transaction_history.price = price;
+    transaction_history.timestamp = now;
+    transaction_history.transaction_index = property.transaction_count;
+    transaction_history.seller_concession = 0;
+    transaction_history.version = TRANSACTION_HISTORY_VERSION;
+
+    history_index.property = property.key();
+    history_index.count = property.transaction_count;
+    history_index.latest_history = transaction_history.key();
+    history_index.updated_at = now;
+    Ok(())
+}
+
+/// Points a property's HistoryIndex at its latest record, creating the index on first use. For
+/// settlement paths that take the index as a remaining account and can't use `init_if_needed`.
+fn write_history_index<'info>(
+    history_index_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    property: &Pubkey,
+    count: u64,
+    latest_history: Pubkey,
+    now: i64,
+) -> Result<()> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[b"history_index", property.as_ref()], &crate::ID);
+    require_keys_eq!(history_index_info.key(), expected, ErrorCode::InvalidRemainingAccounts);
+    require!(history_index_info.is_writable, ErrorCode::InvalidRemainingAccounts);
+
+    if history_index_info.owner != &crate::ID {
+        let space = 8 + HistoryIndex::INIT_SPACE;
+        anchor_lang::system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                anchor_lang::system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: history_index_info.clone(),
+                },
+                &[&[b"history_index", property.as_ref(), &[bump]]],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &crate::ID,
+        )?;
+    }
+
+    let history_index = HistoryIndex {
+        property: *property,
+        count,
+        latest_history,
+        updated_at: now,
+    };
+    let mut data = history_index_info.try_borrow_mut_data()?;
+    history_index.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Lamports an escrow PDA holds above its rent-exempt minimum. Escrows are program-owned data
+/// accounts, so funds leave by adjusting balances directly rather than through the System
+/// Program, and only this excess can go without breaking the account's rent exemption.
+fn escrowed_lamports(escrow: &AccountInfo) -> Result<u64> {
+    let rent_floor = Rent::get()?.minimum_balance(escrow.data_len());
+    Ok(escrow.lamports().saturating_sub(rent_floor))
+}
+
+/// Pays `amount` of an escrow PDA's escrowed lamports to `to`
+fn release_escrow(escrow: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    require!(
+        escrowed_lamports(escrow)? >= amount,
+        ErrorCode::InsufficientEscrowFunds
+    );
+    **escrow.try_borrow_mut_lamports()? = escrow
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Closes a program-owned PDA taken as an unchecked account, paying its lamports to `to`
+fn close_pda(info: &AccountInfo, to: &AccountInfo) -> Result<()> {
+    let lamports = info.lamports();
+    **info.try_borrow_mut_lamports()? = 0;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    info.assign(&anchor_lang::system_program::ID);
+    info.realloc(0, false)?;
+    Ok(())
+}
+
+/// Refunds and rejects a pending offer passed as an (offer, escrow, buyer) triple of remaining
+/// accounts, releasing its slot on the property
+fn reject_pending_offer<'info>(
+    property: &mut Account<'info, Property>,
+    accounts: &'info [AccountInfo<'info>],
+    now: i64,
+) -> Result<()> {
+    let (offer_info, escrow_info, buyer_info) = (&accounts[0], &accounts[1], &accounts[2]);
+
+    require!(
+        offer_info.is_writable && escrow_info.is_writable && buyer_info.is_writable,
+        ErrorCode::InvalidRemainingAccounts
+    );
+
+    let mut offer: Account<'info, Offer> = Account::try_from(offer_info)?;
+    let escrow: Account<'info, Escrow> = Account::try_from(escrow_info)?;
+
+    require!(
+        offer.property == property.key(),
+        ErrorCode::OfferPropertyMismatch
+    );
+    require!(
+        offer.status == OfferStatus::Pending,
+        ErrorCode::OfferNotPending
+    );
+    require!(
+        escrow.key() == offer.escrow && escrow.offer == offer.key(),
+        ErrorCode::EscrowMismatch
+    );
+    require!(
+        buyer_info.key() == offer.buyer,
+        ErrorCode::NotOfferBuyer
+    );
+
+    // Return the escrowed funds to the buyer
+    release_escrow(escrow_info, buyer_info, escrow.amount)?;
+
+    offer.status = OfferStatus::Rejected;
+    offer.updated_at = now;
+    offer.exit(&crate::ID)?;
+    close_offer(property);
+
+    emit!(OfferRejected {
+        offer: offer.key(),
+        marketplace: property.marketplace,
+        property: property.key(),
+        buyer: offer.buyer,
+        seller: property.owner,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Validates listing arguments and builds the new property they describe
+fn new_listing(
+    args: ListingArgs,
+    marketplace: Pubkey,
+    owner: Pubkey,
+    nft_mint: Pubkey,
+    now: i64,
+) -> Result<Property> {
+    validate_property_id(&args.property_id)?;
+    validate_listing_content(&args.metadata_uri, &args.location, &args.content_hashes)?;
+    require!(args.price > 0, ErrorCode::InvalidPrice);
+    validate_amenities(args.property_type, args.amenities)?;
+    let geohash = encode_geohash(&args.geohash)?;
+    validate_coordinates(args.latitude_e6, args.longitude_e6)?;
+    if let Some(expiry) = args.expires_at {
+        require!(expiry > now, ErrorCode::InvalidExpirationTime);
+    }
+
+    Ok(Property {
+        marketplace,
+        owner,
+        property_id: args.property_id,
+        price: args.price,
+        metadata_uri: args.metadata_uri,
+        location: args.location,
+        square_feet: args.square_feet,
+        bedrooms: args.bedrooms,
+        bathrooms: args.bathrooms,
+        is_active: true,
+        created_at: now,
+        updated_at: now,
+        transaction_count: 0,
+        nft_mint,
+        min_offer_bps: 0,
+        cadastral_id: [0; 32],
+        jurisdiction_code: [0; 8],
+        registry_verified: false,
+        registry_verifier: Pubkey::default(),
+        registry_verified_at: 0,
+        auto_accept: false,
+        expires_at: args.expires_at,
+        price_currency: PriceCurrency::Lamports,
+        attribute_change_count: 0,
+        price_schedule: None,
+        co_owned: false,
+        bundle: None,
+        parent: None,
+        child_count: 0,
+        merged_into: None,
+        lien_count: 0,
+        document_count: 0,
+        property_type: args.property_type,
+        amenities: args.amenities,
+        geohash,
+        latitude_e6: args.latitude_e6,
+        longitude_e6: args.longitude_e6,
+        version: PROPERTY_VERSION,
+        open_offer_count: 0,
+        approval_authority: None,
+        energy_rating: EnergyRating::Unrated,
+        leed_certificate_hash: None,
+        epc_certificate_hash: None,
+        energy_verifier: None,
+        energy_attested_at: 0,
+        bump: 0,
+        content_hashes: args.content_hashes,
+    })
+}
+
+fn emit_listed(key: Pubkey, property: &Property, listing_fee: u64, timestamp: i64) {
+    emit!(PropertyListed {
+        property: key,
+        owner: property.owner,
+        property_id: property.property_id.clone(),
+        price: property.price,
+        listing_fee,
+        nft_mint: property.nft_mint,
+        property_type: property.property_type,
+        amenities: property.amenities,
+        geohash: property.geohash,
+        latitude_e6: property.latitude_e6,
+        longitude_e6: property.longitude_e6,
+        content_hashes: property.content_hashes,
+        timestamp,
+    });
+}
+
+/// Books a settlement fee that has already been moved into the treasury
+fn record_fee(treasury: &mut Account<Treasury>, marketplace: Pubkey, fee_amount: u64) -> Result<()> {
+    treasury.marketplace = marketplace;
+    treasury.total_collected = treasury
+        .total_collected
+        .checked_add(fee_amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Charges the marketplace's flat listing fee into its treasury
+fn collect_listing_fee<'info>(
+    marketplace: &Account<'info, Marketplace>,
+    treasury: Option<&mut Account<'info, Treasury>>,
+    owner: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<u64> {
+    let listing_fee = marketplace.listing_fee_lamports;
+    if listing_fee == 0 {
+        return Ok(0);
+    }
+    let treasury = treasury.ok_or(ErrorCode::TreasuryRequired)?;
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: owner.to_account_info(),
+                to: treasury.to_account_info(),
+            },
+        ),
+        listing_fee,
+    )?;
+    record_fee(treasury, marketplace.key(), listing_fee)?;
+    Ok(listing_fee)
+}
+
+/// Clears the bundle marker on every property of `bundle`, passed in bundle order
+fn release_bundle_properties<'info>(
+    bundle: &Account<'info, Bundle>,
+    property_infos: &'info [AccountInfo<'info>],
+    now: i64,
+) -> Result<()> {
+    require!(
+        property_infos.len() == bundle.property_count as usize,
+        ErrorCode::InvalidRemainingAccounts
+    );
+
+    for (i, property_info) in property_infos.iter().enumerate() {
+        require!(
+            property_info.key() == bundle.properties[i] && property_info.is_writable,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let mut property: Account<'info, Property> = Account::try_from(property_info)?;
+        if property.bundle == Some(bundle.key()) {
+            property.bundle = None;
+            property.updated_at = now;
+            property.exit(&crate::ID)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Requires the co-owner approval threshold to have been met for `target` on co-owned
+/// properties, then clears the tally so it cannot be reused.
+fn consume_co_owner_approval(
+    property: &Property,
+    co_ownership: Option<&mut CoOwnership>,
+    target: Pubkey,
+) -> Result<()> {
+    if !property.co_owned {
+        return Ok(());
+    }
+
+    let co_ownership = co_ownership.ok_or(ErrorCode::CoOwnershipRequired)?;
+    require!(
+        co_ownership.approval_target == target
+            && co_ownership.approvals.count_ones() >= co_ownership.threshold as u32,
+        ErrorCode::InsufficientCoOwnerApprovals
+    );
+
+    co_ownership.approval_target = Pubkey::default();
+    co_ownership.approvals = 0;
+    Ok(())
+}
+
+/// Bump of the PDA at `address` derived from `seeds`, for accounts created before bumps were
+/// stored
+fn pda_bump(seeds: &[&[u8]], address: &Pubkey) -> Result<u8> {
+    let (derived, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+    require_keys_eq!(derived, *address, ErrorCode::InvalidMigration);
+    Ok(bump)
+}
+
+/// Writes an upgraded account over its old contents, growing it and topping up rent from `payer`
+fn rewrite_account<'info, T: AccountSerialize>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    upgraded: &T,
+    space: usize,
+) -> Result<()> {
+    let space = space.max(info.data_len());
+    let required = Rent::get()?.minimum_balance(space);
+    if required > info.lamports() {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            required - info.lamports(),
+        )?;
+    }
+    if space > info.data_len() {
+        info.realloc(space, false)?;
+    }
+
+    let mut data = info.try_borrow_mut_data()?;
+    upgraded.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Property account size for the current layout with room for a metadata URI of `metadata_uri_len`.
+/// Only the None tag of the content hashes is reserved, full listings never store them.
+pub fn property_space(metadata_uri_len: usize) -> usize {
+    8 + Property::INIT_SPACE - INITIAL_METADATA_URI_LEN + metadata_uri_len
+        - ContentHashes::INIT_SPACE
+}
+
+/// Property account size for a slim listing: content hashes but no metadata URI or location
+pub fn slim_property_space() -> usize {
+    8 + Property::INIT_SPACE - INITIAL_METADATA_URI_LEN - MAX_LOCATION_LEN
+}
+
+/// Space allocated for a new listing, slim when it carries content hashes
+fn listing_space(content_hashes: &Option<ContentHashes>, metadata_uri_len: usize) -> usize {
+    match content_hashes {
+        Some(_) => slim_property_space(),
+        None => property_space(metadata_uri_len.max(INITIAL_METADATA_URI_LEN)),
+    }
+}
+
+/// Slim listings keep the metadata URI and location off-chain, so both strings must be empty
+fn validate_listing_content(
+    metadata_uri: &str,
+    location: &str,
+    content_hashes: &Option<ContentHashes>,
+) -> Result<()> {
+    match content_hashes {
+        Some(hashes) => {
+            validate_content_hashes(hashes)?;
+            require!(metadata_uri.is_empty() && location.is_empty(), ErrorCode::InvalidContentHashes);
+            Ok(())
+        }
+        None => {
+            validate_metadata_uri(metadata_uri, INITIAL_METADATA_URI_LEN)?;
+            validate_location(location)
+        }
+    }
+}
+
+fn validate_content_hashes(hashes: &ContentHashes) -> Result<()> {
+    require!(
+        hashes.metadata_hash != [0; 32] && hashes.location_hash != [0; 32],
+        ErrorCode::InvalidContentHashes
+    );
+    Ok(())
+}
+
+/// Property ids end up in PDA seeds and URLs, so only ASCII letters, digits, `-` and `_` are allowed
+fn validate_property_id(property_id: &str) -> Result<()> {
+    require!(property_id.len() <= MAX_PROPERTY_ID_LEN, ErrorCode::PropertyIdTooLong);
+    require!(
+        !property_id.is_empty()
+            && property_id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'),
+        ErrorCode::InvalidPropertyId
+    );
+    Ok(())
+}
+
+/// Limits are in bytes, which is what the account layout reserves, not characters
+fn validate_location(location: &str) -> Result<()> {
+    require!(location.len() <= MAX_LOCATION_LEN, ErrorCode::LocationTooLong);
+    require!(
+        !location.trim().is_empty() && !location.chars().any(char::is_control),
+        ErrorCode::InvalidLocation
+    );
+    Ok(())
+}
+
+fn validate_metadata_uri(metadata_uri: &str, max_len: usize) -> Result<()> {
+    require!(metadata_uri.len() <= max_len, ErrorCode::MetadataUriTooLong);
+    require!(
+        !metadata_uri.is_empty() && metadata_uri.bytes().all(|b| b.is_ascii_graphic()),
+        ErrorCode::InvalidMetadataUri
+    );
+    Ok(())
+}
+
+fn validate_amenities(property_type: PropertyType, amenities: u64) -> Result<()> {
+    require!(amenities & !KNOWN_AMENITIES == 0, ErrorCode::InvalidAmenities);
+    if property_type == PropertyType::Land {
+        require!(amenities & BUILDING_AMENITIES == 0, ErrorCode::InvalidAmenities);
+    }
+    Ok(())
+}
+
+fn encode_geohash(geohash: &str) -> Result<[u8; GEOHASH_LEN]> {
+    let bytes = geohash.as_bytes();
+    require!(
+        (MIN_GEOHASH_LEN..=GEOHASH_LEN).contains(&bytes.len())
+            && bytes.iter().all(|c| GEOHASH_ALPHABET.contains(c)),
+        ErrorCode::InvalidGeohash
+    );
+    let mut encoded = [0u8; GEOHASH_LEN];
+    encoded[..bytes.len()].copy_from_slice(bytes);
+    Ok(encoded)
+}
+
+/// Seed for a parcel's PropertyRegistry. Both codes are upper-cased with everything but ASCII
+/// letters and digits dropped, so "12-345 a" and "12345A" name the same parcel.
+pub fn normalized_parcel_key(cadastral_id: &[u8; 32], jurisdiction_code: &[u8; 8]) -> [u8; 32] {
+    fn normalize<const N: usize>(code: &[u8; N]) -> [u8; N] {
+        let mut normalized = [0u8; N];
+        for (slot, c) in normalized
+            .iter_mut()
+            .zip(code.iter().filter(|c| c.is_ascii_alphanumeric()))
+        {
+            *slot = c.to_ascii_uppercase();
+        }
+        normalized
+    }
+    // The jurisdiction keeps its fixed width so the two codes cannot run into each other
+    let jurisdiction = normalize(jurisdiction_code);
+    let cadastral = normalize(cadastral_id);
+    anchor_lang::solana_program::hash::hashv(&[jurisdiction.as_ref(), cadastral.as_ref()]).to_bytes()
+}
+
+/// Coordinates are micro-degrees and must be given together
+fn validate_coordinates(latitude_e6: Option<i32>, longitude_e6: Option<i32>) -> Result<()> {
+    match (latitude_e6, longitude_e6) {
+        (None, None) => Ok(()),
+        (Some(lat), Some(lon)) => {
+            require!(
+                (-90_000_000..=90_000_000).contains(&lat) && (-180_000_000..=180_000_000).contains(&lon),
+                ErrorCode::InvalidCoordinates
+            );
+            Ok(())
+        }
+        _ => Err(ErrorCode::InvalidCoordinates.into()),
+    }
+}
+
+/// Returns the asking price in the listing's currency, applying any scheduled reductions
+pub fn effective_listing_price(property: &Property, now: i64) -> Result<u64> {
+    let schedule = match &property.price_schedule {
+        Some(schedule) => schedule,
+        None => return Ok(property.price),
+    };
+
+    let elapsed = now.saturating_sub(schedule.start_time).max(0);
+    let steps = (elapsed / schedule.interval_secs) as u64;
+    let reduction = steps.saturating_mul(schedule.drop_amount);
+
+    Ok(schedule
+        .start_price
+        .saturating_sub(reduction)
+        .max(schedule.floor_price))
+}
+
+/// lamports = cents * 10^9 / (100 * price * 10^expo)
+fn usd_cents_to_lamports(usd_cents: u64, price: u64, expo: i32) -> Result<u64> {
+    let scale = 10u128
+        .checked_pow(expo.unsigned_abs())
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    let numerator = (usd_cents as u128)
+        .checked_mul(10_000_000)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    let lamports = if expo <= 0 {
+        numerator
+            .checked_mul(scale)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_div(price as u128)
+    } else {
+        numerator.checked_div(
+            (price as u128)
+                .checked_mul(scale)
+                .ok_or(ErrorCode::ArithmeticOverflow)?,
+        )
+    }
+    .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    u64::try_from(lamports).map_err(|_| ErrorCode::ArithmeticOverflow.into())
+}
+
+#[derive(Accounts)]
+#[instruction(marketplace_fee: u64)]
+pub struct InitializeMarketplace<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Marketplace::INIT_SPACE,
+        seeds = [b"marketplace", authority.key().as_ref()],
+        bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureProtocol<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::RealEstateMarketplace>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    property_id: String,
+    price: u64,
+    metadata_uri: String,
+    location: String,
+    square_feet: u64,
+    bedrooms: u8,
+    bathrooms: u8,
+    expires_at: Option<i64>,
+    property_type: PropertyType,
+    amenities: u64,
+    geohash: String,
+    latitude_e6: Option<i32>,
+    longitude_e6: Option<i32>,
+    content_hashes: Option<ContentHashes>
+)]
+pub struct ListProperty<'info> {
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = owner,
+        space = listing_space(&content_hashes, 0),
+        seeds = [b"property", marketplace.key().as_ref(), property_id.as_bytes()],
+        bump
+    )]
+    pub property: Account<'info, Property>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, mint::authority = owner)]
+    pub property_nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = owner
+    )]
+    pub owner_nft_account: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: Listing bond config PDA, only read when the marketplace has configured bonds
+    #[account(seeds = [b"listing_bond_config", marketplace.key().as_ref()], bump)]
+    pub listing_bond_config: UncheckedAccount<'info>,
+    /// Required when the marketplace charges a listing bond
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ListingBond::INIT_SPACE,
+        seeds = [b"listing_bond", property.key().as_ref()],
+        bump
+    )]
+    pub listing_bond: Option<Account<'info, ListingBond>>,
+    /// Required when the marketplace charges a listing fee
+    #[account(mut, seeds = [b"treasury", marketplace.key().as_ref()], bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+    /// CHECK: Listing limits PDA, only read when the marketplace caps listings per owner
+    #[account(seeds = [b"listing_limits", marketplace.key().as_ref()], bump)]
+    pub listing_limits: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OwnerListings::INIT_SPACE,
+        seeds = [b"owner_listings", marketplace.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: Account<'info, OwnerListings>,
+    /// Required while a listing cap applies to the owner
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ListingSlot::INIT_SPACE,
+        seeds = [b"listing_slot", property.key().as_ref()],
+        bump
+    )]
+    pub listing_slot: Option<Account<'info, ListingSlot>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProperty<'info> {
+    #[account(mut, has_one = owner @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = owner,
+        constraint = owner_nft_account.amount >= 1 @ ErrorCode::NotNFTOwner
+    )]
+    pub owner_nft_account: Account<'info, TokenAccount>,
+    #[account(address = property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"co_ownership", property.key().as_ref()],
+        bump
+    )]
+    pub co_ownership: Option<Account<'info, CoOwnership>>,
+    /// CHECK: Listing limits PDA, only read when the marketplace caps listings per owner
+    #[account(seeds = [b"listing_limits", property.marketplace.as_ref()], bump)]
+    pub listing_limits: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OwnerListings::INIT_SPACE,
+        seeds = [b"owner_listings", property.marketplace.as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: Account<'info, OwnerListings>,
+    /// Required when relisting while a listing cap applies to the owner, and freed on delisting
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + ListingSlot::INIT_SPACE,
+        seeds = [b"listing_slot", property.key().as_ref()],
+        bump
+    )]
+    pub listing_slot: Option<Account<'info, ListingSlot>>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Listing bond PDA, only read on delisting when the seller locked one
+    #[account(mut, seeds = [b"listing_bond", property.key().as_ref()], bump)]
+    pub listing_bond: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_amount: u64, expiration_time: i64, offer_nonce: u64)]
+pub struct MakeOffer<'info> {
+    #[account(
+        constraint = property.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        constraint = property.is_active,
+        constraint = property.owner != *buyer.key
+    )]
+    pub property: Account<'info, Property>,
+    /// CHECK: Offer limits PDA, only read once the authority has configured it
+    #[account(seeds = [b"offer_limits", marketplace.key().as_ref()], bump)]
+    pub offer_limits: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Offer::INIT_SPACE,
+        seeds = [
+            b"offer",
+            property.key().as_ref(),
+            buyer.key().as_ref(),
+            &offer_nonce.to_le_bytes()
+        ],
+        bump
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", offer.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// CHECK: Pyth SOL/USD price account, validated against the marketplace config when used
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RespondToOffer<'info> {
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        has_one = marketplace @ ErrorCode::MarketplaceMismatch,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        has_one = property @ ErrorCode::OfferPropertyMismatch,
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        has_one = offer @ ErrorCode::EscrowMismatch,
+        has_one = property @ ErrorCode::EscrowMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: This is the buyer account to return funds if offer is rejected
+    #[account(mut)]
+    pub buyer: AccountInfo<'info>,
+    #[account(
+        mut,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = owner
+    )]
+    pub seller_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = escrow
+    )]
+    pub escrow_nft_account: Account<'info, TokenAccount>,
+    #[account(address = property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        seeds = [b"co_ownership", property.key().as_ref()],
+        bump
+    )]
+    pub co_ownership: Option<Account<'info, CoOwnership>>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SellerConcession::INIT_SPACE,
+        seeds = [b"seller_concession", offer.key().as_ref()],
+        bump
+    )]
+    pub seller_concession: Option<Account<'info, SellerConcession>>,
+    pub system_program: Option<Program<'info, System>>,
+    /// CHECK: Property governance PDA, only read when a governance is attached
+    #[account(seeds = [b"property_governance", property.key().as_ref()], bump)]
+    pub property_governance: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"sale_authorization", property.key().as_ref()],
+        bump
+    )]
+    pub sale_authorization: Option<Account<'info, SaleAuthorization>>,
+}
+
+#[derive(Accounts)]
+pub struct RejectRemainingOffers<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        constraint = accepted_offer.property == property.key() @ ErrorCode::OfferPropertyMismatch,
+        constraint = accepted_offer.status == OfferStatus::Accepted
+            || accepted_offer.status == OfferStatus::Completed @ ErrorCode::OfferNotAccepted
+    )]
+    pub accepted_offer: Account<'info, Offer>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSale<'info> {
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        constraint = property.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        constraint = offer.property == property.key(),
+        constraint = offer.status == OfferStatus::Accepted,
+        constraint = offer.buyer == *buyer.key
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        seeds = [b"escrow", offer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.offer == offer.key(),
+        has_one = property @ ErrorCode::OfferPropertyMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TransactionHistory::INIT_SPACE,
+        seeds = [
+            b"transaction",
+            property.key().as_ref(),
+            &property.transaction_count.checked_add(1).ok_or(ErrorCode::ArithmeticOverflow)?.to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_history: Account<'info, TransactionHistory>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + HistoryIndex::INIT_SPACE,
+        seeds = [b"history_index", property.key().as_ref()],
+        bump
+    )]
+    pub history_index: Account<'info, HistoryIndex>,
+    /// CHECK: Seller concession PDA, only read when the seller created one at acceptance
+    #[account(
+        mut,
+        seeds = [b"seller_concession", offer.key().as_ref()],
+        bump
+    )]
+    pub seller_concession: UncheckedAccount<'info>,
+    /// CHECK: Release schedule PDA, only used when the buyer set one before acceptance
+    #[account(
+        mut,
+        seeds = [b"release_schedule", escrow.key().as_ref()],
+        bump
+    )]
+    pub release_schedule: UncheckedAccount<'info>,
+    /// CHECK: Buyer of the offer, credited any seller concession
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: This is the seller account that will receive the SOL payment
+    #[account(
+        mut,
+        constraint = property.owner == *seller.key
+    )]
+    pub seller: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Staking config PDA, only read when the marketplace has configured staking
+    #[account(seeds = [b"staking_config", marketplace.key().as_ref()], bump)]
+    pub staking_config: UncheckedAccount<'info>,
+    /// CHECK: Buyer's stake PDA, only read when the buyer has staked
+    #[account(seeds = [b"stake", marketplace.key().as_ref(), buyer.key().as_ref()], bump)]
+    pub buyer_stake: UncheckedAccount<'info>,
+    /// CHECK: Seller's stake PDA, only read when the seller has staked
+    #[account(seeds = [b"stake", marketplace.key().as_ref(), seller.key().as_ref()], bump)]
+    pub seller_stake: UncheckedAccount<'info>,
+    /// CHECK: Payout address PDA, only read when the seller has configured one
+    #[account(seeds = [b"payout_address", property.key().as_ref()], bump)]
+    pub payout_address: UncheckedAccount<'info>,
+    /// CHECK: Receives the seller's proceeds; checked against the payout address in the handler
+    #[account(mut)]
+    pub payout: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = escrow_nft_account.mint == property.nft_mint
+            && escrow_nft_account.owner == escrow.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub escrow_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_nft_account.mint == property.nft_mint
+            && buyer_nft_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub buyer_nft_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    #[account(
+        seeds = [b"co_ownership", property.key().as_ref()],
+        bump
+    )]
+    pub co_ownership: Option<Account<'info, CoOwnership>>,
+    /// CHECK: Title insurance PDA, only read when the buyer added a policy to the offer
+    #[account(
+        mut,
+        seeds = [b"title_insurance", offer.key().as_ref()],
+        bump
+    )]
+    pub title_insurance: UncheckedAccount<'info>,
+    /// CHECK: Receives the title insurance premium; checked against the policy in the handler
+    #[account(mut)]
+    pub insurer: Option<UncheckedAccount<'info>>,
+    /// CHECK: Tax config PDA for the property's jurisdiction, only read when one is configured
+    #[account(
+        seeds = [b"tax_config", property.marketplace.as_ref(), property.jurisdiction_code.as_ref()],
+        bump
+    )]
+    pub tax_config: UncheckedAccount<'info>,
+    /// CHECK: Receives the transfer tax; checked against the tax config in the handler
+    #[account(mut)]
+    pub tax_collector: Option<UncheckedAccount<'info>>,
+    /// CHECK: Program-wide protocol config PDA, only read once the protocol fee is configured
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>,
+    /// CHECK: Receives the protocol's share of the fee; checked against the protocol config
+    #[account(mut)]
+    pub protocol_treasury: Option<UncheckedAccount<'info>>,
+    /// CHECK: Transfer approval PDA, only read when the property has an approval authority
+    #[account(mut, seeds = [b"transfer_approval", offer.key().as_ref()], bump)]
+    pub transfer_approval: UncheckedAccount<'info>,
+    /// CHECK: Closing schedule PDA, only read when the buyer proposed a closing date
+    #[account(
+        mut,
+        seeds = [b"closing_schedule", offer.key().as_ref()],
+        bump
+    )]
+    pub closing_schedule: UncheckedAccount<'info>,
+    /// CHECK: Listing slot PDA, only read when the listing took one
+    #[account(mut, seeds = [b"listing_slot", property.key().as_ref()], bump)]
+    pub listing_slot: UncheckedAccount<'info>,
+    /// CHECK: Seller's listing count PDA, only read when the listing took a slot
+    #[account(
+        mut,
+        seeds = [b"owner_listings", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: UncheckedAccount<'info>,
+    /// CHECK: Listing bond PDA, only read when the seller locked one
+    #[account(mut, seeds = [b"listing_bond", property.key().as_ref()], bump)]
+    pub listing_bond: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveReview<'info> {
+    pub transaction_history: Account<'info, TransactionHistory>,
+    #[account(
+        init,
+        payer = reviewer,
+        space = 8 + Review::INIT_SPACE,
+        seeds = [b"review", transaction_history.key().as_ref(), reviewer.key().as_ref()],
+        bump
+    )]
+    pub review: Account<'info, Review>,
+    #[account(
+        mut,
+        constraint = transaction_history.buyer == *reviewer.key
+            || transaction_history.seller == *reviewer.key @ ErrorCode::NotTransactionParty
+    )]
+    pub reviewer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(verifier: Pubkey)]
+pub struct RegisterVerifier<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Verifier::INIT_SPACE,
+        seeds = [b"verifier", marketplace.key().as_ref(), verifier.as_ref()],
+        bump
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveVerifier<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"verifier", marketplace.key().as_ref(), verifier_account.verifier.as_ref()],
+        bump
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(insurer: Pubkey)]
+pub struct RegisterInsurer<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Insurer::INIT_SPACE,
+        seeds = [b"insurer", marketplace.key().as_ref(), insurer.as_ref()],
+        bump
+    )]
+    pub insurer_account: Account<'info, Insurer>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveInsurer<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"insurer", marketplace.key().as_ref(), insurer_account.insurer.as_ref()],
+        bump
+    )]
+    pub insurer_account: Account<'info, Insurer>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddTitleInsurance<'info> {
+    #[account(has_one = buyer @ ErrorCode::NotOfferBuyer)]
+    pub offer: Account<'info, Offer>,
+    #[account(constraint = property.key() == offer.property @ ErrorCode::OfferPropertyMismatch)]
+    pub property: Account<'info, Property>,
+    #[account(
+        seeds = [b"insurer", property.marketplace.as_ref(), insurer_account.insurer.as_ref()],
+        bump
+    )]
+    pub insurer_account: Account<'info, Insurer>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + TitleInsurance::INIT_SPACE,
+        seeds = [b"title_insurance", offer.key().as_ref()],
+        bump
+    )]
+    pub title_insurance: Account<'info, TitleInsurance>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTitleInsurance<'info> {
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"title_insurance", offer.key().as_ref()],
+        bump,
+        has_one = offer @ ErrorCode::OfferPropertyMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub title_insurance: Account<'info, TitleInsurance>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(cadastral_id: [u8; 32], jurisdiction_code: [u8; 8], parcel_key: [u8; 32])]
+pub struct SetLandRegistryInfo<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PropertyRegistry::INIT_SPACE,
+        seeds = [b"property_registry", parcel_key.as_ref()],
+        bump
+    )]
+    pub property_registry: Account<'info, PropertyRegistry>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyProperty<'info> {
+    #[account(
+        mut,
+        constraint = property.marketplace == verifier_account.marketplace @ ErrorCode::NotRegisteredVerifier
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        seeds = [b"verifier", property.marketplace.as_ref(), verifier.key().as_ref()],
+        bump,
+        constraint = verifier_account.verifier == *verifier.key @ ErrorCode::NotRegisteredVerifier
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+    pub verifier: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AttestEnergyRating<'info> {
+    #[account(mut)]
+    pub property: Account<'info, Property>,
+    #[account(
+        seeds = [b"verifier", property.marketplace.as_ref(), verifier.key().as_ref()],
+        bump,
+        constraint = verifier_account.verifier == *verifier.key @ ErrorCode::NotRegisteredVerifier
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+    pub verifier: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoAccept<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = property.nft_mint,
+        token::authority = owner
+    )]
+    pub owner_nft_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Property governance PDA, only read when a governance is attached
+    #[account(seeds = [b"property_governance", property.key().as_ref()], bump)]
+    pub property_governance: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyNow<'info> {
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"property", property.marketplace.as_ref(), property.property_id.as_bytes()],
+        bump = property.bump,
+        constraint = property.marketplace == marketplace.key(),
+        constraint = property.owner != *buyer.key @ ErrorCode::CannotOfferOwnProperty
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + TransactionHistory::INIT_SPACE,
+        seeds = [
+            b"transaction",
+            property.key().as_ref(),
+            &property.transaction_count.checked_add(1).ok_or(ErrorCode::ArithmeticOverflow)?.to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_history: Account<'info, TransactionHistory>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + HistoryIndex::INIT_SPACE,
+        seeds = [b"history_index", property.key().as_ref()],
+        bump
+    )]
+    pub history_index: Account<'info, HistoryIndex>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// CHECK: This is the seller account that will receive the SOL payment
+    #[account(
+        mut,
+        constraint = property.owner == *seller.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub seller: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Staking config PDA, only read when the marketplace has configured staking
+    #[account(seeds = [b"staking_config", marketplace.key().as_ref()], bump)]
+    pub staking_config: UncheckedAccount<'info>,
+    /// CHECK: Buyer's stake PDA, only read when the buyer has staked
+    #[account(seeds = [b"stake", marketplace.key().as_ref(), buyer.key().as_ref()], bump)]
+    pub buyer_stake: UncheckedAccount<'info>,
+    /// CHECK: Seller's stake PDA, only read when the seller has staked
+    #[account(seeds = [b"stake", marketplace.key().as_ref(), seller.key().as_ref()], bump)]
+    pub seller_stake: UncheckedAccount<'info>,
+    /// CHECK: Payout address PDA, only read when the seller has configured one
+    #[account(seeds = [b"payout_address", property.key().as_ref()], bump)]
+    pub payout_address: UncheckedAccount<'info>,
+    /// CHECK: Receives the seller's proceeds; checked against the payout address in the handler
+    #[account(mut)]
+    pub payout: UncheckedAccount<'info>,
+    #[account(address = property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = property_nft_mint,
+        token::authority = seller
+    )]
+    pub seller_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_nft_account: Account<'info, TokenAccount>,
+    /// CHECK: Pyth SOL/USD price account, validated against the marketplace config when used
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Tax config PDA for the property's jurisdiction, only read when one is configured
+    #[account(
+        seeds = [b"tax_config", property.marketplace.as_ref(), property.jurisdiction_code.as_ref()],
+        bump
+    )]
+    pub tax_config: UncheckedAccount<'info>,
+    /// CHECK: Receives the transfer tax; checked against the tax config in the handler
+    #[account(mut)]
+    pub tax_collector: Option<UncheckedAccount<'info>>,
+    /// CHECK: Program-wide protocol config PDA, only read once the protocol fee is configured
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>,
+    /// CHECK: Receives the protocol's share of the fee; checked against the protocol config
+    #[account(mut)]
+    pub protocol_treasury: Option<UncheckedAccount<'info>>,
+    /// CHECK: Listing slot PDA, only read when the listing took one
+    #[account(mut, seeds = [b"listing_slot", property.key().as_ref()], bump)]
+    pub listing_slot: UncheckedAccount<'info>,
+    /// CHECK: Seller's listing count PDA, only read when the listing took a slot
+    #[account(
+        mut,
+        seeds = [b"owner_listings", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: UncheckedAccount<'info>,
+    /// CHECK: Listing bond PDA, only read when the seller locked one
+    #[account(mut, seeds = [b"listing_bond", property.key().as_ref()], bump)]
+    pub listing_bond: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireListing<'info> {
+    #[account(mut)]
+    pub property: Account<'info, Property>,
+    /// CHECK: The listing's owner, who gets back its listing slot
+    #[account(mut, address = property.owner @ ErrorCode::NotPropertyOwner)]
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: Listing slot PDA, only read when the listing took one
+    #[account(mut, seeds = [b"listing_slot", property.key().as_ref()], bump)]
+    pub listing_slot: UncheckedAccount<'info>,
+    /// CHECK: Owner's listing count PDA, only read when the listing took a slot
+    #[account(
+        mut,
+        seeds = [b"owner_listings", property.marketplace.as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: UncheckedAccount<'info>,
+    /// CHECK: Listing bond PDA, only read when the seller locked one
+    #[account(mut, seeds = [b"listing_bond", property.key().as_ref()], bump)]
+    pub listing_bond: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigurePriceOracle<'info> {
+    #[account(mut, has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPriceCurrency<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePropertyAttributes<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AttributeChange::INIT_SPACE,
+        seeds = [
+            b"attribute_change",
+            property.key().as_ref(),
+            &property.attribute_change_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub attribute_change: Account<'info, AttributeChange>,
+    #[account(
+        seeds = [b"verifier", property.marketplace.as_ref(), verifier.key().as_ref()],
+        bump,
+        constraint = verifier_account.verifier == *verifier.key @ ErrorCode::NotRegisteredVerifier
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub verifier: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPriceSchedule<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCoOwnership<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    // Reused after a resale, once the previous group's arrangement has been settled
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CoOwnership::INIT_SPACE,
+        seeds = [b"co_ownership", property.key().as_ref()],
+        bump
+    )]
+    pub co_ownership: Account<'info, CoOwnership>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveSale<'info> {
+    #[account(constraint = property.co_owned @ ErrorCode::CoOwnershipRequired)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        seeds = [b"co_ownership", property.key().as_ref()],
+        bump
+    )]
+    pub co_ownership: Account<'info, CoOwnership>,
+    pub co_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: u64)]
+pub struct CreateBundle<'info> {
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Bundle::INIT_SPACE,
+        seeds = [b"bundle", seller.key().as_ref(), &bundle_id.to_le_bytes()],
+        bump
+    )]
+    pub bundle: Account<'info, Bundle>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBundle<'info> {
+    #[account(
+        mut,
+        constraint = bundle.seller == *seller.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub bundle: Account<'info, Bundle>,
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_amount: u64, expiration_time: i64, offer_nonce: u64)]
+pub struct MakeBundleOffer<'info> {
+    #[account(
+        constraint = bundle.is_active @ ErrorCode::BundleNotActive,
+        constraint = bundle.seller != *buyer.key
+    )]
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + BundleOffer::INIT_SPACE,
+        seeds = [
+            b"bundle_offer",
+            bundle.key().as_ref(),
+            buyer.key().as_ref(),
+            &offer_nonce.to_le_bytes()
+        ],
+        bump
+    )]
+    pub bundle_offer: Account<'info, BundleOffer>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RejectBundleOffer<'info> {
+    #[account(constraint = bundle.seller == *seller.key @ ErrorCode::NotPropertyOwner)]
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        mut,
+        close = buyer,
+        constraint = bundle_offer.bundle == bundle.key() @ ErrorCode::InvalidBundle
+    )]
+    pub bundle_offer: Account<'info, BundleOffer>,
+    pub seller: Signer<'info>,
+    /// CHECK: Receives the escrowed funds and rent back
+    #[account(mut, constraint = bundle_offer.buyer == buyer.key() @ ErrorCode::NotOfferBuyer)]
+    pub buyer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBundleOffer<'info> {
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        mut,
+        close = buyer,
+        constraint = bundle_offer.bundle == bundle.key() @ ErrorCode::InvalidBundle,
+        constraint = bundle_offer.buyer == *buyer.key @ ErrorCode::NotOfferBuyer
+    )]
+    pub bundle_offer: Account<'info, BundleOffer>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptBundleOffer<'info> {
+    #[account(constraint = bundle.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        constraint = bundle.seller == *seller.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        mut,
+        close = buyer,
+        constraint = bundle_offer.bundle == bundle.key() @ ErrorCode::InvalidBundle
+    )]
+    pub bundle_offer: Account<'info, BundleOffer>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// CHECK: Receives the offer account's rent once settled
+    #[account(mut, constraint = bundle_offer.buyer == buyer.key() @ ErrorCode::NotOfferBuyer)]
+    pub buyer: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Staking config PDA, only read when the marketplace has configured staking
+    #[account(seeds = [b"staking_config", marketplace.key().as_ref()], bump)]
+    pub staking_config: UncheckedAccount<'info>,
+    /// CHECK: Buyer's stake PDA, only read when the buyer has staked
+    #[account(seeds = [b"stake", marketplace.key().as_ref(), buyer.key().as_ref()], bump)]
+    pub buyer_stake: UncheckedAccount<'info>,
+    /// CHECK: Seller's stake PDA, only read when the seller has staked
+    #[account(seeds = [b"stake", marketplace.key().as_ref(), seller.key().as_ref()], bump)]
+    pub seller_stake: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Program-wide protocol config PDA, only read once the protocol fee is configured
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>,
+    /// CHECK: Receives the protocol's share of the fee; checked against the protocol config
+    #[account(mut)]
+    pub protocol_treasury: Option<UncheckedAccount<'info>>,
+    /// CHECK: Seller's listing count PDA, only read when a bundled listing took a slot
+    #[account(
+        mut,
+        seeds = [b"owner_listings", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Subdivide<'info> {
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        constraint = parent_property.owner == *owner.key @ ErrorCode::NotPropertyOwner,
+        constraint = parent_property.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch
+    )]
+    pub parent_property: Account<'info, Property>,
+    #[account(mut, address = parent_property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub parent_nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = parent_nft_mint,
+        associated_token::authority = owner
+    )]
+    pub owner_nft_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(merged_property_id: String)]
+pub struct MergeParcels<'info> {
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = owner,
+        space = property_space(MAX_METADATA_URI_LEN),
+        seeds = [b"property", marketplace.key().as_ref(), merged_property_id.as_bytes()],
+        bump
+    )]
+    pub merged_property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MergeRecord::INIT_SPACE,
+        seeds = [b"merge", merged_property.key().as_ref()],
+        bump
+    )]
+    pub merge_record: Account<'info, MergeRecord>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The merged property's NFT mint, created beforehand by the owner
+    #[account(mut, mint::authority = owner)]
+    pub merged_nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = merged_nft_mint,
+        associated_token::authority = owner
+    )]
+    pub owner_nft_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateReceiptCollection<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ReceiptConfig::INIT_SPACE,
+        seeds = [b"receipt_config", marketplace.key().as_ref()],
+        bump
+    )]
+    pub receipt_config: Account<'info, ReceiptConfig>,
+    /// CHECK: PDA that owns the receipt collection and signs receipt mints
+    #[account(seeds = [b"receipt_authority", marketplace.key().as_ref()], bump)]
+    pub receipt_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"receipt_collection", marketplace.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = receipt_authority,
+        mint::freeze_authority = receipt_authority
+    )]
+    pub collection_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = collection_mint,
+        associated_token::authority = receipt_authority
+    )]
+    pub collection_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Created by the token metadata program
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+    /// CHECK: Created by the token metadata program
+    #[account(mut)]
+    pub collection_master_edition: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MintSettlementReceipt<'info> {
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        seeds = [b"receipt_config", marketplace.key().as_ref()],
+        bump
+    )]
+    pub receipt_config: Account<'info, ReceiptConfig>,
+    /// CHECK: PDA that owns the receipt collection and signs receipt mints
+    #[account(
+        seeds = [b"receipt_authority", marketplace.key().as_ref()],
+        bump = receipt_config.authority_bump
+    )]
+    pub receipt_authority: UncheckedAccount<'info>,
+    #[account(
+        constraint = property.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        constraint = transaction_history.property == property.key() @ ErrorCode::OfferPropertyMismatch,
+        constraint = transaction_history.buyer == *buyer.key @ ErrorCode::NotTransactionParty
+    )]
+    pub transaction_history: Account<'info, TransactionHistory>,
+    // One receipt per sale: the mint address is derived from the history entry
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"receipt", transaction_history.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = receipt_authority,
+        mint::freeze_authority = receipt_authority
+    )]
+    pub receipt_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_receipt_account: Account<'info, TokenAccount>,
+    /// CHECK: Created by the token metadata program
+    #[account(mut)]
+    pub receipt_metadata: UncheckedAccount<'info>,
+    /// CHECK: Created by the token metadata program
+    #[account(mut)]
+    pub receipt_master_edition: UncheckedAccount<'info>,
+    #[account(address = receipt_config.collection_mint)]
+    pub collection_mint: Account<'info, Mint>,
+    /// CHECK: Validated by the token metadata program during collection verification
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+    /// CHECK: Validated by the token metadata program during collection verification
+    pub collection_master_edition: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(lienholder: Pubkey)]
+pub struct RegisterLienholder<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Lienholder::INIT_SPACE,
+        seeds = [b"lienholder", marketplace.key().as_ref(), lienholder.as_ref()],
+        bump
+    )]
+    pub lienholder_account: Account<'info, Lienholder>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLienholder<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"lienholder", marketplace.key().as_ref(), lienholder_account.lienholder.as_ref()],
+        bump
+    )]
+    pub lienholder_account: Account<'info, Lienholder>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AttachLien<'info> {
+    #[account(
+        mut,
+        constraint = property.marketplace == lienholder_account.marketplace @ ErrorCode::NotRegisteredLienholder
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        seeds = [b"lienholder", lienholder_account.marketplace.as_ref(), lienholder.key().as_ref()],
+        bump,
+        constraint = lienholder_account.lienholder == *lienholder.key @ ErrorCode::NotRegisteredLienholder
+    )]
+    pub lienholder_account: Account<'info, Lienholder>,
+    #[account(
+        init,
+        payer = lienholder,
+        space = 8 + Lien::INIT_SPACE,
+        seeds = [b"lien", property.key().as_ref(), lienholder.key().as_ref()],
+        bump
+    )]
+    pub lien: Account<'info, Lien>,
+    #[account(mut)]
+    pub lienholder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseLien<'info> {
+    #[account(mut)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = lienholder,
+        seeds = [b"lien", property.key().as_ref(), lienholder.key().as_ref()],
+        bump,
+        has_one = lienholder,
+        constraint = lien.property == property.key() @ ErrorCode::InvalidLien
+    )]
+    pub lien: Account<'info, Lien>,
+    #[account(mut)]
+    pub lienholder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(doc_type: DocumentType, sha256_hash: [u8; 32])]
+pub struct RegisterDocument<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PropertyDocument::INIT_SPACE,
+        seeds = [b"document", property.key().as_ref(), sha256_hash.as_ref()],
+        bump
+    )]
+    pub document: Account<'info, PropertyDocument>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddMediaItem<'info> {
+    #[account(constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PropertyMedia::INIT_SPACE,
+        seeds = [b"property_media", property.key().as_ref()],
+        bump
+    )]
+    pub property_media: Account<'info, PropertyMedia>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveMediaItem<'info> {
+    #[account(constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        seeds = [b"property_media", property.key().as_ref()],
+        bump
+    )]
+    pub property_media: Account<'info, PropertyMedia>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(metadata_uri_capacity: u16)]
+pub struct ResizeProperty<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner,
+        constraint = property.content_hashes.is_none() @ ErrorCode::ContentHashedProperty,
+        constraint = (metadata_uri_capacity as usize) <= MAX_METADATA_URI_LEN @ ErrorCode::MetadataUriTooLong,
+        constraint = (metadata_uri_capacity as usize) >= property.metadata_uri.len() @ ErrorCode::InvalidResize,
+        constraint = property_space(metadata_uri_capacity as usize) >= property.to_account_info().data_len() @ ErrorCode::InvalidResize,
+        realloc = property_space(metadata_uri_capacity as usize),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub property: Account<'info, Property>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetContentHashes<'info> {
+    #[account(
+        mut,
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner,
+        realloc = slim_property_space(),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub property: Account<'info, Property>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarketplace<'info> {
+    /// CHECK: may hold an older layout; discriminator and layout are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub marketplace: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProperty<'info> {
+    /// CHECK: may hold an older layout; discriminator and layout are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub property: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateOffer<'info> {
+    /// CHECK: may hold an older layout; discriminator and layout are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub offer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+    /// CHECK: may hold an older layout; discriminator and layout are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub escrow: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetResponseRule<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ResponseRule::INIT_SPACE,
+        seeds = [b"response_rule", marketplace.key().as_ref()],
+        bump
+    )]
+    pub response_rule: Account<'info, ResponseRule>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSellerBond<'info> {
+    #[account(
+        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + SellerBond::INIT_SPACE,
+        seeds = [b"seller_bond", property.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub seller_bond: Account<'info, SellerBond>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSellerBond<'info> {
+    #[account(
+        mut,
+        seeds = [b"seller_bond", seller_bond.property.as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub seller_bond: Account<'info, SellerBond>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LockResponseBond<'info> {
+    #[account(
+        seeds = [b"response_rule", property.marketplace.as_ref()],
+        bump
+    )]
+    pub response_rule: Account<'info, ResponseRule>,
+    pub property: Account<'info, Property>,
+    #[account(
+        has_one = property @ ErrorCode::OfferPropertyMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        seeds = [b"seller_bond", property.key().as_ref(), property.owner.as_ref()],
+        bump
+    )]
+    pub seller_bond: Option<Account<'info, SellerBond>>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + ResponseBond::INIT_SPACE,
+        seeds = [b"response_bond", offer.key().as_ref()],
+        bump
+    )]
+    pub response_bond: Account<'info, ResponseBond>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseResponseBond<'info> {
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"response_bond", offer.key().as_ref()],
+        bump,
+        has_one = offer,
+        has_one = buyer
+    )]
+    pub response_bond: Account<'info, ResponseBond>,
+    #[account(mut)]
+    pub seller_bond: Option<Account<'info, SellerBond>>,
+    /// CHECK: Receives the response bond rent, checked against the response bond
+    #[account(mut)]
+    pub buyer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimUnansweredOffer<'info> {
+    #[account(mut, address = offer.property @ ErrorCode::OfferPropertyMismatch)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        has_one = offer @ ErrorCode::EscrowMismatch,
+        has_one = property @ ErrorCode::OfferPropertyMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"response_bond", offer.key().as_ref()],
+        bump,
+        has_one = offer
+    )]
+    pub response_bond: Account<'info, ResponseBond>,
+    #[account(mut)]
+    pub seller_bond: Option<Account<'info, SellerBond>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncHistoryIndex<'info> {
+    pub property: Account<'info, Property>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + HistoryIndex::INIT_SPACE,
+        seeds = [b"history_index", property.key().as_ref()],
+        bump
+    )]
+    pub history_index: Account<'info, HistoryIndex>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireOffer<'info> {
+    #[account(mut)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        has_one = property @ ErrorCode::OfferPropertyMismatch,
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        has_one = offer @ ErrorCode::EscrowMismatch,
+        has_one = property @ ErrorCode::OfferPropertyMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    /// CHECK: Receives the refund, checked against the offer
+    #[account(mut)]
+    pub buyer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ForceCancelOffer<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(mut, has_one = marketplace @ ErrorCode::MarketplaceMismatch)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        has_one = property @ ErrorCode::OfferPropertyMismatch,
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        seeds = [b"escrow", offer.key().as_ref()],
+        bump = escrow.bump,
+        has_one = offer @ ErrorCode::EscrowMismatch,
+        has_one = property @ ErrorCode::OfferPropertyMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    /// CHECK: Receives the refund, checked against the offer
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    /// Only needed once the NFT is in escrow
+    #[account(
+        mut,
+        constraint = escrow_nft_account.mint == property.nft_mint
+            && escrow_nft_account.owner == escrow.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub escrow_nft_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_nft_account.mint == property.nft_mint
+            && seller_nft_account.owner == property.owner @ ErrorCode::InvalidTokenAccount
+    )]
+    pub seller_nft_account: Option<Account<'info, TokenAccount>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_buyer: Pubkey)]
+pub struct AssignOffer<'info> {
+    #[account(constraint = property.key() == offer.property @ ErrorCode::OfferPropertyMismatch)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(mut, has_one = offer @ ErrorCode::EscrowMismatch)]
+    pub escrow: Account<'info, Escrow>,
+    /// CHECK: Release schedule PDA, only updated when the buyer set one
+    #[account(
+        mut,
+        seeds = [b"release_schedule", escrow.key().as_ref()],
+        bump
+    )]
+    pub release_schedule: UncheckedAccount<'info>,
+    /// CHECK: Title insurance PDA, which must not exist
+    #[account(seeds = [b"title_insurance", offer.key().as_ref()], bump)]
+    pub title_insurance: UncheckedAccount<'info>,
+    /// Records the original buyer; an offer can be assigned once
+    #[account(
+        init,
+        payer = assignee,
+        space = 8 + OfferAssignment::INIT_SPACE,
+        seeds = [b"offer_assignment", offer.key().as_ref()],
+        bump
+    )]
+    pub offer_assignment: Account<'info, OfferAssignment>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut, address = new_buyer @ ErrorCode::InvalidAssignment)]
+    pub assignee: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetApprovalAuthority<'info> {
+    #[account(mut)]
+    pub property: Account<'info, Property>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveTransfer<'info> {
+    #[account(constraint = property.key() == offer.property @ ErrorCode::OfferPropertyMismatch)]
+    pub property: Account<'info, Property>,
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + TransferApproval::INIT_SPACE,
+        seeds = [b"transfer_approval", offer.key().as_ref()],
+        bump
+    )]
+    pub transfer_approval: Account<'info, TransferApproval>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFinancingRule<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + FinancingRule::INIT_SPACE,
+        seeds = [b"financing_rule", marketplace.key().as_ref()],
+        bump
+    )]
+    pub financing_rule: Account<'info, FinancingRule>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_amount: u64, earnest_amount: u64, expiration_time: i64, offer_nonce: u64)]
+pub struct MakeFinancedOffer<'info> {
+    #[account(
+        constraint = property.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        seeds = [b"financing_rule", marketplace.key().as_ref()],
+        bump
+    )]
+    pub financing_rule: Option<Account<'info, FinancingRule>>,
+    #[account(
+        mut,
+        constraint = property.is_active,
+        constraint = property.owner != *buyer.key
+    )]
+    pub property: Account<'info, Property>,
+    /// CHECK: Offer limits PDA, only read once the authority has configured it
+    #[account(seeds = [b"offer_limits", marketplace.key().as_ref()], bump)]
+    pub offer_limits: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Offer::INIT_SPACE,
+        seeds = [
+            b"offer",
+            property.key().as_ref(),
+            buyer.key().as_ref(),
+            &offer_nonce.to_le_bytes()
+        ],
+        bump
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", offer.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + FinancingContingency::INIT_SPACE,
+        seeds = [b"financing", offer.key().as_ref()],
+        bump
+    )]
+    pub financing: Account<'info, FinancingContingency>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// CHECK: Pyth SOL/USD price account, validated against the marketplace config when used
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundFinancedOffer<'info> {
+    #[account(
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        has_one = offer @ ErrorCode::EscrowMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        seeds = [b"financing", offer.key().as_ref()],
+        bump,
+        has_one = offer
+    )]
+    pub financing: Account<'info, FinancingContingency>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelForFinancing<'info> {
+    #[account(mut)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        has_one = property @ ErrorCode::OfferPropertyMismatch,
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        seeds = [b"escrow", offer.key().as_ref()],
+        bump = escrow.bump,
+        has_one = offer @ ErrorCode::EscrowMismatch,
+        has_one = property @ ErrorCode::OfferPropertyMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"financing", offer.key().as_ref()],
+        bump,
+        has_one = offer
+    )]
+    pub financing: Account<'info, FinancingContingency>,
+    pub caller: Signer<'info>,
+    /// CHECK: Receives the earnest money refund, checked against the offer
+    #[account(mut)]
+    pub buyer: AccountInfo<'info>,
+    /// CHECK: Receives the penalty, checked against the property owner
+    #[account(mut, address = property.owner @ ErrorCode::NotPropertyOwner)]
+    pub seller: AccountInfo<'info>,
+    #[account(
+        mut,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = seller
+    )]
+    pub seller_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = escrow
+    )]
+    pub escrow_nft_account: Account<'info, TokenAccount>,
+    #[account(address = property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTransactionHistory<'info> {
+    /// CHECK: may hold an older layout; discriminator and layout are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub transaction_history: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetReleaseSchedule<'info> {
+    #[account(
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(has_one = offer @ ErrorCode::EscrowMismatch)]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + ReleaseSchedule::INIT_SPACE,
+        seeds = [b"release_schedule", escrow.key().as_ref()],
+        bump
+    )]
+    pub release_schedule: Account<'info, ReleaseSchedule>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseMilestone<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        seeds = [b"release_schedule", escrow.key().as_ref()],
+        bump,
+        has_one = escrow,
+        has_one = seller
+    )]
+    pub release_schedule: Account<'info, ReleaseSchedule>,
+    /// CHECK: Receives the tranche, checked against the release schedule
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, recipient: Pubkey)]
+pub struct WithdrawFees<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump,
+        has_one = marketplace
+    )]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Any account can receive fees; it must match the `recipient` argument
+    #[account(mut, address = recipient)]
+    pub destination: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueueParameterChange<'info> {
+    #[account(mut, has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + QueuedChange::INIT_SPACE,
+        seeds = [b"queued_change", marketplace.key().as_ref(), &marketplace.change_count.to_le_bytes()],
+        bump
+    )]
+    pub queued_change: Account<'info, QueuedChange>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelParameterChange<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"queued_change", marketplace.key().as_ref(), &queued_change.index.to_le_bytes()],
+        bump,
+        has_one = marketplace
+    )]
+    pub queued_change: Account<'info, QueuedChange>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteParameterChange<'info> {
+    #[account(mut, has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"queued_change", marketplace.key().as_ref(), &queued_change.index.to_le_bytes()],
+        bump,
+        has_one = marketplace
+    )]
+    pub queued_change: Account<'info, QueuedChange>,
+    /// CHECK: Receives the queued change's rent, checked against the marketplace
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Verifier::INIT_SPACE,
+        seeds = [b"verifier", marketplace.key().as_ref(), queued_change.change.verifier().as_ref()],
+        bump
+    )]
+    pub verifier_account: Option<Account<'info, Verifier>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureStaking<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + StakingConfig::INIT_SPACE,
+        seeds = [b"staking_config", marketplace.key().as_ref()],
+        bump
+    )]
+    pub staking_config: Account<'info, StakingConfig>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"staking_config", marketplace.key().as_ref()],
+        bump,
+        has_one = marketplace,
+        has_one = stake_mint
+    )]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + StakeAccount::INIT_SPACE,
+        seeds = [b"stake", marketplace.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"stake_vault", marketplace.key().as_ref()],
+        bump,
+        token::mint = stake_mint,
+        token::authority = staking_config
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = stake_mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"staking_config", marketplace.key().as_ref()],
+        bump,
+        has_one = marketplace
+    )]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(
+        mut,
+        seeds = [b"stake", marketplace.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    #[account(
+        mut,
+        seeds = [b"stake_vault", marketplace.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = staking_config.stake_mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestLoan<'info> {
+    #[account(constraint = property.owner == borrower.key() @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [b"loan", property.key().as_ref()],
+        bump
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(address = property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = borrower
+    )]
+    pub borrower_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = borrower,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = loan
+    )]
+    pub loan_nft_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLoanRequest<'info> {
+    #[account(
+        mut,
+        close = borrower,
+        seeds = [b"loan", loan.property.as_ref()],
+        bump,
+        has_one = borrower
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(
+        mut,
+        associated_token::mint = loan.nft_mint,
+        associated_token::authority = loan
+    )]
+    pub loan_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = loan.nft_mint,
+        associated_token::authority = borrower
+    )]
+    pub borrower_nft_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FundLoan<'info> {
+    #[account(
+        mut,
+        seeds = [b"loan", loan.property.as_ref()],
+        bump,
+        has_one = borrower
+    )]
+    pub loan: Account<'info, Loan>,
+    /// CHECK: Receives the principal, checked against the loan
+    #[account(mut)]
+    pub borrower: AccountInfo<'info>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RepayLoan<'info> {
+    #[account(
+        mut,
+        close = borrower,
+        seeds = [b"loan", loan.property.as_ref()],
+        bump,
+        has_one = borrower,
+        has_one = lender
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(
+        mut,
+        associated_token::mint = loan.nft_mint,
+        associated_token::authority = loan
+    )]
+    pub loan_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = loan.nft_mint,
+        associated_token::authority = borrower
+    )]
+    pub borrower_nft_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    /// CHECK: Receives the repayment, checked against the loan
+    #[account(mut)]
+    pub lender: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLoanCollateral<'info> {
+    #[account(mut, address = loan.property @ ErrorCode::LoanPropertyMismatch)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = borrower,
+        seeds = [b"loan", loan.property.as_ref()],
+        bump,
+        has_one = borrower,
+        has_one = lender @ ErrorCode::NotLender
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(
+        mut,
+        associated_token::mint = loan.nft_mint,
+        associated_token::authority = loan
+    )]
+    pub loan_nft_account: Account<'info, TokenAccount>,
+    #[account(address = loan.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = lender,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = lender
+    )]
+    pub lender_nft_account: Account<'info, TokenAccount>,
+    /// CHECK: Receives the loan and vault rent, checked against the loan
+    #[account(mut)]
+    pub borrower: AccountInfo<'info>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateLoan<'info> {
+    #[account(address = loan.property @ ErrorCode::LoanPropertyMismatch)]
+    pub property: Account<'info, Property>,
+    #[account(mut, seeds = [b"loan", loan.property.as_ref()], bump)]
+    pub loan: Account<'info, Loan>,
+    /// CHECK: Auction config PDA, only read once the authority has configured it
+    #[account(seeds = [b"auction_config", property.marketplace.as_ref()], bump)]
+    pub auction_config: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + LiquidationAuction::INIT_SPACE,
+        seeds = [b"liquidation", loan.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceLiquidationBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"liquidation", auction.loan.as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+    /// CHECK: Refunded when outbid; checked against the auction's highest bidder
+    #[account(mut)]
+    pub previous_bidder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleLiquidation<'info> {
+    #[account(mut, address = loan.property @ ErrorCode::LoanPropertyMismatch)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = borrower,
+        seeds = [b"loan", loan.property.as_ref()],
+        bump,
+        has_one = borrower,
+        has_one = lender
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(
+        mut,
+        close = opened_by,
+        seeds = [b"liquidation", loan.key().as_ref()],
+        bump,
+        has_one = opened_by
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+    #[account(
+        mut,
+        associated_token::mint = loan.nft_mint,
+        associated_token::authority = loan
+    )]
+    pub loan_nft_account: Account<'info, TokenAccount>,
+    #[account(address = loan.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    /// CHECK: The winning bidder, or the lender when there were no bids
+    #[account(
+        constraint = recipient.key() == auction.highest_bidder.unwrap_or(loan.lender)
+            @ ErrorCode::InvalidCollateralRecipient
+    )]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = recipient
+    )]
+    pub recipient_nft_account: Account<'info, TokenAccount>,
+    /// CHECK: Receives any surplus and the loan rent, checked against the loan
+    #[account(mut)]
+    pub borrower: AccountInfo<'info>,
+    /// CHECK: Receives the repayment, checked against the loan
+    #[account(mut)]
+    pub lender: AccountInfo<'info>,
+    /// CHECK: Receives the auction rent, checked against the auction
+    #[account(mut)]
+    pub opened_by: AccountInfo<'info>,
+    #[account(mut)]
+    pub settler: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutAddress<'info> {
+    #[account(has_one = owner @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PayoutAddress::INIT_SPACE,
+        seeds = [b"payout_address", property.key().as_ref()],
+        bump
+    )]
+    pub payout_address: Account<'info, PayoutAddress>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearPayoutAddress<'info> {
+    #[account(has_one = owner @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"payout_address", property.key().as_ref()],
+        bump
+    )]
+    pub payout_address: Account<'info, PayoutAddress>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DelegateMetadataAuthority<'info> {
+    #[account(has_one = owner @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    /// CHECK: The property NFT's metadata; the token metadata program checks the update authority
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), property.nft_mint.as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + MetadataCustody::INIT_SPACE,
+        seeds = [b"metadata_custody", property.key().as_ref()],
+        bump
+    )]
+    pub metadata_custody: Account<'info, MetadataCustody>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct HandOverMetadataAuthority<'info> {
+    #[account(
+        seeds = [b"property", property.marketplace.as_ref(), property.property_id.as_bytes()],
+        bump = property.bump
+    )]
+    pub property: Account<'info, Property>,
+    /// CHECK: The property NFT's metadata; the token metadata program checks the update authority
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), property.nft_mint.as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"metadata_custody", property.key().as_ref()],
+        bump
+    )]
+    pub metadata_custody: Account<'info, MetadataCustody>,
+    /// CHECK: Owner who delegated, refunded the custody record's rent
+    #[account(mut, address = metadata_custody.delegated_by)]
+    pub delegator: UncheckedAccount<'info>,
+    pub caller: Signer<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct CreateLease<'info> {
+    #[account(constraint = property.owner == landlord.key() @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = landlord,
+        space = 8 + Lease::INIT_SPACE,
+        seeds = [b"lease", property.key().as_ref()],
+        bump
+    )]
+    pub lease: Account<'info, Lease>,
+    #[account(
+        init,
+        payer = landlord,
+        space = 8 + SecurityDeposit::INIT_SPACE,
+        seeds = [b"security_deposit", lease.key().as_ref()],
+        bump
+    )]
+    pub security_deposit: Account<'info, SecurityDeposit>,
+    #[account(mut)]
+    pub landlord: Signer<'info>,
+    #[account(mut)]
+    pub tenant: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PayRent<'info> {
+    #[account(
+        mut,
+        seeds = [b"lease", lease.property.as_ref()],
+        bump,
+        has_one = tenant,
+        has_one = landlord
+    )]
+    pub lease: Account<'info, Lease>,
+    #[account(mut)]
+    pub tenant: Signer<'info>,
+    /// CHECK: Receives the rent, checked against the lease
+    #[account(mut)]
+    pub landlord: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EndLease<'info> {
+    #[account(
+        mut,
+        close = landlord,
+        seeds = [b"lease", lease.property.as_ref()],
+        bump,
+        has_one = landlord,
+        has_one = tenant
+    )]
+    pub lease: Account<'info, Lease>,
+    #[account(
+        mut,
+        seeds = [b"security_deposit", lease.key().as_ref()],
+        bump
+    )]
+    pub security_deposit: Account<'info, SecurityDeposit>,
+    #[account(mut)]
+    pub landlord: Signer<'info>,
+    /// CHECK: Checked against the lease; signs to end it early and is refunded unused rent
+    #[account(mut)]
+    pub tenant: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AttachGovernance<'info> {
+    #[account(constraint = property.owner == owner.key() @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PropertyGovernance::INIT_SPACE,
+        seeds = [b"property_governance", property.key().as_ref()],
+        bump
+    )]
+    pub property_governance: Account<'info, PropertyGovernance>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DetachGovernance<'info> {
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"property_governance", property.key().as_ref()],
+        bump,
+        has_one = governance @ ErrorCode::InvalidGovernance
+    )]
+    pub property_governance: Account<'info, PropertyGovernance>,
+    pub governance: Signer<'info>,
+    /// CHECK: Receives the rent, checked against the property owner
+    #[account(mut, address = property.owner @ ErrorCode::NotPropertyOwner)]
+    pub owner: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AuthorizeGovernedSale<'info> {
+    pub property: Account<'info, Property>,
+    #[account(
+        seeds = [b"property_governance", property.key().as_ref()],
+        bump,
+        has_one = governance @ ErrorCode::InvalidGovernance
+    )]
+    pub property_governance: Account<'info, PropertyGovernance>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SaleAuthorization::INIT_SPACE,
+        seeds = [b"sale_authorization", property.key().as_ref()],
+        bump
+    )]
+    pub sale_authorization: Account<'info, SaleAuthorization>,
+    pub governance: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitDepositDeductions<'info> {
+    #[account(
+        mut,
+        seeds = [b"security_deposit", security_deposit.lease.as_ref()],
+        bump,
+        has_one = landlord
+    )]
+    pub security_deposit: Account<'info, SecurityDeposit>,
+    pub landlord: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeDepositDeductions<'info> {
+    #[account(
+        mut,
+        seeds = [b"security_deposit", security_deposit.lease.as_ref()],
+        bump,
+        has_one = tenant
+    )]
+    pub security_deposit: Account<'info, SecurityDeposit>,
+    pub tenant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSecurityDeposit<'info> {
+    #[account(
+        mut,
+        close = landlord,
+        seeds = [b"security_deposit", security_deposit.lease.as_ref()],
+        bump,
+        has_one = landlord,
+        has_one = tenant
+    )]
+    pub security_deposit: Account<'info, SecurityDeposit>,
+    /// CHECK: Receives the deductions and the rent, checked against the deposit
+    #[account(mut)]
+    pub landlord: AccountInfo<'info>,
+    /// CHECK: Receives the refund, checked against the deposit
+    #[account(mut)]
+    pub tenant: AccountInfo<'info>,
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDepositDispute<'info> {
+    #[account(
+        mut,
+        close = landlord,
+        seeds = [b"security_deposit", security_deposit.lease.as_ref()],
+        bump,
+        has_one = landlord,
+        has_one = tenant
+    )]
+    pub security_deposit: Account<'info, SecurityDeposit>,
+    /// CHECK: Receives the awarded amount and the rent, checked against the deposit
+    #[account(mut)]
+    pub landlord: AccountInfo<'info>,
+    /// CHECK: Receives the refund, checked against the deposit
+    #[account(mut)]
+    pub tenant: AccountInfo<'info>,
+    pub arbiter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureOfferLimits<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + OfferLimits::INIT_SPACE,
+        seeds = [b"offer_limits", marketplace.key().as_ref()],
+        bump
+    )]
+    pub offer_limits: Account<'info, OfferLimits>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureAuctions<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + AuctionConfig::INIT_SPACE,
+        seeds = [b"auction_config", marketplace.key().as_ref()],
+        bump
+    )]
+    pub auction_config: Account<'info, AuctionConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureHistoryRetention<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + HistoryRetention::INIT_SPACE,
+        seeds = [b"history_retention", marketplace.key().as_ref()],
+        bump
+    )]
+    pub history_retention: Account<'info, HistoryRetention>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(jurisdiction_code: [u8; 8])]
+pub struct SetTaxConfig<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + TaxConfig::INIT_SPACE,
+        seeds = [b"tax_config", marketplace.key().as_ref(), jurisdiction_code.as_ref()],
+        bump
+    )]
+    pub tax_config: Account<'info, TaxConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveTaxConfig<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"tax_config", marketplace.key().as_ref(), tax_config.jurisdiction_code.as_ref()],
+        bump
+    )]
+    pub tax_config: Account<'info, TaxConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureListingBond<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ListingBondConfig::INIT_SPACE,
+        seeds = [b"listing_bond_config", marketplace.key().as_ref()],
+        bump
+    )]
+    pub listing_bond_config: Account<'info, ListingBondConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseListingBond<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"listing_bond", listing_bond.property.as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub listing_bond: Account<'info, ListingBond>,
+    /// CHECK: The bonded property, which may have been retired since
+    #[account(address = listing_bond.property)]
+    pub property: UncheckedAccount<'info>,
+    /// CHECK: Seller who locked the bond, checked against it
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SlashListingBond<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        constraint = property.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"listing_bond", property.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub listing_bond: Account<'info, ListingBond>,
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump,
+        has_one = marketplace
+    )]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Seller who locked the bond, refunded its rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+    /// CHECK: Listing slot PDA, only read when the listing took one
+    #[account(mut, seeds = [b"listing_slot", property.key().as_ref()], bump)]
+    pub listing_slot: UncheckedAccount<'info>,
+    /// CHECK: Owner's listing count PDA, only read when the listing took a slot
+    #[account(
+        mut,
+        seeds = [b"owner_listings", marketplace.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ArchiveHistory<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        seeds = [b"history_retention", marketplace.key().as_ref()],
+        bump,
+        has_one = marketplace
+    )]
+    pub history_retention: Account<'info, HistoryRetention>,
+    #[account(
+        constraint = property.marketplace == marketplace.key() @ ErrorCode::MarketplaceMismatch
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = treasury,
+        seeds = [
+            b"transaction",
+            property.key().as_ref(),
+            &transaction_history.transaction_index.to_le_bytes()
+        ],
+        bump,
+        has_one = property
+    )]
+    pub transaction_history: Account<'info, TransactionHistory>,
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump,
+        has_one = marketplace
+    )]
+    pub treasury: Account<'info, Treasury>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_owner: Pubkey)]
+pub struct TransferProperty<'info> {
+    #[account(mut, has_one = owner @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + TransactionHistory::INIT_SPACE,
+        seeds = [
+            b"transaction",
+            property.key().as_ref(),
+            &property.transaction_count.checked_add(1).ok_or(ErrorCode::ArithmeticOverflow)?.to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_history: Account<'info, TransactionHistory>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + HistoryIndex::INIT_SPACE,
+        seeds = [b"history_index", property.key().as_ref()],
+        bump
+    )]
+    pub history_index: Account<'info, HistoryIndex>,
+    /// CHECK: Property governance PDA; governed properties can't be transferred
+    #[account(seeds = [b"property_governance", property.key().as_ref()], bump)]
+    pub property_governance: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = owner
+    )]
+    pub owner_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = property_nft_mint,
+        associated_token::authority = recipient
+    )]
+    pub recipient_nft_account: Account<'info, TokenAccount>,
+    #[account(address = property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub property_nft_mint: Account<'info, Mint>,
+    /// CHECK: Any wallet can receive the property; must match the `new_owner` argument
+    #[account(address = new_owner)]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SwapProperties<'info> {
+    #[account(
+        mut,
+        constraint = property_a.owner == owner_a.key() @ ErrorCode::NotPropertyOwner
+    )]
+    pub property_a: Account<'info, Property>,
+    #[account(
+        mut,
+        constraint = property_b.owner == owner_b.key() @ ErrorCode::NotPropertyOwner,
+        constraint = property_b.key() != property_a.key() @ ErrorCode::InvalidTransferRecipient
+    )]
+    pub property_b: Account<'info, Property>,
+    #[account(
+        init,
+        payer = owner_a,
+        space = 8 + TransactionHistory::INIT_SPACE,
+        seeds = [
+            b"transaction",
+            property_a.key().as_ref(),
+            &property_a.transaction_count.checked_add(1).ok_or(ErrorCode::ArithmeticOverflow)?.to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_history_a: Account<'info, TransactionHistory>,
+    #[account(
+        init,
+        payer = owner_b,
+        space = 8 + TransactionHistory::INIT_SPACE,
+        seeds = [
+            b"transaction",
+            property_b.key().as_ref(),
+            &property_b.transaction_count.checked_add(1).ok_or(ErrorCode::ArithmeticOverflow)?.to_le_bytes()
+        ],
+        bump
+    )]
+    pub transaction_history_b: Account<'info, TransactionHistory>,
+    #[account(
+        init_if_needed,
+        payer = owner_a,
+        space = 8 + HistoryIndex::INIT_SPACE,
+        seeds = [b"history_index", property_a.key().as_ref()],
+        bump
+    )]
+    pub history_index_a: Account<'info, HistoryIndex>,
+    #[account(
+        init_if_needed,
+        payer = owner_b,
+        space = 8 + HistoryIndex::INIT_SPACE,
+        seeds = [b"history_index", property_b.key().as_ref()],
+        bump
+    )]
+    pub history_index_b: Account<'info, HistoryIndex>,
+    /// CHECK: Property governance PDA; governed properties can't be swapped
+    #[account(seeds = [b"property_governance", property_a.key().as_ref()], bump)]
+    pub property_governance_a: UncheckedAccount<'info>,
+    /// CHECK: Property governance PDA; governed properties can't be swapped
+    #[account(seeds = [b"property_governance", property_b.key().as_ref()], bump)]
+    pub property_governance_b: UncheckedAccount<'info>,
+    #[account(address = property_a.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub nft_mint_a: Account<'info, Mint>,
+    #[account(address = property_b.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub nft_mint_b: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = nft_mint_a,
+        associated_token::authority = owner_a
+    )]
+    pub owner_a_nft_a: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner_a,
+        associated_token::mint = nft_mint_a,
+        associated_token::authority = owner_b
+    )]
+    pub owner_b_nft_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = nft_mint_b,
+        associated_token::authority = owner_b
+    )]
+    pub owner_b_nft_b: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner_b,
+        associated_token::mint = nft_mint_b,
+        associated_token::authority = owner_a
+    )]
+    pub owner_a_nft_b: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner_a: Signer<'info>,
+    #[account(mut)]
+    pub owner_b: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ListPropertiesBatch<'info> {
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Listing bond config PDA, only read when the marketplace has configured bonds
+    #[account(seeds = [b"listing_bond_config", marketplace.key().as_ref()], bump)]
+    pub listing_bond_config: UncheckedAccount<'info>,
+    /// Required when the marketplace charges a listing fee
+    #[account(mut, seeds = [b"treasury", marketplace.key().as_ref()], bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+    /// CHECK: Listing limits PDA, only read when the marketplace caps listings per owner
+    #[account(seeds = [b"listing_limits", marketplace.key().as_ref()], bump)]
+    pub listing_limits: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OwnerListings::INIT_SPACE,
+        seeds = [b"owner_listings", marketplace.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_listings: Account<'info, OwnerListings>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseParcel<'info> {
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = claimant,
+        seeds = [b"property_registry", property_registry.parcel_key.as_ref()],
+        bump,
+        constraint = property_registry.property == property.key() @ ErrorCode::ParcelAlreadyClaimed,
+        has_one = claimant
+    )]
+    pub property_registry: Account<'info, PropertyRegistry>,
+    /// CHECK: receives the claim's rent, checked against the registry
+    #[account(mut)]
+    pub claimant: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RetireProperty<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::NotPropertyOwner
+    )]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"history_index", property.key().as_ref()],
+        bump
+    )]
+    pub history_index: Option<Account<'info, HistoryIndex>>,
+    /// CHECK: Property governance PDA, only read when a governance is attached
+    #[account(seeds = [b"property_governance", property.key().as_ref()], bump)]
+    pub property_governance: UncheckedAccount<'info>,
+    /// CHECK: Parcel registry PDA for the property's cadastral id; checked in the handler and
+    /// only read when the property registered one
+    #[account(mut)]
+    pub property_registry: UncheckedAccount<'info>,
+    /// CHECK: Receives the parcel claim's rent; checked against the registry in the handler
+    #[account(mut)]
+    pub registry_claimant: Option<UncheckedAccount<'info>>,
+    #[account(mut, address = property.nft_mint @ ErrorCode::InvalidNFTMint)]
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = owner,
+        constraint = owner_nft_account.amount >= 1 @ ErrorCode::NotNFTOwner
+    )]
+    pub owner_nft_account: Account<'info, TokenAccount>,
+    /// CHECK: The NFT's metadata, closed by the token metadata program when passed
+    #[account(mut)]
+    pub nft_metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: The NFT's master edition, closed by the token metadata program when passed
+    #[account(mut)]
+    pub nft_master_edition: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Program-wide share of settlement fees, at `[b"protocol_config"]`
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    /// Portion of each marketplace fee paid to the protocol treasury
+    pub protocol_fee_bps: u16,
+    pub treasury: Pubkey,
+    pub updated_at: i64,
+}
+
+#[derive(Accounts)]
+pub struct SetClosingDate<'info> {
+    #[account(has_one = buyer @ ErrorCode::NotOfferBuyer)]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + ClosingSchedule::INIT_SPACE,
+        seeds = [b"closing_schedule", offer.key().as_ref()],
+        bump
+    )]
+    pub closing_schedule: Account<'info, ClosingSchedule>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseClosingEscrow<'info> {
+    #[account(mut)]
+    pub property: Account<'info, Property>,
+    #[account(
+        mut,
+        has_one = property @ ErrorCode::OfferPropertyMismatch,
+        has_one = escrow @ ErrorCode::EscrowMismatch,
+        has_one = buyer @ ErrorCode::NotOfferBuyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        seeds = [b"escrow", offer.key().as_ref()],
+        bump = escrow.bump,
+        has_one = offer @ ErrorCode::EscrowMismatch,
+        has_one = property @ ErrorCode::OfferPropertyMismatch
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"closing_schedule", offer.key().as_ref()],
+        bump,
+        has_one = offer @ ErrorCode::OfferPropertyMismatch
+    )]
+    pub closing_schedule: Account<'info, ClosingSchedule>,
+    /// CHECK: Financing contingency PDA, only read when the offer was financed
+    #[account(seeds = [b"financing", offer.key().as_ref()], bump)]
+    pub financing: UncheckedAccount<'info>,
+    /// CHECK: Receives the refund and the schedule's rent, checked against the offer
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    /// CHECK: Receives any financing penalty
+    #[account(mut, address = property.owner @ ErrorCode::NotPropertyOwner)]
+    pub seller: UncheckedAccount<'info>,
+    /// Only needed while the NFT is in escrow
+    #[account(
+        mut,
+        constraint = escrow_nft_account.mint == property.nft_mint
+            && escrow_nft_account.owner == escrow.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub escrow_nft_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_nft_account.mint == property.nft_mint
+            && seller_nft_account.owner == property.owner @ ErrorCode::InvalidTokenAccount
+    )]
+    pub seller_nft_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureListingLimits<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ListingLimits::INIT_SPACE,
+        seeds = [b"listing_limits", marketplace.key().as_ref()],
+        bump
+    )]
+    pub listing_limits: Account<'info, ListingLimits>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct SetOwnerListingLimit<'info> {
+    #[account(has_one = authority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + OwnerListings::INIT_SPACE,
+        seeds = [b"owner_listings", marketplace.key().as_ref(), owner.as_ref()],
+        bump
+    )]
+    pub owner_listings: Account<'info, OwnerListings>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseListingSlot<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"listing_slot", listing_slot.property.as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub listing_slot: Account<'info, ListingSlot>,
+    #[account(
+        mut,
+        seeds = [
+            b"owner_listings",
+            listing_slot.marketplace.as_ref(),
+            listing_slot.owner.as_ref()
+        ],
+        bump
+    )]
+    pub owner_listings: Account<'info, OwnerListings>,
+    /// CHECK: The listed property, which may have been retired since
+    #[account(address = listing_slot.property)]
+    pub property: UncheckedAccount<'info>,
+    /// CHECK: Owner who took the slot, refunded its rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddRenovation<'info> {
+    #[account(constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner)]
+    pub property: Account<'info, Property>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PropertyRenovations::INIT_SPACE,
+        seeds = [b"renovations", property.key().as_ref()],
+        bump
+    )]
+    pub renovations: Account<'info, PropertyRenovations>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Marketplace {
+    pub authority: Pubkey,
+    pub properties_count: u64,
+    pub fee_percentage: u64,
+    pub sol_usd_price_feed: Pubkey,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u16,
+    pub version: u8,
+    /// Blocks new listings, offers and instant purchases; settlement of existing deals continues
+    pub paused: bool,
+    /// Delay between queueing a parameter change and when it can be executed
+    pub timelock_secs: i64,
+    /// Index of the next queued parameter change
+    pub change_count: u64,
+    /// Flat fee charged into the treasury for each new listing
+    pub listing_fee_lamports: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Property {
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    #[max_len(MAX_PROPERTY_ID_LEN)]
+    pub property_id: String,
+    pub price: u64,
+    #[max_len(INITIAL_METADATA_URI_LEN)]
+    pub metadata_uri: String,
+    #[max_len(MAX_LOCATION_LEN)]
+    pub location: String,
+    pub square_feet: u64,
+    pub bedrooms: u8,
+    pub bathrooms: u8,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub transaction_count: u64,
+    pub nft_mint: Pubkey,
+    pub min_offer_bps: u16,
+    pub cadastral_id: [u8; 32],
+    pub jurisdiction_code: [u8; 8],
+    pub registry_verified: bool,
+    pub registry_verifier: Pubkey,
+    pub registry_verified_at: i64,
+    pub auto_accept: bool,
+    pub expires_at: Option<i64>,
+    pub price_currency: PriceCurrency,
+    pub attribute_change_count: u64,
+    pub price_schedule: Option<PriceSchedule>,
+    pub co_owned: bool,
+    pub bundle: Option<Pubkey>,
+    pub parent: Option<Pubkey>,
+    pub child_count: u8,
+    pub merged_into: Option<Pubkey>,
+    pub lien_count: u8,
+    pub document_count: u32,
+    pub property_type: PropertyType,
+    pub amenities: u64,
+    /// Zero-padded base32 geohash, 8 to 10 characters
+    pub geohash: [u8; GEOHASH_LEN],
+    pub latitude_e6: Option<i32>,
+    pub longitude_e6: Option<i32>,
+    pub version: u8,
+    /// Pending and accepted offers, capped by the marketplace's offer limits
+    pub open_offer_count: u16,
+    /// Association that must sign off on each sale with approve_transfer
+    pub approval_authority: Option<Pubkey>,
+    /// Energy fields are only set by a registered verifier through attest_energy_rating
+    pub energy_rating: EnergyRating,
+    /// SHA-256 of the LEED certificate
+    pub leed_certificate_hash: Option<[u8; 32]>,
+    /// SHA-256 of the Energy Performance Certificate
+    pub epc_certificate_hash: Option<[u8; 32]>,
+    pub energy_verifier: Option<Pubkey>,
+    pub energy_attested_at: i64,
+    pub bump: u8,
+    /// Set on slim listings, whose metadata URI and location are left empty and live only in the
+    /// off-chain metadata JSON
+    pub content_hashes: Option<ContentHashes>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Offer {
+    pub buyer: Pubkey,
+    pub property: Pubkey,
+    pub amount: u64,
+    pub status: OfferStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub expiration_time: i64,
+    pub escrow: Pubkey,
+    pub nonce: u64,
+    pub version: u8,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TransactionHistory {
+    pub property: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+    pub transaction_index: u64,
+    /// Credited to the buyer out of `price`, so the seller netted `price - seller_concession` before fees
+    pub seller_concession: u64,
+    pub version: u8,
+    /// Hash of the title insurance policy document, when the buyer bought one
+    pub title_policy_hash: Option<[u8; 32]>,
+    /// Association that approved the sale, when the property required one
+    pub transfer_approved_by: Option<Pubkey>,
+}
+
+/// Entry point for walking a property's sales: records 1..=count live at
+/// `[b"transaction", property, index]`, newest at `latest_history`. Older records may have been
+/// closed by `archive_history`.
+#[account]
+#[derive(InitSpace)]
+pub struct HistoryIndex {
+    pub property: Pubkey,
+    pub count: u64,
+    pub latest_history: Pubkey,
+    pub updated_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    pub offer: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub nft_held: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Assignment of an accepted offer, at `[b"offer_assignment", offer]`. The offer and escrow
+/// name the assignee as buyer from then on.
+#[account]
+#[derive(InitSpace)]
+pub struct OfferAssignment {
+    pub offer: Pubkey,
+    pub original_buyer: Pubkey,
+    pub assignee: Pubkey,
+    /// Escrowed deposit the assignee paid back to the original buyer
+    pub reimbursed: u64,
+    pub assignment_fee: u64,
+    pub assigned_at: i64,
+}
+
+/// Association sign-off on an offer, at `[b"transfer_approval", offer]`. Only good for the buyer
+/// and price it was given for.
+#[account]
+#[derive(InitSpace)]
+pub struct TransferApproval {
+    pub property: Pubkey,
+    pub offer: Pubkey,
+    pub authority: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub approved_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Review {
+    pub transaction_history: Pubkey,
+    pub property: Pubkey,
+    pub reviewer: Pubkey,
+    pub reviewee: Pubkey,
+    pub rating: u8,
+    pub comment_hash: [u8; 32],
+    pub created_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Verifier {
+    pub marketplace: Pubkey,
+    pub verifier: Pubkey,
+    pub registered_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Insurer {
+    pub marketplace: Pubkey,
+    pub insurer: Pubkey,
+    pub registered_at: i64,
+}
+
+/// A buyer's title insurance policy on an offer. Holds the premium above its rent until
+/// settlement pays it to the insurer.
+#[account]
+#[derive(InitSpace)]
+pub struct TitleInsurance {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub insurer: Pubkey,
+    pub premium: u64,
+    pub policy_hash: [u8; 32],
+    pub created_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PriceCurrency {
+    Lamports,
+    UsdCents,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AttributeChange {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub verifier: Pubkey,
+    pub change_index: u64,
+    pub old_square_feet: u64,
+    pub old_bedrooms: u8,
+    pub old_bathrooms: u8,
+    pub new_square_feet: u64,
+    pub new_bedrooms: u8,
+    pub new_bathrooms: u8,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct PriceSchedule {
+    pub start_price: u64,
+    pub drop_amount: u64,
+    pub interval_secs: i64,
+    pub floor_price: u64,
+    pub start_time: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CoOwnership {
+    pub property: Pubkey,
+    pub owners: [Pubkey; MAX_CO_OWNERS],
+    pub shares_bps: [u16; MAX_CO_OWNERS],
+    pub owner_count: u8,
+    pub threshold: u8,
+    pub approval_target: Pubkey,
+    pub approvals: u16,
+    pub created_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Bundle {
+    pub marketplace: Pubkey,
+    pub seller: Pubkey,
+    pub bundle_id: u64,
+    pub properties: [Pubkey; MAX_BUNDLE_PROPERTIES],
+    pub property_count: u8,
+    pub price: u64,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BundleOffer {
+    pub bundle: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub status: OfferStatus,
+    pub nonce: u64,
+    pub created_at: i64,
+    pub expiration_time: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MergeRecord {
+    pub merged_property: Pubkey,
+    pub sources: [Pubkey; MAX_MERGE_PARCELS],
+    pub source_count: u8,
+    pub merged_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiptConfig {
+    pub marketplace: Pubkey,
+    pub collection_mint: Pubkey,
+    #[max_len(MAX_RECEIPT_BASE_URI_LEN)]
+    pub base_uri: String,
+    pub authority_bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Lienholder {
+    pub marketplace: Pubkey,
+    pub lienholder: Pubkey,
+    pub registered_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Lien {
+    pub property: Pubkey,
+    pub lienholder: Pubkey,
+    pub amount: u64,
+    pub document_hash: [u8; 32],
+    pub created_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum DocumentType {
+    Deed,
+    Survey,
+    InspectionReport,
+    Other,
+}
+
+/// Hash of an off-chain document, so buyers can check the copy they were given
+#[account]
+#[derive(InitSpace)]
+pub struct PropertyDocument {
+    pub property: Pubkey,
+    pub doc_type: DocumentType,
+    pub sha256_hash: [u8; 32],
+    #[max_len(MAX_DOCUMENT_URI_LEN)]
+    pub uri: String,
+    pub registered_by: Pubkey,
+    pub created_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum MediaKind {
+    Photo,
+    FloorPlan,
+    VirtualTour,
+    Video,
+    Other,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct MediaItem {
+    pub kind: MediaKind,
+    /// SHA-256 of the media URI, which is served off-chain alongside the metadata
+    pub uri_hash: [u8; 32],
+}
+
+/// Gallery beyond the single metadata URI, at `[b"property_media", property]`. Stays with the
+/// property across sales.
+#[account]
+#[derive(InitSpace)]
+pub struct PropertyMedia {
+    pub property: Pubkey,
+    #[max_len(MAX_MEDIA_ITEMS)]
+    pub items: Vec<MediaItem>,
+    pub updated_at: i64,
+}
+
+/// SHA-256 of the off-chain metadata JSON and of the location string it carries
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct ContentHashes {
+    pub metadata_hash: [u8; 32],
+    pub location_hash: [u8; 32],
+}
+
+/// EPC-style energy efficiency band, A being the most efficient
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum EnergyRating {
+    Unrated,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PropertyType {
+    Residential,
+    Commercial,
+    Land,
+    Industrial,
+}
+
+/// Marketplace as originally deployed, before accounts carried a version
+#[derive(AnchorDeserialize)]
+pub struct LegacyMarketplace {
+    pub authority: Pubkey,
+    pub properties_count: u64,
+    pub fee_percentage: u64,
+}
+
+impl LegacyMarketplace {
+    fn upgrade(self) -> Marketplace {
+        Marketplace {
+            authority: self.authority,
+            properties_count: self.properties_count,
+            fee_percentage: self.fee_percentage,
+            sol_usd_price_feed: Pubkey::default(),
+            max_price_age_secs: DEFAULT_MAX_PRICE_AGE_SECS,
+            max_confidence_bps: DEFAULT_MAX_CONFIDENCE_BPS,
+            version: MARKETPLACE_VERSION,
+            paused: false,
+            timelock_secs: 0,
+            change_count: 0,
+            listing_fee_lamports: 0,
+            bump: 0,
+        }
+    }
+}
+
+/// Marketplace at version 1, before governance parameters
+#[derive(AnchorDeserialize)]
+pub struct MarketplaceV1 {
+    pub authority: Pubkey,
+    pub properties_count: u64,
+    pub fee_percentage: u64,
+    pub sol_usd_price_feed: Pubkey,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u16,
+    pub version: u8,
+}
+
+impl MarketplaceV1 {
+    fn upgrade(self) -> Marketplace {
+        Marketplace {
+            authority: self.authority,
+            properties_count: self.properties_count,
+            fee_percentage: self.fee_percentage,
+            sol_usd_price_feed: self.sol_usd_price_feed,
+            max_price_age_secs: self.max_price_age_secs,
+            max_confidence_bps: self.max_confidence_bps,
+            version: MARKETPLACE_VERSION,
+            paused: false,
+            timelock_secs: 0,
+            change_count: 0,
+            listing_fee_lamports: 0,
+            bump: 0,
+        }
+    }
+}
+
+/// Property as originally deployed, before accounts carried a version
+#[derive(AnchorDeserialize)]
+pub struct LegacyProperty {
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    pub property_id: String,
+    pub price: u64,
+    pub metadata_uri: String,
+    pub location: String,
+    pub square_feet: u64,
+    pub bedrooms: u8,
+    pub bathrooms: u8,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub transaction_count: u64,
+    pub nft_mint: Pubkey,
+}
+
+impl LegacyProperty {
+    fn upgrade(self) -> Property {
+        Property {
+            marketplace: self.marketplace,
+            owner: self.owner,
+            property_id: self.property_id,
+            price: self.price,
+            metadata_uri: self.metadata_uri,
+            location: self.location,
+            square_feet: self.square_feet,
+            bedrooms: self.bedrooms,
+            bathrooms: self.bathrooms,
+            is_active: self.is_active,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            transaction_count: self.transaction_count,
+            nft_mint: self.nft_mint,
+            min_offer_bps: 0,
+            cadastral_id: [0; 32],
+            jurisdiction_code: [0; 8],
+            registry_verified: false,
+            registry_verifier: Pubkey::default(),
+            registry_verified_at: 0,
+            auto_accept: false,
+            expires_at: None,
+            price_currency: PriceCurrency::Lamports,
+            attribute_change_count: 0,
+            price_schedule: None,
+            co_owned: false,
+            bundle: None,
+            parent: None,
+            child_count: 0,
+            merged_into: None,
+            lien_count: 0,
+            document_count: 0,
+            property_type: PropertyType::Residential,
+            amenities: 0,
+            geohash: [0; GEOHASH_LEN],
+            latitude_e6: None,
+            longitude_e6: None,
+            version: PROPERTY_VERSION,
+            open_offer_count: 0,
+            approval_authority: None,
+            energy_rating: EnergyRating::Unrated,
+            leed_certificate_hash: None,
+            epc_certificate_hash: None,
+            energy_verifier: None,
+            energy_attested_at: 0,
+            bump: 0,
+            content_hashes: None,
+        }
+    }
+}
+
+/// Transaction history as originally deployed, before seller concessions were recorded
+#[derive(AnchorDeserialize)]
+pub struct LegacyTransactionHistory {
+    pub property: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+    pub transaction_index: u64,
+}
+
+impl LegacyTransactionHistory {
+    fn upgrade(self) -> TransactionHistory {
+        TransactionHistory {
+            property: self.property,
+            seller: self.seller,
+            buyer: self.buyer,
+            price: self.price,
+            timestamp: self.timestamp,
+            transaction_index: self.transaction_index,
+            seller_concession: 0,
+            version: TRANSACTION_HISTORY_VERSION,
+            title_policy_hash: None,
+            transfer_approved_by: None,
+        }
+    }
+}
+
+/// Offer as originally deployed, before offers were seeded with a nonce
+#[derive(AnchorDeserialize)]
+pub struct LegacyOffer {
+    pub buyer: Pubkey,
+    pub property: Pubkey,
+    pub amount: u64,
+    pub status: OfferStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub expiration_time: i64,
+    pub escrow: Pubkey,
+}
+
+impl LegacyOffer {
+    fn upgrade(self) -> Offer {
+        Offer {
+            buyer: self.buyer,
+            property: self.property,
+            amount: self.amount,
+            status: self.status,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            expiration_time: self.expiration_time,
+            escrow: self.escrow,
+            nonce: 0,
+            version: OFFER_VERSION,
+            bump: 0,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ResponseRule {
+    pub marketplace: Pubkey,
+    pub response_window_secs: i64,
+    pub bond_lamports: u64,
+    pub updated_at: i64,
+}
+
+/// Lamports a seller puts up for a property, paid to buyers whose offers go unanswered
+#[account]
+#[derive(InitSpace)]
+pub struct SellerBond {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub reserved: u64,
+    pub updated_at: i64,
+}
+
+/// Response deadline for one offer and the seller bond reserved against it
+#[account]
+#[derive(InitSpace)]
+pub struct ResponseBond {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub seller_bond: Pubkey,
+    pub amount: u64,
+    pub deadline: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FinancingRule {
+    pub marketplace: Pubkey,
+    pub penalty_bps: u16,
+    pub updated_at: i64,
+}
+
+/// Terms of an offer escrowed with earnest money only, at `[b"financing", offer]`
+#[account]
+#[derive(InitSpace)]
+pub struct FinancingContingency {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub earnest_amount: u64,
+    pub financing_deadline: i64,
+    pub penalty_bps: u16,
+    pub funded: bool,
+}
+
+/// Closing-cost credit the seller agreed to at acceptance, at `[b"seller_concession", offer]`
+#[account]
+#[derive(InitSpace)]
+pub struct SellerConcession {
+    pub offer: Pubkey,
+    pub amount: u64,
+}
+
+/// Milestone tranches of a sale's seller payout, at `[b"release_schedule", escrow]`. The payout
+/// is fixed and held in the escrow once the sale executes.
+#[account]
+#[derive(InitSpace)]
+pub struct ReleaseSchedule {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub arbiter: Option<Pubkey>,
+    #[max_len(MAX_MILESTONES)]
+    pub tranche_bps: Vec<u16>,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub released_count: u8,
+    pub funded: bool,
+}
+
+/// Program-owned fee custody for a marketplace, at `[b"treasury", marketplace]`
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub marketplace: Pubkey,
+    pub total_collected: u64,
+    pub total_withdrawn: u64,
+}
+
+/// A marketplace parameter change awaiting its timelock, at `[b"queued_change", marketplace, index]`
+#[account]
+#[derive(InitSpace)]
+pub struct QueuedChange {
+    pub marketplace: Pubkey,
+    pub index: u64,
+    pub change: ParameterChange,
+    pub queued_at: i64,
+    pub eta: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum ParameterChange {
+    SetFee { fee_bps: u64 },
+    SetPaused { paused: bool },
+    SetTimelock { timelock_secs: i64 },
+    SetAuthority { authority: Pubkey },
+    AddVerifier { verifier: Pubkey },
+    RemoveVerifier { verifier: Pubkey },
+    SetListingFee { listing_fee_lamports: u64 },
+}
+
+impl ParameterChange {
+    /// Verifier a change applies to, used to derive `verifier_account`
+    pub fn verifier(&self) -> Pubkey {
+        match self {
+            ParameterChange::AddVerifier { verifier } | ParameterChange::RemoveVerifier { verifier } => *verifier,
+            _ => Pubkey::default(),
+        }
+    }
+}
+
+/// Staking settings for a marketplace at `[b"staking_config", marketplace]`; also the stake vault's authority
+#[account]
+#[derive(InitSpace)]
+pub struct StakingConfig {
+    pub marketplace: Pubkey,
+    pub stake_mint: Pubkey,
+    #[max_len(MAX_STAKE_TIERS)]
+    pub tiers: Vec<StakeTier>,
+    pub min_lock_secs: i64,
+    pub total_staked: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct StakeTier {
+    pub min_amount: u64,
+    pub discount_bps: u16,
+}
+
+/// A user's staked balance at `[b"stake", marketplace, owner]`
+#[account]
+#[derive(InitSpace)]
+pub struct StakeAccount {
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked_at: i64,
+}
+
+/// NFT-collateralized loan at `[b"loan", property]`; the NFT sits in the loan's associated token account
+#[account]
+#[derive(InitSpace)]
+pub struct Loan {
+    pub property: Pubkey,
+    pub borrower: Pubkey,
+    /// Default until funded
+    pub lender: Pubkey,
+    pub nft_mint: Pubkey,
+    pub principal: u64,
+    /// Flat interest owed on top of the principal
+    pub interest: u64,
+    pub duration_secs: i64,
+    pub requested_at: i64,
+    pub funded_at: i64,
+    pub due_at: i64,
+    pub status: LoanStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum LoanStatus {
+    Requested,
+    Active,
+    Liquidating,
+}
+
+/// Auction for a defaulted loan's collateral at `[b"liquidation", loan]`. The highest bid is
+/// held in this account's lamports until it is outbid or settled.
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidationAuction {
+    pub loan: Pubkey,
+    /// Paid the rent and gets it back on settlement
+    pub opened_by: Pubkey,
+    pub started_at: i64,
+    pub ends_at: i64,
+    pub highest_bid: u64,
+    pub highest_bidder: Option<Pubkey>,
+    /// Bids placed with this long or less remaining extend the auction
+    pub snipe_window_secs: i64,
+    pub extension_secs: i64,
+}
+
+/// Where a property's sale proceeds are paid, at `[b"payout_address", property]`. Only honoured
+/// while `owner` still owns the property.
+#[account]
+#[derive(InitSpace)]
+pub struct PayoutAddress {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub beneficiary: Pubkey,
+    pub updated_at: i64,
+}
+
+/// Marks that a property NFT's metadata update authority is held by the property PDA, at
+/// `[b"metadata_custody", property]`
+#[account]
+#[derive(InitSpace)]
+pub struct MetadataCustody {
+    pub property: Pubkey,
+    pub delegated_by: Pubkey,
+    pub delegated_at: i64,
+}
+
+/// Rental agreement for a property at `[b"lease", property]`
+#[account]
+#[derive(InitSpace)]
+pub struct Lease {
+    pub property: Pubkey,
+    pub landlord: Pubkey,
+    pub tenant: Pubkey,
+    pub monthly_rent: u64,
+    /// Charged on top of the rent when a period is paid after its grace period
+    pub late_fee_bps: u16,
+    pub grace_period_secs: i64,
+    pub start_at: i64,
+    pub term_periods: u16,
+    /// End of the last paid period; the next period is due at this time
+    pub paid_through: i64,
+    pub periods_paid: u16,
+    pub created_at: i64,
+}
+
+/// Governance a property is held under, at `[b"property_governance", property]`
+#[account]
+#[derive(InitSpace)]
+pub struct PropertyGovernance {
+    pub property: Pubkey,
+    pub governance: Pubkey,
+    pub attached_at: i64,
+}
+
+/// Single-use approval from a property's governance to accept an offer of at least
+/// `min_amount`, at `[b"sale_authorization", property]`
+#[account]
+#[derive(InitSpace)]
+pub struct SaleAuthorization {
+    pub property: Pubkey,
+    pub governance: Pubkey,
+    pub min_amount: u64,
+    pub expires_at: i64,
+    pub authorized_at: i64,
+}
+
+/// Tenant's security deposit for a lease, at `[b"security_deposit", lease]`. The deposit is held
+/// in this account's lamports on top of its rent.
+#[account]
+#[derive(InitSpace)]
+pub struct SecurityDeposit {
+    pub lease: Pubkey,
+    pub landlord: Pubkey,
+    pub tenant: Pubkey,
+    /// Resolves disputed deductions; without one the tenant cannot dispute
+    pub arbiter: Option<Pubkey>,
+    pub amount: u64,
+    #[max_len(MAX_DEPOSIT_DEDUCTIONS)]
+    pub deductions: Vec<DepositDeduction>,
+    pub status: DepositStatus,
+    pub lease_ended_at: i64,
+    pub submitted_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct DepositDeduction {
+    pub amount: u64,
+    /// SHA-256 of the supporting document (invoice, photos, inspection report)
+    pub document_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum DepositStatus {
+    Held,
+    DeductionsSubmitted,
+    Disputed,
+}
+
+/// Per-property offer cap for a marketplace at `[b"offer_limits", marketplace]`
+#[account]
+#[derive(InitSpace)]
+pub struct OfferLimits {
+    pub marketplace: Pubkey,
+    /// Most pending or accepted offers a property can have; zero means unlimited
+    pub max_open_offers: u16,
+}
+
+/// Anti-sniping settings for a marketplace's auctions at `[b"auction_config", marketplace]`
+#[account]
+#[derive(InitSpace)]
+pub struct AuctionConfig {
+    pub marketplace: Pubkey,
+    pub snipe_window_secs: i64,
+    pub extension_secs: i64,
+}
+
+/// How long sale records are kept for a marketplace, at `[b"history_retention", marketplace]`
+#[account]
+#[derive(InitSpace)]
+pub struct HistoryRetention {
+    pub marketplace: Pubkey,
+    pub retention_years: u16,
+}
+
+/// Transfer tax for properties in one jurisdiction, at
+/// `[b"tax_config", marketplace, jurisdiction_code]`
+#[account]
+#[derive(InitSpace)]
+pub struct TaxConfig {
+    pub marketplace: Pubkey,
+    pub jurisdiction_code: [u8; 8],
+    pub tax_bps: u16,
+    pub collector: Pubkey,
+    pub updated_at: i64,
+}
+
+/// Lamports sellers lock per listing, at `[b"listing_bond_config", marketplace]`
+#[account]
+#[derive(InitSpace)]
+pub struct ListingBondConfig {
+    pub marketplace: Pubkey,
+    pub bond_lamports: u64,
+}
+
+/// Bond locked by `owner` when listing a property, held above the account's rent
+#[account]
+#[derive(InitSpace)]
+pub struct ListingBond {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub locked_at: i64,
+}
+
+/// Arguments for one listing in `list_properties_batch`, matching `list_property`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ListingArgs {
+    pub property_id: String,
+    pub price: u64,
+    pub metadata_uri: String,
+    pub location: String,
+    pub square_feet: u64,
+    pub bedrooms: u8,
+    pub bathrooms: u8,
+    pub expires_at: Option<i64>,
+    pub property_type: PropertyType,
+    pub amenities: u64,
+    pub geohash: String,
+    pub latitude_e6: Option<i32>,
+    pub longitude_e6: Option<i32>,
+    /// Lists slim, with `metadata_uri` and `location` left empty
+    pub content_hashes: Option<ContentHashes>,
+}
+
+/// Claim on a real-world parcel across all marketplaces, at
+/// `[b"property_registry", normalized_parcel_key(cadastral_id, jurisdiction_code)]`
+#[account]
+#[derive(InitSpace)]
+pub struct PropertyRegistry {
+    pub parcel_key: [u8; 32],
+    /// Property holding the parcel
+    pub property: Pubkey,
+    pub marketplace: Pubkey,
+    /// Paid the rent and gets it back on release
+    pub claimant: Pubkey,
+    pub claimed_at: i64,
+}
+
+/// Closing date the buyer proposed and the seller accepted, at `[b"closing_schedule", offer]`
+#[account]
+#[derive(InitSpace)]
+pub struct ClosingSchedule {
+    pub offer: Pubkey,
+    pub closing_date: i64,
+    pub scheduled_at: i64,
+}
+
+/// Per-owner listing cap for a marketplace at `[b"listing_limits", marketplace]`
+#[account]
+#[derive(InitSpace)]
+pub struct ListingLimits {
+    pub marketplace: Pubkey,
+    /// Most active listings one wallet can hold; zero means unlimited
+    pub max_listings_per_owner: u16,
+}
+
+/// A wallet's active listings in a marketplace, at `[b"owner_listings", marketplace, owner]`
+#[account]
+#[derive(InitSpace)]
+pub struct OwnerListings {
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    /// Listings holding a slot
+    pub active_listings: u16,
+    /// Authority override of the marketplace's cap for this wallet; zero means unlimited
+    pub max_listings: Option<u16>,
+}
+
+/// Marks a listing as counted against `owner`, at `[b"listing_slot", property]`
+#[account]
+#[derive(InitSpace)]
+pub struct ListingSlot {
+    pub property: Pubkey,
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    pub taken_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct RenovationRecord {
+    /// SHA-256 of the off-chain description of the work
+    pub description_hash: [u8; 32],
+    /// Cost of the work in lamports
+    pub cost: u64,
+    pub completed_at: i64,
+    /// Owner at the time the record was added
+    pub recorded_by: Pubkey,
+    pub recorded_at: i64,
+}
+
+/// Improvement history at `[b"renovations", property]`, oldest first. Stays with the property
+/// across sales.
+#[account]
+#[derive(InitSpace)]
+pub struct PropertyRenovations {
+    pub property: Pubkey,
+    #[max_len(MAX_RENOVATIONS)]
+    pub records: Vec<RenovationRecord>,
+    pub updated_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum OfferStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Completed,
+    Expired,
+    /// Unwound by the marketplace authority with `force_cancel_offer`
+    Cancelled,
+}
+
+/// Why the marketplace authority stepped in, recorded in `AdminIntervention`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum InterventionReason {
+    BadMetadata,
+    CompromisedWallet,
+    Dispute,
+    Other,
+}
+
+#[event]
+pub struct PropertyListed {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub property_id: String,
+    pub price: u64,
+    /// Paid into the marketplace treasury for the listing
+    pub listing_fee: u64,
+    pub nft_mint: Pubkey,
+    pub property_type: PropertyType,
+    pub amenities: u64,
+    pub geohash: [u8; GEOHASH_LEN],
+    pub latitude_e6: Option<i32>,
+    pub longitude_e6: Option<i32>,
+    pub content_hashes: Option<ContentHashes>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertyUpdated {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub price: u64,
+    pub is_active: bool,
+    pub min_offer_bps: u16,
+    pub property_type: PropertyType,
+    pub amenities: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferCreated {
+    pub offer: Pubkey,
+    pub marketplace: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub offer: Pubkey,
+    pub marketplace: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferRejected {
+    pub offer: Pubkey,
+    pub marketplace: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertySold {
+    pub property: Pubkey,
+    pub marketplace: Pubkey,
+    pub transaction_history: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub price: u64,
+    pub seller_concession: u64,
+    /// Settlement fee rate after any staking discount
+    pub fee_bps: u64,
+    /// Paid to the marketplace treasury out of `price`
+    pub marketplace_fee: u64,
+    /// Paid to the protocol treasury out of `price`; with `marketplace_fee` makes up `fee_bps`
+    pub protocol_fee: u64,
+    /// Paid to the jurisdiction's tax collector out of the seller's share
+    pub transfer_tax: u64,
+    /// Paid to lienholders out of the seller's share
+    pub lien_payoff: u64,
+    /// What the seller side netted after the fee, transfer tax, lien payoffs and concession,
+    /// including co-owner shares and any amount held back under a release schedule
+    pub seller_proceeds: u64,
+    /// Mint the price was paid in; None for SOL
+    pub payment_mint: Option<Pubkey>,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferExpired {
+    pub offer: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReviewSubmitted {
+    pub review: Pubkey,
+    pub transaction_history: Pubkey,
+    pub property: Pubkey,
+    pub reviewer: Pubkey,
+    pub reviewee: Pubkey,
+    pub rating: u8,
+    pub comment_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VerifierRegistered {
+    pub marketplace: Pubkey,
+    pub verifier: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VerifierRemoved {
+    pub marketplace: Pubkey,
+    pub verifier: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsurerRegistered {
+    pub marketplace: Pubkey,
+    pub insurer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsurerRemoved {
+    pub marketplace: Pubkey,
+    pub insurer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TitleInsuranceAdded {
+    pub offer: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub insurer: Pubkey,
+    pub premium: u64,
+    pub policy_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TitleInsuranceCancelled {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub premium: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TitlePremiumReleased {
+    pub offer: Pubkey,
+    pub insurer: Pubkey,
+    pub premium: u64,
+    pub policy_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LandRegistryInfoSet {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub cadastral_id: [u8; 32],
+    pub jurisdiction_code: [u8; 8],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertyVerified {
+    pub property: Pubkey,
+    pub verifier: Pubkey,
+    pub cadastral_id: [u8; 32],
+    pub jurisdiction_code: [u8; 8],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EnergyRatingAttested {
+    pub property: Pubkey,
+    pub verifier: Pubkey,
+    pub energy_rating: EnergyRating,
+    pub leed_certificate_hash: Option<[u8; 32]>,
+    pub epc_certificate_hash: Option<[u8; 32]>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AutoAcceptUpdated {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub auto_accept: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingExpired {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceOracleConfigured {
+    pub marketplace: Pubkey,
+    pub sol_usd_price_feed: Pubkey,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceCurrencyUpdated {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub price_currency: PriceCurrency,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertyAttributesUpdated {
+    pub property: Pubkey,
+    pub attribute_change: Pubkey,
+    pub verifier: Pubkey,
+    pub square_feet: u64,
+    pub bedrooms: u8,
+    pub bathrooms: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceScheduleUpdated {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub price_schedule: Option<PriceSchedule>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CoOwnershipCreated {
+    pub property: Pubkey,
+    pub co_ownership: Pubkey,
+    pub owners: Vec<Pubkey>,
+    pub shares_bps: Vec<u16>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SaleApproved {
+    pub property: Pubkey,
+    pub co_owner: Pubkey,
+    pub target: Pubkey,
+    pub approvals: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleCreated {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub properties: Vec<Pubkey>,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleCancelled {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleOfferCreated {
+    pub bundle_offer: Pubkey,
+    pub bundle: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleOfferClosed {
+    pub bundle_offer: Pubkey,
+    pub bundle: Pubkey,
+    pub buyer: Pubkey,
+    pub status: OfferStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleSold {
+    pub bundle: Pubkey,
+    pub bundle_offer: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub fee_bps: u64,
+    pub marketplace_fee: u64,
+    pub protocol_fee: u64,
+    pub transfer_tax: u64,
+    pub seller_proceeds: u64,
+    /// Mint the price was paid in; None for SOL
+    pub payment_mint: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertySubdivided {
+    pub parent: Pubkey,
+    pub owner: Pubkey,
+    pub children: Vec<Pubkey>,
+    pub child_property_ids: Vec<String>,
+    pub child_square_feet: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParcelsMerged {
+    pub merged_property: Pubkey,
+    pub owner: Pubkey,
+    pub sources: Vec<Pubkey>,
+    pub square_feet: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptCollectionCreated {
+    pub marketplace: Pubkey,
+    pub collection_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettlementReceiptMinted {
+    pub transaction_history: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LienholderRegistered {
+    pub marketplace: Pubkey,
+    pub lienholder: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LienholderRemoved {
+    pub marketplace: Pubkey,
+    pub lienholder: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LienAttached {
+    pub lien: Pubkey,
+    pub property: Pubkey,
+    pub lienholder: Pubkey,
+    pub amount: u64,
+    pub document_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LienReleased {
+    pub lien: Pubkey,
+    pub property: Pubkey,
+    pub lienholder: Pubkey,
+    pub amount: u64,
+    pub paid_off: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DocumentRegistered {
+    pub document: Pubkey,
+    pub property: Pubkey,
+    pub doc_type: DocumentType,
+    pub sha256_hash: [u8; 32],
+    pub uri: String,
+    pub registered_by: Pubkey,
+    pub timestamp: i64,
+}
 
-#[derive(Accounts)]
-pub struct UpdateProperty<'info> {
-    #[account(
-        mut,
-        constraint = property.owner == *owner.key @ ErrorCode::NotPropertyOwner
-    )]
-    pub property: Account<'info, Property>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// CHECK: This is the owner's NFT token account
-    #[account(
-        mut,
-        constraint = owner_nft_account.owner == &token::ID @ ErrorCode::InvalidTokenAccount
-    )]
-    pub owner_nft_account: AccountInfo<'info>,
-    /// CHECK: This is the NFT mint account
-    #[account(
-        constraint = property.nft_mint == *property_nft_mint.key @ ErrorCode::InvalidNFTMint
-    )]
-    pub property_nft_mint: AccountInfo<'info>,
+#[event]
+pub struct PropertyMediaUpdated {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    /// The full gallery after the change
+    pub items: Vec<MediaItem>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertyResized {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub metadata_uri_capacity: u16,
+    pub space: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContentHashesSet {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub metadata_hash: [u8; 32],
+    pub location_hash: [u8; 32],
+    pub space: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResponseRuleUpdated {
+    pub marketplace: Pubkey,
+    pub response_window_secs: i64,
+    pub bond_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SellerBondUpdated {
+    pub seller_bond: Pubkey,
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub available: u64,
+    pub reserved: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResponseBondLocked {
+    pub offer: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnansweredOfferClaimed {
+    pub offer: Pubkey,
+    pub property: Pubkey,
+    pub buyer: Pubkey,
+    pub refunded: u64,
+    pub bond_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FinancingRuleUpdated {
+    pub marketplace: Pubkey,
+    pub penalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FinancingContingencyCreated {
+    pub offer: Pubkey,
+    pub earnest_amount: u64,
+    pub financing_deadline: i64,
+    pub penalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FinancedOfferFunded {
+    pub offer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FinancingCancelled {
+    pub offer: Pubkey,
+    pub property: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub penalty: u64,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReleaseScheduleSet {
+    pub offer: Pubkey,
+    pub escrow: Pubkey,
+    pub tranche_bps: Vec<u16>,
+    pub arbiter: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MilestoneReleased {
+    pub escrow: Pubkey,
+    pub milestone: u8,
+    pub amount: u64,
+    pub released_by: Pubkey,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub marketplace: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParameterChangeQueued {
+    pub marketplace: Pubkey,
+    pub index: u64,
+    pub change: ParameterChange,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParameterChangeCancelled {
+    pub marketplace: Pubkey,
+    pub index: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParameterChangeExecuted {
+    pub marketplace: Pubkey,
+    pub index: u64,
+    pub change: ParameterChange,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketplaceInitialized {
+    pub marketplace: Pubkey,
+    pub authority: Pubkey,
+    pub fee_percentage: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolConfigured {
+    pub protocol_fee_bps: u16,
+    pub treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketplaceConfigUpdated {
+    pub marketplace: Pubkey,
+    pub authority: Pubkey,
+    pub fee_percentage: u64,
+    pub paused: bool,
+    pub timelock_secs: i64,
+    pub sol_usd_price_feed: Pubkey,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u16,
+    pub listing_fee_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakingConfigured {
+    pub marketplace: Pubkey,
+    pub stake_mint: Pubkey,
+    pub tiers: Vec<StakeTier>,
+    pub min_lock_secs: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Staked {
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanRequested {
+    pub loan: Pubkey,
+    pub property: Pubkey,
+    pub borrower: Pubkey,
+    pub principal: u64,
+    pub interest: u64,
+    pub duration_secs: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanCancelled {
+    pub loan: Pubkey,
+    pub property: Pubkey,
+    pub borrower: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanFunded {
+    pub loan: Pubkey,
+    pub property: Pubkey,
+    pub lender: Pubkey,
+    pub due_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanRepaid {
+    pub loan: Pubkey,
+    pub property: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanCollateralClaimed {
+    pub loan: Pubkey,
+    pub property: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidationStarted {
+    pub auction: Pubkey,
+    pub loan: Pubkey,
+    pub property: Pubkey,
+    pub ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidationBidPlaced {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidationSettled {
+    pub auction: Pubkey,
+    pub loan: Pubkey,
+    pub property: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub winning_bid: u64,
+    pub lender_amount: u64,
+    pub borrower_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutAddressSet {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub beneficiary: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutAddressCleared {
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataAuthorityTransferred {
+    pub property: Pubkey,
+    pub nft_mint: Pubkey,
+    pub new_update_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeaseCreated {
+    pub lease: Pubkey,
+    pub property: Pubkey,
+    pub landlord: Pubkey,
+    pub tenant: Pubkey,
+    pub monthly_rent: u64,
+    pub start_at: i64,
+    pub term_periods: u16,
+    pub timestamp: i64,
+}
+
+/// Receipt for one month of rent; the lease account identifies the property and parties
+#[event]
+pub struct RentReceipt {
+    pub lease: Pubkey,
+    pub period: u16,
+    pub due_at: i64,
+    pub paid_through: i64,
+    pub rent: u64,
+    pub late_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeaseEnded {
+    pub lease: Pubkey,
+    pub property: Pubkey,
+    pub periods_paid: u16,
+    pub rent_refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernanceAttached {
+    pub property: Pubkey,
+    pub governance: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernanceDetached {
+    pub property: Pubkey,
+    pub governance: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernedSaleAuthorized {
+    pub property: Pubkey,
+    pub governance: Pubkey,
+    pub min_amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositDeductionsSubmitted {
+    pub security_deposit: Pubkey,
+    pub lease: Pubkey,
+    pub total: u64,
+    pub deduction_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositDisputed {
+    pub security_deposit: Pubkey,
+    pub lease: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SecurityDepositSettled {
+    pub security_deposit: Pubkey,
+    pub lease: Pubkey,
+    pub landlord_amount: u64,
+    pub tenant_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferLimitsConfigured {
+    pub marketplace: Pubkey,
+    pub max_open_offers: u16,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-#[instruction(offer_amount: u64, expiration_time: i64)]
-pub struct MakeOffer<'info> {
-    #[account(
-        constraint = property.is_active,
-        constraint = property.owner != *buyer.key
-    )]
-    pub property: Account<'info, Property>,
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + size_of::<Offer>(),
-        seeds = [b"offer", property.key().as_ref(), buyer.key().as_ref()],
-        bump
-    )]
-    pub offer: Account<'info, Offer>,
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + size_of::<Escrow>(),
-        seeds = [b"escrow", offer.key().as_ref()],
-        bump
-    )]
-    pub escrow: Account<'info, Escrow>,
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+#[event]
+pub struct AuctionConfigured {
+    pub marketplace: Pubkey,
+    pub snipe_window_secs: i64,
+    pub extension_secs: i64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct RespondToOffer<'info> {
-    #[account(
-        mut,
-        constraint = property.owner == *owner.key
-    )]
-    pub property: Account<'info, Property>,
-    #[account(
-        mut,
-        constraint = offer.property == property.key()
-    )]
-    pub offer: Account<'info, Offer>,
-    #[account(
-        mut,
-        constraint = escrow.offer == offer.key()
-    )]
-    pub escrow: Account<'info, Escrow>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// CHECK: This is the buyer account to return funds if offer is rejected
-    #[account(mut, constraint = offer.buyer == buyer.key())]
-    pub buyer: AccountInfo<'info>,
-    /// CHECK: This is the seller's NFT token account
-    #[account(
-        mut,
-        constraint = seller_nft_account.owner == &token::ID
-    )]
-    pub seller_nft_account: AccountInfo<'info>,
-    /// CHECK: This is the escrow's NFT token account
-    #[account(
-        mut,
-        constraint = escrow_nft_account.owner == &token::ID
-    )]
-    pub escrow_nft_account: AccountInfo<'info>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct AuctionExtended {
+    pub auction: Pubkey,
+    pub previous_ends_at: i64,
+    pub ends_at: i64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct ExecuteSale<'info> {
-    #[account(mut)]
-    pub marketplace: Account<'info, Marketplace>,
-    #[account(mut)]
-    pub property: Account<'info, Property>,
-    #[account(
-        mut,
-        constraint = offer.property == property.key(),
-        constraint = offer.status == OfferStatus::Accepted,
-        constraint = offer.buyer == *buyer.key
-    )]
-    pub offer: Account<'info, Offer>,
-    #[account(
-        mut,
-        seeds = [b"escrow", offer.key().as_ref()],
-        bump,
-        constraint = escrow.offer == offer.key()
-    )]
-    pub escrow: Account<'info, Escrow>,
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + size_of::<TransactionHistory>(),
-        seeds = [
-            b"transaction",
-            property.key().as_ref(),
-            &property.transaction_count.checked_add(1).ok_or(ErrorCode::ArithmeticOverflow)?.to_le_bytes()
-        ],
-        bump
-    )]
-    pub transaction_history: Account<'info, TransactionHistory>,
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    /// CHECK: This is the seller account that will receive the SOL payment
-    #[account(
-        mut,
-        constraint = property.owner == *seller.key
-    )]
-    pub seller: AccountInfo<'info>,
-    /// CHECK: This is the marketplace authority to receive fees
-    #[account(
-        mut,
-        constraint = marketplace.authority == marketplace_authority.key()
-    )]
-    pub marketplace_authority: AccountInfo<'info>,
-    /// CHECK: This is the escrow's NFT token account
-    #[account(mut)]
-    pub escrow_nft_account: AccountInfo<'info>,
-    /// CHECK: This is the buyer's NFT token account
-    #[account(mut)]
-    pub buyer_nft_account: AccountInfo<'info>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+#[event]
+pub struct HistoryRetentionConfigured {
+    pub marketplace: Pubkey,
+    pub retention_years: u16,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct Marketplace {
-    pub authority: Pubkey,
-    pub properties_count: u64,
-    pub fee_percentage: u64,
+#[event]
+pub struct TaxConfigUpdated {
+    pub marketplace: Pubkey,
+    pub jurisdiction_code: [u8; 8],
+    pub tax_bps: u16,
+    pub collector: Pubkey,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct Property {
+#[event]
+pub struct TaxConfigRemoved {
+    pub marketplace: Pubkey,
+    pub jurisdiction_code: [u8; 8],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingBondConfigured {
     pub marketplace: Pubkey,
+    pub bond_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingBondLocked {
+    pub listing_bond: Pubkey,
+    pub property: Pubkey,
     pub owner: Pubkey,
-    pub property_id: String,
-    pub price: u64,
-    pub metadata_uri: String,
-    pub location: String,
-    pub square_feet: u64,
-    pub bedrooms: u8,
-    pub bathrooms: u8,
-    pub is_active: bool,
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub transaction_count: u64,
-    pub nft_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct Offer {
-    pub buyer: Pubkey,
+#[event]
+pub struct ListingBondReleased {
+    pub listing_bond: Pubkey,
     pub property: Pubkey,
+    pub owner: Pubkey,
     pub amount: u64,
-    pub status: OfferStatus,
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub expiration_time: i64,
-    pub escrow: Pubkey,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct TransactionHistory {
+#[event]
+pub struct ListingBondSlashed {
+    pub listing_bond: Pubkey,
+    pub property: Pubkey,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub reason: InterventionReason,
+    pub timestamp: i64,
+}
+
+/// Full contents of a closed sale record
+#[event]
+pub struct HistoryArchived {
+    pub transaction_history: Pubkey,
     pub property: Pubkey,
     pub seller: Pubkey,
     pub buyer: Pubkey,
     pub price: u64,
-    pub timestamp: i64,
+    pub sold_at: i64,
     pub transaction_index: u64,
+    pub seller_concession: u64,
+    pub version: u8,
+    pub rent_recovered: u64,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct Escrow {
-    pub offer: Pubkey,
+#[event]
+pub struct PropertyTransferred {
     pub property: Pubkey,
-    pub buyer: Pubkey,
-    pub seller: Pubkey,
-    pub amount: u64,
-    pub nft_held: bool,
-    pub created_at: i64,
+    pub transaction_history: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum OfferStatus {
-    Pending,
-    Accepted,
-    Rejected,
-    Completed,
-    Expired,
+#[event]
+pub struct PropertiesSwapped {
+    pub property_a: Pubkey,
+    pub property_b: Pubkey,
+    pub transaction_history_a: Pubkey,
+    pub transaction_history_b: Pubkey,
+    pub owner_a: Pubkey,
+    pub owner_b: Pubkey,
+    /// Lamports `owner_a` paid `owner_b`; negative when `owner_b` paid
+    pub cash_difference: i64,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct PropertyListed {
+pub struct ParcelClaimed {
+    pub property_registry: Pubkey,
+    pub parcel_key: [u8; 32],
+    pub property: Pubkey,
+    pub marketplace: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParcelReleased {
+    pub property_registry: Pubkey,
+    pub parcel_key: [u8; 32],
+    pub property: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertyRetired {
     pub property: Pubkey,
+    pub marketplace: Pubkey,
     pub owner: Pubkey,
     pub property_id: String,
-    pub price: u64,
     pub nft_mint: Pubkey,
+    /// Sales recorded for the property, whose history entries remain on-chain
+    pub transaction_count: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct PropertyUpdated {
+pub struct AdminIntervention {
+    pub marketplace: Pubkey,
+    pub authority: Pubkey,
+    pub offer: Pubkey,
     pub property: Pubkey,
-    pub owner: Pubkey,
-    pub price: u64,
-    pub is_active: bool,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    /// Lamports returned to the buyer
+    pub refunded: u64,
+    /// Whether an escrowed NFT went back to the property owner
+    pub nft_returned: bool,
+    pub reason: InterventionReason,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct OfferCreated {
+pub struct OfferAssigned {
     pub offer: Pubkey,
     pub property: Pubkey,
+    pub original_buyer: Pubkey,
+    pub new_buyer: Pubkey,
+    pub reimbursed: u64,
+    pub assignment_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ApprovalAuthorityUpdated {
+    pub property: Pubkey,
+    pub previous: Option<Pubkey>,
+    pub approval_authority: Option<Pubkey>,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransferApproved {
+    pub property: Pubkey,
+    pub offer: Pubkey,
+    pub authority: Pubkey,
     pub buyer: Pubkey,
-    pub amount: u64,
+    pub price: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct OfferAccepted {
+pub struct ClosingScheduled {
     pub offer: Pubkey,
     pub property: Pubkey,
     pub buyer: Pubkey,
-    pub seller: Pubkey,
-    pub amount: u64,
+    pub closing_date: i64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct OfferRejected {
+pub struct ClosingEscrowReleased {
     pub offer: Pubkey,
     pub property: Pubkey,
+    pub marketplace: Pubkey,
     pub buyer: Pubkey,
     pub seller: Pubkey,
+    pub closing_date: i64,
+    pub refunded: u64,
+    pub penalty: u64,
+    pub nft_returned: bool,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct PropertySold {
+pub struct ListingLimitsConfigured {
+    pub marketplace: Pubkey,
+    pub max_listings_per_owner: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnerListingLimitSet {
+    pub marketplace: Pubkey,
+    pub owner: Pubkey,
+    pub max_listings: Option<u16>,
+    pub active_listings: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingSlotReleased {
+    pub marketplace: Pubkey,
     pub property: Pubkey,
-    pub transaction_history: Pubkey,
-    pub previous_owner: Pubkey,
-    pub new_owner: Pubkey,
-    pub price: u64,
-    pub nft_mint: Pubkey,
+    pub owner: Pubkey,
+    pub active_listings: u16,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct OfferExpired {
-    pub offer: Pubkey,
+pub struct RenovationAdded {
     pub property: Pubkey,
-    pub buyer: Pubkey,
-    pub seller: Pubkey,
-    pub amount: u64,
+    pub owner: Pubkey,
+    /// Position of the record in the property's history
+    pub index: u8,
+    pub description_hash: [u8; 32],
+    pub cost: u64,
+    pub completed_at: i64,
     pub timestamp: i64,
 }
 
@@ -873,4 +12627,310 @@ pub enum ErrorCode {
     NFTNotInEscrow,
     #[msg("Insufficient funds in escrow")]
     InsufficientEscrowFunds,
+    #[msg("Rating must be between 1 and 5")]
+    InvalidRating,
+    #[msg("Signer is not a party to this transaction")]
+    NotTransactionParty,
+    #[msg("Remaining accounts must be (offer, escrow, buyer) triples and writable")]
+    InvalidRemainingAccounts,
+    #[msg("The accepted offer cannot be swept")]
+    CannotSweepAcceptedOffer,
+    #[msg("Minimum offer threshold must not exceed 10000 basis points")]
+    InvalidMinOfferBps,
+    #[msg("Offer is below the seller's minimum")]
+    OfferBelowMinimum,
+    #[msg("Land registry information has already been set")]
+    LandRegistryInfoAlreadySet,
+    #[msg("Invalid land registry information")]
+    InvalidLandRegistryInfo,
+    #[msg("Signer is not a registered verifier for this marketplace")]
+    NotRegisteredVerifier,
+    #[msg("Auto-accept is not enabled for this property")]
+    AutoAcceptDisabled,
+    #[msg("Offer is below the asking price")]
+    OfferBelowAskingPrice,
+    #[msg("Offers at or above asking on auto-accept listings must use buy_now")]
+    AutoAcceptAvailable,
+    #[msg("Listing has expired")]
+    ListingExpired,
+    #[msg("Listing has not expired yet")]
+    ListingNotExpired,
+    #[msg("Listing has no expiration time")]
+    ListingHasNoExpiry,
+    #[msg("Invalid price oracle configuration")]
+    InvalidOracleConfig,
+    #[msg("A price feed account is required for USD-priced listings")]
+    PriceFeedRequired,
+    #[msg("Invalid price feed account")]
+    InvalidPriceFeed,
+    #[msg("Oracle price is stale")]
+    StalePrice,
+    #[msg("Oracle price confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    #[msg("Property does not belong to this marketplace")]
+    MarketplaceMismatch,
+    #[msg("No attribute changes requested")]
+    NoAttributeChanges,
+    #[msg("Invalid price schedule")]
+    InvalidPriceSchedule,
+    #[msg("Invalid co-ownership configuration")]
+    InvalidCoOwnership,
+    #[msg("Signer is not a co-owner of this property")]
+    NotCoOwner,
+    #[msg("Co-ownership account is required for this property")]
+    CoOwnershipRequired,
+    #[msg("Not enough co-owners have approved this action")]
+    InsufficientCoOwnerApprovals,
+    #[msg("Operation is not allowed on a co-owned property")]
+    CoOwnedProperty,
+    #[msg("Invalid bundle")]
+    InvalidBundle,
+    #[msg("Property is part of a bundle")]
+    PropertyInBundle,
+    #[msg("Bundle is not active")]
+    BundleNotActive,
+    #[msg("Offer has not expired yet")]
+    OfferNotExpired,
+    #[msg("Invalid subdivision")]
+    InvalidSubdivision,
+    #[msg("Property has been subdivided")]
+    PropertySubdivided,
+    #[msg("Invalid parcel merge")]
+    InvalidMerge,
+    #[msg("Property has been merged into another property")]
+    PropertyMerged,
+    #[msg("Signer is not a registered lienholder for this marketplace")]
+    NotRegisteredLienholder,
+    #[msg("Invalid lien")]
+    InvalidLien,
+    #[msg("Property has active liens")]
+    PropertyHasLiens,
+    #[msg("Lien payoffs exceed the seller's proceeds")]
+    LiensExceedProceeds,
+    #[msg("Invalid document")]
+    InvalidDocument,
+    #[msg("Unknown amenity flags or amenities not valid for the property type")]
+    InvalidAmenities,
+    #[msg("Geohash must be 8 to 10 base32 characters")]
+    InvalidGeohash,
+    #[msg("Latitude and longitude must be given together and within range")]
+    InvalidCoordinates,
+    #[msg("Property account is too small for this update, call resize_property first")]
+    PropertyAccountTooSmall,
+    #[msg("Property account can only grow")]
+    InvalidResize,
+    #[msg("Account is not a migratable layout of this type")]
+    InvalidMigration,
+    #[msg("Account already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("Invalid seller response rule")]
+    InvalidResponseRule,
+    #[msg("Marketplace has no seller response rule")]
+    ResponseRuleDisabled,
+    #[msg("Seller response window has already closed")]
+    ResponseWindowClosed,
+    #[msg("Seller still has time to respond to this offer")]
+    ResponseWindowOpen,
+    #[msg("Offer is still pending")]
+    OfferStillPending,
+    #[msg("Invalid seller bond")]
+    InvalidSellerBond,
+    #[msg("Amount exceeds the unreserved seller bond")]
+    SellerBondReserved,
+    #[msg("Financing penalty cannot exceed 100%")]
+    InvalidFinancingPenalty,
+    #[msg("Earnest money must be positive and below the offer amount")]
+    InvalidEarnestAmount,
+    #[msg("Financing deadline must be in the future")]
+    InvalidFinancingDeadline,
+    #[msg("Offer is already fully funded")]
+    OfferAlreadyFunded,
+    #[msg("Offer has not been fully funded")]
+    OfferNotFunded,
+    #[msg("Financing deadline has passed")]
+    FinancingDeadlinePassed,
+    #[msg("Financing deadline has not been reached")]
+    FinancingDeadlineNotReached,
+    #[msg("Only the buyer or seller can cancel this offer")]
+    NotOfferParty,
+    #[msg("Property ID may only contain ASCII letters, digits, '-' and '_'")]
+    InvalidPropertyId,
+    #[msg("Location must be non-empty printable text")]
+    InvalidLocation,
+    #[msg("Metadata URI must be non-empty printable ASCII")]
+    InvalidMetadataUri,
+    #[msg("Seller concession must be below the offer amount, within the seller's proceeds, and set only on acceptance")]
+    InvalidSellerConcession,
+    #[msg("Invalid milestone release schedule")]
+    InvalidReleaseSchedule,
+    #[msg("Sale has not settled into the release schedule yet")]
+    ReleaseScheduleNotFunded,
+    #[msg("All milestones have been released")]
+    AllMilestonesReleased,
+    #[msg("Only the buyer or arbiter can release a milestone")]
+    NotMilestoneApprover,
+    #[msg("Amount exceeds the treasury's withdrawable balance")]
+    InsufficientTreasuryFunds,
+    #[msg("Marketplace is paused")]
+    MarketplacePaused,
+    #[msg("Invalid timelock")]
+    InvalidTimelock,
+    #[msg("Parameter change timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Marketplace changes must go through the timelock")]
+    TimelockActive,
+    #[msg("Verifier changes require the verifier account")]
+    VerifierAccountRequired,
+    #[msg("Invalid staking configuration")]
+    InvalidStakingConfig,
+    #[msg("Stake mint cannot change once set")]
+    StakeMintMismatch,
+    #[msg("Invalid stake amount")]
+    InvalidStakeAmount,
+    #[msg("Stake is still locked")]
+    StakeLocked,
+    #[msg("Property must be delisted first")]
+    PropertyStillListed,
+    #[msg("Invalid loan terms")]
+    InvalidLoanTerms,
+    #[msg("Loan is not awaiting funding")]
+    LoanNotRequested,
+    #[msg("Loan is not active")]
+    LoanNotActive,
+    #[msg("Loan is not yet due")]
+    LoanNotDue,
+    #[msg("Only the lender can do this")]
+    NotLender,
+    #[msg("Property does not match the loan")]
+    LoanPropertyMismatch,
+    #[msg("Auction has ended")]
+    AuctionEnded,
+    #[msg("Auction has not ended yet")]
+    AuctionNotEnded,
+    #[msg("Bid is too low")]
+    BidTooLow,
+    #[msg("Previous bidder does not match the auction")]
+    PreviousBidderMismatch,
+    #[msg("Collateral recipient must be the winning bidder or the lender")]
+    InvalidCollateralRecipient,
+    #[msg("Invalid payout address")]
+    InvalidPayoutAddress,
+    #[msg("Payout account does not match the seller's payout address")]
+    PayoutAddressMismatch,
+    #[msg("Invalid lease terms")]
+    InvalidLeaseTerms,
+    #[msg("Every period of the lease has been paid")]
+    LeaseFullyPaid,
+    #[msg("Rent is already paid too far ahead")]
+    RentPaidTooFarAhead,
+    #[msg("Invalid governance")]
+    InvalidGovernance,
+    #[msg("Property is governed; sales need a governance authorization")]
+    GovernedProperty,
+    #[msg("Invalid sale authorization")]
+    InvalidSaleAuthorization,
+    #[msg("A sale authorization from the property's governance is required")]
+    SaleAuthorizationRequired,
+    #[msg("Sale authorization has expired")]
+    SaleAuthorizationExpired,
+    #[msg("Offer is below the governance-authorized amount")]
+    OfferBelowAuthorizedAmount,
+    #[msg("Lease has not ended")]
+    LeaseNotEnded,
+    #[msg("Security deposit is not in the required state")]
+    InvalidDepositStatus,
+    #[msg("Invalid deposit deductions")]
+    InvalidDeductions,
+    #[msg("Deposit has no arbiter to resolve a dispute")]
+    NoDepositArbiter,
+    #[msg("Only the deposit arbiter can resolve this dispute")]
+    NotDepositArbiter,
+    #[msg("Deposit cannot be settled yet")]
+    DepositWindowOpen,
+    #[msg("Property has reached the maximum number of open offers")]
+    OpenOfferLimitReached,
+    #[msg("Invalid auction configuration")]
+    InvalidAuctionConfig,
+    #[msg("Retention period is too short")]
+    InvalidRetentionPeriod,
+    #[msg("Sale record is still within the retention period")]
+    HistoryRetentionActive,
+    #[msg("A property's latest sale record cannot be archived")]
+    CannotArchiveLatestHistory,
+    #[msg("Property cannot be transferred to its current owner")]
+    InvalidTransferRecipient,
+    #[msg("All open offers must be refunded first")]
+    OpenOffersRemain,
+    #[msg("Batch must list between one and five properties, with accounts for each")]
+    InvalidBatchListing,
+    #[msg("Parcel key does not match the normalized land registry reference")]
+    InvalidParcelKey,
+    #[msg("Parcel is already claimed by another property")]
+    ParcelAlreadyClaimed,
+    #[msg("Parcel is still held by a live property")]
+    ParcelStillListed,
+    #[msg("Only pending or accepted offers can be force-cancelled")]
+    OfferNotCancellable,
+    #[msg("Title insurance premium must be greater than zero")]
+    InvalidTitleInsurance,
+    #[msg("Title insurance can't be cancelled while the offer is accepted")]
+    TitleInsuranceLocked,
+    #[msg("Insurer does not match the title insurance policy")]
+    InsurerMismatch,
+    #[msg("Invalid tax configuration")]
+    InvalidTaxConfig,
+    #[msg("Tax collector does not match the jurisdiction's tax config")]
+    TaxCollectorMismatch,
+    #[msg("Media item hash is empty or already in the gallery")]
+    InvalidMediaItem,
+    #[msg("Property gallery is full")]
+    MediaLimitReached,
+    #[msg("Media item not found")]
+    MediaItemNotFound,
+    #[msg("Metadata authority can only be handed over after a sale or by the owner")]
+    MetadataAuthorityNotTransferable,
+    #[msg("Parcel registry account does not match the property's parcel")]
+    InvalidParcelRegistry,
+    #[msg("Marketplace requires a listing bond")]
+    ListingBondRequired,
+    #[msg("Listing bond can't be released while the listing is active")]
+    ListingStillActive,
+    #[msg("Marketplace treasury account is required")]
+    TreasuryRequired,
+    #[msg("Protocol fee must be at most 10000 bps of the marketplace fee")]
+    InvalidProtocolConfig,
+    #[msg("Protocol treasury does not match the protocol config")]
+    ProtocolTreasuryMismatch,
+    #[msg("Signer is not the program's upgrade authority")]
+    NotUpgradeAuthority,
+    #[msg("Offer can't be assigned to this buyer")]
+    InvalidAssignment,
+    #[msg("Signer is not the property's approval authority")]
+    NotApprovalAuthority,
+    #[msg("Property transfers need a matching approval from its approval authority")]
+    TransferNotApproved,
+    #[msg("Closing date must be in the future")]
+    InvalidClosingDate,
+    #[msg("Closing date has not been reached")]
+    ClosingDateNotReached,
+    #[msg("Escrow is ready to settle with execute_sale")]
+    ClosingReadyToSettle,
+    #[msg("Owner has reached their active listing limit")]
+    ListingLimitReached,
+    #[msg("Listing slot account is required while a listing limit applies")]
+    ListingSlotRequired,
+    #[msg("Listing slot is still held by the previous owner, release it first")]
+    ListingSlotHeld,
+    #[msg("Renovation needs a description hash and a completion date that has passed")]
+    InvalidRenovation,
+    #[msg("Property has reached the maximum number of renovation records")]
+    RenovationLimitReached,
+    #[msg("Certificate hashes must not be empty")]
+    InvalidEnergyAttestation,
+    #[msg("Slim listings need non-empty content hashes and no inline metadata URI or location")]
+    InvalidContentHashes,
+    #[msg("Property stores content hashes; update them with set_content_hashes")]
+    ContentHashedProperty,
+    #[msg("Lease term is not over; ending it early needs the tenant's signature")]
+    LeaseTermNotOver,
 }
\ No newline at end of file
@@ -17,6 +17,7 @@ describe("Real Estate Marketplace", () => {
   let propertyNFTMint: PublicKey;
   let ownerNFTAccount: PublicKey;
   let buyer: anchor.web3.Keypair;
+  let offerLimitsPDA: PublicKey;
   
   // Create unique IDs for testing to avoid "account already in use" errors
   const uniqueId = Math.floor(Math.random() * 1000000).toString();
@@ -24,6 +25,8 @@ describe("Real Estate Marketplace", () => {
   const propertyId2 = `Property${uniqueId}2`;
   const propertyId3 = `Property${uniqueId}3`;
   const propertyId4 = `Property${uniqueId}4`;
+  // Each offer below is the buyer's first on its property
+  const offerNonce = new anchor.BN(0);
 
   async function ensureMinimumBalance(pubkey: PublicKey, minBalance: number): Promise<void> {
     const balance = await provider.connection.getBalance(pubkey);
@@ -71,6 +74,10 @@ describe("Real Estate Marketplace", () => {
       [Buffer.from("marketplace"), authority.publicKey.toBuffer()],
       program.programId
     );
+    [offerLimitsPDA] = await PublicKey.findProgramAddress(
+      [Buffer.from("offer_limits"), marketplacePDA.toBuffer()],
+      program.programId
+    );
 
     // Fund buyer
     buyer = anchor.web3.Keypair.generate();
@@ -134,7 +141,14 @@ describe("Real Estate Marketplace", () => {
         "123 Blockchain St",
         new anchor.BN(2500),
         3,
-        2
+        2,
+        null, // expires_at
+        { residential: {} },
+        new anchor.BN(0), // amenities
+        "9q8yyk8y", // geohash
+        null,
+        null,
+        null // content_hashes
       )
       .accounts({
         marketplace: marketplacePDA,
@@ -159,7 +173,7 @@ describe("Real Estate Marketplace", () => {
 
   describe("Property Update", () => {
     it("Update property price by owner", async () => {
-      await program.methods.updateProperty(new anchor.BN(1.5 * LAMPORTS_PER_SOL), null, null)
+      await program.methods.updateProperty(new anchor.BN(1.5 * LAMPORTS_PER_SOL), null, null, null, null, null, null)
         .accounts({
           property: propertyPDA,
           owner: authority.publicKey,
@@ -172,7 +186,7 @@ describe("Real Estate Marketplace", () => {
     });
 
     it("Modify property status (active/inactive)", async () => {
-      await program.methods.updateProperty(null, null, false)
+      await program.methods.updateProperty(null, null, false, null, null, null, null)
         .accounts({
           property: propertyPDA,
           owner: authority.publicKey,
@@ -187,7 +201,7 @@ describe("Real Estate Marketplace", () => {
     it("Prevent unauthorized property updates", async () => {
       const unauthorized = anchor.web3.Keypair.generate();
       try {
-        await program.methods.updateProperty(new anchor.BN(2 * LAMPORTS_PER_SOL), null, null)
+        await program.methods.updateProperty(new anchor.BN(2 * LAMPORTS_PER_SOL), null, null, null, null, null, null)
           .accounts({
             property: propertyPDA,
             owner: unauthorized.publicKey,
@@ -229,7 +243,14 @@ describe("Real Estate Marketplace", () => {
         "456 Blockchain St",
         new anchor.BN(3000),
         4,
-        3
+        3,
+        null, // expires_at
+        { residential: {} },
+        new anchor.BN(0), // amenities
+        "9q8yyk8y", // geohash
+        null,
+        null,
+        null // content_hashes
       )
       .accounts({
         marketplace: marketplacePDA,
@@ -245,7 +266,7 @@ describe("Real Estate Marketplace", () => {
       .rpc();
       
       // Make sure property is active
-      await program.methods.updateProperty(null, null, true)
+      await program.methods.updateProperty(null, null, true, null, null, null, null)
         .accounts({
           property: newPropertyPDA,
           owner: authority.publicKey,
@@ -255,7 +276,12 @@ describe("Real Estate Marketplace", () => {
         .rpc();
       
       [offerPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("offer"), newPropertyPDA.toBuffer(), buyer.publicKey.toBuffer()],
+        [
+          Buffer.from("offer"),
+          newPropertyPDA.toBuffer(),
+          buyer.publicKey.toBuffer(),
+          offerNonce.toArrayLike(Buffer, "le", 8),
+        ],
         program.programId
       );
       
@@ -271,10 +297,13 @@ describe("Real Estate Marketplace", () => {
       
       await program.methods.makeOffer(
         new anchor.BN(offerAmount),
-        new anchor.BN(Math.floor(Date.now() / 1000) + 86400)
+        new anchor.BN(Math.floor(Date.now() / 1000) + 86400),
+        offerNonce
       )
       .accounts({
+        marketplace: marketplacePDA,
         property: newPropertyPDA,
+        offerLimits: offerLimitsPDA,
         offer: offerPDA,
         escrow: escrowPDA,
         buyer: buyer.publicKey,
@@ -325,7 +354,14 @@ describe("Real Estate Marketplace", () => {
         "456 Blockchain St",
         new anchor.BN(3000),
         4,
-        3
+        3,
+        null, // expires_at
+        { residential: {} },
+        new anchor.BN(0), // amenities
+        "9q8yyk8y", // geohash
+        null,
+        null,
+        null // content_hashes
       )
       .accounts({
         marketplace: marketplacePDA,
@@ -341,7 +377,12 @@ describe("Real Estate Marketplace", () => {
       .rpc();
 
       [offerPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("offer"), propertyPDA.toBuffer(), buyer.publicKey.toBuffer()],
+        [
+          Buffer.from("offer"),
+          propertyPDA.toBuffer(),
+          buyer.publicKey.toBuffer(),
+          offerNonce.toArrayLike(Buffer, "le", 8),
+        ],
         program.programId
       );
       
@@ -362,10 +403,13 @@ describe("Real Estate Marketplace", () => {
 
       await program.methods.makeOffer(
         new anchor.BN(offerAmount),
-        new anchor.BN(Math.floor(Date.now() / 1000) + expirationOffset)
+        new anchor.BN(Math.floor(Date.now() / 1000) + expirationOffset),
+        offerNonce
       )
       .accounts({
+        marketplace: marketplacePDA,
         property: propertyPDA,
+        offerLimits: offerLimitsPDA,
         offer: offerPDA,
         escrow: escrowPDA,
         buyer: buyer.publicKey,
@@ -379,8 +423,9 @@ describe("Real Estate Marketplace", () => {
     it("Accept pending offer within expiration", async () => {
       await setupPropertyAndOffer(propertyId3, 0.5 * LAMPORTS_PER_SOL, 86400);
       
-      await program.methods.respondToOffer(true)
+      await program.methods.respondToOffer(true, null)
         .accounts({
+          marketplace: marketplacePDA,
           property: propertyPDA,
           offer: offerPDA,
           escrow: escrowPDA,
@@ -388,6 +433,7 @@ describe("Real Estate Marketplace", () => {
           buyer: buyer.publicKey,
           sellerNftAccount: ownerNFTAccount,
           escrowNftAccount: escrowNFTAccount,
+          propertyNftMint: propertyNFTMint,
           tokenProgram: token.TOKEN_PROGRAM_ID
         })
         .rpc();
@@ -410,8 +456,9 @@ describe("Real Estate Marketplace", () => {
       
       const buyerBalanceBefore = await provider.connection.getBalance(buyer.publicKey);
       
-      await program.methods.respondToOffer(false)
+      await program.methods.respondToOffer(false, null)
         .accounts({
+          marketplace: marketplacePDA,
           property: propertyPDA,
           offer: offerPDA,
           escrow: escrowPDA,
@@ -419,6 +466,7 @@ describe("Real Estate Marketplace", () => {
           buyer: buyer.publicKey,
           sellerNftAccount: ownerNFTAccount,
           escrowNftAccount: escrowNFTAccount,
+          propertyNftMint: propertyNFTMint,
           tokenProgram: token.TOKEN_PROGRAM_ID
         })
         .rpc();
@@ -462,7 +510,14 @@ describe("Real Estate Marketplace", () => {
         "789 Blockchain St",
         new anchor.BN(3500),
         5,
-        4
+        4,
+        null, // expires_at
+        { residential: {} },
+        new anchor.BN(0), // amenities
+        "9q8yyk8y", // geohash
+        null,
+        null,
+        null // content_hashes
       )
       .accounts({
         marketplace: marketplacePDA,
@@ -478,7 +533,12 @@ describe("Real Estate Marketplace", () => {
       .rpc();
 
       [offerPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("offer"), propertyPDA.toBuffer(), buyer.publicKey.toBuffer()],
+        [
+          Buffer.from("offer"),
+          propertyPDA.toBuffer(),
+          buyer.publicKey.toBuffer(),
+          offerNonce.toArrayLike(Buffer, "le", 8),
+        ],
         program.programId
       );
       
@@ -500,10 +560,13 @@ describe("Real Estate Marketplace", () => {
       // Use a smaller amount to avoid insufficient funds errors
       await program.methods.makeOffer(
         new anchor.BN(0.5 * LAMPORTS_PER_SOL),
-        new anchor.BN(Math.floor(Date.now() / 1000) + 86400)
+        new anchor.BN(Math.floor(Date.now() / 1000) + 86400),
+        offerNonce
       )
       .accounts({
+        marketplace: marketplacePDA,
         property: propertyPDA,
+        offerLimits: offerLimitsPDA,
         offer: offerPDA,
         escrow: escrowPDA,
         buyer: buyer.publicKey,
@@ -513,8 +576,9 @@ describe("Real Estate Marketplace", () => {
       .signers([buyer])
       .rpc();
 
-      await program.methods.respondToOffer(true)
+      await program.methods.respondToOffer(true, null)
         .accounts({
+          marketplace: marketplacePDA,
           property: propertyPDA,
           offer: offerPDA,
           escrow: escrowPDA,
@@ -522,6 +586,7 @@ describe("Real Estate Marketplace", () => {
           buyer: buyer.publicKey,
           sellerNftAccount: ownerNFTAccount,
           escrowNftAccount: escrowNFTAccount,
+          propertyNftMint: propertyNFTMint,
           tokenProgram: token.TOKEN_PROGRAM_ID
         })
         .rpc();
@@ -549,7 +614,9 @@ describe("Real Estate Marketplace", () => {
           escrow: escrowPDA,
           transactionHistory: transactionHistoryPDA,
           buyer: buyer.publicKey,
+          payer: buyer.publicKey,
           seller: authority.publicKey,
+          payout: authority.publicKey,
           marketplaceAuthority: authority.publicKey,
           escrowNftAccount: escrowNFTAccount,
           buyerNftAccount: buyerNFTAccount,